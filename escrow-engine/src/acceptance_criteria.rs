@@ -0,0 +1,389 @@
+//! Acceptance Criteria - Machine-checkable requirements for task proofs
+//!
+//! Tasks declare their criteria under `acceptance_criteria` in their
+//! metadata, as a list of tagged objects:
+//!
+//! ```json
+//! [
+//!   { "type": "file_type", "allowed": ["pdf", "image/png"] },
+//!   { "type": "size", "min_bytes": 1024, "max_bytes": 1048576 },
+//!   { "type": "hash_equals", "hash": "<hex sha256>" },
+//!   { "type": "required_fields", "fields": ["commit", "results.passed"] },
+//!   { "type": "min_count", "field": "results.tests", "min": 10 }
+//! ]
+//! ```
+//!
+//! Criteria are validated when the task is created and evaluated against
+//! the downloaded proof, each with its own pass/fail result.
+
+use crate::{
+    EscrowResult,
+    error::EscrowError,
+    proof_fetcher::{ContentType, FetchedProof},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Metadata key the criteria are declared under
+pub const METADATA_KEY: &str = "acceptance_criteria";
+
+/// A single requirement on the proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Criterion {
+    /// Content is of one of the given types, by extension or MIME type
+    FileType { allowed: Vec<String> },
+    /// Content size lies within the bounds
+    Size {
+        #[serde(default)]
+        min_bytes: Option<u64>,
+        #[serde(default)]
+        max_bytes: Option<u64>,
+    },
+    /// Content hashes to the given hex SHA-256
+    HashEquals { hash: String },
+    /// JSON content has every field, given as dotted paths
+    RequiredFields { fields: Vec<String> },
+    /// JSON array at `field` (the whole document when omitted) has at least
+    /// `min` items; for text content without a field, non-empty lines count
+    MinCount {
+        #[serde(default)]
+        field: Option<String>,
+        min: u64,
+    },
+}
+
+impl Criterion {
+    /// Criterion type as declared
+    pub fn name(&self) -> &'static str {
+        match self {
+            Criterion::FileType { .. } => "file_type",
+            Criterion::Size { .. } => "size",
+            Criterion::HashEquals { .. } => "hash_equals",
+            Criterion::RequiredFields { .. } => "required_fields",
+            Criterion::MinCount { .. } => "min_count",
+        }
+    }
+
+    /// Check that the criterion can be evaluated at all
+    fn validate(&self) -> EscrowResult<()> {
+        match self {
+            Criterion::FileType { allowed } => {
+                if allowed.is_empty() || allowed.iter().any(|allowed| allowed.trim().is_empty()) {
+                    return Err(EscrowError::task_validation(
+                        "file_type criterion needs non-empty allowed types",
+                    ));
+                }
+            }
+            Criterion::Size {
+                min_bytes,
+                max_bytes,
+            } => match (min_bytes, max_bytes) {
+                (None, None) => {
+                    return Err(EscrowError::task_validation(
+                        "size criterion needs min_bytes or max_bytes",
+                    ));
+                }
+                (Some(min), Some(max)) if min > max => {
+                    return Err(EscrowError::task_validation(format!(
+                        "size criterion min_bytes {} exceeds max_bytes {}",
+                        min, max
+                    )));
+                }
+                _ => {}
+            },
+            Criterion::HashEquals { hash } => {
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(EscrowError::task_validation(
+                        "hash_equals criterion needs a 64 character hex SHA256",
+                    ));
+                }
+            }
+            Criterion::RequiredFields { fields } => {
+                if fields.is_empty() || fields.iter().any(|field| !is_valid_path(field)) {
+                    return Err(EscrowError::task_validation(
+                        "required_fields criterion needs non-empty dotted field paths",
+                    ));
+                }
+            }
+            Criterion::MinCount { field, min } => {
+                if field.as_deref().is_some_and(|field| !is_valid_path(field)) {
+                    return Err(EscrowError::task_validation(
+                        "min_count criterion field must be a dotted path",
+                    ));
+                }
+                if *min == 0 {
+                    return Err(EscrowError::task_validation(
+                        "min_count criterion needs a min above 0",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate the criterion, returning whether it passed and why
+    fn evaluate(&self, proof: &FetchedProof, json: Option<&Value>) -> (bool, String) {
+        match self {
+            Criterion::FileType { allowed } => {
+                let content_type = proof.content_type;
+                let passed = allowed.iter().any(|allowed| {
+                    let allowed = allowed.trim().trim_start_matches('.');
+                    allowed.eq_ignore_ascii_case(content_type.mime_type())
+                        || content_type
+                            .extensions()
+                            .iter()
+                            .any(|extension| allowed.eq_ignore_ascii_case(extension))
+                });
+                (passed, format!("Proof is {}", content_type.mime_type()))
+            }
+            Criterion::Size {
+                min_bytes,
+                max_bytes,
+            } => {
+                let passed = min_bytes.is_none_or(|min| proof.size >= min)
+                    && max_bytes.is_none_or(|max| proof.size <= max);
+                (passed, format!("Proof is {} bytes", proof.size))
+            }
+            Criterion::HashEquals { hash } => (
+                proof.content_hash.eq_ignore_ascii_case(hash),
+                format!("Proof hashes to {}", proof.content_hash),
+            ),
+            Criterion::RequiredFields { fields } => {
+                let Some(json) = json else {
+                    return (false, "Proof is not JSON".to_string());
+                };
+                let missing: Vec<&str> = fields
+                    .iter()
+                    .filter(|field| lookup(json, field).is_none_or(Value::is_null))
+                    .map(String::as_str)
+                    .collect();
+                if missing.is_empty() {
+                    (true, "All fields present".to_string())
+                } else {
+                    (false, format!("Missing {}", missing.join(", ")))
+                }
+            }
+            Criterion::MinCount { field, min } => {
+                let count = match field {
+                    Some(field) => json
+                        .and_then(|json| lookup(json, field))
+                        .and_then(Value::as_array)
+                        .map(Vec::len),
+                    None => match json.and_then(Value::as_array) {
+                        Some(items) => Some(items.len()),
+                        None if proof.content_type == ContentType::Text => Some(
+                            String::from_utf8_lossy(&proof.data)
+                                .lines()
+                                .filter(|line| !line.trim().is_empty())
+                                .count(),
+                        ),
+                        None => None,
+                    },
+                };
+                match count {
+                    Some(count) => (count as u64 >= *min, format!("Found {}", count)),
+                    None => (false, "Nothing to count".to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// Whether `path` is a dotted path of non-empty segments
+fn is_valid_path(path: &str) -> bool {
+    !path.is_empty() && path.split('.').all(|segment| !segment.is_empty())
+}
+
+/// Value at a dotted path, with numeric segments indexing arrays
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(object) => object.get(segment),
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+/// Acceptance criteria of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AcceptanceCriteria {
+    pub criteria: Vec<Criterion>,
+}
+
+impl AcceptanceCriteria {
+    /// Parse and validate the criteria declared in task metadata, if any
+    pub fn from_metadata(metadata: Option<&Value>) -> EscrowResult<Option<Self>> {
+        let Some(declared) = metadata.and_then(|metadata| metadata.get(METADATA_KEY)) else {
+            return Ok(None);
+        };
+        let criteria: Self = serde_json::from_value(declared.clone()).map_err(|e| {
+            EscrowError::task_validation(format!("Invalid acceptance criteria: {}", e))
+        })?;
+        if criteria.criteria.is_empty() {
+            return Err(EscrowError::task_validation(
+                "Acceptance criteria cannot be empty",
+            ));
+        }
+        for criterion in &criteria.criteria {
+            criterion.validate()?;
+        }
+        Ok(Some(criteria))
+    }
+
+    /// Evaluate every criterion against the downloaded proof
+    pub fn evaluate(&self, proof: &FetchedProof) -> CriteriaReport {
+        let json: Option<Value> = serde_json::from_slice(&proof.data).ok();
+        let results: Vec<CriterionResult> = self
+            .criteria
+            .iter()
+            .map(|criterion| {
+                let (passed, detail) = criterion.evaluate(proof, json.as_ref());
+                CriterionResult {
+                    criterion: criterion.clone(),
+                    passed,
+                    detail,
+                }
+            })
+            .collect();
+
+        CriteriaReport {
+            passed: results.iter().all(|result| result.passed),
+            results,
+        }
+    }
+}
+
+/// Outcome of one criterion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CriterionResult {
+    pub criterion: Criterion,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Per-criterion outcome of evaluating a proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CriteriaReport {
+    /// Whether every criterion passed
+    pub passed: bool,
+    pub results: Vec<CriterionResult>,
+}
+
+impl CriteriaReport {
+    /// Number of criteria that passed
+    pub fn passed_count(&self) -> usize {
+        self.results.iter().filter(|result| result.passed).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    fn proof(data: &[u8]) -> FetchedProof {
+        FetchedProof {
+            content_hash: hex::encode(Sha256::digest(data)),
+            size: data.len() as u64,
+            content_type: ContentType::sniff(data),
+            data: data.to_vec(),
+        }
+    }
+
+    fn criteria(declared: Value) -> EscrowResult<Option<AcceptanceCriteria>> {
+        AcceptanceCriteria::from_metadata(Some(&json!({ METADATA_KEY: declared })))
+    }
+
+    #[test]
+    fn test_criteria_validation() {
+        assert_eq!(AcceptanceCriteria::from_metadata(None).unwrap(), None);
+        assert_eq!(
+            AcceptanceCriteria::from_metadata(Some(&json!({ "task_type": "report" }))).unwrap(),
+            None
+        );
+
+        let parsed = criteria(json!([
+            { "type": "file_type", "allowed": ["txt"] },
+            { "type": "size", "max_bytes": 1024 },
+            { "type": "min_count", "min": 2 },
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            parsed.criteria[1],
+            Criterion::Size {
+                min_bytes: None,
+                max_bytes: Some(1024)
+            }
+        );
+
+        for invalid in [
+            json!([]),
+            json!({ "type": "size", "max_bytes": 1 }),
+            json!([{ "type": "word_count", "min": 1 }]),
+            json!([{ "type": "size", "max_bytes": 1, "max_byte": 2 }]),
+            json!([{ "type": "size" }]),
+            json!([{ "type": "size", "min_bytes": 10, "max_bytes": 1 }]),
+            json!([{ "type": "file_type", "allowed": [] }]),
+            json!([{ "type": "hash_equals", "hash": "abc" }]),
+            json!([{ "type": "required_fields", "fields": ["results..passed"] }]),
+            json!([{ "type": "min_count", "min": 0 }]),
+        ] {
+            assert!(
+                matches!(
+                    criteria(invalid.clone()),
+                    Err(EscrowError::TaskValidation(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_criteria_evaluation() {
+        let report =
+            proof(br#"{"commit": "abc123", "results": {"passed": true, "tests": [1, 2, 3]}}"#);
+        let declared = criteria(json!([
+            { "type": "file_type", "allowed": ["text/plain"] },
+            { "type": "size", "min_bytes": 10, "max_bytes": 1024 },
+            { "type": "hash_equals", "hash": report.content_hash.to_uppercase() },
+            { "type": "required_fields", "fields": ["commit", "results.passed", "results.tests.0"] },
+            { "type": "min_count", "field": "results.tests", "min": 3 },
+        ]))
+        .unwrap()
+        .unwrap();
+        let evaluated = declared.evaluate(&report);
+        assert!(evaluated.passed, "{:?}", evaluated);
+        assert_eq!(evaluated.passed_count(), 5);
+
+        // Each criterion reports on its own
+        let evaluated = declared.evaluate(&proof(br#"{"commit": null, "results": {"tests": []}}"#));
+        assert!(!evaluated.passed);
+        let passed: Vec<bool> = evaluated
+            .results
+            .iter()
+            .map(|result| result.passed)
+            .collect();
+        assert_eq!(passed, [true, true, false, false, false]);
+        assert_eq!(
+            evaluated.results[3].detail,
+            "Missing commit, results.passed, results.tests.0"
+        );
+
+        // Text proofs count their non-empty lines; images have nothing to count
+        let lines = criteria(json!([
+            { "type": "file_type", "allowed": ["md", "png"] },
+            { "type": "min_count", "min": 3 },
+        ]))
+        .unwrap()
+        .unwrap();
+        assert!(lines.evaluate(&proof(b"# Report\n\n- one\n- two\n")).passed);
+        let evaluated = lines.evaluate(&proof(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert_eq!(evaluated.passed_count(), 1);
+        assert_eq!(evaluated.results[1].detail, "Nothing to count");
+    }
+}
@@ -0,0 +1,626 @@
+//! Backup - Channel monitor backup and guarded restore for the LDK node
+//!
+//! Every channel monitor update persisted by LDK is streamed to a local
+//! backup directory and mirrored to any registered remote sinks. A manifest
+//! tracks the latest update per channel so backup freshness can be checked
+//! against the live node, and so a restore can refuse to start from state
+//! older than what any sink has seen (restoring stale channel state can lose
+//! funds through penalty transactions).
+
+use crate::{EscrowResult, error::EscrowError};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Manifest key shared by the local directory and remote sinks
+const MANIFEST_KEY: &str = "manifest.json";
+
+/// Length of a hex-encoded LDK channel ID
+const CHANNEL_ID_HEX_LEN: usize = 64;
+
+/// Configuration for channel monitor backups
+#[derive(Debug, Clone)]
+pub struct BackupConfig {
+    /// Local directory receiving every channel monitor update
+    pub backup_dir: PathBuf,
+    /// Maximum backup age accepted by freshness checks and restores
+    pub max_backup_age_secs: u64,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            backup_dir: PathBuf::from("/var/lib/escrow/ldk-backup"),
+            max_backup_age_secs: 3600, // 1 hour
+        }
+    }
+}
+
+/// A persisted channel monitor update
+#[derive(Debug, Clone)]
+pub struct ChannelMonitorUpdate {
+    pub channel_id: String,
+    pub update_id: u64,
+    pub data: Vec<u8>,
+}
+
+/// Latest backed-up state of a single channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelBackupEntry {
+    pub latest_update_id: u64,
+    pub key: String,
+    pub sha256: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Index of the latest backup for every channel
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub channels: BTreeMap<String, ChannelBackupEntry>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Backup freshness report
+#[derive(Debug, Clone)]
+pub struct BackupStatus {
+    pub fresh: bool,
+    pub last_backup_at: Option<DateTime<Utc>>,
+    /// Channels whose backup lags the live node
+    pub stale_channels: Vec<String>,
+    /// Channels known to the live node but absent from the backup
+    pub missing_channels: Vec<String>,
+    /// Remote sinks whose last write failed
+    pub failed_sinks: Vec<String>,
+}
+
+/// Result of a successful restore
+#[derive(Debug, Clone)]
+pub struct RestoreReport {
+    pub restored_channels: usize,
+    pub target_dir: PathBuf,
+    pub manifest: BackupManifest,
+}
+
+/// Destination for backup data (local disk, object storage, peer, ...)
+#[async_trait]
+pub trait BackupSink: Send + Sync {
+    /// Human-readable sink name
+    fn name(&self) -> &str;
+
+    /// Store a blob under a key, overwriting any previous value
+    async fn store(&self, key: &str, data: &[u8]) -> EscrowResult<()>;
+
+    /// Fetch a blob by key
+    async fn fetch(&self, key: &str) -> EscrowResult<Option<Vec<u8>>>;
+}
+
+/// Backup sink writing to a local directory
+pub struct LocalDirectorySink {
+    root: PathBuf,
+}
+
+impl LocalDirectorySink {
+    /// Create a sink rooted at the given directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl BackupSink for LocalDirectorySink {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn store(&self, key: &str, data: &[u8]) -> EscrowResult<()> {
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| EscrowError::integration(format!("Backup mkdir failed: {}", e)))?;
+        }
+
+        // Write to a temporary file and rename so readers never see a torn write
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, data)
+            .await
+            .map_err(|e| EscrowError::integration(format!("Backup write failed: {}", e)))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| EscrowError::integration(format!("Backup rename failed: {}", e)))
+    }
+
+    async fn fetch(&self, key: &str) -> EscrowResult<Option<Vec<u8>>> {
+        match tokio::fs::read(self.path_for(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(EscrowError::integration(format!(
+                "Backup read failed: {}",
+                e
+            ))),
+        }
+    }
+}
+
+/// Streams channel monitor updates to backup sinks
+pub struct BackupManager {
+    config: BackupConfig,
+    local: LocalDirectorySink,
+    remote_sinks: RwLock<Vec<Arc<dyn BackupSink>>>,
+    manifest: RwLock<BackupManifest>,
+    failed_sinks: RwLock<Vec<String>>,
+}
+
+impl BackupManager {
+    /// Create a backup manager, loading any existing manifest from disk
+    pub async fn new(config: BackupConfig) -> EscrowResult<Self> {
+        let local = LocalDirectorySink::new(config.backup_dir.clone());
+        let manifest = match local.fetch(MANIFEST_KEY).await? {
+            Some(data) => serde_json::from_slice(&data)?,
+            None => BackupManifest::default(),
+        };
+
+        info!(
+            "Channel monitor backups at {} ({} channel(s) tracked)",
+            config.backup_dir.display(),
+            manifest.channels.len()
+        );
+
+        Ok(Self {
+            config,
+            local,
+            remote_sinks: RwLock::new(Vec::new()),
+            manifest: RwLock::new(manifest),
+            failed_sinks: RwLock::new(Vec::new()),
+        })
+    }
+
+    /// Register a remote sink that mirrors every update
+    pub async fn add_remote_sink(&self, sink: Arc<dyn BackupSink>) {
+        info!("Registered remote backup sink: {}", sink.name());
+        self.remote_sinks.write().await.push(sink);
+    }
+
+    /// Get a copy of the current manifest
+    pub async fn manifest(&self) -> BackupManifest {
+        self.manifest.read().await.clone()
+    }
+
+    /// Back up a channel monitor update
+    ///
+    /// The local write must succeed before LDK may consider the update
+    /// persisted. Remote sink failures are logged and surfaced through
+    /// `check_freshness` instead of blocking channel operation.
+    pub async fn record_monitor_update(&self, update: ChannelMonitorUpdate) -> EscrowResult<()> {
+        validate_channel_id(&update.channel_id)?;
+        let mut manifest = self.manifest.write().await;
+
+        if let Some(entry) = manifest.channels.get(&update.channel_id)
+            && update.update_id <= entry.latest_update_id
+        {
+            return Err(EscrowError::integration(format!(
+                "Channel {} update {} is not newer than backed-up update {}",
+                update.channel_id, update.update_id, entry.latest_update_id
+            )));
+        }
+
+        let key = format!(
+            "monitors/{}/{:020}.bin",
+            update.channel_id, update.update_id
+        );
+        self.local.store(&key, &update.data).await?;
+
+        let entry = ChannelBackupEntry {
+            latest_update_id: update.update_id,
+            key: key.clone(),
+            sha256: hex::encode(Sha256::digest(&update.data)),
+            updated_at: Utc::now(),
+        };
+        manifest.channels.insert(update.channel_id.clone(), entry);
+        manifest.updated_at = Some(Utc::now());
+
+        let manifest_data = serde_json::to_vec_pretty(&*manifest)?;
+        self.local.store(MANIFEST_KEY, &manifest_data).await?;
+
+        let mut failed = Vec::new();
+        for sink in self.remote_sinks.read().await.iter() {
+            let result = match sink.store(&key, &update.data).await {
+                Ok(()) => sink.store(MANIFEST_KEY, &manifest_data).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("Remote backup to {} failed: {}", sink.name(), e);
+                failed.push(sink.name().to_string());
+            }
+        }
+        *self.failed_sinks.write().await = failed;
+
+        Ok(())
+    }
+
+    /// Check backup freshness against the live node's channel state
+    pub async fn check_freshness(&self, live_update_ids: &HashMap<String, u64>) -> BackupStatus {
+        let manifest = self.manifest.read().await;
+
+        let mut stale_channels = Vec::new();
+        let mut missing_channels = Vec::new();
+        for (channel_id, live_update_id) in live_update_ids {
+            match manifest.channels.get(channel_id) {
+                Some(entry) if entry.latest_update_id < *live_update_id => {
+                    stale_channels.push(channel_id.clone())
+                }
+                Some(_) => {}
+                None => missing_channels.push(channel_id.clone()),
+            }
+        }
+        stale_channels.sort();
+        missing_channels.sort();
+
+        // Without live state to compare against, fall back to the backup's age
+        let too_old = live_update_ids.is_empty()
+            && manifest.updated_at.is_some_and(|updated_at| {
+                (Utc::now() - updated_at).num_seconds() > self.config.max_backup_age_secs as i64
+            });
+
+        let failed_sinks = self.failed_sinks.read().await.clone();
+
+        BackupStatus {
+            fresh: stale_channels.is_empty()
+                && missing_channels.is_empty()
+                && failed_sinks.is_empty()
+                && !too_old,
+            last_backup_at: manifest.updated_at,
+            stale_channels,
+            missing_channels,
+            failed_sinks,
+        }
+    }
+
+    /// Restore the latest backed-up channel monitors into `target_dir`
+    ///
+    /// Refuses to restore when any remote sink has seen a newer update for a
+    /// channel than the local backup, when `known_update_ids` (e.g. from peers
+    /// or monitoring) shows newer state, or when a backup blob fails its
+    /// checksum. `target_dir` must be empty so live state is never overwritten.
+    ///
+    /// A backup older than `max_backup_age_secs` may miss updates nobody else
+    /// has seen, so it is only restored with `allow_old_backup`.
+    pub async fn restore(
+        &self,
+        target_dir: &Path,
+        known_update_ids: &HashMap<String, u64>,
+        allow_old_backup: bool,
+    ) -> EscrowResult<RestoreReport> {
+        let manifest = self.manifest.read().await.clone();
+
+        if manifest.channels.is_empty() {
+            return Err(EscrowError::integration("No channel backups to restore"));
+        }
+        for channel_id in manifest.channels.keys() {
+            validate_channel_id(channel_id)?;
+        }
+
+        let age_secs = manifest
+            .updated_at
+            .map(|updated_at| (Utc::now() - updated_at).num_seconds());
+        if !allow_old_backup
+            && age_secs.is_none_or(|age_secs| age_secs > self.config.max_backup_age_secs as i64)
+        {
+            return Err(EscrowError::integration(format!(
+                "Refusing restore: backup is older than {}s",
+                self.config.max_backup_age_secs
+            )));
+        }
+
+        // Compare against every remote manifest
+        for sink in self.remote_sinks.read().await.iter() {
+            let Some(data) = sink.fetch(MANIFEST_KEY).await? else {
+                continue;
+            };
+            let remote: BackupManifest = serde_json::from_slice(&data)?;
+            for (channel_id, remote_entry) in &remote.channels {
+                let local_update_id = manifest
+                    .channels
+                    .get(channel_id)
+                    .map(|entry| entry.latest_update_id);
+                if local_update_id.is_none_or(|id| id < remote_entry.latest_update_id) {
+                    return Err(EscrowError::integration(format!(
+                        "Refusing restore: sink {} has newer state for channel {} (update {})",
+                        sink.name(),
+                        channel_id,
+                        remote_entry.latest_update_id
+                    )));
+                }
+            }
+        }
+
+        for (channel_id, known_update_id) in known_update_ids {
+            let local_update_id = manifest
+                .channels
+                .get(channel_id)
+                .map(|entry| entry.latest_update_id);
+            if local_update_id.is_none_or(|id| id < *known_update_id) {
+                return Err(EscrowError::integration(format!(
+                    "Refusing restore: channel {} backup is older than known update {}",
+                    channel_id, known_update_id
+                )));
+            }
+        }
+
+        if let Ok(mut entries) = tokio::fs::read_dir(target_dir).await
+            && entries
+                .next_entry()
+                .await
+                .map_err(|e| EscrowError::integration(format!("Restore read_dir failed: {}", e)))?
+                .is_some()
+        {
+            return Err(EscrowError::integration(format!(
+                "Refusing restore: target {} is not empty",
+                target_dir.display()
+            )));
+        }
+
+        let target = LocalDirectorySink::new(target_dir);
+        for (channel_id, entry) in &manifest.channels {
+            let data = self.local.fetch(&entry.key).await?.ok_or_else(|| {
+                EscrowError::integration(format!("Backup blob {} is missing", entry.key))
+            })?;
+
+            if hex::encode(Sha256::digest(&data)) != entry.sha256 {
+                return Err(EscrowError::integration(format!(
+                    "Backup blob {} failed checksum",
+                    entry.key
+                )));
+            }
+
+            target
+                .store(&format!("monitors/{}.bin", channel_id), &data)
+                .await?;
+        }
+
+        info!(
+            "Restored {} channel monitor(s) to {}",
+            manifest.channels.len(),
+            target_dir.display()
+        );
+
+        Ok(RestoreReport {
+            restored_channels: manifest.channels.len(),
+            target_dir: target_dir.to_path_buf(),
+            manifest,
+        })
+    }
+}
+
+/// Check that a channel ID is a hex-encoded LDK channel ID
+///
+/// Channel IDs become backup paths, so anything else is refused.
+fn validate_channel_id(channel_id: &str) -> EscrowResult<()> {
+    if channel_id.len() != CHANNEL_ID_HEX_LEN || !channel_id.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        return Err(EscrowError::integration(format!(
+            "Invalid channel ID {:?}",
+            channel_id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MemorySink {
+        blobs: RwLock<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait]
+    impl BackupSink for MemorySink {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn store(&self, key: &str, data: &[u8]) -> EscrowResult<()> {
+            self.blobs
+                .write()
+                .await
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        async fn fetch(&self, key: &str) -> EscrowResult<Option<Vec<u8>>> {
+            Ok(self.blobs.read().await.get(key).cloned())
+        }
+    }
+
+    /// Temporary directory removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("escrow-backup-{}", uuid::Uuid::new_v4())))
+        }
+
+        fn path(&self) -> PathBuf {
+            self.0.clone()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn channel(name: &str) -> String {
+        hex::encode(Sha256::digest(name))
+    }
+
+    fn update(name: &str, update_id: u64) -> ChannelMonitorUpdate {
+        ChannelMonitorUpdate {
+            channel_id: channel(name),
+            update_id,
+            data: format!("monitor-{}-{}", name, update_id).into_bytes(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backup_freshness_and_restore() {
+        let backup_dir = TempDir::new();
+        let config = BackupConfig {
+            backup_dir: backup_dir.path(),
+            ..BackupConfig::default()
+        };
+        let manager = BackupManager::new(config.clone()).await.unwrap();
+        let remote = Arc::new(MemorySink {
+            blobs: RwLock::new(HashMap::new()),
+        });
+        manager.add_remote_sink(remote.clone()).await;
+
+        manager
+            .record_monitor_update(update("chan_a", 1))
+            .await
+            .unwrap();
+        manager
+            .record_monitor_update(update("chan_a", 2))
+            .await
+            .unwrap();
+        manager
+            .record_monitor_update(update("chan_b", 7))
+            .await
+            .unwrap();
+
+        // Regressions are refused
+        assert!(
+            manager
+                .record_monitor_update(update("chan_a", 2))
+                .await
+                .is_err()
+        );
+
+        let live = HashMap::from([(channel("chan_a"), 2), (channel("chan_b"), 7)]);
+        assert!(manager.check_freshness(&live).await.fresh);
+
+        let live_ahead = HashMap::from([(channel("chan_a"), 3)]);
+        let status = manager.check_freshness(&live_ahead).await;
+        assert!(!status.fresh);
+        assert_eq!(status.stale_channels, vec![channel("chan_a")]);
+
+        // Manifest survives a restart
+        let reloaded = BackupManager::new(config).await.unwrap();
+        assert_eq!(
+            reloaded.manifest().await.channels[&channel("chan_a")].latest_update_id,
+            2
+        );
+
+        // Restore refuses state older than what is known to exist
+        let target = TempDir::new();
+        assert!(
+            manager
+                .restore(&target.path(), &live_ahead, false)
+                .await
+                .is_err()
+        );
+
+        let report = manager.restore(&target.path(), &live, false).await.unwrap();
+        assert_eq!(report.restored_channels, 2);
+        let restored = tokio::fs::read(
+            target
+                .path()
+                .join(format!("monitors/{}.bin", channel("chan_a"))),
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored, b"monitor-chan_a-2");
+
+        // Restoring over existing state is refused
+        assert!(manager.restore(&target.path(), &live, false).await.is_err());
+
+        // Channel IDs never escape the backup directory
+        let escape = ChannelMonitorUpdate {
+            channel_id: "../../etc".to_string(),
+            ..update("chan_a", 3)
+        };
+        assert!(matches!(
+            manager.record_monitor_update(escape).await,
+            Err(EscrowError::Integration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_restore_refuses_old_backup() {
+        let backup_dir = TempDir::new();
+        let manager = BackupManager::new(BackupConfig {
+            backup_dir: backup_dir.path(),
+            max_backup_age_secs: 0,
+        })
+        .await
+        .unwrap();
+        manager
+            .record_monitor_update(update("chan_a", 1))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+        // A lone local backup past its age is only restored on request
+        let target = TempDir::new();
+        let result = manager
+            .restore(&target.path(), &HashMap::new(), false)
+            .await;
+        assert!(matches!(result, Err(EscrowError::Integration(_))));
+
+        let report = manager
+            .restore(&target.path(), &HashMap::new(), true)
+            .await
+            .unwrap();
+        assert_eq!(report.restored_channels, 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_refuses_when_remote_is_newer() {
+        let backup_dir = TempDir::new();
+        let manager = BackupManager::new(BackupConfig {
+            backup_dir: backup_dir.path(),
+            ..BackupConfig::default()
+        })
+        .await
+        .unwrap();
+        manager
+            .record_monitor_update(update("chan_a", 1))
+            .await
+            .unwrap();
+
+        // A remote sink that saw a later update than the local directory
+        let mut newer = manager.manifest().await;
+        newer
+            .channels
+            .get_mut(&channel("chan_a"))
+            .unwrap()
+            .latest_update_id = 5;
+        let remote = Arc::new(MemorySink {
+            blobs: RwLock::new(HashMap::from([(
+                MANIFEST_KEY.to_string(),
+                serde_json::to_vec(&newer).unwrap(),
+            )])),
+        });
+        manager.add_remote_sink(remote).await;
+
+        let target = TempDir::new();
+        let result = manager
+            .restore(&target.path(), &HashMap::new(), false)
+            .await;
+        assert!(matches!(result, Err(EscrowError::Integration(_))));
+    }
+}
@@ -0,0 +1,724 @@
+//! Boltz - Typed client for the Boltz v2 REST API
+//!
+//! Covers the endpoints the escrow needs for on-chain funding and payouts:
+//! swap pairs (with their fees and limits), submarine and reverse swap
+//! creation, swap status, cooperative refund signatures, chain helpers, and
+//! the websocket stream of swap updates. Every
+//! failure (transport, timeout, non-2xx status, malformed body) surfaces as
+//! `EscrowError::ExternalApi`.
+
+use crate::{EscrowResult, error::EscrowError};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{collections::HashMap, time::Duration};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, tungstenite::Message};
+
+/// Asset symbol for bitcoin (both on-chain and Lightning) in Boltz pairs
+pub const BTC: &str = "BTC";
+
+/// Direction of a Boltz swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    /// On-chain -> Lightning
+    Submarine,
+    /// Lightning -> on-chain
+    Reverse,
+}
+
+impl SwapKind {
+    fn path(&self) -> &'static str {
+        match self {
+            SwapKind::Submarine => "/v2/swap/submarine",
+            SwapKind::Reverse => "/v2/swap/reverse",
+        }
+    }
+}
+
+/// Submarine swap pair information
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmarinePair {
+    pub hash: String,
+    pub rate: f64,
+    pub limits: SubmarinePairLimits,
+    pub fees: SubmarinePairFees,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmarinePairLimits {
+    pub minimal: u64,
+    pub maximal: u64,
+    #[serde(default)]
+    pub maximal_zero_conf: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmarinePairFees {
+    pub percentage: f64,
+    pub miner_fees: u64,
+}
+
+/// Reverse swap pair information
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReversePair {
+    pub hash: String,
+    pub rate: f64,
+    pub limits: ReversePairLimits,
+    pub fees: ReversePairFees,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReversePairLimits {
+    pub minimal: u64,
+    pub maximal: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReversePairFees {
+    pub percentage: f64,
+    pub miner_fees: ReverseMinerFees,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseMinerFees {
+    pub lockup: u64,
+    pub claim: u64,
+}
+
+/// Swap limits in sats, normalized across swap kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapLimits {
+    pub minimal_sats: u64,
+    pub maximal_sats: u64,
+}
+
+/// Swap fees, normalized across swap kinds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapFees {
+    /// Service fee as a percentage of the swap amount
+    pub percentage: f64,
+    /// Total miner fees charged by Boltz in sats
+    pub miner_fees_sats: u64,
+}
+
+impl SwapFees {
+    /// Total fee in sats for swapping `amount_sats`
+    pub fn total_for(&self, amount_sats: u64) -> u64 {
+        (amount_sats as f64 * self.percentage / 100.0).ceil() as u64 + self.miner_fees_sats
+    }
+}
+
+/// Leaf of a Boltz taproot swap tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapTreeLeaf {
+    pub version: u8,
+    pub output: String,
+}
+
+/// Taproot script tree of a swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapTree {
+    pub claim_leaf: SwapTreeLeaf,
+    pub refund_leaf: SwapTreeLeaf,
+}
+
+/// Request to create a submarine swap
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSubmarineSwapRequest {
+    pub from: String,
+    pub to: String,
+    /// Invoice Boltz pays once the on-chain lockup confirms
+    pub invoice: String,
+    pub refund_public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referral_id: Option<String>,
+}
+
+/// Created submarine swap
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmarineSwapResponse {
+    pub id: String,
+    pub address: String,
+    #[serde(default)]
+    pub bip21: Option<String>,
+    #[serde(default)]
+    pub swap_tree: Option<SwapTree>,
+    #[serde(default)]
+    pub claim_public_key: Option<String>,
+    pub timeout_block_height: u32,
+    #[serde(default)]
+    pub accept_zero_conf: bool,
+    pub expected_amount: u64,
+}
+
+/// Request to create a reverse swap
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReverseSwapRequest {
+    pub from: String,
+    pub to: String,
+    pub preimage_hash: String,
+    pub claim_public_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice_amount: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onchain_amount: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair_hash: Option<String>,
+    /// Destination address for Boltz's cooperative claim
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Created reverse swap
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseSwapResponse {
+    pub id: String,
+    pub invoice: String,
+    #[serde(default)]
+    pub swap_tree: Option<SwapTree>,
+    pub lockup_address: String,
+    #[serde(default)]
+    pub refund_public_key: Option<String>,
+    pub timeout_block_height: u32,
+    pub onchain_amount: u64,
+}
+
+/// On-chain transaction referenced by a swap status
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapTransaction {
+    pub id: String,
+    #[serde(default)]
+    pub hex: Option<String>,
+}
+
+/// Current status of a swap
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapStatusResponse {
+    pub status: String,
+    #[serde(default)]
+    pub zero_conf_rejected: Option<bool>,
+    #[serde(default)]
+    pub transaction: Option<SwapTransaction>,
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+}
+
+/// Swap status update, from the websocket stream or a status poll
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapUpdate {
+    pub id: String,
+    pub status: String,
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    #[serde(default)]
+    pub transaction: Option<SwapTransaction>,
+}
+
+/// Request for Boltz's partial signature on a cooperative refund
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundSignatureRequest {
+    /// Our MuSig2 public nonce (hex)
+    pub pub_nonce: String,
+    /// Unsigned refund transaction (hex)
+    pub transaction: String,
+    /// Index of the lockup input
+    pub index: u32,
+}
+
+/// Boltz's MuSig2 contribution to a cooperative refund
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialSignatureResponse {
+    pub pub_nonce: String,
+    pub partial_signature: String,
+}
+
+/// Lockup transaction of a submarine swap
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockupTransaction {
+    pub id: String,
+    pub hex: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BroadcastResponse {
+    id: String,
+}
+
+/// Websocket message envelope
+#[derive(Debug, Deserialize)]
+struct WsMessage {
+    event: String,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    args: Vec<serde_json::Value>,
+}
+
+/// Live stream of swap status updates
+pub struct SwapUpdateStream {
+    ws: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl SwapUpdateStream {
+    /// Subscribe to updates for the given swaps
+    ///
+    /// Boltz replies with the current status of each swap, so a fresh
+    /// subscription also catches up on anything missed while disconnected.
+    pub async fn subscribe(&mut self, swap_ids: &[String]) -> EscrowResult<()> {
+        let message = serde_json::json!({
+            "op": "subscribe",
+            "channel": "swap.update",
+            "args": swap_ids,
+        });
+        self.ws
+            .send(Message::Text(message.to_string()))
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Boltz websocket send failed: {}", e)))
+    }
+
+    /// Wait for the next batch of swap updates (`None` once the socket closes)
+    pub async fn next_updates(&mut self) -> EscrowResult<Option<Vec<SwapUpdate>>> {
+        while let Some(message) = self.ws.next().await {
+            let message = message
+                .map_err(|e| EscrowError::external_api(format!("Boltz websocket error: {}", e)))?;
+
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => return Ok(None),
+                _ => continue,
+            };
+
+            let Ok(envelope) = serde_json::from_str::<WsMessage>(&text) else {
+                continue;
+            };
+            if envelope.event != "update" || envelope.channel.as_deref() != Some("swap.update") {
+                continue;
+            }
+
+            let updates = envelope
+                .args
+                .into_iter()
+                .filter_map(|arg| serde_json::from_value(arg).ok())
+                .collect();
+            return Ok(Some(updates));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Error body returned by Boltz
+#[derive(Debug, Deserialize)]
+struct BoltzErrorBody {
+    error: String,
+}
+
+/// Boltz v2 REST client
+#[derive(Debug, Clone)]
+pub struct BoltzClient {
+    base_url: String,
+    timeout: Duration,
+    http: reqwest::Client,
+}
+
+impl BoltzClient {
+    /// Create a client for the given API base URL
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                EscrowError::external_api(format!("Failed to build Boltz client: {}", e))
+            })?;
+
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            timeout,
+            http,
+        })
+    }
+
+    /// API base URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// All submarine swap pairs (`from -> to -> pair`)
+    pub async fn get_submarine_pairs(
+        &self,
+    ) -> EscrowResult<HashMap<String, HashMap<String, SubmarinePair>>> {
+        self.get(SwapKind::Submarine.path()).await
+    }
+
+    /// All reverse swap pairs (`from -> to -> pair`)
+    pub async fn get_reverse_pairs(
+        &self,
+    ) -> EscrowResult<HashMap<String, HashMap<String, ReversePair>>> {
+        self.get(SwapKind::Reverse.path()).await
+    }
+
+    /// The BTC/BTC submarine pair
+    pub async fn get_submarine_pair(&self) -> EscrowResult<SubmarinePair> {
+        let mut pairs = self.get_submarine_pairs().await?;
+        pairs
+            .get_mut(BTC)
+            .and_then(|to| to.remove(BTC))
+            .ok_or_else(|| EscrowError::external_api("Boltz has no BTC/BTC submarine pair"))
+    }
+
+    /// The BTC/BTC reverse pair
+    pub async fn get_reverse_pair(&self) -> EscrowResult<ReversePair> {
+        let mut pairs = self.get_reverse_pairs().await?;
+        pairs
+            .get_mut(BTC)
+            .and_then(|to| to.remove(BTC))
+            .ok_or_else(|| EscrowError::external_api("Boltz has no BTC/BTC reverse pair"))
+    }
+
+    /// Current fees for a BTC/BTC swap
+    pub async fn get_fees(&self, kind: SwapKind) -> EscrowResult<SwapFees> {
+        Ok(match kind {
+            SwapKind::Submarine => {
+                let fees = self.get_submarine_pair().await?.fees;
+                SwapFees {
+                    percentage: fees.percentage,
+                    miner_fees_sats: fees.miner_fees,
+                }
+            }
+            SwapKind::Reverse => {
+                let fees = self.get_reverse_pair().await?.fees;
+                SwapFees {
+                    percentage: fees.percentage,
+                    miner_fees_sats: fees.miner_fees.lockup + fees.miner_fees.claim,
+                }
+            }
+        })
+    }
+
+    /// Current amount limits for a BTC/BTC swap
+    pub async fn get_limits(&self, kind: SwapKind) -> EscrowResult<SwapLimits> {
+        let (minimal_sats, maximal_sats) = match kind {
+            SwapKind::Submarine => {
+                let limits = self.get_submarine_pair().await?.limits;
+                (limits.minimal, limits.maximal)
+            }
+            SwapKind::Reverse => {
+                let limits = self.get_reverse_pair().await?.limits;
+                (limits.minimal, limits.maximal)
+            }
+        };
+
+        Ok(SwapLimits {
+            minimal_sats,
+            maximal_sats,
+        })
+    }
+
+    /// Create a submarine swap (on-chain -> Lightning)
+    pub async fn create_submarine_swap(
+        &self,
+        request: &CreateSubmarineSwapRequest,
+    ) -> EscrowResult<SubmarineSwapResponse> {
+        self.post(SwapKind::Submarine.path(), request).await
+    }
+
+    /// Create a reverse swap (Lightning -> on-chain)
+    pub async fn create_reverse_swap(
+        &self,
+        request: &CreateReverseSwapRequest,
+    ) -> EscrowResult<ReverseSwapResponse> {
+        self.post(SwapKind::Reverse.path(), request).await
+    }
+
+    /// Get the status of a swap
+    pub async fn get_swap_status(&self, swap_id: &str) -> EscrowResult<SwapStatusResponse> {
+        self.get(&format!("/v2/swap/{}", swap_id)).await
+    }
+
+    /// Ask Boltz to co-sign a key-path refund of a failed submarine swap
+    pub async fn get_refund_signature(
+        &self,
+        swap_id: &str,
+        request: &RefundSignatureRequest,
+    ) -> EscrowResult<PartialSignatureResponse> {
+        self.post(
+            &format!("{}/{}/refund", SwapKind::Submarine.path(), swap_id),
+            request,
+        )
+        .await
+    }
+
+    /// Lockup transaction of a swap (sent by the payer for submarine swaps,
+    /// by Boltz for reverse swaps)
+    pub async fn get_lockup_transaction(
+        &self,
+        kind: SwapKind,
+        swap_id: &str,
+    ) -> EscrowResult<LockupTransaction> {
+        self.get(&format!("{}/{}/transaction", kind.path(), swap_id))
+            .await
+    }
+
+    /// Current bitcoin block height as seen by Boltz
+    pub async fn get_block_height(&self) -> EscrowResult<u32> {
+        let heights: HashMap<String, u32> = self.get("/v2/chain/heights").await?;
+        heights
+            .get(BTC)
+            .copied()
+            .ok_or_else(|| EscrowError::external_api("Boltz returned no BTC block height"))
+    }
+
+    /// Broadcast a raw bitcoin transaction, returning its txid
+    pub async fn broadcast_transaction(&self, tx_hex: &str) -> EscrowResult<String> {
+        let body = serde_json::json!({ "hex": tx_hex });
+        let response: BroadcastResponse = self
+            .post(&format!("/v2/chain/{}/transaction", BTC), &body)
+            .await?;
+        Ok(response.id)
+    }
+
+    /// Websocket endpoint derived from the API base URL
+    pub fn websocket_url(&self) -> String {
+        let url = if let Some(rest) = self.base_url.strip_prefix("https://") {
+            format!("wss://{}", rest)
+        } else if let Some(rest) = self.base_url.strip_prefix("http://") {
+            format!("ws://{}", rest)
+        } else {
+            self.base_url.clone()
+        };
+        format!("{}/v2/ws", url)
+    }
+
+    /// Open the swap update websocket
+    pub async fn connect_swap_updates(&self) -> EscrowResult<SwapUpdateStream> {
+        let url = self.websocket_url();
+        let (ws, _) = tokio::time::timeout(self.timeout, tokio_tungstenite::connect_async(&url))
+            .await
+            .map_err(|_| EscrowError::external_api(format!("Boltz websocket {} timed out", url)))?
+            .map_err(|e| {
+                EscrowError::external_api(format!("Boltz websocket {} failed: {}", url, e))
+            })?;
+
+        Ok(SwapUpdateStream { ws })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> EscrowResult<T> {
+        let result = self.http.get(self.url(path)).send().await;
+        self.handle_response(path, result).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> EscrowResult<T> {
+        let result = self.http.post(self.url(path)).json(body).send().await;
+        self.handle_response(path, result).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    async fn handle_response<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        result: Result<reqwest::Response, reqwest::Error>,
+    ) -> EscrowResult<T> {
+        let response = result.map_err(|e| self.transport_error(path, e))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| self.transport_error(path, e))?;
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<BoltzErrorBody>(&body)
+                .map(|b| b.error)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&body).to_string());
+            return Err(EscrowError::external_api(format!(
+                "Boltz {} returned {}: {}",
+                path,
+                status.as_u16(),
+                message
+            )));
+        }
+
+        serde_json::from_slice(&body).map_err(|e| {
+            EscrowError::external_api(format!("Invalid Boltz response from {}: {}", path, e))
+        })
+    }
+
+    fn transport_error(&self, path: &str, error: reqwest::Error) -> EscrowError {
+        if error.is_timeout() {
+            EscrowError::external_api(format!(
+                "Boltz {} timed out after {}ms",
+                path,
+                self.timeout.as_millis()
+            ))
+        } else {
+            EscrowError::external_api(format!("Boltz {} request failed: {}", path, error))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use serde_json::json;
+
+    async fn mock_boltz() -> MockHttpServer {
+        MockHttpServer::start(
+            |request| match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/v2/swap/submarine") => MockResponse::json(
+                    200,
+                    json!({ "BTC": { "BTC": {
+                        "hash": "sub_hash",
+                        "rate": 1,
+                        "limits": { "minimal": 1000, "maximal": 25000000, "maximalZeroConf": 0 },
+                        "fees": { "percentage": 0.1, "minerFees": 300 }
+                    }}}),
+                ),
+                ("GET", "/v2/swap/reverse") => MockResponse::json(
+                    200,
+                    json!({ "BTC": { "BTC": {
+                        "hash": "rev_hash",
+                        "rate": 1,
+                        "limits": { "minimal": 1000, "maximal": 25000000 },
+                        "fees": { "percentage": 0.25, "minerFees": { "lockup": 250, "claim": 150 } }
+                    }}}),
+                ),
+                ("POST", "/v2/swap/submarine") => {
+                    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                    if body["invoice"] == "lnbc_bad" {
+                        return MockResponse::json(400, json!({ "error": "invalid invoice" }));
+                    }
+                    MockResponse::json(
+                        201,
+                        json!({
+                            "id": "sub123",
+                            "address": "bc1pswapaddress",
+                            "bip21": "bitcoin:bc1pswapaddress?amount=0.001003",
+                            "claimPublicKey": "02aa",
+                            "swapTree": {
+                                "claimLeaf": { "version": 192, "output": "a914" },
+                                "refundLeaf": { "version": 192, "output": "20ab" }
+                            },
+                            "timeoutBlockHeight": 850000,
+                            "acceptZeroConf": false,
+                            "expectedAmount": 100400
+                        }),
+                    )
+                }
+                ("GET", "/v2/swap/sub123") => MockResponse::json(
+                    200,
+                    json!({ "status": "transaction.mempool", "transaction": { "id": "txid1" } }),
+                ),
+                _ => MockResponse::json(404, json!({ "error": "not found" })),
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_pairs_fees_and_limits() {
+        let server = mock_boltz().await;
+        let client = BoltzClient::new(server.url(), Duration::from_secs(5)).unwrap();
+
+        let fees = client.get_fees(SwapKind::Reverse).await.unwrap();
+        assert_eq!(fees.miner_fees_sats, 400);
+        assert_eq!(fees.total_for(100_000), 650);
+
+        let limits = client.get_limits(SwapKind::Submarine).await.unwrap();
+        assert_eq!(limits.minimal_sats, 1000);
+        assert_eq!(limits.maximal_sats, 25_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_submarine_swap_and_status() {
+        let server = mock_boltz().await;
+        let client = BoltzClient::new(server.url(), Duration::from_secs(5)).unwrap();
+
+        let swap = client
+            .create_submarine_swap(&CreateSubmarineSwapRequest {
+                from: BTC.to_string(),
+                to: BTC.to_string(),
+                invoice: "lnbc1000u1pvjluez".to_string(),
+                refund_public_key: "03bb".to_string(),
+                pair_hash: Some("sub_hash".to_string()),
+                referral_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(swap.id, "sub123");
+        assert_eq!(swap.expected_amount, 100400);
+        assert_eq!(swap.swap_tree.unwrap().refund_leaf.output, "20ab");
+
+        let requests = server.requests().await;
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(body["refundPublicKey"], "03bb");
+        assert_eq!(body["pairHash"], "sub_hash");
+        assert!(body.get("referralId").is_none());
+
+        let status = client.get_swap_status("sub123").await.unwrap();
+        assert_eq!(status.status, "transaction.mempool");
+        assert_eq!(status.transaction.unwrap().id, "txid1");
+    }
+
+    #[tokio::test]
+    async fn test_error_mapping() {
+        let server = mock_boltz().await;
+        let client = BoltzClient::new(server.url(), Duration::from_secs(5)).unwrap();
+
+        let err = client
+            .create_submarine_swap(&CreateSubmarineSwapRequest {
+                from: BTC.to_string(),
+                to: BTC.to_string(),
+                invoice: "lnbc_bad".to_string(),
+                refund_public_key: "03bb".to_string(),
+                pair_hash: None,
+                referral_id: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, EscrowError::ExternalApi(msg) if msg.contains("400: invalid invoice"))
+        );
+
+        assert!(matches!(
+            client.get_swap_status("missing").await,
+            Err(EscrowError::ExternalApi(_))
+        ));
+
+        // A server that accepts connections but never answers
+        let silent = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", silent.local_addr().unwrap());
+        let client = BoltzClient::new(url, Duration::from_millis(200)).unwrap();
+        let err = client.get_swap_status("sub123").await.unwrap_err();
+        assert!(matches!(&err, EscrowError::ExternalApi(msg) if msg.contains("timed out")));
+    }
+}
@@ -0,0 +1,1158 @@
+//! Cashu - Ecash tokens and a typed client for Cashu mints
+//!
+//! Covers what the escrow needs to hold ecash: V3 tokens (NUT-00), the
+//! blind signature scheme and DLEQ proofs of mint signatures (NUT-00/12),
+//! `P2PK` spending conditions and their signatures (NUT-10/11), and the mint
+//! endpoints for keysets, swaps and proof states (NUT-01/02/03/07). Mint
+//! failures surface as `EscrowError::ExternalApi`; malformed or invalid
+//! tokens as `EscrowError::Payment`.
+
+use crate::{EscrowResult, error::EscrowError};
+use bitcoin::base64::{
+    Engine, engine::general_purpose::URL_SAFE, engine::general_purpose::URL_SAFE_NO_PAD,
+};
+use secp256k1::{
+    Keypair, Message, PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey, schnorr,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, str::FromStr, time::Duration};
+
+/// Currency unit of the keysets the escrow accepts
+pub const UNIT_SAT: &str = "sat";
+
+/// Prefix of serialized V3 tokens
+const TOKEN_V3_PREFIX: &str = "cashuA";
+
+/// Domain separator of `hash_to_curve`
+const HASH_TO_CURVE_DOMAIN: &[u8] = b"Secp256k1_HashToCurve_Cashu_";
+
+/// NUT-10 kind of pay-to-public-key secrets
+const P2PK_KIND: &str = "P2PK";
+
+/// DLEQ proof that a mint signed with its published key (NUT-12)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DleqProof {
+    pub e: String,
+    pub s: String,
+    /// Blinding factor, only in proofs held by a wallet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r: Option<String>,
+}
+
+/// Unblinded mint signature on a secret: one piece of ecash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    pub amount: u64,
+    /// Keyset the mint signed with
+    pub id: String,
+    pub secret: String,
+    #[serde(rename = "C")]
+    pub c: String,
+    /// Witness (JSON) fulfilling the secret's spending conditions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<DleqProof>,
+}
+
+/// Blinded secret sent to the mint for signing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlindedMessage {
+    pub amount: u64,
+    pub id: String,
+    #[serde(rename = "B_")]
+    pub b: String,
+}
+
+/// Mint signature on a blinded secret
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlindSignature {
+    pub amount: u64,
+    pub id: String,
+    #[serde(rename = "C_")]
+    pub c: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dleq: Option<DleqProof>,
+}
+
+/// Proofs of one mint within a token
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenEntry {
+    pub mint: String,
+    pub proofs: Vec<Proof>,
+}
+
+/// Cashu token (V3, serialized as `cashuA...`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    pub token: Vec<TokenEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+impl Token {
+    /// Token of sat `proofs` from `mint_url`
+    pub fn new(mint_url: impl Into<String>, proofs: Vec<Proof>, memo: Option<String>) -> Self {
+        Self {
+            token: vec![TokenEntry {
+                mint: mint_url.into(),
+                proofs,
+            }],
+            unit: Some(UNIT_SAT.to_string()),
+            memo,
+        }
+    }
+
+    /// Parse a serialized V3 token (`cashuA...`, optionally as a `cashu:` URI)
+    pub fn decode(encoded: &str) -> EscrowResult<Self> {
+        let encoded = encoded.trim();
+        let encoded = encoded.strip_prefix("cashu:").unwrap_or(encoded);
+        let payload = encoded
+            .strip_prefix(TOKEN_V3_PREFIX)
+            .ok_or_else(|| EscrowError::payment("Unsupported Cashu token version"))?;
+
+        // Wallets differ on padding and the base64 alphabet
+        let payload = payload
+            .trim_end_matches('=')
+            .replace('+', "-")
+            .replace('/', "_");
+        let json = URL_SAFE_NO_PAD
+            .decode(payload)
+            .map_err(|e| EscrowError::payment(format!("Invalid Cashu token encoding: {}", e)))?;
+        let token: Token = serde_json::from_slice(&json)
+            .map_err(|e| EscrowError::payment(format!("Invalid Cashu token: {}", e)))?;
+        if token.token.iter().all(|entry| entry.proofs.is_empty()) {
+            return Err(EscrowError::payment("Cashu token has no proofs"));
+        }
+        Ok(token)
+    }
+
+    /// Serialize as a V3 token
+    pub fn encode(&self) -> EscrowResult<String> {
+        Ok(format!(
+            "{}{}",
+            TOKEN_V3_PREFIX,
+            URL_SAFE.encode(serde_json::to_vec(self)?)
+        ))
+    }
+
+    /// The mint of every proof; tokens spanning mints are refused
+    pub fn mint_url(&self) -> EscrowResult<&str> {
+        let mut mints = self
+            .token
+            .iter()
+            .map(|entry| entry.mint.trim_end_matches('/'));
+        let first = mints
+            .next()
+            .ok_or_else(|| EscrowError::payment("Cashu token has no proofs"))?;
+        if mints.any(|mint| mint != first) {
+            return Err(EscrowError::payment("Cashu token spans several mints"));
+        }
+        Ok(first)
+    }
+
+    /// All proofs of the token
+    pub fn proofs(&self) -> impl Iterator<Item = &Proof> {
+        self.token.iter().flat_map(|entry| entry.proofs.iter())
+    }
+
+    /// Total amount of the token
+    pub fn amount(&self) -> u64 {
+        self.proofs().map(|proof| proof.amount).sum()
+    }
+}
+
+/// Pay-to-public-key spending conditions of a proof (NUT-11)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P2pkConditions {
+    /// Key whose signature spends the proof
+    pub pubkey: PublicKey,
+    /// Unix time from which the refund keys can spend the proof as well
+    pub locktime: Option<u64>,
+    /// Keys allowed to spend the proof after `locktime`
+    pub refund_keys: Vec<PublicKey>,
+}
+
+impl P2pkConditions {
+    /// Well-known secret (NUT-10) locking a new proof to these conditions
+    pub fn to_secret(&self) -> EscrowResult<String> {
+        let mut tags = Vec::new();
+        if let Some(locktime) = self.locktime {
+            tags.push(vec!["locktime".to_string(), locktime.to_string()]);
+        }
+        if !self.refund_keys.is_empty() {
+            let mut refund = vec!["refund".to_string()];
+            refund.extend(self.refund_keys.iter().map(PublicKey::to_string));
+            tags.push(refund);
+        }
+        let secret = serde_json::json!([
+            P2PK_KIND,
+            {
+                "nonce": hex::encode(random_bytes()?),
+                "data": self.pubkey.to_string(),
+                "tags": tags,
+            }
+        ]);
+        Ok(secret.to_string())
+    }
+
+    /// Conditions of a proof's secret, `None` for secrets that are not `P2PK`
+    ///
+    /// Conditions the escrow cannot fulfil alone (several signatures, or
+    /// signatures over the outputs) are refused.
+    pub fn from_secret(secret: &str) -> EscrowResult<Option<Self>> {
+        let Ok((kind, body)) = serde_json::from_str::<(String, WellKnownSecret)>(secret) else {
+            return Ok(None);
+        };
+        if kind != P2PK_KIND {
+            return Ok(None);
+        }
+
+        let invalid =
+            |reason: &str| EscrowError::payment(format!("Invalid P2PK secret: {}", reason));
+        let pubkey = parse_pubkey(&body.data).map_err(|_| invalid("bad public key"))?;
+        let mut conditions = Self {
+            pubkey,
+            locktime: None,
+            refund_keys: Vec::new(),
+        };
+        for tag in &body.tags {
+            let Some((name, values)) = tag.split_first() else {
+                continue;
+            };
+            match name.as_str() {
+                "locktime" => {
+                    let locktime = values.first().and_then(|value| value.parse().ok());
+                    conditions.locktime = Some(locktime.ok_or_else(|| invalid("bad locktime"))?);
+                }
+                "refund" => {
+                    for key in values {
+                        conditions
+                            .refund_keys
+                            .push(parse_pubkey(key).map_err(|_| invalid("bad refund key"))?);
+                    }
+                }
+                "n_sigs" if values.first().is_some_and(|n| n != "1") => {
+                    return Err(invalid("more than one signature required"));
+                }
+                "sigflag" if values.first().is_some_and(|flag| flag != "SIG_INPUTS") => {
+                    return Err(invalid("only SIG_INPUTS is supported"));
+                }
+                _ => {}
+            }
+        }
+        Ok(Some(conditions))
+    }
+}
+
+/// Body of a NUT-10 well-known secret
+#[derive(Debug, Deserialize)]
+struct WellKnownSecret {
+    data: String,
+    #[serde(default)]
+    tags: Vec<Vec<String>>,
+}
+
+/// Parse a Cashu public key: compressed (33 bytes) or x-only (32 bytes, even Y) hex
+pub fn parse_pubkey(key: &str) -> EscrowResult<PublicKey> {
+    let key = key.trim();
+    let parsed = if key.len() == 64 {
+        XOnlyPublicKey::from_str(key).map(|x_only| x_only.public_key(secp256k1::Parity::Even))
+    } else {
+        PublicKey::from_str(key)
+    };
+    parsed.map_err(|e| EscrowError::payment(format!("Invalid Cashu public key {}: {}", key, e)))
+}
+
+/// Map a secret to a curve point (`hash_to_curve` of NUT-00)
+pub fn hash_to_curve(message: &[u8]) -> EscrowResult<PublicKey> {
+    let message_hash = Sha256::new()
+        .chain_update(HASH_TO_CURVE_DOMAIN)
+        .chain_update(message)
+        .finalize();
+    for counter in 0u32..1 << 16 {
+        let hash = Sha256::new()
+            .chain_update(message_hash)
+            .chain_update(counter.to_le_bytes())
+            .finalize();
+        let mut candidate = [0x02; 33];
+        candidate[1..].copy_from_slice(&hash);
+        if let Ok(point) = PublicKey::from_slice(&candidate) {
+            return Ok(point);
+        }
+    }
+    Err(EscrowError::crypto("No curve point found for Cashu secret"))
+}
+
+/// Id of a keyset derived from its keys (NUT-02, version 00)
+pub fn keyset_id(keys: &BTreeMap<u64, PublicKey>) -> String {
+    let mut hasher = Sha256::new();
+    for key in keys.values() {
+        hasher.update(key.serialize());
+    }
+    format!("00{}", &hex::encode(hasher.finalize())[..14])
+}
+
+/// Split an amount into the power-of-two denominations of ecash
+pub fn split_amount(amount: u64) -> Vec<u64> {
+    (0..u64::BITS)
+        .map(|bit| 1u64 << bit)
+        .filter(|denomination| amount & denomination != 0)
+        .collect()
+}
+
+/// Check a proof's DLEQ proof against the mint's key for its amount (NUT-12)
+///
+/// Proves offline that the mint signed the proof; whether it is still
+/// unspent takes a `check_state` call.
+pub fn verify_proof_dleq(proof: &Proof, mint_key: &PublicKey) -> EscrowResult<()> {
+    let invalid =
+        || EscrowError::payment(format!("Invalid DLEQ proof for {} sat proof", proof.amount));
+    let dleq = proof
+        .dleq
+        .as_ref()
+        .ok_or_else(|| EscrowError::payment("Cashu proofs must carry DLEQ proofs"))?;
+    let blinding_factor = dleq
+        .r
+        .as_deref()
+        .ok_or_else(invalid)
+        .and_then(|r| parse_secret(r).map_err(|_| invalid()))?;
+
+    // Reconstruct what the mint signed: B' = Y + rG and C' = C + rK
+    let secp = Secp256k1::new();
+    let y = hash_to_curve(proof.secret.as_bytes())?;
+    let c = parse_point(&proof.c).map_err(|_| invalid())?;
+    let blinding = Scalar::from(blinding_factor);
+    let blinded = y
+        .combine(&PublicKey::from_secret_key(&secp, &blinding_factor))
+        .map_err(|_| invalid())?;
+    let signed = mint_key
+        .mul_tweak(&secp, &blinding)
+        .and_then(|r_k| c.combine(&r_k))
+        .map_err(|_| invalid())?;
+
+    if verify_blind_dleq(&blinded, &signed, mint_key, dleq) {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Check `C' = kB'` for the mint key `K = kG` through the DLEQ proof `(e, s)`
+fn verify_blind_dleq(
+    blinded: &PublicKey,
+    signed: &PublicKey,
+    mint_key: &PublicKey,
+    dleq: &DleqProof,
+) -> bool {
+    let secp = Secp256k1::new();
+    let check = || -> Option<bool> {
+        let e: [u8; 32] = hex::decode(&dleq.e).ok()?.try_into().ok()?;
+        let e_scalar = Scalar::from_be_bytes(e).ok()?;
+        let s = parse_secret(&dleq.s).ok()?;
+
+        // R1 = sG - eK, R2 = sB' - eC'
+        let r1 = PublicKey::from_secret_key(&secp, &s)
+            .combine(&mint_key.mul_tweak(&secp, &e_scalar).ok()?.negate(&secp))
+            .ok()?;
+        let r2 = blinded
+            .mul_tweak(&secp, &Scalar::from(s))
+            .ok()?
+            .combine(&signed.mul_tweak(&secp, &e_scalar).ok()?.negate(&secp))
+            .ok()?;
+        Some(hash_e(&[r1, r2, *mint_key, *signed]) == e)
+    };
+    check().unwrap_or(false)
+}
+
+/// Challenge of a DLEQ proof: SHA-256 of the points' uncompressed hex
+fn hash_e(points: &[PublicKey]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for point in points {
+        hasher.update(hex::encode(point.serialize_uncompressed()).as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+/// Sign a proof's secret with `secret_key`, fulfilling its `P2PK` condition
+pub fn sign_p2pk(proof: &mut Proof, secret_key: &SecretKey) {
+    let secp = Secp256k1::new();
+    let signature = secp.sign_schnorr_no_aux_rand(
+        &secret_message(&proof.secret),
+        &Keypair::from_secret_key(&secp, secret_key),
+    );
+    proof.witness = Some(serde_json::json!({ "signatures": [signature.to_string()] }).to_string());
+}
+
+/// Whether a proof's witness holds a signature of its secret by `pubkey`
+pub fn verify_p2pk_witness(proof: &Proof, pubkey: &PublicKey) -> bool {
+    #[derive(Deserialize)]
+    struct Witness {
+        signatures: Vec<String>,
+    }
+
+    let Some(witness) = proof
+        .witness
+        .as_deref()
+        .and_then(|witness| serde_json::from_str::<Witness>(witness).ok())
+    else {
+        return false;
+    };
+    let secp = Secp256k1::new();
+    let message = secret_message(&proof.secret);
+    let (x_only, _) = pubkey.x_only_public_key();
+    witness.signatures.iter().any(|signature| {
+        schnorr::Signature::from_str(signature)
+            .is_ok_and(|signature| secp.verify_schnorr(&signature, &message, &x_only).is_ok())
+    })
+}
+
+/// Message signed by `P2PK` witnesses: the SHA-256 of the secret
+fn secret_message(secret: &str) -> Message {
+    Message::from_digest(Sha256::digest(secret.as_bytes()).into())
+}
+
+/// Output requested from a mint, with what is needed to unblind its signature
+#[derive(Debug, Clone)]
+pub struct PreparedOutput {
+    pub message: BlindedMessage,
+    pub secret: String,
+    pub blinding_factor: SecretKey,
+}
+
+impl PreparedOutput {
+    /// Blind `secret` for a proof of `amount` in keyset `keyset_id`
+    pub fn new(amount: u64, keyset_id: &str, secret: String) -> EscrowResult<Self> {
+        let secp = Secp256k1::new();
+        let blinding_factor = random_secret_key()?;
+        let blinded = hash_to_curve(secret.as_bytes())?
+            .combine(&PublicKey::from_secret_key(&secp, &blinding_factor))
+            .map_err(|e| EscrowError::crypto(format!("Blinding failed: {}", e)))?;
+        Ok(Self {
+            message: BlindedMessage {
+                amount,
+                id: keyset_id.to_string(),
+                b: blinded.to_string(),
+            },
+            secret,
+            blinding_factor,
+        })
+    }
+
+    /// Unblind the mint's signature into a proof
+    ///
+    /// The signature's DLEQ proof, when the mint sends one, is checked and
+    /// kept so the proof can be verified by whoever receives it.
+    pub fn unblind(self, signature: &BlindSignature, mint_key: &PublicKey) -> EscrowResult<Proof> {
+        let invalid =
+            |reason: &str| EscrowError::external_api(format!("Invalid mint signature: {}", reason));
+        if signature.amount != self.message.amount || signature.id != self.message.id {
+            return Err(invalid("amount or keyset differs from the request"));
+        }
+        let secp = Secp256k1::new();
+        let signed = parse_point(&signature.c).map_err(|_| invalid("bad point"))?;
+        if let Some(dleq) = &signature.dleq {
+            let blinded = parse_point(&self.message.b)?;
+            if !verify_blind_dleq(&blinded, &signed, mint_key, dleq) {
+                return Err(invalid("DLEQ proof does not verify"));
+            }
+        }
+
+        // C = C' - rK
+        let c = mint_key
+            .mul_tweak(&secp, &Scalar::from(self.blinding_factor))
+            .and_then(|r_k| signed.combine(&r_k.negate(&secp)))
+            .map_err(|_| invalid("unblinding failed"))?;
+        Ok(Proof {
+            amount: self.message.amount,
+            id: self.message.id,
+            secret: self.secret,
+            c: c.to_string(),
+            witness: None,
+            dleq: signature.dleq.as_ref().map(|dleq| DleqProof {
+                e: dleq.e.clone(),
+                s: dleq.s.clone(),
+                r: Some(hex::encode(self.blinding_factor.secret_bytes())),
+            }),
+        })
+    }
+}
+
+/// Keyset summary from `/v1/keysets`
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeysetInfo {
+    pub id: String,
+    pub unit: String,
+    pub active: bool,
+    /// Fee per input, in thousandths of a sat (NUT-02)
+    #[serde(default)]
+    pub input_fee_ppk: u64,
+}
+
+/// Public keys of a keyset, by amount
+#[derive(Debug, Clone)]
+pub struct MintKeyset {
+    pub id: String,
+    pub unit: String,
+    pub keys: BTreeMap<u64, PublicKey>,
+}
+
+/// State of a proof at the mint (NUT-07)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ProofState {
+    Unspent,
+    Pending,
+    Spent,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysetsResponse {
+    keysets: Vec<KeysetInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeysResponse {
+    keysets: Vec<RawKeyset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawKeyset {
+    id: String,
+    unit: String,
+    keys: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckStateResponse {
+    states: Vec<ProofStateEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProofStateEntry {
+    #[serde(rename = "Y")]
+    y: String,
+    state: ProofState,
+}
+
+#[derive(Debug, Deserialize)]
+struct SwapResponse {
+    signatures: Vec<BlindSignature>,
+}
+
+/// Error body returned by Cashu mints
+#[derive(Debug, Deserialize)]
+struct MintErrorBody {
+    detail: String,
+}
+
+/// Cashu mint REST client
+#[derive(Debug, Clone)]
+pub struct CashuMintClient {
+    mint_url: String,
+    timeout: Duration,
+    http: reqwest::Client,
+}
+
+impl CashuMintClient {
+    /// Create a client for the mint at `mint_url`
+    pub fn new(mint_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                EscrowError::external_api(format!("Failed to build Cashu mint client: {}", e))
+            })?;
+
+        Ok(Self {
+            mint_url: mint_url.into().trim_end_matches('/').to_string(),
+            timeout,
+            http,
+        })
+    }
+
+    /// Mint URL, as it appears in tokens
+    pub fn mint_url(&self) -> &str {
+        &self.mint_url
+    }
+
+    /// All keysets of the mint
+    pub async fn get_keysets(&self) -> EscrowResult<Vec<KeysetInfo>> {
+        let response: KeysetsResponse = self.get("/v1/keysets").await?;
+        Ok(response.keysets)
+    }
+
+    /// Public keys of a keyset, checked against its id
+    pub async fn get_keyset(&self, id: &str) -> EscrowResult<MintKeyset> {
+        let path = format!("/v1/keys/{}", id);
+        let response: KeysResponse = self.get(&path).await?;
+        let raw = response
+            .keysets
+            .into_iter()
+            .find(|keyset| keyset.id == id)
+            .ok_or_else(|| EscrowError::external_api(format!("Mint returned no keyset {}", id)))?;
+
+        let mut keys = BTreeMap::new();
+        for (amount, key) in &raw.keys {
+            let amount: u64 = amount.parse().map_err(|_| {
+                EscrowError::external_api(format!("Invalid amount {} in keyset {}", amount, id))
+            })?;
+            let key = PublicKey::from_str(key).map_err(|e| {
+                EscrowError::external_api(format!("Invalid key in keyset {}: {}", id, e))
+            })?;
+            keys.insert(amount, key);
+        }
+        // Version 00 ids commit to the keys; never trust keys that do not match
+        if raw.id.starts_with("00") && keyset_id(&keys) != raw.id {
+            return Err(EscrowError::external_api(format!(
+                "Keys of keyset {} do not match its id",
+                raw.id
+            )));
+        }
+
+        Ok(MintKeyset {
+            id: raw.id,
+            unit: raw.unit,
+            keys,
+        })
+    }
+
+    /// States of `proofs` at the mint, in order
+    pub async fn check_state(&self, proofs: &[Proof]) -> EscrowResult<Vec<ProofState>> {
+        let ys = proofs
+            .iter()
+            .map(|proof| Ok(hash_to_curve(proof.secret.as_bytes())?.to_string()))
+            .collect::<EscrowResult<Vec<_>>>()?;
+        let response: CheckStateResponse = self
+            .post("/v1/checkstate", &serde_json::json!({ "Ys": ys }))
+            .await?;
+
+        ys.iter()
+            .map(|y| {
+                response
+                    .states
+                    .iter()
+                    .find(|entry| entry.y == *y)
+                    .map(|entry| entry.state)
+                    .ok_or_else(|| EscrowError::external_api("Mint returned no state for a proof"))
+            })
+            .collect()
+    }
+
+    /// Swap `inputs` for signatures on `outputs` (NUT-03)
+    pub async fn swap(
+        &self,
+        inputs: &[Proof],
+        outputs: &[BlindedMessage],
+    ) -> EscrowResult<Vec<BlindSignature>> {
+        let response: SwapResponse = self
+            .post(
+                "/v1/swap",
+                &serde_json::json!({ "inputs": inputs, "outputs": outputs }),
+            )
+            .await?;
+        if response.signatures.len() != outputs.len() {
+            return Err(EscrowError::external_api(format!(
+                "Mint returned {} signatures for {} outputs",
+                response.signatures.len(),
+                outputs.len()
+            )));
+        }
+        Ok(response.signatures)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> EscrowResult<T> {
+        let result = self.http.get(self.url(path)).send().await;
+        self.handle_response(path, result).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> EscrowResult<T> {
+        let result = self.http.post(self.url(path)).json(body).send().await;
+        self.handle_response(path, result).await
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.mint_url, path)
+    }
+
+    async fn handle_response<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        result: Result<reqwest::Response, reqwest::Error>,
+    ) -> EscrowResult<T> {
+        let response = result.map_err(|e| self.transport_error(path, e))?;
+        let status = response.status();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| self.transport_error(path, e))?;
+
+        if !status.is_success() {
+            let message = serde_json::from_slice::<MintErrorBody>(&body)
+                .map(|b| b.detail)
+                .unwrap_or_else(|_| String::from_utf8_lossy(&body).to_string());
+            return Err(EscrowError::external_api(format!(
+                "Cashu mint {} returned {}: {}",
+                path,
+                status.as_u16(),
+                message
+            )));
+        }
+
+        serde_json::from_slice(&body).map_err(|e| {
+            EscrowError::external_api(format!("Invalid Cashu mint response from {}: {}", path, e))
+        })
+    }
+
+    fn transport_error(&self, path: &str, error: reqwest::Error) -> EscrowError {
+        if error.is_timeout() {
+            EscrowError::external_api(format!(
+                "Cashu mint {} timed out after {}ms",
+                path,
+                self.timeout.as_millis()
+            ))
+        } else {
+            EscrowError::external_api(format!("Cashu mint {} request failed: {}", path, error))
+        }
+    }
+}
+
+fn parse_point(hex_point: &str) -> EscrowResult<PublicKey> {
+    PublicKey::from_str(hex_point)
+        .map_err(|e| EscrowError::payment(format!("Invalid curve point: {}", e)))
+}
+
+fn parse_secret(hex_scalar: &str) -> EscrowResult<SecretKey> {
+    SecretKey::from_str(hex_scalar)
+        .map_err(|e| EscrowError::payment(format!("Invalid scalar: {}", e)))
+}
+
+/// 32 bytes from the OS random number generator
+fn random_bytes() -> EscrowResult<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| EscrowError::crypto(format!("Random number generation failed: {}", e)))?;
+    Ok(bytes)
+}
+
+fn random_secret_key() -> EscrowResult<SecretKey> {
+    SecretKey::from_slice(&random_bytes()?)
+        .map_err(|e| EscrowError::crypto(format!("Invalid secret key: {}", e)))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use serde_json::json;
+    use std::{
+        collections::HashSet,
+        sync::{Arc, Mutex},
+    };
+
+    /// Keys and spent proofs of the local mint
+    struct MintState {
+        id: String,
+        secret_keys: BTreeMap<u64, SecretKey>,
+        input_fee_ppk: u64,
+        spent: Mutex<HashSet<String>>,
+    }
+
+    impl MintState {
+        fn public_keys(&self) -> BTreeMap<u64, PublicKey> {
+            let secp = Secp256k1::new();
+            self.secret_keys
+                .iter()
+                .map(|(amount, key)| (*amount, PublicKey::from_secret_key(&secp, key)))
+                .collect()
+        }
+
+        /// Sign a blinded message with a DLEQ proof, as NUT-12 mints do
+        fn sign(&self, message: &BlindedMessage) -> Result<BlindSignature, String> {
+            let secp = Secp256k1::new();
+            let key = self
+                .secret_keys
+                .get(&message.amount)
+                .ok_or("unknown amount")?;
+            let blinded = PublicKey::from_str(&message.b).map_err(|e| e.to_string())?;
+            let signed = blinded.mul_tweak(&secp, &Scalar::from(*key)).unwrap();
+
+            let nonce = random_secret_key().unwrap();
+            let r1 = PublicKey::from_secret_key(&secp, &nonce);
+            let r2 = blinded.mul_tweak(&secp, &Scalar::from(nonce)).unwrap();
+            let e = hash_e(&[r1, r2, PublicKey::from_secret_key(&secp, key), signed]);
+            let s = key
+                .mul_tweak(&Scalar::from_be_bytes(e).unwrap())
+                .unwrap()
+                .add_tweak(&Scalar::from(nonce))
+                .unwrap();
+            Ok(BlindSignature {
+                amount: message.amount,
+                id: self.id.clone(),
+                c: signed.to_string(),
+                dleq: Some(DleqProof {
+                    e: hex::encode(e),
+                    s: hex::encode(s.secret_bytes()),
+                    r: None,
+                }),
+            })
+        }
+
+        /// Check an input: a valid signature, unspent, its conditions met
+        fn check_input(&self, proof: &Proof) -> Result<String, String> {
+            let secp = Secp256k1::new();
+            let key = self
+                .secret_keys
+                .get(&proof.amount)
+                .ok_or("unknown amount")?;
+            let y = hash_to_curve(proof.secret.as_bytes()).unwrap();
+            if y.mul_tweak(&secp, &Scalar::from(*key)).unwrap().to_string() != proof.c {
+                return Err("invalid proof".to_string());
+            }
+            if let Some(conditions) =
+                P2pkConditions::from_secret(&proof.secret).map_err(|e| e.to_string())?
+            {
+                let now = chrono::Utc::now().timestamp() as u64;
+                let mut signers = vec![conditions.pubkey];
+                if conditions.locktime.is_some_and(|locktime| locktime <= now) {
+                    signers.extend(conditions.refund_keys);
+                }
+                if !signers
+                    .iter()
+                    .any(|signer| verify_p2pk_witness(proof, signer))
+                {
+                    return Err("no valid signature".to_string());
+                }
+            }
+            Ok(y.to_string())
+        }
+
+        fn swap(
+            &self,
+            inputs: &[Proof],
+            outputs: &[BlindedMessage],
+        ) -> Result<Vec<BlindSignature>, String> {
+            let ys = inputs
+                .iter()
+                .map(|proof| self.check_input(proof))
+                .collect::<Result<Vec<_>, _>>()?;
+            let fee = (inputs.len() as u64 * self.input_fee_ppk).div_ceil(1000);
+            let input_sats: u64 = inputs.iter().map(|proof| proof.amount).sum();
+            let output_sats: u64 = outputs.iter().map(|output| output.amount).sum();
+            if input_sats != output_sats + fee {
+                return Err("inputs and outputs do not balance".to_string());
+            }
+            let signatures = outputs
+                .iter()
+                .map(|output| self.sign(output))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let mut spent = self.spent.lock().unwrap();
+            if ys.iter().any(|y| spent.contains(y)) {
+                return Err("proofs already spent".to_string());
+            }
+            spent.extend(ys);
+            Ok(signatures)
+        }
+
+        fn handle(&self, method: &str, path: &str, body: &serde_json::Value) -> MockResponse {
+            match (method, path) {
+                ("GET", "/v1/keysets") => MockResponse::json(
+                    200,
+                    json!({ "keysets": [{
+                        "id": self.id, "unit": UNIT_SAT, "active": true, "input_fee_ppk": self.input_fee_ppk
+                    }]}),
+                ),
+                ("GET", path) if path == format!("/v1/keys/{}", self.id) => {
+                    let keys: BTreeMap<String, String> = self
+                        .public_keys()
+                        .iter()
+                        .map(|(amount, key)| (amount.to_string(), key.to_string()))
+                        .collect();
+                    MockResponse::json(
+                        200,
+                        json!({ "keysets": [{ "id": self.id, "unit": UNIT_SAT, "keys": keys }] }),
+                    )
+                }
+                ("POST", "/v1/checkstate") => {
+                    let spent = self.spent.lock().unwrap();
+                    let states: Vec<_> = body["Ys"]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|y| {
+                            let state = if spent.contains(y.as_str().unwrap()) {
+                                "SPENT"
+                            } else {
+                                "UNSPENT"
+                            };
+                            json!({ "Y": y, "state": state, "witness": null })
+                        })
+                        .collect();
+                    MockResponse::json(200, json!({ "states": states }))
+                }
+                ("POST", "/v1/swap") => {
+                    let inputs: Vec<Proof> =
+                        serde_json::from_value(body["inputs"].clone()).unwrap();
+                    let outputs: Vec<BlindedMessage> =
+                        serde_json::from_value(body["outputs"].clone()).unwrap();
+                    match self.swap(&inputs, &outputs) {
+                        Ok(signatures) => {
+                            MockResponse::json(200, json!({ "signatures": signatures }))
+                        }
+                        Err(detail) => {
+                            MockResponse::json(400, json!({ "detail": detail, "code": 11000 }))
+                        }
+                    }
+                }
+                _ => MockResponse::json(404, json!({ "detail": "not found" })),
+            }
+        }
+    }
+
+    /// Local stand-in for a Cashu mint with real blind signatures
+    pub(crate) struct MockMint {
+        server: MockHttpServer,
+        state: Arc<MintState>,
+    }
+
+    impl MockMint {
+        /// Start a mint with denominations up to 2^20 sats
+        pub(crate) async fn start(input_fee_ppk: u64) -> Self {
+            let secret_keys: BTreeMap<u64, SecretKey> = (0..=20)
+                .map(|bit| (1u64 << bit, random_secret_key().unwrap()))
+                .collect();
+            let mut state = MintState {
+                id: String::new(),
+                secret_keys,
+                input_fee_ppk,
+                spent: Mutex::default(),
+            };
+            state.id = keyset_id(&state.public_keys());
+            let state = Arc::new(state);
+
+            let handler_state = state.clone();
+            let server = MockHttpServer::start(move |request| {
+                let body = serde_json::from_slice(&request.body).unwrap_or_default();
+                handler_state.handle(&request.method, &request.path, &body)
+            })
+            .await;
+            Self { server, state }
+        }
+
+        pub(crate) fn url(&self) -> String {
+            self.server.url()
+        }
+
+        /// Proofs for `amount` with secrets from `secret`, as a wallet holds them
+        pub(crate) fn issue(&self, amount: u64, secret: impl Fn() -> String) -> Vec<Proof> {
+            let keys = self.state.public_keys();
+            split_amount(amount)
+                .into_iter()
+                .map(|amount| {
+                    let output = PreparedOutput::new(amount, &self.state.id, secret()).unwrap();
+                    let signature = self.state.sign(&output.message).unwrap();
+                    output.unblind(&signature, &keys[&amount]).unwrap()
+                })
+                .collect()
+        }
+
+        /// Token locked to `conditions`, as an employer's wallet would send it
+        pub(crate) fn locked_token(&self, amount: u64, conditions: &P2pkConditions) -> String {
+            let proofs = self.issue(amount, || conditions.to_secret().unwrap());
+            Token::new(self.url(), proofs, None).encode().unwrap()
+        }
+    }
+
+    fn key(byte: u8) -> (SecretKey, PublicKey) {
+        let secret_key = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (
+            secret_key,
+            PublicKey::from_secret_key(&Secp256k1::new(), &secret_key),
+        )
+    }
+
+    #[test]
+    fn test_hash_to_curve_and_tokens() {
+        // NUT-00 test vectors
+        assert_eq!(
+            hash_to_curve(&[0u8; 32]).unwrap().to_string(),
+            "024cce997d3b518f739663b757deaec95bcd9473c30a14ac2fd04023a739d1a725"
+        );
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        assert_eq!(
+            hash_to_curve(&one).unwrap().to_string(),
+            "022e7158e11c9506f1aa4248bf531298daa7febd6194f003edcd9b93ade6253acf"
+        );
+        assert_eq!(split_amount(13), vec![1, 4, 8]);
+
+        let proof = Proof {
+            amount: 8,
+            id: "009a1f293253e41e".to_string(),
+            secret: "secret".to_string(),
+            c: "02bc9097997d81afb2cc7346b5e4345a9346bd2a506eb7958598a72f0cf85163ea".to_string(),
+            witness: None,
+            dleq: None,
+        };
+        let token = Token::new(
+            "https://mint.example/",
+            vec![proof.clone()],
+            Some("bounty".to_string()),
+        );
+        let encoded = token.encode().unwrap();
+        assert!(encoded.starts_with("cashuA"));
+        let decoded = Token::decode(&format!("cashu:{}", encoded.trim_end_matches('='))).unwrap();
+        assert_eq!(decoded, token);
+        assert_eq!(decoded.mint_url().unwrap(), "https://mint.example");
+        assert_eq!(decoded.amount(), 8);
+
+        assert!(Token::decode("cashuB123").is_err());
+        let empty = Token::new("https://mint.example", Vec::new(), None)
+            .encode()
+            .unwrap();
+        assert!(Token::decode(&empty).is_err());
+    }
+
+    #[test]
+    fn test_p2pk_conditions() {
+        let (escrow_key, escrow) = key(0x41);
+        let (_, refund) = key(0x42);
+        let conditions = P2pkConditions {
+            pubkey: escrow,
+            locktime: Some(1_900_000_000),
+            refund_keys: vec![refund],
+        };
+        let secret = conditions.to_secret().unwrap();
+        assert_eq!(
+            P2pkConditions::from_secret(&secret).unwrap(),
+            Some(conditions.clone())
+        );
+        // Fresh nonce for every proof
+        assert_ne!(conditions.to_secret().unwrap(), secret);
+        assert_eq!(
+            P2pkConditions::from_secret("407915bc212be61a77e3e6d2aeb4c727").unwrap(),
+            None
+        );
+
+        let multisig = format!(
+            r#"["P2PK",{{"nonce":"00","data":"{}","tags":[["n_sigs","2"],["pubkeys","{}"]]}}]"#,
+            escrow, refund
+        );
+        assert!(P2pkConditions::from_secret(&multisig).is_err());
+
+        // x-only keys are read with an even Y
+        let (x_only, _) = escrow.x_only_public_key();
+        let parsed = parse_pubkey(&x_only.to_string()).unwrap();
+        assert_eq!(parsed.x_only_public_key().0, x_only);
+
+        let mut proof = Proof {
+            amount: 1,
+            id: "00".to_string(),
+            secret,
+            c: String::new(),
+            witness: None,
+            dleq: None,
+        };
+        assert!(!verify_p2pk_witness(&proof, &escrow));
+        sign_p2pk(&mut proof, &escrow_key);
+        assert!(verify_p2pk_witness(&proof, &escrow));
+        assert!(!verify_p2pk_witness(&proof, &refund));
+    }
+
+    #[tokio::test]
+    async fn test_swap_locked_proofs_at_mint() {
+        let mint = MockMint::start(100).await;
+        let client =
+            CashuMintClient::new(format!("{}/", mint.url()), Duration::from_secs(5)).unwrap();
+        let (escrow_key, escrow) = key(0x51);
+        let (_, worker) = key(0x52);
+        let locked = P2pkConditions {
+            pubkey: escrow,
+            locktime: Some(1_900_000_000),
+            refund_keys: vec![key(0x53).1],
+        };
+
+        let token = Token::decode(&mint.locked_token(21, &locked)).unwrap();
+        let keysets = client.get_keysets().await.unwrap();
+        let keyset = client.get_keyset(&keysets[0].id).await.unwrap();
+        let mut inputs: Vec<Proof> = token.proofs().cloned().collect();
+        for proof in &inputs {
+            verify_proof_dleq(proof, &keyset.keys[&proof.amount]).unwrap();
+        }
+        // A proof for another amount does not verify
+        let mut forged = inputs[0].clone();
+        forged.amount = 2;
+        assert!(verify_proof_dleq(&forged, &keyset.keys[&2]).is_err());
+        assert!(
+            client
+                .check_state(&inputs)
+                .await
+                .unwrap()
+                .iter()
+                .all(|state| *state == ProofState::Unspent)
+        );
+
+        // 3 inputs at 100 ppk cost 1 sat; the rest is re-locked to the worker
+        let to_worker = P2pkConditions {
+            pubkey: worker,
+            locktime: None,
+            refund_keys: Vec::new(),
+        };
+        let outputs = split_amount(20)
+            .into_iter()
+            .map(|amount| {
+                PreparedOutput::new(amount, &keyset.id, to_worker.to_secret().unwrap()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let messages: Vec<BlindedMessage> = outputs
+            .iter()
+            .map(|output| output.message.clone())
+            .collect();
+        // Unsigned inputs are refused
+        let error = client.swap(&inputs, &messages).await.unwrap_err();
+        assert!(error.to_string().contains("no valid signature"));
+
+        for proof in &mut inputs {
+            sign_p2pk(proof, &escrow_key);
+        }
+        let signatures = client.swap(&inputs, &messages).await.unwrap();
+        let proofs = outputs
+            .into_iter()
+            .zip(&signatures)
+            .map(|(output, signature)| {
+                output
+                    .unblind(signature, &keyset.keys[&signature.amount])
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(proofs.iter().map(|proof| proof.amount).sum::<u64>(), 20);
+        for proof in &proofs {
+            verify_proof_dleq(proof, &keyset.keys[&proof.amount]).unwrap();
+            assert_eq!(
+                P2pkConditions::from_secret(&proof.secret)
+                    .unwrap()
+                    .unwrap()
+                    .pubkey,
+                worker
+            );
+        }
+
+        assert!(
+            client
+                .check_state(&inputs)
+                .await
+                .unwrap()
+                .iter()
+                .all(|state| *state == ProofState::Spent)
+        );
+        assert!(client.swap(&inputs, &messages).await.is_err());
+    }
+}
@@ -0,0 +1,471 @@
+//! Chain Source - Best-chain blocks from bitcoind, Esplora or memory
+//!
+//! The chain watcher only needs the height of the best tip, the hash of the
+//! best-chain block at a height and full blocks by hash. bitcoind serves
+//! them over JSON-RPC, Esplora over REST, and `InMemoryChain` keeps a chain
+//! in memory that tests (and regtest demos) mine and reorganise at will.
+
+use crate::{EscrowResult, error::EscrowError, fee_estimator::http_client};
+use async_trait::async_trait;
+use bitcoin::{
+    Block, BlockHash, CompactTarget, Transaction, TxMerkleNode,
+    block::{Header, Version},
+    consensus::encode,
+    hashes::Hash,
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+use std::{
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::RwLock;
+
+/// Source of best-chain blocks
+#[async_trait]
+pub trait ChainSource: Send + Sync {
+    /// Human-readable source name
+    fn name(&self) -> &str;
+
+    /// Height of the best chain tip
+    async fn tip_height(&self) -> EscrowResult<u32>;
+
+    /// Hash of the best-chain block at `height`, `None` above the tip
+    async fn block_hash(&self, height: u32) -> EscrowResult<Option<BlockHash>>;
+
+    /// Full block by hash
+    async fn block(&self, hash: &BlockHash) -> EscrowResult<Block>;
+}
+
+/// Configured chain source
+#[derive(Debug, Clone)]
+pub enum ChainSourceConfig {
+    /// bitcoind JSON-RPC endpoint
+    Bitcoind {
+        rpc_url: String,
+        rpc_user: Option<String>,
+        rpc_password: Option<String>,
+    },
+    /// Esplora REST API base URL
+    Esplora { base_url: String },
+}
+
+impl ChainSourceConfig {
+    /// Build the chain source
+    pub fn build(&self, timeout: Duration) -> EscrowResult<Arc<dyn ChainSource>> {
+        Ok(match self {
+            ChainSourceConfig::Bitcoind {
+                rpc_url,
+                rpc_user,
+                rpc_password,
+            } => {
+                let credentials = rpc_user
+                    .clone()
+                    .map(|user| (user, rpc_password.clone().unwrap_or_default()));
+                Arc::new(BitcoindChainSource::new(BitcoindRpc::new(
+                    rpc_url.clone(),
+                    credentials,
+                    timeout,
+                )?))
+            }
+            ChainSourceConfig::Esplora { base_url } => {
+                Arc::new(EsploraChainSource::new(base_url.clone(), timeout)?)
+            }
+        })
+    }
+}
+
+/// Minimal bitcoind JSON-RPC client
+#[derive(Debug, Clone)]
+pub(crate) struct BitcoindRpc {
+    url: String,
+    credentials: Option<(String, String)>,
+    http: reqwest::Client,
+}
+
+/// JSON-RPC response envelope
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    message: String,
+}
+
+impl BitcoindRpc {
+    pub(crate) fn new(
+        url: impl Into<String>,
+        credentials: Option<(String, String)>,
+        timeout: Duration,
+    ) -> EscrowResult<Self> {
+        Ok(Self {
+            url: url.into(),
+            credentials,
+            http: http_client(timeout)?,
+        })
+    }
+
+    /// Call `method` and return its result
+    pub(crate) async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> EscrowResult<T> {
+        let body = json!({
+            "jsonrpc": "1.0",
+            "id": "escrow",
+            "method": method,
+            "params": params,
+        });
+        let mut request = self.http.post(&self.url).json(&body);
+        if let Some((user, password)) = &self.credentials {
+            request = request.basic_auth(user, Some(password));
+        }
+        // bitcoind answers RPC errors with non-2xx statuses and a JSON body
+        let response: RpcResponse<T> = request
+            .send()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("bitcoind {} failed: {}", method, e)))?
+            .json()
+            .await
+            .map_err(|e| {
+                EscrowError::external_api(format!("Invalid bitcoind {} response: {}", method, e))
+            })?;
+
+        if let Some(error) = response.error {
+            return Err(EscrowError::external_api(format!(
+                "bitcoind {} error: {}",
+                method, error.message
+            )));
+        }
+        response.result.ok_or_else(|| {
+            EscrowError::external_api(format!("bitcoind {} returned no result", method))
+        })
+    }
+}
+
+/// Chain source backed by bitcoind
+pub struct BitcoindChainSource {
+    rpc: BitcoindRpc,
+}
+
+impl BitcoindChainSource {
+    pub(crate) fn new(rpc: BitcoindRpc) -> Self {
+        Self { rpc }
+    }
+}
+
+#[async_trait]
+impl ChainSource for BitcoindChainSource {
+    fn name(&self) -> &str {
+        "bitcoind"
+    }
+
+    async fn tip_height(&self) -> EscrowResult<u32> {
+        self.rpc.call("getblockcount", json!([])).await
+    }
+
+    async fn block_hash(&self, height: u32) -> EscrowResult<Option<BlockHash>> {
+        if height > self.tip_height().await? {
+            return Ok(None);
+        }
+        let hash: String = self.rpc.call("getblockhash", json!([height])).await?;
+        parse_block_hash(&hash).map(Some)
+    }
+
+    async fn block(&self, hash: &BlockHash) -> EscrowResult<Block> {
+        let hex: String = self
+            .rpc
+            .call("getblock", json!([hash.to_string(), 0]))
+            .await?;
+        let bytes = hex::decode(hex.trim())
+            .map_err(|e| EscrowError::external_api(format!("Invalid block hex: {}", e)))?;
+        decode_block(&bytes)
+    }
+}
+
+/// Chain source backed by an Esplora REST API
+pub struct EsploraChainSource {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl EsploraChainSource {
+    /// Create a source for the Esplora API at `base_url`
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: http_client(timeout)?,
+        })
+    }
+
+    /// GET `path`, returning `None` on 404
+    async fn get(&self, path: &str) -> EscrowResult<Option<Vec<u8>>> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Esplora request failed: {}", e)))?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(EscrowError::external_api(format!(
+                "Esplora {} returned {}",
+                url,
+                status.as_u16()
+            )));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Esplora request failed: {}", e)))?;
+        Ok(Some(body.to_vec()))
+    }
+
+    async fn get_text(&self, path: &str) -> EscrowResult<Option<String>> {
+        Ok(self
+            .get(path)
+            .await?
+            .map(|body| String::from_utf8_lossy(&body).trim().to_string()))
+    }
+}
+
+#[async_trait]
+impl ChainSource for EsploraChainSource {
+    fn name(&self) -> &str {
+        "esplora"
+    }
+
+    async fn tip_height(&self) -> EscrowResult<u32> {
+        self.get_text("/blocks/tip/height")
+            .await?
+            .and_then(|height| height.parse().ok())
+            .ok_or_else(|| EscrowError::external_api("Esplora returned no tip height"))
+    }
+
+    async fn block_hash(&self, height: u32) -> EscrowResult<Option<BlockHash>> {
+        match self.get_text(&format!("/block-height/{}", height)).await? {
+            Some(hash) => parse_block_hash(&hash).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn block(&self, hash: &BlockHash) -> EscrowResult<Block> {
+        let bytes = self
+            .get(&format!("/block/{}/raw", hash))
+            .await?
+            .ok_or_else(|| EscrowError::external_api(format!("Esplora has no block {}", hash)))?;
+        decode_block(&bytes)
+    }
+}
+
+/// Chain kept in memory, mined and reorganised on demand
+pub struct InMemoryChain {
+    blocks: RwLock<Vec<Block>>,
+    /// Makes competing blocks at the same height differ
+    nonce: AtomicU32,
+}
+
+impl InMemoryChain {
+    /// Create a chain holding only a genesis block
+    pub fn new() -> Self {
+        let chain = Self {
+            blocks: RwLock::new(Vec::new()),
+            nonce: AtomicU32::new(0),
+        };
+        let genesis = chain.next_block(BlockHash::all_zeros(), 0, Vec::new());
+        chain
+            .blocks
+            .try_write()
+            .expect("new chain is unshared")
+            .push(genesis);
+        chain
+    }
+
+    /// Mine a block with `txdata` on top of the tip and return its hash
+    pub async fn mine(&self, txdata: Vec<Transaction>) -> BlockHash {
+        let mut blocks = self.blocks.write().await;
+        let tip = blocks.last().expect("chain has a genesis block");
+        let block = self.next_block(tip.block_hash(), blocks.len() as u32, txdata);
+        let hash = block.block_hash();
+        blocks.push(block);
+        hash
+    }
+
+    /// Mine `count` empty blocks
+    pub async fn mine_empty(&self, count: u32) {
+        for _ in 0..count {
+            self.mine(Vec::new()).await;
+        }
+    }
+
+    /// Replace the top `depth` blocks with one block per entry of `replacement`
+    pub async fn reorg(
+        &self,
+        depth: usize,
+        replacement: Vec<Vec<Transaction>>,
+    ) -> EscrowResult<()> {
+        {
+            let mut blocks = self.blocks.write().await;
+            if depth >= blocks.len() {
+                return Err(EscrowError::config("Cannot reorganise the genesis block"));
+            }
+            let keep = blocks.len() - depth;
+            blocks.truncate(keep);
+        }
+        for txdata in replacement {
+            self.mine(txdata).await;
+        }
+        Ok(())
+    }
+
+    fn next_block(
+        &self,
+        prev_blockhash: BlockHash,
+        height: u32,
+        txdata: Vec<Transaction>,
+    ) -> Block {
+        Block {
+            header: Header {
+                version: Version::TWO,
+                prev_blockhash,
+                merkle_root: TxMerkleNode::all_zeros(),
+                time: 1_700_000_000 + height * 600,
+                bits: CompactTarget::from_consensus(0x207f_ffff),
+                nonce: self.nonce.fetch_add(1, Ordering::Relaxed),
+            },
+            txdata,
+        }
+    }
+}
+
+impl Default for InMemoryChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ChainSource for InMemoryChain {
+    fn name(&self) -> &str {
+        "memory"
+    }
+
+    async fn tip_height(&self) -> EscrowResult<u32> {
+        Ok(self.blocks.read().await.len() as u32 - 1)
+    }
+
+    async fn block_hash(&self, height: u32) -> EscrowResult<Option<BlockHash>> {
+        Ok(self
+            .blocks
+            .read()
+            .await
+            .get(height as usize)
+            .map(Block::block_hash))
+    }
+
+    async fn block(&self, hash: &BlockHash) -> EscrowResult<Block> {
+        self.blocks
+            .read()
+            .await
+            .iter()
+            .find(|block| block.block_hash() == *hash)
+            .cloned()
+            .ok_or_else(|| {
+                EscrowError::external_api(format!("Block {} is not in the best chain", hash))
+            })
+    }
+}
+
+fn parse_block_hash(hash: &str) -> EscrowResult<BlockHash> {
+    BlockHash::from_str(hash.trim())
+        .map_err(|e| EscrowError::external_api(format!("Invalid block hash {}: {}", hash, e)))
+}
+
+fn decode_block(bytes: &[u8]) -> EscrowResult<Block> {
+    encode::deserialize(bytes)
+        .map_err(|e| EscrowError::external_api(format!("Invalid block: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+
+    #[tokio::test]
+    async fn test_esplora_and_bitcoind_serve_the_same_chain() {
+        let chain = InMemoryChain::new();
+        chain.mine_empty(2).await;
+        let hash = chain.block_hash(2).await.unwrap().unwrap();
+        let block = encode::serialize(&chain.block(&hash).await.unwrap());
+
+        let (esplora_hash, esplora_block) = (hash.to_string(), block.clone());
+        let esplora = MockHttpServer::start(move |request| match request.path.as_str() {
+            "/blocks/tip/height" => MockResponse::bytes(200, "text/plain", "2"),
+            "/block-height/2" => MockResponse::bytes(200, "text/plain", esplora_hash.clone()),
+            "/block-height/3" => MockResponse::bytes(404, "text/plain", "Block not found"),
+            path if path.ends_with("/raw") => {
+                MockResponse::bytes(200, "application/octet-stream", esplora_block.clone())
+            }
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let (rpc_hash, rpc_block) = (hash.to_string(), hex::encode(&block));
+        let bitcoind = MockHttpServer::start(move |request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let result = match body["method"].as_str().unwrap() {
+                "getblockcount" => json!(2),
+                "getblockhash" => json!(rpc_hash),
+                "getblock" => json!(rpc_block),
+                _ => return MockResponse::json(404, json!({ "result": null, "error": { "code": -32601, "message": "Method not found" } })),
+            };
+            MockResponse::json(200, json!({ "result": result, "error": null, "id": "escrow" }))
+        })
+        .await;
+
+        let sources: [Arc<dyn ChainSource>; 2] = [
+            ChainSourceConfig::Esplora {
+                base_url: esplora.url(),
+            }
+            .build(Duration::from_secs(5))
+            .unwrap(),
+            ChainSourceConfig::Bitcoind {
+                rpc_url: bitcoind.url(),
+                rpc_user: Some("user".to_string()),
+                rpc_password: Some("pass".to_string()),
+            }
+            .build(Duration::from_secs(5))
+            .unwrap(),
+        ];
+        for source in sources {
+            assert_eq!(source.tip_height().await.unwrap(), 2, "{}", source.name());
+            assert_eq!(source.block_hash(2).await.unwrap(), Some(hash));
+            assert_eq!(source.block_hash(3).await.unwrap(), None);
+            assert_eq!(source.block(&hash).await.unwrap().block_hash(), hash);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_reorg_replaces_blocks() {
+        let chain = InMemoryChain::new();
+        chain.mine_empty(3).await;
+        let stale = chain.block_hash(3).await.unwrap().unwrap();
+
+        chain.reorg(1, vec![Vec::new(), Vec::new()]).await.unwrap();
+        assert_eq!(chain.tip_height().await.unwrap(), 4);
+        assert_ne!(chain.block_hash(3).await.unwrap(), Some(stale));
+        assert!(chain.block(&stale).await.is_err());
+        assert!(chain.reorg(5, Vec::new()).await.is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Chain Watcher - Confirmation tracking and reorg detection
+//!
+//! Follows the best chain of a `ChainSource` block by block and reports
+//! transactions paying watched scripts or matching watched txids, with their
+//! confirmation depth. The hashes of recent blocks are kept so a reorg is
+//! noticed on the next sync: transactions confirmed in disconnected blocks
+//! are reported as reorged and picked up again if the new chain includes
+//! them (EDGE_CASES #10).
+
+use crate::{EscrowResult, chain_source::ChainSource};
+use bitcoin::{BlockHash, ScriptBuf, Transaction, Txid, consensus::encode};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{RwLock, mpsc};
+use tracing::{info, warn};
+
+/// Configuration for the chain watcher
+#[derive(Debug, Clone)]
+pub struct ChainWatcherConfig {
+    /// Interval between syncs with the chain source
+    pub poll_interval_secs: u64,
+    /// Blocks below the tip scanned on the first sync
+    pub rescan_depth: u32,
+    /// Recent block hashes kept to detect reorgs
+    pub max_reorg_depth: u32,
+}
+
+impl Default for ChainWatcherConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 30,
+            rescan_depth: 6,
+            max_reorg_depth: 100,
+        }
+    }
+}
+
+/// What a watch matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchTarget {
+    /// Transactions with an output paying this script
+    Script(ScriptBuf),
+    /// The transaction with this txid
+    Transaction(Txid),
+}
+
+impl WatchTarget {
+    fn matches(&self, tx: &Transaction) -> bool {
+        match self {
+            WatchTarget::Script(script) => tx
+                .output
+                .iter()
+                .any(|output| output.script_pubkey == *script),
+            WatchTarget::Transaction(txid) => tx.txid() == *txid,
+        }
+    }
+}
+
+/// Change of a watched transaction's place in the best chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    /// The transaction is `confirmations` deep in the best chain
+    Confirmed {
+        watch_id: String,
+        txid: Txid,
+        tx_hex: String,
+        block_height: u32,
+        confirmations: u32,
+    },
+    /// The block confirming the transaction left the best chain
+    Reorged {
+        watch_id: String,
+        txid: Txid,
+        block_height: u32,
+    },
+}
+
+/// A confirmed transaction matching a watch
+#[derive(Debug, Clone)]
+struct ConfirmedTx {
+    block_height: u32,
+    tx_hex: String,
+    /// Depth last reported, so unchanged depths are not reported again
+    reported_confirmations: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Watch {
+    target: WatchTarget,
+    confirmed: HashMap<Txid, ConfirmedTx>,
+}
+
+#[derive(Debug, Default)]
+struct WatcherState {
+    /// Hashes of recently processed best-chain blocks
+    blocks: BTreeMap<u32, BlockHash>,
+    watches: HashMap<String, Watch>,
+}
+
+/// Watches the chain for transactions of interest
+pub struct ChainWatcher {
+    config: ChainWatcherConfig,
+    source: Arc<dyn ChainSource>,
+    state: RwLock<WatcherState>,
+}
+
+impl ChainWatcher {
+    /// Create a watcher following `source`
+    pub fn new(config: ChainWatcherConfig, source: Arc<dyn ChainSource>) -> Self {
+        Self {
+            config,
+            source,
+            state: RwLock::new(WatcherState::default()),
+        }
+    }
+
+    /// Start watching `target` under `watch_id`, replacing any previous watch
+    ///
+    /// Only blocks processed from now on are scanned, plus `rescan_depth`
+    /// blocks on the first sync.
+    pub async fn watch(&self, watch_id: impl Into<String>, target: WatchTarget) {
+        self.state.write().await.watches.insert(
+            watch_id.into(),
+            Watch {
+                target,
+                confirmed: HashMap::new(),
+            },
+        );
+    }
+
+    /// Stop watching `watch_id`
+    pub async fn unwatch(&self, watch_id: &str) {
+        self.state.write().await.watches.remove(watch_id);
+    }
+
+    /// Height of the last processed block
+    pub async fn synced_height(&self) -> Option<u32> {
+        self.state.read().await.blocks.keys().next_back().copied()
+    }
+
+    /// Catch up with the chain source
+    ///
+    /// Disconnects blocks that left the best chain, scans the new blocks and
+    /// returns reorgs and changed confirmation depths of watched transactions.
+    pub async fn sync(&self) -> EscrowResult<Vec<ChainEvent>> {
+        let tip = self.source.tip_height().await?;
+        let mut state = self.state.write().await;
+        let mut events = Vec::new();
+
+        // Find the last processed block still in the best chain
+        let mut fork_height = None;
+        for (height, hash) in state.blocks.iter().rev() {
+            if *height <= tip && self.source.block_hash(*height).await? == Some(*hash) {
+                fork_height = Some(*height);
+                break;
+            }
+        }
+        let next_height = match (fork_height, state.blocks.keys().next().copied()) {
+            (Some(fork), _) => fork + 1,
+            (None, None) => tip.saturating_sub(self.config.rescan_depth.saturating_sub(1)),
+            (None, Some(oldest)) => {
+                warn!(
+                    "Reorg deeper than the {} tracked blocks, rescanning from {}",
+                    state.blocks.len(),
+                    oldest
+                );
+                oldest
+            }
+        };
+
+        // Disconnect blocks above the fork
+        let disconnected = state.blocks.split_off(&next_height);
+        if let Some((height, _)) = disconnected.iter().next() {
+            info!(
+                "Chain reorg: {} block(s) from height {} disconnected",
+                disconnected.len(),
+                height
+            );
+        }
+        for (watch_id, watch) in state.watches.iter_mut() {
+            watch.confirmed.retain(|txid, confirmed| {
+                if confirmed.block_height < next_height {
+                    return true;
+                }
+                events.push(ChainEvent::Reorged {
+                    watch_id: watch_id.clone(),
+                    txid: *txid,
+                    block_height: confirmed.block_height,
+                });
+                false
+            });
+        }
+
+        // Connect the new blocks
+        for height in next_height..=tip {
+            // The tip may move back while we scan; the next sync picks up from here
+            let Some(hash) = self.source.block_hash(height).await? else {
+                break;
+            };
+            let block = self.source.block(&hash).await?;
+            for tx in &block.txdata {
+                for watch in state.watches.values_mut() {
+                    if watch.target.matches(tx) {
+                        watch.confirmed.insert(
+                            tx.txid(),
+                            ConfirmedTx {
+                                block_height: height,
+                                tx_hex: encode::serialize_hex(tx),
+                                reported_confirmations: 0,
+                            },
+                        );
+                    }
+                }
+            }
+            state.blocks.insert(height, hash);
+        }
+
+        let Some(synced) = state.blocks.keys().next_back().copied() else {
+            return Ok(events);
+        };
+        let keep_from = synced.saturating_sub(self.config.max_reorg_depth);
+        state.blocks = state.blocks.split_off(&keep_from);
+
+        for (watch_id, watch) in state.watches.iter_mut() {
+            for (txid, confirmed) in watch.confirmed.iter_mut() {
+                let confirmations = synced.saturating_sub(confirmed.block_height) + 1;
+                if confirmations != confirmed.reported_confirmations {
+                    confirmed.reported_confirmations = confirmations;
+                    events.push(ChainEvent::Confirmed {
+                        watch_id: watch_id.clone(),
+                        txid: *txid,
+                        tx_hex: confirmed.tx_hex.clone(),
+                        block_height: confirmed.block_height,
+                        confirmations,
+                    });
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Sync every `poll_interval_secs` until `events` is closed
+    pub async fn run(&self, events: mpsc::UnboundedSender<ChainEvent>) {
+        let poll_interval = Duration::from_secs(self.config.poll_interval_secs.max(1));
+
+        while !events.is_closed() {
+            match self.sync().await {
+                Ok(synced) => {
+                    for event in synced {
+                        if events.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => warn!("Chain sync with {} failed: {}", self.source.name(), e),
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_source::InMemoryChain;
+    use bitcoin::{Amount, TxIn, TxOut, absolute::LockTime, transaction};
+
+    fn payment(script: &ScriptBuf, sats: u64) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(sats),
+                script_pubkey: script.clone(),
+            }],
+        }
+    }
+
+    fn depth(events: &[ChainEvent], id: &str) -> Option<u32> {
+        events.iter().find_map(|event| match event {
+            ChainEvent::Confirmed {
+                watch_id,
+                confirmations,
+                ..
+            } if watch_id == id => Some(*confirmations),
+            _ => None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_confirmations_through_reorg() {
+        let chain = Arc::new(InMemoryChain::new());
+        chain.mine_empty(10).await;
+        let watcher = ChainWatcher::new(ChainWatcherConfig::default(), chain.clone());
+
+        let escrow = ScriptBuf::from_bytes(vec![0x51]);
+        let funding = payment(&escrow, 100_000);
+        let claim = payment(&ScriptBuf::from_bytes(vec![0x52]), 50_000);
+        watcher
+            .watch("escrow", WatchTarget::Script(escrow.clone()))
+            .await;
+        watcher
+            .watch("claim", WatchTarget::Transaction(claim.txid()))
+            .await;
+        assert!(watcher.sync().await.unwrap().is_empty());
+        assert_eq!(watcher.synced_height().await, Some(10));
+
+        chain.mine(vec![funding.clone(), claim.clone()]).await;
+        let events = watcher.sync().await.unwrap();
+        assert_eq!(depth(&events, "escrow"), Some(1));
+        assert_eq!(depth(&events, "claim"), Some(1));
+
+        chain.mine_empty(2).await;
+        let events = watcher.sync().await.unwrap();
+        assert_eq!(depth(&events, "escrow"), Some(3));
+        // Nothing changed, nothing reported
+        assert!(watcher.sync().await.unwrap().is_empty());
+
+        // Reorg out the confirming block; only the funding is re-mined
+        chain
+            .reorg(
+                3,
+                vec![Vec::new(), vec![funding.clone()], Vec::new(), Vec::new()],
+            )
+            .await
+            .unwrap();
+        let events = watcher.sync().await.unwrap();
+        let reorged: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                ChainEvent::Reorged {
+                    watch_id,
+                    block_height,
+                    ..
+                } => {
+                    assert_eq!(*block_height, 11);
+                    Some(watch_id.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(reorged.len(), 2);
+        assert_eq!(depth(&events, "escrow"), Some(3));
+        assert_eq!(depth(&events, "claim"), None);
+
+        watcher.unwatch("escrow").await;
+        chain.mine_empty(1).await;
+        assert!(watcher.sync().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_first_sync_rescans_recent_blocks() {
+        let chain = Arc::new(InMemoryChain::new());
+        let script = ScriptBuf::from_bytes(vec![0x53]);
+        chain.mine(vec![payment(&script, 1_000)]).await;
+        chain.mine_empty(3).await;
+        chain.mine(vec![payment(&script, 2_000)]).await;
+        chain.mine_empty(20).await;
+
+        let watcher = ChainWatcher::new(
+            ChainWatcherConfig {
+                rescan_depth: 24,
+                max_reorg_depth: 5,
+                ..ChainWatcherConfig::default()
+            },
+            chain.clone(),
+        );
+        watcher.watch("script", WatchTarget::Script(script)).await;
+        let events = watcher.sync().await.unwrap();
+        // The payment at height 1 is below the rescan window
+        assert_eq!(events.len(), 1);
+        assert_eq!(depth(&events, "script"), Some(21));
+    }
+}
@@ -5,34 +5,55 @@
 //! escrow functionality that enables trust-minimized task payments.
 
 use crate::{
+    backup::{
+        BackupConfig, BackupManager, BackupSink, BackupStatus, ChannelMonitorUpdate, RestoreReport,
+    },
     error::EscrowError,
-    models::{FundingStatus, HoldInvoiceData, InvoiceSettlementData},
+    models::{
+        FundingStatus, HoldInvoiceData, InvoiceCancellationData, InvoiceSettlementData, PayoutData,
+    },
+    network::{self, Network},
 };
 use chrono::{DateTime, Utc};
 // LDK types are stubbed out for compilation; wire real LDK in production
 use crate::EscrowResult;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Configuration for the escrow engine
 #[derive(Debug, Clone)]
 pub struct EscrowEngineConfig {
+    /// Bitcoin network the Lightning node runs on
+    pub network: Network,
     /// Invoice expiry time in seconds
     pub invoice_expiry_secs: u64,
     /// Maximum invoice amount in sats
     pub max_invoice_amount_sats: u64,
     /// Webhook URL for invoice events
     pub webhook_url: Option<String>,
+    /// Shared secret used to sign payloads sent to `webhook_url`
+    pub webhook_secret: Option<String>,
+    /// Maximum accepted overpayment as a percentage of the invoice amount
+    pub max_overpayment_percent: f64,
+    /// Seconds to wait for the remaining parts of a multi-part payment
+    pub mpp_timeout_secs: u64,
+    /// Channel monitor backup settings (disabled when `None`)
+    pub backup: Option<BackupConfig>,
 }
 
 impl Default for EscrowEngineConfig {
     fn default() -> Self {
         Self {
+            network: Network::Bitcoin,
             invoice_expiry_secs: 3600,           // 1 hour
             max_invoice_amount_sats: 10_000_000, // 0.1 BTC
             webhook_url: None,
+            webhook_secret: None,
+            max_overpayment_percent: 1.0, // LDK default tolerance
+            mpp_timeout_secs: 60,         // BOLT 4 recommended MPP timeout
+            backup: None,
         }
     }
 }
@@ -41,10 +62,14 @@ impl Default for EscrowEngineConfig {
 pub struct EscrowEngine {
     /// Configuration
     config: EscrowEngineConfig,
-    /// Active hold invoices (invoice_hash -> hold_invoice_id)
-    active_invoices: Arc<RwLock<HashMap<String, String>>>,
+    /// Active hold invoices (invoice_hash -> invoice state)
+    active_invoices: Arc<RwLock<HashMap<String, ActiveInvoice>>>,
     /// Invoice status callbacks
     status_callbacks: Arc<RwLock<HashMap<String, Box<dyn Fn(InvoiceStatusUpdate) + Send + Sync>>>>,
+    /// Latest persisted monitor update per channel (channel_id -> update_id)
+    channel_update_ids: Arc<RwLock<HashMap<String, u64>>>,
+    /// Channel monitor backups
+    backup: Option<Arc<BackupManager>>,
 }
 
 /// Invoice status update event
@@ -53,10 +78,38 @@ pub struct InvoiceStatusUpdate {
     pub invoice_hash: String,
     pub status: FundingStatus,
     pub amount_sats: Option<u64>,
+    pub part_count: usize,
     pub preimage: Option<String>,
     pub timestamp: DateTime<Utc>,
 }
 
+/// Tracked state of an active hold invoice
+#[derive(Debug, Clone)]
+struct ActiveInvoice {
+    hold_invoice_id: String,
+    amount_sats: u64,
+    received_sats: u64,
+    status: FundingStatus,
+    expires_at: DateTime<Utc>,
+    /// HTLCs held for this invoice (one per MPP part)
+    parts: Vec<HtlcPart>,
+}
+
+impl ActiveInvoice {
+    /// Sum of all held HTLC parts
+    fn total_parts_sats(&self) -> u64 {
+        self.parts.iter().map(|part| part.amount_sats).sum()
+    }
+}
+
+/// A single HTLC held against a hold invoice
+#[derive(Debug, Clone)]
+struct HtlcPart {
+    htlc_id: String,
+    amount_sats: u64,
+    received_at: DateTime<Utc>,
+}
+
 /// Invoice settlement request
 #[derive(Debug, Clone)]
 pub struct SettlementRequest {
@@ -69,10 +122,17 @@ impl EscrowEngine {
     pub async fn new(config: EscrowEngineConfig) -> EscrowResult<Self> {
         info!("Initializing escrow engine (LDK stub)");
 
+        let backup = match &config.backup {
+            Some(backup_config) => Some(Arc::new(BackupManager::new(backup_config.clone()).await?)),
+            None => None,
+        };
+
         Ok(Self {
             config,
             active_invoices: Arc::new(RwLock::new(HashMap::new())),
             status_callbacks: Arc::new(RwLock::new(HashMap::new())),
+            channel_update_ids: Arc::new(RwLock::new(HashMap::new())),
+            backup,
         })
     }
 
@@ -102,14 +162,29 @@ impl EscrowEngine {
 
         // Simulate hold invoice creation (replace with LDK call in production)
         let invoice_hash = format!("hash_{}", uuid::Uuid::new_v4());
-        let invoice = format!("lnbc{}u1{}", amount_sats, invoice_hash);
+        let invoice = format!(
+            "{}{}u1{}",
+            network::bolt11_prefix(self.config.network),
+            amount_sats,
+            invoice_hash
+        );
         let hold_invoice_id = format!("hold_{}", invoice_hash);
 
+        let expires_at =
+            Utc::now() + chrono::Duration::seconds(self.config.invoice_expiry_secs as i64);
+
         // Store active invoice
-        self.active_invoices
-            .write()
-            .await
-            .insert(invoice_hash.clone(), hold_invoice_id.clone());
+        self.active_invoices.write().await.insert(
+            invoice_hash.clone(),
+            ActiveInvoice {
+                hold_invoice_id: hold_invoice_id.clone(),
+                amount_sats,
+                received_sats: 0,
+                status: FundingStatus::Created,
+                expires_at,
+                parts: Vec::new(),
+            },
+        );
 
         // Set up invoice monitoring (in a real implementation, this would be done via webhooks)
         self.setup_invoice_monitoring(&invoice_hash).await?;
@@ -119,8 +194,7 @@ impl EscrowEngine {
             invoice_hash: invoice_hash.clone(),
             hold_invoice_id,
             amount_sats,
-            expires_at: Utc::now()
-                + chrono::Duration::seconds(self.config.invoice_expiry_secs as i64),
+            expires_at,
         };
 
         info!("Created hold invoice: {}", invoice_hash);
@@ -130,17 +204,264 @@ impl EscrowEngine {
 
     /// Get the status of a hold invoice
     pub async fn get_invoice_status(&self, invoice_hash: &str) -> EscrowResult<FundingStatus> {
-        // Check if invoice is in our active invoices
-        if !self.active_invoices.read().await.contains_key(invoice_hash) {
+        // In a real implementation, this would query LDK for the actual status
+        self.active_invoices
+            .read()
+            .await
+            .get(invoice_hash)
+            .map(|invoice| invoice.status)
+            .ok_or_else(|| EscrowError::invoice(format!("Invoice {} not found", invoice_hash)))
+    }
+
+    /// Get the amount actually received for a hold invoice
+    pub async fn get_received_amount(&self, invoice_hash: &str) -> EscrowResult<u64> {
+        self.active_invoices
+            .read()
+            .await
+            .get(invoice_hash)
+            .map(|invoice| invoice.received_sats)
+            .ok_or_else(|| EscrowError::invoice(format!("Invoice {} not found", invoice_hash)))
+    }
+
+    /// Handle a claimable HTLC set for a hold invoice
+    ///
+    /// Mirrors LDK's `PaymentClaimable` handling for a complete HTLC set:
+    /// underpayments are failed back to the payer, overpayments beyond
+    /// `max_overpayment_percent` are refused, and accepted payments are held
+    /// with the exact received amount recorded.
+    pub async fn handle_payment_claimable(
+        &self,
+        invoice_hash: &str,
+        amount_received_sats: u64,
+    ) -> EscrowResult<InvoiceStatusUpdate> {
+        let update = {
+            let mut active = self.active_invoices.write().await;
+            let invoice = Self::payable_invoice(&mut active, invoice_hash)?;
+
+            if !invoice.parts.is_empty() {
+                return Err(EscrowError::invoice(format!(
+                    "Invoice {} has a multi-part payment in progress",
+                    invoice_hash
+                )));
+            }
+
+            invoice.parts.push(HtlcPart {
+                htlc_id: "single".to_string(),
+                amount_sats: amount_received_sats,
+                received_at: Utc::now(),
+            });
+            self.evaluate_htlc_set(invoice_hash, invoice)?
+        };
+
+        self.notify_status(&update).await;
+
+        Ok(update)
+    }
+
+    /// Handle one HTLC of a multi-part payment
+    ///
+    /// Parts are held until their sum reaches the invoice amount, at which
+    /// point the whole set is accepted (or refused as a unit). Incomplete sets
+    /// are failed back by `expire_incomplete_htlc_sets` after
+    /// `mpp_timeout_secs`.
+    pub async fn receive_htlc_part(
+        &self,
+        invoice_hash: &str,
+        htlc_id: &str,
+        amount_sats: u64,
+    ) -> EscrowResult<InvoiceStatusUpdate> {
+        let update = {
+            let mut active = self.active_invoices.write().await;
+            let invoice = Self::payable_invoice(&mut active, invoice_hash)?;
+
+            if amount_sats == 0 {
+                return Err(EscrowError::payment("HTLC amount must be greater than 0"));
+            }
+
+            if invoice.parts.iter().any(|part| part.htlc_id == htlc_id) {
+                return Err(EscrowError::payment(format!(
+                    "Duplicate HTLC {} for invoice {}",
+                    htlc_id, invoice_hash
+                )));
+            }
+
+            invoice.parts.push(HtlcPart {
+                htlc_id: htlc_id.to_string(),
+                amount_sats,
+                received_at: Utc::now(),
+            });
+
+            let total_sats = invoice.total_parts_sats();
+            if total_sats < invoice.amount_sats {
+                invoice.status = FundingStatus::Pending;
+                info!(
+                    "Holding HTLC part {} for {}: {} of {} sats received",
+                    htlc_id, invoice_hash, total_sats, invoice.amount_sats
+                );
+
+                InvoiceStatusUpdate {
+                    invoice_hash: invoice_hash.to_string(),
+                    status: FundingStatus::Pending,
+                    amount_sats: Some(total_sats),
+                    part_count: invoice.parts.len(),
+                    preimage: None,
+                    timestamp: Utc::now(),
+                }
+            } else {
+                self.evaluate_htlc_set(invoice_hash, invoice)?
+            }
+        };
+
+        self.notify_status(&update).await;
+
+        Ok(update)
+    }
+
+    /// Fail back every incomplete multi-part set older than `mpp_timeout_secs`
+    ///
+    /// All parts of a timed-out set are released together; the invoice stays
+    /// open so the payer can retry.
+    pub async fn expire_incomplete_htlc_sets(&self) -> Vec<InvoiceStatusUpdate> {
+        let timeout = chrono::Duration::seconds(self.config.mpp_timeout_secs as i64);
+        let now = Utc::now();
+        let mut updates = Vec::new();
+
+        {
+            let mut active = self.active_invoices.write().await;
+            for (invoice_hash, invoice) in active.iter_mut() {
+                // Accepted and settling holds are complete and never time out
+                if invoice.status != FundingStatus::Pending {
+                    continue;
+                }
+
+                let Some(first_part_at) = invoice.parts.iter().map(|part| part.received_at).min()
+                else {
+                    continue;
+                };
+
+                if now - first_part_at < timeout {
+                    continue;
+                }
+
+                warn!(
+                    "MPP timeout for {}: failing back {} part(s) totalling {} sats",
+                    invoice_hash,
+                    invoice.parts.len(),
+                    invoice.total_parts_sats()
+                );
+
+                // In production, this would fail every held HTLC back via LDK
+                updates.push(InvoiceStatusUpdate {
+                    invoice_hash: invoice_hash.clone(),
+                    status: FundingStatus::Created,
+                    amount_sats: Some(invoice.total_parts_sats()),
+                    part_count: invoice.parts.len(),
+                    preimage: None,
+                    timestamp: now,
+                });
+                invoice.parts.clear();
+                invoice.status = FundingStatus::Created;
+            }
+        }
+
+        for update in &updates {
+            self.notify_status(update).await;
+        }
+
+        updates
+    }
+
+    /// Get an invoice that can still receive payment
+    fn payable_invoice<'a>(
+        active: &'a mut HashMap<String, ActiveInvoice>,
+        invoice_hash: &str,
+    ) -> EscrowResult<&'a mut ActiveInvoice> {
+        let invoice = active
+            .get_mut(invoice_hash)
+            .ok_or_else(|| EscrowError::invoice(format!("Invoice {} not found", invoice_hash)))?;
+
+        if !matches!(
+            invoice.status,
+            FundingStatus::Created | FundingStatus::Pending
+        ) {
             return Err(EscrowError::invoice(format!(
-                "Invoice {} not found",
+                "Invoice {} already paid",
                 invoice_hash
             )));
         }
 
-        // In a real implementation, this would query LDK for the actual status
-        // For now, we'll assume it's pending if it's in our active list
-        Ok(FundingStatus::Created)
+        if Utc::now() > invoice.expires_at {
+            return Err(EscrowError::invoice(format!(
+                "Invoice {} expired",
+                invoice_hash
+            )));
+        }
+
+        Ok(invoice)
+    }
+
+    /// Accept or refuse a complete HTLC set as a unit
+    fn evaluate_htlc_set(
+        &self,
+        invoice_hash: &str,
+        invoice: &mut ActiveInvoice,
+    ) -> EscrowResult<InvoiceStatusUpdate> {
+        let amount_received_sats = invoice.total_parts_sats();
+
+        if amount_received_sats < invoice.amount_sats {
+            warn!(
+                "Rejecting underpayment for {}: {} of {} sats",
+                invoice_hash, amount_received_sats, invoice.amount_sats
+            );
+            invoice.parts.clear();
+            invoice.status = FundingStatus::Created;
+            return Err(EscrowError::payment(format!(
+                "Underpayment: received {} of {} sats",
+                amount_received_sats, invoice.amount_sats
+            )));
+        }
+
+        let max_overpayment_sats =
+            (invoice.amount_sats as f64 * self.config.max_overpayment_percent / 100.0) as u64;
+        if amount_received_sats - invoice.amount_sats > max_overpayment_sats {
+            warn!(
+                "Rejecting overpayment for {}: {} sats for a {} sat invoice",
+                invoice_hash, amount_received_sats, invoice.amount_sats
+            );
+            invoice.parts.clear();
+            invoice.status = FundingStatus::Created;
+            return Err(EscrowError::payment(format!(
+                "Overpayment: received {} sats, maximum accepted is {}",
+                amount_received_sats,
+                invoice.amount_sats + max_overpayment_sats
+            )));
+        }
+
+        invoice.received_sats = amount_received_sats;
+        invoice.status = FundingStatus::Accepted;
+
+        info!(
+            "Accepted {} sats in {} part(s) for hold invoice {}",
+            amount_received_sats,
+            invoice.parts.len(),
+            invoice_hash
+        );
+
+        Ok(InvoiceStatusUpdate {
+            invoice_hash: invoice_hash.to_string(),
+            status: FundingStatus::Accepted,
+            amount_sats: Some(amount_received_sats),
+            part_count: invoice.parts.len(),
+            preimage: None,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Invoke the registered status callback for an invoice, if any
+    async fn notify_status(&self, update: &InvoiceStatusUpdate) {
+        if let Some(callback) = self.status_callbacks.read().await.get(&update.invoice_hash) {
+            callback(update.clone());
+        }
     }
 
     /// Monitor invoice for payment and status changes
@@ -166,65 +487,178 @@ impl EscrowEngine {
     ) -> EscrowResult<InvoiceSettlementData> {
         info!("Settling hold invoice: {}", hold_invoice_id);
 
-        // Find the invoice hash for this hold invoice ID
-        let invoice_hash = {
-            let active = self.active_invoices.read().await;
-            active
-                .iter()
-                .find(|(_, id)| *id == hold_invoice_id)
-                .map(|(hash, _)| hash.clone())
-                .ok_or_else(|| {
-                    EscrowError::invoice(format!("Hold invoice {} not found", hold_invoice_id))
-                })?
-        };
-
-        // Validate worker invoice format (basic check)
+        // Validate worker invoice format before releasing the held funds
         if worker_invoice.is_empty() {
             return Err(EscrowError::invoice("Worker invoice cannot be empty"));
         }
+        network::validate_payout_destination(worker_invoice, self.config.network)?;
 
-        // In a real implementation, this would interact with LDK to settle and route payment
+        // Mark the hold in flight so a concurrent settle, cancel or expiry
+        // cannot act on it while the worker is being paid
+        let received_sats = {
+            let mut active = self.active_invoices.write().await;
+            let invoice_hash =
+                Self::held_invoice_hash(&active, hold_invoice_id, FundingStatus::Accepted)?;
+            let invoice = active.get_mut(&invoice_hash).ok_or_else(|| {
+                EscrowError::invoice(format!("Invoice {} not found", invoice_hash))
+            })?;
+            invoice.status = FundingStatus::Settling;
+            invoice.received_sats
+        };
 
-        // For this demo, we'll simulate the settlement
-        let preimage = self.simulate_preimage_retrieval(&invoice_hash).await?;
+        // Pay the worker first: if the payout fails the funds go back to
+        // being held and can still be paid out or refunded
+        if let Err(e) = self.pay_destination(worker_invoice, received_sats).await {
+            let mut active = self.active_invoices.write().await;
+            if let Some(invoice) = active
+                .values_mut()
+                .find(|invoice| invoice.hold_invoice_id == hold_invoice_id)
+            {
+                invoice.status = FundingStatus::Accepted;
+            }
+            return Err(e);
+        }
 
-        // Simulate payment routing to worker
-        self.simulate_payment_routing(worker_invoice, &preimage)
+        let settlement_data = self
+            .take_hold_invoice(hold_invoice_id, FundingStatus::Settling)
             .await?;
 
-        // Remove from active invoices
-        self.active_invoices.write().await.remove(&invoice_hash);
+        info!("Successfully settled hold invoice: {}", hold_invoice_id);
 
-        let settlement_data = InvoiceSettlementData {
+        Ok(settlement_data)
+    }
+
+    /// Release a hold invoice into the node's balance without paying out
+    ///
+    /// Used by batched settlement, where several released holds are paid to
+    /// the same destination in one payment via `pay_destination`.
+    pub async fn release_hold_invoice(
+        &self,
+        hold_invoice_id: &str,
+    ) -> EscrowResult<InvoiceSettlementData> {
+        self.take_hold_invoice(hold_invoice_id, FundingStatus::Accepted)
+            .await
+    }
+
+    /// Claim the held HTLCs of a hold invoice in the expected status
+    async fn take_hold_invoice(
+        &self,
+        hold_invoice_id: &str,
+        expected: FundingStatus,
+    ) -> EscrowResult<InvoiceSettlementData> {
+        let (invoice_hash, invoice) = {
+            let mut active = self.active_invoices.write().await;
+            let invoice_hash = Self::held_invoice_hash(&active, hold_invoice_id, expected)?;
+            let invoice = active.remove(&invoice_hash).ok_or_else(|| {
+                EscrowError::invoice(format!("Hold invoice {} not found", hold_invoice_id))
+            })?;
+            (invoice_hash, invoice)
+        };
+
+        // In a real implementation, this would claim the held HTLCs via LDK
+        let preimage = self.simulate_preimage_retrieval(&invoice_hash).await?;
+
+        info!("Released hold invoice: {}", hold_invoice_id);
+
+        Ok(InvoiceSettlementData {
             invoice_hash,
             preimage,
-            amount_sats: 0, // Would get actual amount from LDK
+            amount_sats: invoice.received_sats,
             settled_at: Utc::now(),
-        };
+        })
+    }
 
-        info!("Successfully settled hold invoice: {}", hold_invoice_id);
+    /// Pay an amount from the node's balance to a worker destination
+    pub async fn pay_destination(
+        &self,
+        destination: &str,
+        amount_sats: u64,
+    ) -> EscrowResult<PayoutData> {
+        network::validate_payout_destination(destination, self.config.network)?;
 
-        Ok(settlement_data)
+        if amount_sats == 0 {
+            return Err(EscrowError::payment("Payout amount must be greater than 0"));
+        }
+
+        // Simulate payment routing to worker
+        self.simulate_payment_routing(destination, amount_sats)
+            .await?;
+
+        info!("Paid {} sats to {}", amount_sats, destination);
+
+        Ok(PayoutData {
+            payment_id: format!("payout_{}", uuid::Uuid::new_v4()),
+            destination: destination.to_string(),
+            amount_sats,
+            paid_at: Utc::now(),
+        })
     }
 
     /// Cancel a hold invoice and return funds
-    pub async fn cancel_hold_invoice(&self, hold_invoice_id: &str) -> EscrowResult<()> {
+    pub async fn cancel_hold_invoice(
+        &self,
+        hold_invoice_id: &str,
+    ) -> EscrowResult<InvoiceCancellationData> {
         info!("Cancelling hold invoice: {}", hold_invoice_id);
 
-        // Find the invoice hash for this hold invoice ID
-        let invoice_hash = {
+        let (invoice_hash, invoice) = {
             let mut active = self.active_invoices.write().await;
-            active.remove(hold_invoice_id).ok_or_else(|| {
+            let (invoice_hash, status) = Self::find_hold_invoice(&active, hold_invoice_id)?;
+            // The held funds are already being paid to the worker
+            if status == FundingStatus::Settling {
+                return Err(EscrowError::invoice(format!(
+                    "Hold invoice {} is being settled",
+                    hold_invoice_id
+                )));
+            }
+
+            // In a real implementation, this would call LDK to cancel the hold invoice
+            // The held HTLCs (the exact received amount) would be failed back to the payer
+            let invoice = active.remove(&invoice_hash).ok_or_else(|| {
                 EscrowError::invoice(format!("Hold invoice {} not found", hold_invoice_id))
-            })?
+            })?;
+            (invoice_hash, invoice)
         };
 
-        // In a real implementation, this would call LDK to cancel the hold invoice
-        // The funds would be returned to the payer
-
         info!("Cancelled hold invoice: {}", hold_invoice_id);
 
-        Ok(())
+        Ok(InvoiceCancellationData {
+            invoice_hash,
+            refunded_sats: invoice.received_sats,
+            cancelled_at: Utc::now(),
+        })
+    }
+
+    /// Look up an active invoice by its hold invoice ID
+    fn find_hold_invoice(
+        active: &HashMap<String, ActiveInvoice>,
+        hold_invoice_id: &str,
+    ) -> EscrowResult<(String, FundingStatus)> {
+        active
+            .iter()
+            .find(|(_, invoice)| invoice.hold_invoice_id == hold_invoice_id)
+            .map(|(hash, invoice)| (hash.clone(), invoice.status))
+            .ok_or_else(|| {
+                EscrowError::invoice(format!("Hold invoice {} not found", hold_invoice_id))
+            })
+    }
+
+    /// Look up a hold invoice that must be in the expected status
+    fn held_invoice_hash(
+        active: &HashMap<String, ActiveInvoice>,
+        hold_invoice_id: &str,
+        expected: FundingStatus,
+    ) -> EscrowResult<String> {
+        let (invoice_hash, status) = Self::find_hold_invoice(active, hold_invoice_id)?;
+        if status == expected {
+            return Ok(invoice_hash);
+        }
+        Err(EscrowError::invoice(match status {
+            FundingStatus::Settling => {
+                format!("Hold invoice {} is already being settled", hold_invoice_id)
+            }
+            _ => format!("Hold invoice {} has not been paid", hold_invoice_id),
+        }))
     }
 
     /// Register a callback for invoice status updates
@@ -254,12 +688,12 @@ impl EscrowEngine {
     /// Simulate payment routing (in production, this would use LDK's payment routing)
     async fn simulate_payment_routing(
         &self,
-        _worker_invoice: &str,
-        _preimage: &str,
+        _destination: &str,
+        _amount_sats: u64,
     ) -> EscrowResult<()> {
         // In production, this would:
-        // 1. Parse the worker's invoice
-        // 2. Use LDK to route the payment using the preimage
+        // 1. Parse the worker's invoice or address
+        // 2. Use LDK to route the payment
         // 3. Confirm the payment completed successfully
 
         tokio::time::sleep(Duration::from_millis(100)).await; // Simulate processing time
@@ -267,6 +701,76 @@ impl EscrowEngine {
         Ok(())
     }
 
+    /// Persist a channel monitor update and stream it to backups
+    ///
+    /// In production, this is called from LDK's `Persist` implementation; the
+    /// update only counts as persisted once the local backup write succeeds.
+    pub async fn persist_channel_monitor(&self, update: ChannelMonitorUpdate) -> EscrowResult<()> {
+        let channel_id = update.channel_id.clone();
+        let update_id = update.update_id;
+
+        if let Some(backup) = &self.backup {
+            backup.record_monitor_update(update).await?;
+        }
+
+        self.channel_update_ids
+            .write()
+            .await
+            .insert(channel_id, update_id);
+
+        Ok(())
+    }
+
+    /// Register an additional remote backup sink
+    pub async fn add_backup_sink(&self, sink: Arc<dyn BackupSink>) -> EscrowResult<()> {
+        let backup = self.backup_manager()?;
+        backup.add_remote_sink(sink).await;
+        Ok(())
+    }
+
+    /// Compare the backup against the live channel monitor state
+    pub async fn get_backup_status(&self) -> EscrowResult<BackupStatus> {
+        let backup = self.backup_manager()?;
+        let live = self.channel_update_ids.read().await.clone();
+        Ok(backup.check_freshness(&live).await)
+    }
+
+    /// Restore channel monitors from backup into an empty LDK data directory
+    ///
+    /// Refuses to restore state older than any remote sink or `known_update_ids`,
+    /// and a backup past its maximum age unless `allow_old_backup` is set.
+    pub async fn restore_channel_monitors(
+        &self,
+        target_dir: &Path,
+        known_update_ids: &HashMap<String, u64>,
+        allow_old_backup: bool,
+    ) -> EscrowResult<RestoreReport> {
+        let backup = self.backup_manager()?;
+        warn!(
+            "Restoring channel monitors to {} - never run against a live node",
+            target_dir.display()
+        );
+        backup
+            .restore(target_dir, known_update_ids, allow_old_backup)
+            .await
+    }
+
+    fn backup_manager(&self) -> EscrowResult<&Arc<BackupManager>> {
+        self.backup
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("Channel monitor backups are not configured"))
+    }
+
+    /// Bitcoin network the node runs on
+    pub fn network(&self) -> Network {
+        self.config.network
+    }
+
+    /// Seconds an incomplete multi-part payment is held before failing back
+    pub fn mpp_timeout_secs(&self) -> u64 {
+        self.config.mpp_timeout_secs
+    }
+
     /// Get node information
     pub async fn get_node_info(&self) -> EscrowResult<NodeInfo> {
         // In production, this would query LDK for actual node information
@@ -349,4 +853,136 @@ mod tests {
             _ => panic!("Expected invoice error"),
         }
     }
+
+    #[tokio::test]
+    async fn test_partial_and_over_payment() {
+        let config = EscrowEngineConfig::default();
+        let engine = EscrowEngine::new(config).await.unwrap();
+
+        let invoice_data = engine
+            .create_hold_invoice(50000, "Test".to_string(), "task".to_string())
+            .await
+            .unwrap();
+        let hash = invoice_data.invoice_hash.as_str();
+
+        // Underpayment is refused and the invoice stays open
+        let result = engine.handle_payment_claimable(hash, 45000).await;
+        assert!(matches!(result, Err(EscrowError::Payment(_))));
+        assert_eq!(
+            engine.get_invoice_status(hash).await.unwrap(),
+            FundingStatus::Created
+        );
+
+        // Overpayment beyond the 1% tolerance is refused
+        let result = engine.handle_payment_claimable(hash, 55000).await;
+        assert!(matches!(result, Err(EscrowError::Payment(_))));
+
+        // Overpayment within tolerance is accepted and recorded exactly
+        let update = engine.handle_payment_claimable(hash, 50400).await.unwrap();
+        assert_eq!(update.status, FundingStatus::Accepted);
+        assert_eq!(engine.get_received_amount(hash).await.unwrap(), 50400);
+
+        // Duplicate payments are rejected
+        assert!(engine.handle_payment_claimable(hash, 50000).await.is_err());
+
+        let cancellation = engine
+            .cancel_hold_invoice(&invoice_data.hold_invoice_id)
+            .await
+            .unwrap();
+        assert_eq!(cancellation.refunded_sats, 50400);
+    }
+
+    #[tokio::test]
+    async fn test_multi_part_payment() {
+        let config = EscrowEngineConfig {
+            mpp_timeout_secs: 0,
+            ..EscrowEngineConfig::default()
+        };
+        let engine = EscrowEngine::new(config).await.unwrap();
+
+        let invoice_data = engine
+            .create_hold_invoice(9_000_000, "Large task".to_string(), "task".to_string())
+            .await
+            .unwrap();
+        let hash = invoice_data.invoice_hash.as_str();
+
+        // Incomplete set is held, then failed back as a unit on timeout
+        let update = engine
+            .receive_htlc_part(hash, "htlc_1", 4_000_000)
+            .await
+            .unwrap();
+        assert_eq!(update.status, FundingStatus::Pending);
+        let expired = engine.expire_incomplete_htlc_sets().await;
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].part_count, 1);
+        assert_eq!(
+            engine.get_invoice_status(hash).await.unwrap(),
+            FundingStatus::Created
+        );
+
+        // A complete set is accepted only once the last part arrives
+        engine
+            .receive_htlc_part(hash, "htlc_2", 3_000_000)
+            .await
+            .unwrap();
+        assert!(
+            engine
+                .receive_htlc_part(hash, "htlc_2", 3_000_000)
+                .await
+                .is_err()
+        );
+        engine
+            .receive_htlc_part(hash, "htlc_3", 3_000_000)
+            .await
+            .unwrap();
+        let update = engine
+            .receive_htlc_part(hash, "htlc_4", 3_000_000)
+            .await
+            .unwrap();
+        assert_eq!(update.status, FundingStatus::Accepted);
+        assert_eq!(update.part_count, 3);
+        assert_eq!(engine.get_received_amount(hash).await.unwrap(), 9_000_000);
+        assert!(engine.expire_incomplete_htlc_sets().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_settle_holds_invoice_until_paid_out() {
+        let config = EscrowEngineConfig::default();
+        let engine = EscrowEngine::new(config).await.unwrap();
+
+        let invoice_data = engine
+            .create_hold_invoice(50000, "Test".to_string(), "task".to_string())
+            .await
+            .unwrap();
+        let hash = invoice_data.invoice_hash.as_str();
+        let hold_invoice_id = invoice_data.hold_invoice_id.as_str();
+        engine.handle_payment_claimable(hash, 50000).await.unwrap();
+
+        // While the payout is in flight the hold can't be settled again,
+        // released, cancelled or expired
+        let (settled, competing) = tokio::join!(
+            engine.settle_hold_invoice(hold_invoice_id, "lnbc500u1pvjluez"),
+            async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                assert_eq!(
+                    engine.get_invoice_status(hash).await.unwrap(),
+                    FundingStatus::Settling
+                );
+                assert!(engine.expire_incomplete_htlc_sets().await.is_empty());
+                (
+                    engine
+                        .settle_hold_invoice(hold_invoice_id, "lnbc500u1pvjluez")
+                        .await,
+                    engine.release_hold_invoice(hold_invoice_id).await,
+                    engine.cancel_hold_invoice(hold_invoice_id).await,
+                )
+            }
+        );
+
+        assert_eq!(settled.unwrap().amount_sats, 50000);
+        assert!(matches!(competing.0, Err(EscrowError::Invoice(_))));
+        assert!(matches!(competing.1, Err(EscrowError::Invoice(_))));
+        assert!(matches!(competing.2, Err(EscrowError::Invoice(_))));
+        assert!(engine.cancel_hold_invoice(hold_invoice_id).await.is_err());
+    }
 }
@@ -143,4 +143,9 @@ impl EscrowError {
     pub fn internal<S: Into<String>>(msg: S) -> Self {
         Self::Internal(msg.into())
     }
+
+    /// Whether the operation may succeed when retried unchanged
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Timeout(_) | Self::ExternalApi(_))
+    }
 }
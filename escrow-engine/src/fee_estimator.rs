@@ -0,0 +1,316 @@
+//! Fee Estimator - On-chain fee rates and Lightning routing fees
+//!
+//! Fee rates come from pluggable sources (a bitcoind node over JSON-RPC, an
+//! Esplora instance) tried in order; Lightning routing fees come from
+//! probing the route to a destination. When every source is unavailable the
+//! payment coordinator falls back to a static fee schedule so quotes and
+//! transactions never block on an external service.
+
+use crate::{EscrowResult, chain_source::BitcoindRpc, error::EscrowError};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+/// Source of on-chain fee rates
+#[async_trait]
+pub trait FeeEstimator: Send + Sync {
+    /// Human-readable source name
+    fn name(&self) -> &str;
+
+    /// Fee rate (sat/vB) expected to confirm within `target_blocks`
+    async fn estimate_fee_rate(&self, target_blocks: u16) -> EscrowResult<f64>;
+}
+
+/// Lightning routing fee source
+#[async_trait]
+pub trait RoutingFeeProber: Send + Sync {
+    /// Routing fee in sats for paying `amount_sats` to `destination`
+    /// (an invoice or node id), or to a well-connected node when `None`
+    async fn probe_routing_fee(
+        &self,
+        destination: Option<&str>,
+        amount_sats: u64,
+    ) -> EscrowResult<u64>;
+}
+
+/// Configured fee rate source
+#[derive(Debug, Clone)]
+pub enum FeeSourceConfig {
+    /// bitcoind JSON-RPC endpoint
+    Bitcoind {
+        rpc_url: String,
+        rpc_user: Option<String>,
+        rpc_password: Option<String>,
+    },
+    /// Esplora REST API base URL
+    Esplora { base_url: String },
+}
+
+impl FeeSourceConfig {
+    /// Build the estimator for this source
+    pub fn build(&self, timeout: Duration) -> EscrowResult<Arc<dyn FeeEstimator>> {
+        Ok(match self {
+            FeeSourceConfig::Bitcoind {
+                rpc_url,
+                rpc_user,
+                rpc_password,
+            } => {
+                let credentials = rpc_user
+                    .clone()
+                    .map(|user| (user, rpc_password.clone().unwrap_or_default()));
+                Arc::new(BitcoindFeeEstimator::new(
+                    rpc_url.clone(),
+                    credentials,
+                    timeout,
+                )?)
+            }
+            FeeSourceConfig::Esplora { base_url } => {
+                Arc::new(EsploraFeeEstimator::new(base_url.clone(), timeout)?)
+            }
+        })
+    }
+}
+
+/// Fees and limits used when live sources are unavailable
+#[derive(Debug, Clone)]
+pub struct StaticFeeSchedule {
+    /// Lightning routing fee (parts per million)
+    pub lightning_fee_ppm: u64,
+    /// Submarine swap service and miner fees (parts per million)
+    pub submarine_fee_ppm: u64,
+    /// Reverse swap service and miner fees (parts per million)
+    pub reverse_fee_ppm: u64,
+    /// Smallest submarine swap
+    pub submarine_min_sats: u64,
+    /// Smallest reverse swap
+    pub reverse_min_sats: u64,
+    /// Smallest multisig escrow worth its settlement fees
+    pub multisig_min_sats: u64,
+}
+
+impl Default for StaticFeeSchedule {
+    fn default() -> Self {
+        Self {
+            lightning_fee_ppm: 1_000, // 0.1%
+            submarine_fee_ppm: 5_000, // 0.5%
+            reverse_fee_ppm: 3_000,   // 0.3%
+            submarine_min_sats: 10_000,
+            reverse_min_sats: 50_000,
+            multisig_min_sats: 100_000,
+        }
+    }
+}
+
+/// `amount_sats` times `ppm` parts per million, rounded up
+pub fn ppm_fee(amount_sats: u64, ppm: u64) -> u64 {
+    (amount_sats as u128 * ppm as u128).div_ceil(1_000_000) as u64
+}
+
+/// Fee estimator returning a fixed rate
+pub struct StaticFeeEstimator {
+    fee_rate_sat_vb: f64,
+}
+
+impl StaticFeeEstimator {
+    /// Create an estimator always returning `fee_rate_sat_vb`
+    pub fn new(fee_rate_sat_vb: f64) -> Self {
+        Self { fee_rate_sat_vb }
+    }
+}
+
+#[async_trait]
+impl FeeEstimator for StaticFeeEstimator {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    async fn estimate_fee_rate(&self, _target_blocks: u16) -> EscrowResult<f64> {
+        Ok(self.fee_rate_sat_vb)
+    }
+}
+
+/// Fee estimator backed by bitcoind's `estimatesmartfee`
+pub struct BitcoindFeeEstimator {
+    rpc: BitcoindRpc,
+}
+
+/// Result of `estimatesmartfee`
+#[derive(Debug, Deserialize)]
+struct SmartFeeEstimate {
+    /// Fee rate in BTC/kvB
+    feerate: Option<f64>,
+    #[serde(default)]
+    errors: Vec<String>,
+}
+
+impl BitcoindFeeEstimator {
+    /// Create an estimator for the node at `rpc_url`
+    pub fn new(
+        rpc_url: impl Into<String>,
+        credentials: Option<(String, String)>,
+        timeout: Duration,
+    ) -> EscrowResult<Self> {
+        Ok(Self {
+            rpc: BitcoindRpc::new(rpc_url, credentials, timeout)?,
+        })
+    }
+}
+
+#[async_trait]
+impl FeeEstimator for BitcoindFeeEstimator {
+    fn name(&self) -> &str {
+        "bitcoind"
+    }
+
+    async fn estimate_fee_rate(&self, target_blocks: u16) -> EscrowResult<f64> {
+        let estimate: SmartFeeEstimate = self
+            .rpc
+            .call("estimatesmartfee", json!([target_blocks]))
+            .await?;
+        match estimate.feerate {
+            // BTC/kvB -> sat/vB
+            Some(btc_per_kvb) => Ok(btc_per_kvb * 100_000_000.0 / 1_000.0),
+            None => Err(EscrowError::external_api(format!(
+                "bitcoind has no fee estimate: {}",
+                estimate.errors.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Fee estimator backed by an Esplora `/fee-estimates` endpoint
+pub struct EsploraFeeEstimator {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl EsploraFeeEstimator {
+    /// Create an estimator for the Esplora API at `base_url`
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: http_client(timeout)?,
+        })
+    }
+}
+
+#[async_trait]
+impl FeeEstimator for EsploraFeeEstimator {
+    fn name(&self) -> &str {
+        "esplora"
+    }
+
+    async fn estimate_fee_rate(&self, target_blocks: u16) -> EscrowResult<f64> {
+        let url = format!("{}/fee-estimates", self.base_url);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Esplora request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(EscrowError::external_api(format!(
+                "Esplora {} returned {}",
+                url,
+                response.status().as_u16()
+            )));
+        }
+        let estimates: HashMap<String, f64> = response
+            .json()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Invalid Esplora response: {}", e)))?;
+
+        // Esplora answers for a fixed set of targets: take the slowest one
+        // still within the requested target, or the fastest available
+        let mut estimates: Vec<(u16, f64)> = estimates
+            .into_iter()
+            .filter_map(|(target, rate)| Some((target.parse().ok()?, rate)))
+            .collect();
+        estimates.sort_by_key(|(target, _)| *target);
+        estimates
+            .iter()
+            .rev()
+            .find(|(target, _)| *target <= target_blocks)
+            .or(estimates.first())
+            .map(|(_, rate)| *rate)
+            .ok_or_else(|| EscrowError::external_api("Esplora returned no fee estimates"))
+    }
+}
+
+pub(crate) fn http_client(timeout: Duration) -> EscrowResult<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| EscrowError::external_api(format!("Failed to build HTTP client: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+
+    #[tokio::test]
+    async fn test_bitcoind_estimate() {
+        let server = MockHttpServer::start(|request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            match body["params"][0].as_u64() {
+                Some(6) => MockResponse::json(200, json!({
+                    "result": { "feerate": 0.00012, "blocks": 6 },
+                    "error": null,
+                    "id": "escrow"
+                })),
+                _ => MockResponse::json(200, json!({
+                    "result": { "errors": ["Insufficient data or no feerate found"], "blocks": 0 },
+                    "error": null,
+                    "id": "escrow"
+                })),
+            }
+        })
+        .await;
+        let estimator = BitcoindFeeEstimator::new(
+            server.url(),
+            Some(("user".to_string(), "pass".to_string())),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        assert_eq!(estimator.estimate_fee_rate(6).await.unwrap(), 12.0);
+        assert!(estimator.estimate_fee_rate(1).await.is_err());
+        let requests = server.requests().await;
+        assert!(
+            requests[0]
+                .header("authorization")
+                .unwrap()
+                .starts_with("Basic ")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_esplora_estimate_picks_target() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/api/fee-estimates" => MockResponse::json(
+                200,
+                json!({
+                    "1": 25.0, "2": 20.0, "6": 8.5, "144": 1.0
+                }),
+            ),
+            _ => MockResponse::json(404, json!({})),
+        })
+        .await;
+        let estimator =
+            EsploraFeeEstimator::new(format!("{}/api/", server.url()), Duration::from_secs(5))
+                .unwrap();
+
+        assert_eq!(estimator.estimate_fee_rate(6).await.unwrap(), 8.5);
+        assert_eq!(estimator.estimate_fee_rate(10).await.unwrap(), 8.5);
+        assert_eq!(estimator.estimate_fee_rate(1000).await.unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_ppm_fee_rounds_up() {
+        assert_eq!(ppm_fee(100_000, 1_000), 100);
+        assert_eq!(ppm_fee(1_001, 1_000), 2);
+        assert_eq!(ppm_fee(0, 5_000), 0);
+    }
+}
@@ -6,15 +6,37 @@
 //! - PostgreSQL for state management
 //! - Cryptographic verification for security
 
+pub mod acceptance_criteria;
+pub mod backup;
+pub mod boltz;
+pub mod cashu;
+pub mod chain_source;
+pub mod chain_watcher;
 pub mod engine;
 pub mod error;
+pub mod fee_estimator;
 pub mod models;
+pub mod multisig_escrow;
+pub mod musig;
+pub mod network;
 pub mod node;
 pub mod nostr_publisher;
 pub mod payment_coordinator;
+pub mod price_oracle;
+pub mod proof_archive;
+pub mod proof_fetcher;
+pub mod proof_verifier;
 pub mod reputation_indexer;
+pub mod settlement_scheduler;
+pub mod signed_action;
+pub mod swap_script;
+pub mod taproot_escrow;
 pub mod task_manager;
 pub mod verification_service;
+pub mod webhook_dispatcher;
+
+#[cfg(test)]
+mod test_utils;
 
 use error::EscrowError;
 
@@ -81,6 +81,8 @@ pub enum FundingMode {
     OnchainReverse,
     /// Multi-signature on-chain escrow (last resort)
     OnchainMultisig,
+    /// Cashu ecash locked to the escrow's key, refundable after a timelock
+    Cashu,
 }
 
 /// Funding status enum
@@ -92,6 +94,8 @@ pub enum FundingStatus {
     Pending,
     /// Payment confirmed and held
     Accepted,
+    /// Held while the payout it funds is in flight
+    Settling,
     /// Preimage revealed, funds released
     Settled,
     /// Hold cancelled, funds returned
@@ -134,11 +138,15 @@ pub struct Task {
     pub description: Option<String>,
     pub reward_sats: i64,
     pub currency: String,
+    /// Fiat price of fiat-denominated tasks; `reward_sats` is then the
+    /// amount locked by the latest funding
+    pub reward_fiat: Option<FiatAmount>,
     pub state: TaskState,
 
     // Parties
     pub employer_pubkey: String,
     pub worker_pubkey: Option<String>,
+    pub payout_destination: Option<String>,
 
     // Funding reference
     pub funding_id: Option<Uuid>,
@@ -147,6 +155,8 @@ pub struct Task {
     pub proof_url: Option<String>,
     pub proof_hash: Option<String>,
     pub proof_nostr_event_id: Option<String>,
+    /// Signed proof event as submitted, kept for audits
+    pub proof_nostr_event: Option<String>,
 
     // Verification
     pub verified_by: Option<String>,
@@ -184,6 +194,7 @@ pub struct Funding {
 
     // Amount & expiry
     pub amount_sats: i64,
+    pub amount_received_sats: Option<i64>,
     pub expires_at: Option<DateTime<Utc>>,
 
     // On-chain / Submarine swap
@@ -191,6 +202,8 @@ pub struct Funding {
     pub swap_id: Option<String>,
     pub lockup_script: Option<String>,
     pub timeout_block: Option<i32>,
+    /// Employer address refunds of on-chain fundings are paid to
+    pub refund_address: Option<String>,
 
     // Status tracking
     pub status: FundingStatus,
@@ -198,6 +211,9 @@ pub struct Funding {
     pub settled_at: Option<DateTime<Utc>>,
     pub cancelled_at: Option<DateTime<Utc>>,
 
+    /// Exchange rate the amount was locked at, for fiat-denominated tasks
+    pub fiat_quote: Option<FiatQuote>,
+
     // External references
     pub external_id: Option<String>,
     pub external_metadata: Option<serde_json::Value>,
@@ -207,6 +223,33 @@ pub struct Funding {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Amount of a fiat currency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FiatAmount {
+    /// ISO 4217 code, e.g. "USD"
+    pub currency: String,
+    /// Amount in cents (minor units)
+    pub amount_cents: i64,
+}
+
+impl std::fmt::Display for FiatAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02} {}", self.amount_cents / 100, self.amount_cents % 100, self.currency)
+    }
+}
+
+/// Fiat amount converted to sats at a snapshotted exchange rate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiatQuote {
+    pub amount: FiatAmount,
+    pub amount_sats: u64,
+    /// Price of one bitcoin in the amount's currency
+    pub btc_price: f64,
+    /// Price oracle the rate came from
+    pub source: String,
+    pub quoted_at: DateTime<Utc>,
+}
+
 /// Escrow event for audit trail
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EscrowEvent {
@@ -359,6 +402,23 @@ pub struct InvoiceSettlementData {
     pub settled_at: DateTime<Utc>,
 }
 
+/// Outgoing payout data from LDK
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutData {
+    pub payment_id: String,
+    pub destination: String,
+    pub amount_sats: u64,
+    pub paid_at: DateTime<Utc>,
+}
+
+/// Invoice cancellation data from LDK
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceCancellationData {
+    pub invoice_hash: String,
+    pub refunded_sats: u64,
+    pub cancelled_at: DateTime<Utc>,
+}
+
 /// State transition validation
 #[derive(Debug, Clone)]
 pub struct StateTransition {
@@ -383,13 +443,16 @@ impl Task {
             description,
             reward_sats,
             currency: "BTC".to_string(),
+            reward_fiat: None,
             state: TaskState::Draft,
             employer_pubkey,
             worker_pubkey: None,
+            payout_destination: None,
             funding_id: None,
             proof_url: None,
             proof_hash: None,
             proof_nostr_event_id: None,
+            proof_nostr_event: None,
             verified_by: None,
             verified_at: None,
             verification_reason: None,
@@ -470,15 +533,18 @@ impl Funding {
             preimage_hash: None,
             hold_invoice_id: None,
             amount_sats,
+            amount_received_sats: None,
             expires_at,
             onchain_address: None,
             swap_id: None,
             lockup_script: None,
             timeout_block: None,
+            refund_address: None,
             status: FundingStatus::Created,
             payment_received_at: None,
             settled_at: None,
             cancelled_at: None,
+            fiat_quote: None,
             external_id: None,
             external_metadata: None,
             created_at: Utc::now(),
@@ -0,0 +1,522 @@
+//! Multisig Escrow - 2-of-3 P2WSH escrow outputs and the PSBTs spending them
+//!
+//! On-chain escrow locks the reward in a P2WSH output spendable by any two of
+//! the employer, the worker and the escrow node acting as arbitrator:
+//! - release: employer and worker pay the worker
+//! - refund: employer and worker return the funds to the employer
+//! - arbitrated: the arbitrator and one party split the funds as decided
+//!
+//! Spends are handed to the parties as PSBTs. Each party adds its signature;
+//! the node combines them, checks every signature against the escrow keys
+//! and finalises the witness.
+
+use crate::{
+    EscrowResult,
+    error::EscrowError,
+    network::{self, Network},
+    swap_script::DUST_LIMIT_SATS,
+};
+use bitcoin::{
+    Address, Amount, EcdsaSighashType, OutPoint, Psbt, Script, ScriptBuf, Sequence, Transaction,
+    TxIn, TxOut, Witness,
+    absolute::LockTime,
+    ecdsa,
+    hashes::Hash,
+    opcodes::all::{OP_CHECKMULTISIG, OP_PUSHNUM_2, OP_PUSHNUM_3},
+    script::Builder,
+    sighash::SighashCache,
+    transaction,
+};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+
+/// Keys of the three escrow participants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultisigKeys {
+    pub employer: PublicKey,
+    pub worker: PublicKey,
+    /// Escrow node key, used to settle disputes
+    pub arbitrator: PublicKey,
+}
+
+/// How a multisig escrow is settled
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MultisigOutcome {
+    /// Everything to the worker
+    Release { worker_address: String },
+    /// Everything back to the employer
+    Refund { employer_address: String },
+    /// Split decided by the arbitrator; the fee comes out of the employer's share
+    Arbitrated {
+        worker_address: String,
+        worker_sats: u64,
+        employer_address: String,
+    },
+    /// Everything back to the employer alone after the refund delay
+    /// (Taproot escrows only)
+    TimeoutRefund { employer_address: String },
+}
+
+/// A 2-of-3 P2WSH escrow output
+#[derive(Debug, Clone)]
+pub struct MultisigEscrow {
+    keys: MultisigKeys,
+    witness_script: ScriptBuf,
+    address: Address,
+    network: Network,
+}
+
+impl MultisigEscrow {
+    /// Build the escrow for the given participants
+    pub fn new(keys: MultisigKeys, network: Network) -> Self {
+        let witness_script = multisig_script(&keys);
+        let address = Address::p2wsh(&witness_script, network);
+
+        Self {
+            keys,
+            witness_script,
+            address,
+            network,
+        }
+    }
+
+    /// Participant keys
+    pub fn keys(&self) -> &MultisigKeys {
+        &self.keys
+    }
+
+    /// `2 <key> <key> <key> 3 CHECKMULTISIG`, committed to by the address
+    pub fn witness_script(&self) -> &Script {
+        &self.witness_script
+    }
+
+    /// Escrow address the employer funds
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    /// Find the output of `tx` paying this escrow
+    pub fn find_funding(&self, tx: &Transaction) -> Option<(OutPoint, TxOut)> {
+        let script_pubkey = self.address.script_pubkey();
+        tx.output
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.script_pubkey == script_pubkey)
+            .map(|(vout, output)| (OutPoint::new(tx.txid(), vout as u32), output.clone()))
+    }
+
+    /// Build the unsigned PSBT settling the escrow output with `outcome`
+    ///
+    /// The fee is `fee_rate_sat_vb` times the virtual size of the transaction
+    /// once two signatures are added.
+    pub fn settlement_psbt(
+        &self,
+        funding: OutPoint,
+        funding_output: &TxOut,
+        outcome: &MultisigOutcome,
+        fee_rate_sat_vb: u64,
+    ) -> EscrowResult<Psbt> {
+        if matches!(outcome, MultisigOutcome::TimeoutRefund { .. }) {
+            return Err(EscrowError::payment(
+                "P2WSH escrows have no timeout refund path",
+            ));
+        }
+
+        let placeholder_witness = Witness::from_slice(&[
+            &[][..],
+            &[0u8; 73],
+            &[0u8; 73],
+            self.witness_script.as_bytes(),
+        ]);
+        let transaction = settlement_transaction(
+            funding,
+            funding_output,
+            outcome,
+            self.network,
+            Sequence::ENABLE_RBF_NO_LOCKTIME,
+            placeholder_witness,
+            fee_rate_sat_vb,
+        )?;
+
+        let mut psbt = Psbt::from_unsigned_tx(transaction)
+            .map_err(|e| EscrowError::internal(format!("Failed to build PSBT: {}", e)))?;
+        psbt.inputs[0].witness_utxo = Some(funding_output.clone());
+        psbt.inputs[0].witness_script = Some(self.witness_script.clone());
+        Ok(psbt)
+    }
+
+    /// Add a signature by `secret_key` to the PSBT
+    pub fn sign_psbt(&self, psbt: &mut Psbt, secret_key: &SecretKey) -> EscrowResult<()> {
+        let secp = Secp256k1::new();
+        let public_key = PublicKey::from_secret_key(&secp, secret_key);
+        if !self.participants().contains(&public_key) {
+            return Err(EscrowError::crypto("Key is not part of this escrow"));
+        }
+
+        let message = self.sighash(psbt)?;
+        let signature = ecdsa::Signature::sighash_all(secp.sign_ecdsa(&message, secret_key));
+        psbt.inputs[0]
+            .partial_sigs
+            .insert(bitcoin::PublicKey::new(public_key), signature);
+        Ok(())
+    }
+
+    /// Merge signed copies of `psbt`, check their signatures and finalise
+    ///
+    /// Fails unless two distinct escrow participants signed the exact
+    /// transaction.
+    pub fn finalize(&self, mut psbt: Psbt, signed: Vec<Psbt>) -> EscrowResult<Transaction> {
+        for other in signed {
+            psbt.combine(other).map_err(|e| {
+                EscrowError::payment(format!("PSBT does not match the settlement: {}", e))
+            })?;
+        }
+
+        if psbt.inputs.len() != 1
+            || psbt.inputs[0].witness_script.as_deref() != Some(self.witness_script())
+        {
+            return Err(EscrowError::payment("PSBT does not spend this escrow"));
+        }
+
+        let secp = Secp256k1::verification_only();
+        let message = self.sighash(&psbt)?;
+        let participants = self.participants();
+        for (public_key, signature) in &psbt.inputs[0].partial_sigs {
+            if !participants.contains(&public_key.inner) {
+                return Err(EscrowError::crypto(format!(
+                    "Signature by {} who is not part of this escrow",
+                    public_key
+                )));
+            }
+            if signature.hash_ty != EcdsaSighashType::All
+                || secp
+                    .verify_ecdsa(&message, &signature.sig, &public_key.inner)
+                    .is_err()
+            {
+                return Err(EscrowError::crypto(format!(
+                    "Invalid signature by {}",
+                    public_key
+                )));
+            }
+        }
+
+        // CHECKMULTISIG expects signatures in the order of the script's keys
+        let signatures: Vec<Vec<u8>> = sorted_keys(&self.keys)
+            .iter()
+            .filter_map(|key| {
+                psbt.inputs[0]
+                    .partial_sigs
+                    .get(&bitcoin::PublicKey::new(*key))
+            })
+            .take(2)
+            .map(|signature| signature.to_vec())
+            .collect();
+        if signatures.len() < 2 {
+            return Err(EscrowError::payment(format!(
+                "Settlement needs 2 signatures, has {}",
+                signatures.len()
+            )));
+        }
+
+        let mut witness = Witness::new();
+        // CHECKMULTISIG pops one extra element
+        witness.push([]);
+        for signature in &signatures {
+            witness.push(signature);
+        }
+        witness.push(self.witness_script.as_bytes());
+
+        let mut transaction = psbt.unsigned_tx;
+        transaction.input[0].witness = witness;
+        Ok(transaction)
+    }
+
+    fn participants(&self) -> [PublicKey; 3] {
+        [self.keys.employer, self.keys.worker, self.keys.arbitrator]
+    }
+
+    /// BIP-143 sighash of the PSBT's single escrow input
+    fn sighash(&self, psbt: &Psbt) -> EscrowResult<Message> {
+        let value = psbt.inputs[0]
+            .witness_utxo
+            .as_ref()
+            .ok_or_else(|| EscrowError::payment("PSBT is missing the escrow output"))?
+            .value;
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .p2wsh_signature_hash(0, &self.witness_script, value, EcdsaSighashType::All)
+            .map_err(|e| EscrowError::crypto(format!("Failed to compute sighash: {}", e)))?;
+        Ok(Message::from_digest(sighash.to_byte_array()))
+    }
+}
+
+/// Unsigned transaction paying out an escrow output according to `outcome`
+///
+/// `placeholder_witness` has the shape of the final witness; the fee is
+/// `fee_rate_sat_vb` times the resulting virtual size and comes out of the
+/// last output.
+pub(crate) fn settlement_transaction(
+    funding: OutPoint,
+    funding_output: &TxOut,
+    outcome: &MultisigOutcome,
+    network: Network,
+    sequence: Sequence,
+    placeholder_witness: Witness,
+    fee_rate_sat_vb: u64,
+) -> EscrowResult<Transaction> {
+    let total_sats = funding_output.value.to_sat();
+    // Shares in output order; the last one pays the fee
+    let shares = match outcome {
+        MultisigOutcome::Release { worker_address } => vec![(worker_address, total_sats)],
+        MultisigOutcome::Refund { employer_address }
+        | MultisigOutcome::TimeoutRefund { employer_address } => {
+            vec![(employer_address, total_sats)]
+        }
+        MultisigOutcome::Arbitrated {
+            worker_address,
+            worker_sats,
+            employer_address,
+        } => {
+            let employer_sats = total_sats.checked_sub(*worker_sats).ok_or_else(|| {
+                EscrowError::payment(format!(
+                    "Worker share of {} sats exceeds the escrowed {} sats",
+                    worker_sats, total_sats
+                ))
+            })?;
+            vec![
+                (worker_address, *worker_sats),
+                (employer_address, employer_sats),
+            ]
+            .into_iter()
+            .filter(|(_, sats)| *sats > 0)
+            .collect()
+        }
+    };
+
+    let output = shares
+        .into_iter()
+        .map(|(address, sats)| {
+            Ok(TxOut {
+                value: Amount::from_sat(sats),
+                script_pubkey: network::parse_address(address, network)?.script_pubkey(),
+            })
+        })
+        .collect::<EscrowResult<Vec<_>>>()?;
+
+    let mut transaction = Transaction {
+        version: transaction::Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: funding,
+            script_sig: ScriptBuf::new(),
+            sequence,
+            witness: placeholder_witness,
+        }],
+        output,
+    };
+    let fee_sats = transaction.vsize() as u64 * fee_rate_sat_vb;
+    transaction.input[0].witness = Witness::new();
+
+    let last = transaction
+        .output
+        .last_mut()
+        .ok_or_else(|| EscrowError::payment("Settlement has no outputs"))?;
+    let remaining = last
+        .value
+        .to_sat()
+        .checked_sub(fee_sats)
+        .filter(|amount| *amount >= DUST_LIMIT_SATS)
+        .ok_or_else(|| {
+            EscrowError::payment(format!(
+                "Share of {} sats cannot cover a {} sat fee",
+                last.value.to_sat(),
+                fee_sats
+            ))
+        })?;
+    last.value = Amount::from_sat(remaining);
+    if transaction
+        .output
+        .iter()
+        .any(|o| o.value.to_sat() < DUST_LIMIT_SATS)
+    {
+        return Err(EscrowError::payment(
+            "Settlement output below the dust limit",
+        ));
+    }
+
+    Ok(transaction)
+}
+
+/// Keys in lexicographic order (BIP-67), so every party derives the same script
+fn sorted_keys(keys: &MultisigKeys) -> [PublicKey; 3] {
+    let mut sorted = [keys.employer, keys.worker, keys.arbitrator];
+    sorted.sort_by_key(|key| key.serialize());
+    sorted
+}
+
+/// 2-of-3 witness script over the participants' keys
+fn multisig_script(keys: &MultisigKeys) -> ScriptBuf {
+    sorted_keys(keys)
+        .iter()
+        .fold(Builder::new().push_opcode(OP_PUSHNUM_2), |builder, key| {
+            builder.push_key(&bitcoin::PublicKey::new(*key))
+        })
+        .push_opcode(OP_PUSHNUM_3)
+        .push_opcode(OP_CHECKMULTISIG)
+        .into_script()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> (SecretKey, PublicKey) {
+        let secret_key = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (
+            secret_key,
+            PublicKey::from_secret_key(&Secp256k1::new(), &secret_key),
+        )
+    }
+
+    fn escrow() -> (MultisigEscrow, [SecretKey; 3]) {
+        let (employer_key, employer) = key(0x11);
+        let (worker_key, worker) = key(0x12);
+        let (arbitrator_key, arbitrator) = key(0x13);
+        let escrow = MultisigEscrow::new(
+            MultisigKeys {
+                employer,
+                worker,
+                arbitrator,
+            },
+            Network::Regtest,
+        );
+        (escrow, [employer_key, worker_key, arbitrator_key])
+    }
+
+    fn funding_tx(escrow: &MultisigEscrow, amount_sats: u64) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(amount_sats),
+                script_pubkey: escrow.address().script_pubkey(),
+            }],
+        }
+    }
+
+    const WORKER_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn employer_address() -> String {
+        let (_, employer) = key(0x11);
+        Address::p2wpkh(&bitcoin::PublicKey::new(employer), Network::Regtest)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_release_needs_two_valid_signatures() {
+        let (escrow, [employer_key, worker_key, _]) = escrow();
+        assert!(escrow.address().to_string().starts_with("bcrt1q"));
+        // Key order does not change the escrow
+        let (_, employer) = key(0x11);
+        let (_, worker) = key(0x12);
+        let (_, arbitrator) = key(0x13);
+        let swapped = MultisigEscrow::new(
+            MultisigKeys {
+                employer: worker,
+                worker: arbitrator,
+                arbitrator: employer,
+            },
+            Network::Regtest,
+        );
+        assert_eq!(swapped.address(), escrow.address());
+
+        let funding = funding_tx(&escrow, 100_000);
+        let (outpoint, output) = escrow.find_funding(&funding).unwrap();
+        let psbt = escrow
+            .settlement_psbt(
+                outpoint,
+                &output,
+                &MultisigOutcome::Release {
+                    worker_address: WORKER_ADDRESS.to_string(),
+                },
+                2,
+            )
+            .unwrap();
+
+        // Each party signs its own copy
+        let mut by_employer = psbt.clone();
+        escrow.sign_psbt(&mut by_employer, &employer_key).unwrap();
+        let mut by_worker = psbt.clone();
+        escrow.sign_psbt(&mut by_worker, &worker_key).unwrap();
+
+        // One signature is not enough
+        assert!(
+            escrow
+                .finalize(psbt.clone(), vec![by_employer.clone()])
+                .is_err()
+        );
+
+        // A signature over another transaction is rejected
+        let mut tampered = psbt.clone();
+        tampered.unsigned_tx.output[0].value = Amount::from_sat(1_000);
+        escrow.sign_psbt(&mut tampered, &worker_key).unwrap();
+        let mut forged = psbt.clone();
+        forged.inputs[0].partial_sigs = tampered.inputs[0].partial_sigs.clone();
+        assert!(
+            escrow
+                .finalize(psbt.clone(), vec![forged, by_employer.clone()])
+                .is_err()
+        );
+
+        let transaction = escrow.finalize(psbt, vec![by_employer, by_worker]).unwrap();
+        let witness = &transaction.input[0].witness;
+        assert_eq!(witness.len(), 4);
+        assert!(witness.nth(0).unwrap().is_empty());
+        assert_eq!(witness.nth(3).unwrap(), escrow.witness_script().as_bytes());
+        // The fee assumes maximum-size signatures
+        let fee_sats = 100_000 - transaction.output[0].value.to_sat();
+        assert!((0..=2).contains(&(fee_sats - transaction.vsize() as u64 * 2)));
+    }
+
+    #[test]
+    fn test_arbitrated_split() {
+        let (escrow, [_, worker_key, arbitrator_key]) = escrow();
+        let funding = funding_tx(&escrow, 100_000);
+        let (outpoint, output) = escrow.find_funding(&funding).unwrap();
+
+        let outcome = MultisigOutcome::Arbitrated {
+            worker_address: WORKER_ADDRESS.to_string(),
+            worker_sats: 60_000,
+            employer_address: employer_address(),
+        };
+        let mut psbt = escrow
+            .settlement_psbt(outpoint, &output, &outcome, 3)
+            .unwrap();
+        escrow.sign_psbt(&mut psbt, &arbitrator_key).unwrap();
+        escrow.sign_psbt(&mut psbt, &worker_key).unwrap();
+        let transaction = escrow.finalize(psbt, Vec::new()).unwrap();
+
+        // The worker gets exactly its share; the employer's share pays the fee
+        assert_eq!(transaction.output[0].value.to_sat(), 60_000);
+        let fee_sats = 40_000 - transaction.output[1].value.to_sat();
+        assert!((0..=3).contains(&(fee_sats - transaction.vsize() as u64 * 3)));
+
+        // Outsiders cannot sign and shares cannot exceed the escrow
+        let (outsider, _) = key(0x14);
+        let mut psbt = escrow
+            .settlement_psbt(outpoint, &output, &outcome, 3)
+            .unwrap();
+        assert!(escrow.sign_psbt(&mut psbt, &outsider).is_err());
+        let greedy = MultisigOutcome::Arbitrated {
+            worker_address: WORKER_ADDRESS.to_string(),
+            worker_sats: 100_001,
+            employer_address: employer_address(),
+        };
+        assert!(
+            escrow
+                .settlement_psbt(outpoint, &output, &greedy, 3)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,693 @@
+//! MuSig2 - Two-round Schnorr multi-signatures (BIP-327)
+//!
+//! Used for cooperative (key-path) spends of taproot outputs whose internal
+//! key is shared with a counterparty, such as Boltz swap lockups. Only the
+//! pieces the escrow needs are implemented: key aggregation with plain and
+//! x-only (taproot) tweaks, nonce generation and aggregation, partial signing,
+//! partial signature verification and signature aggregation.
+
+use crate::{EscrowResult, error::EscrowError};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey, schnorr};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// BIP-340 style tagged hash
+fn tagged_hash(tag: &str, data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    for chunk in data {
+        hasher.update(chunk);
+    }
+    hasher.finalize().into()
+}
+
+/// Interpret a hash as a non-zero scalar
+fn hash_to_key(hash: [u8; 32]) -> EscrowResult<SecretKey> {
+    SecretKey::from_slice(&hash).map_err(|_| EscrowError::crypto("MuSig2 hash out of range"))
+}
+
+fn add(a: SecretKey, b: &SecretKey) -> EscrowResult<SecretKey> {
+    a.add_tweak(&Scalar::from(*b))
+        .map_err(|e| EscrowError::crypto(format!("MuSig2 scalar addition failed: {}", e)))
+}
+
+fn mul(a: SecretKey, b: &SecretKey) -> EscrowResult<SecretKey> {
+    a.mul_tweak(&Scalar::from(*b))
+        .map_err(|e| EscrowError::crypto(format!("MuSig2 scalar multiplication failed: {}", e)))
+}
+
+fn has_even_y(point: &PublicKey) -> bool {
+    point.x_only_public_key().1 == secp256k1::Parity::Even
+}
+
+/// Aggregated public key of a MuSig2 signer set
+#[derive(Debug, Clone)]
+pub struct KeyAggContext {
+    pubkeys: Vec<PublicKey>,
+    list_hash: [u8; 32],
+    second_key: Option<PublicKey>,
+    aggregated: PublicKey,
+    /// Whether the accumulated sign factor `gacc` is -1
+    gacc_negated: bool,
+    /// Accumulated tweak (`None` is zero)
+    tacc: Option<SecretKey>,
+}
+
+impl KeyAggContext {
+    /// Aggregate public keys in the given order
+    pub fn new(pubkeys: Vec<PublicKey>) -> EscrowResult<Self> {
+        if pubkeys.is_empty() {
+            return Err(EscrowError::crypto("MuSig2 requires at least one key"));
+        }
+
+        let serialized: Vec<[u8; 33]> = pubkeys.iter().map(|pk| pk.serialize()).collect();
+        let slices: Vec<&[u8]> = serialized.iter().map(|pk| pk.as_slice()).collect();
+        let list_hash = tagged_hash("KeyAgg list", &slices);
+        let second_key = pubkeys.iter().find(|pk| **pk != pubkeys[0]).copied();
+
+        let mut ctx = Self {
+            pubkeys: pubkeys.clone(),
+            list_hash,
+            second_key,
+            aggregated: pubkeys[0],
+            gacc_negated: false,
+            tacc: None,
+        };
+
+        let secp = Secp256k1::verification_only();
+        let mut points = Vec::with_capacity(pubkeys.len());
+        for pk in &pubkeys {
+            let point = match ctx.coefficient(pk)? {
+                Some(a) => pk.mul_tweak(&secp, &Scalar::from(a)).map_err(|e| {
+                    EscrowError::crypto(format!("MuSig2 key aggregation failed: {}", e))
+                })?,
+                None => *pk,
+            };
+            points.push(point);
+        }
+        let refs: Vec<&PublicKey> = points.iter().collect();
+        ctx.aggregated = PublicKey::combine_keys(&refs)
+            .map_err(|e| EscrowError::crypto(format!("MuSig2 key aggregation failed: {}", e)))?;
+
+        Ok(ctx)
+    }
+
+    /// Key aggregation coefficient (`None` means 1)
+    fn coefficient(&self, pk: &PublicKey) -> EscrowResult<Option<SecretKey>> {
+        if Some(*pk) == self.second_key {
+            return Ok(None);
+        }
+        hash_to_key(tagged_hash(
+            "KeyAgg coefficient",
+            &[&self.list_hash, &pk.serialize()],
+        ))
+        .map(Some)
+    }
+
+    /// The (possibly tweaked) aggregated key
+    pub fn aggregated_key(&self) -> PublicKey {
+        self.aggregated
+    }
+
+    /// The aggregated key as an x-only key
+    pub fn x_only_public_key(&self) -> XOnlyPublicKey {
+        self.aggregated.x_only_public_key().0
+    }
+
+    /// Apply an x-only tweak (e.g. the taproot tweak)
+    pub fn with_xonly_tweak(self, tweak: Scalar) -> EscrowResult<Self> {
+        self.with_tweak(tweak, true)
+    }
+
+    /// Apply a plain tweak (e.g. a BIP-32 derivation step)
+    pub fn with_plain_tweak(self, tweak: Scalar) -> EscrowResult<Self> {
+        self.with_tweak(tweak, false)
+    }
+
+    fn with_tweak(mut self, tweak: Scalar, xonly: bool) -> EscrowResult<Self> {
+        let secp = Secp256k1::verification_only();
+
+        if xonly && !has_even_y(&self.aggregated) {
+            self.aggregated = self.aggregated.negate(&secp);
+            self.gacc_negated = !self.gacc_negated;
+            self.tacc = self.tacc.map(SecretKey::negate);
+        }
+
+        self.aggregated = self
+            .aggregated
+            .add_exp_tweak(&secp, &tweak)
+            .map_err(|e| EscrowError::crypto(format!("MuSig2 tweak failed: {}", e)))?;
+
+        let tweak_key = SecretKey::from_slice(&tweak.to_be_bytes()).ok();
+        self.tacc = match (self.tacc, tweak_key) {
+            (Some(tacc), Some(t)) => Some(add(tacc, &t)?),
+            (tacc, t) => tacc.or(t),
+        };
+
+        Ok(self)
+    }
+
+    /// The individual public keys, in aggregation order
+    pub fn pubkeys(&self) -> &[PublicKey] {
+        &self.pubkeys
+    }
+
+    fn contains(&self, pk: &PublicKey) -> bool {
+        self.pubkeys.contains(pk)
+    }
+
+    /// Whether the signing key is negated: `g * gacc` is -1
+    fn negates_key(&self) -> bool {
+        has_even_y(&self.aggregated) == self.gacc_negated
+    }
+}
+
+/// Secret nonce, consumed by the one signature it may be used for
+///
+/// Neither `Clone` nor `Copy`, and wiped from memory when dropped.
+pub struct SecretNonce {
+    k1: [u8; 32],
+    k2: [u8; 32],
+}
+
+impl SecretNonce {
+    fn keys(&self) -> EscrowResult<(SecretKey, SecretKey)> {
+        Ok((hash_to_key(self.k1)?, hash_to_key(self.k2)?))
+    }
+}
+
+impl Drop for SecretNonce {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
+/// Public nonce shared with the other signers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicNonce {
+    r1: PublicKey,
+    r2: PublicKey,
+}
+
+impl PublicNonce {
+    /// 66-byte serialization (`R1 || R2`)
+    pub fn serialize(&self) -> [u8; 66] {
+        let mut bytes = [0u8; 66];
+        bytes[..33].copy_from_slice(&self.r1.serialize());
+        bytes[33..].copy_from_slice(&self.r2.serialize());
+        bytes
+    }
+
+    /// Parse a 66-byte public nonce
+    pub fn from_slice(bytes: &[u8]) -> EscrowResult<Self> {
+        if bytes.len() != 66 {
+            return Err(EscrowError::crypto("MuSig2 public nonce must be 66 bytes"));
+        }
+        let parse = |slice: &[u8]| {
+            PublicKey::from_slice(slice)
+                .map_err(|e| EscrowError::crypto(format!("Invalid MuSig2 nonce: {}", e)))
+        };
+        Ok(Self {
+            r1: parse(&bytes[..33])?,
+            r2: parse(&bytes[33..])?,
+        })
+    }
+}
+
+/// Generate a fresh nonce pair
+pub fn generate_nonce() -> EscrowResult<(SecretNonce, PublicNonce)> {
+    let secp = Secp256k1::signing_only();
+    let random_bytes = || {
+        let mut bytes = [0u8; 32];
+        getrandom::getrandom(&mut bytes)
+            .map_err(|e| EscrowError::crypto(format!("Random number generation failed: {}", e)))?;
+        Ok::<_, EscrowError>(bytes)
+    };
+
+    let nonce = SecretNonce {
+        k1: random_bytes()?,
+        k2: random_bytes()?,
+    };
+    let (mut k1, mut k2) = nonce.keys()?;
+    let public = PublicNonce {
+        r1: PublicKey::from_secret_key(&secp, &k1),
+        r2: PublicKey::from_secret_key(&secp, &k2),
+    };
+    k1.non_secure_erase();
+    k2.non_secure_erase();
+
+    Ok((nonce, public))
+}
+
+/// Sum the public nonces of all signers
+pub fn aggregate_nonces(nonces: &[PublicNonce]) -> EscrowResult<PublicNonce> {
+    let combine = |points: Vec<&PublicKey>| {
+        PublicKey::combine_keys(&points)
+            .map_err(|e| EscrowError::crypto(format!("MuSig2 nonce aggregation failed: {}", e)))
+    };
+    Ok(PublicNonce {
+        r1: combine(nonces.iter().map(|n| &n.r1).collect())?,
+        r2: combine(nonces.iter().map(|n| &n.r2).collect())?,
+    })
+}
+
+/// Partial signature of one signer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartialSignature(SecretKey);
+
+impl PartialSignature {
+    /// 32-byte serialization
+    pub fn serialize(&self) -> [u8; 32] {
+        self.0.secret_bytes()
+    }
+
+    /// Parse a 32-byte partial signature
+    pub fn from_slice(bytes: &[u8]) -> EscrowResult<Self> {
+        SecretKey::from_slice(bytes)
+            .map(Self)
+            .map_err(|e| EscrowError::crypto(format!("Invalid MuSig2 partial signature: {}", e)))
+    }
+}
+
+/// Signing session over one message
+pub struct SigningSession {
+    ctx: KeyAggContext,
+    message: [u8; 32],
+    nonce_coefficient: SecretKey,
+    final_nonce: PublicKey,
+    challenge: SecretKey,
+}
+
+impl SigningSession {
+    /// Start a session from the aggregated nonce
+    pub fn new(
+        ctx: &KeyAggContext,
+        aggregated_nonce: &PublicNonce,
+        message: [u8; 32],
+    ) -> EscrowResult<Self> {
+        let secp = Secp256k1::verification_only();
+        let q_x = ctx.x_only_public_key().serialize();
+
+        let nonce_coefficient = hash_to_key(tagged_hash(
+            "MuSig/noncecoef",
+            &[&aggregated_nonce.serialize(), &q_x, &message],
+        ))?;
+
+        let r2 = aggregated_nonce
+            .r2
+            .mul_tweak(&secp, &Scalar::from(nonce_coefficient))
+            .map_err(|e| EscrowError::crypto(format!("MuSig2 nonce failed: {}", e)))?;
+        let final_nonce = PublicKey::combine_keys(&[&aggregated_nonce.r1, &r2])
+            .map_err(|e| EscrowError::crypto(format!("MuSig2 nonce failed: {}", e)))?;
+
+        let challenge = hash_to_key(tagged_hash(
+            "BIP0340/challenge",
+            &[
+                &final_nonce.x_only_public_key().0.serialize(),
+                &q_x,
+                &message,
+            ],
+        ))?;
+
+        Ok(Self {
+            ctx: ctx.clone(),
+            message,
+            nonce_coefficient,
+            final_nonce,
+            challenge,
+        })
+    }
+
+    /// Produce this signer's partial signature, consuming the secret nonce
+    pub fn partial_sign(
+        &self,
+        nonce: SecretNonce,
+        secret_key: &SecretKey,
+    ) -> EscrowResult<PartialSignature> {
+        let secp = Secp256k1::signing_only();
+        let pubkey = PublicKey::from_secret_key(&secp, secret_key);
+        if !self.ctx.contains(&pubkey) {
+            return Err(EscrowError::crypto(
+                "Signing key is not part of the MuSig2 key set",
+            ));
+        }
+
+        // The nonce is dropped, and wiped, whether or not signing succeeds
+        let (mut k1, mut k2) = nonce.keys()?;
+        drop(nonce);
+        if !has_even_y(&self.final_nonce) {
+            k1 = k1.negate();
+            k2 = k2.negate();
+        }
+
+        // d = g * gacc * d'
+        let mut d = *secret_key;
+        if self.ctx.negates_key() {
+            d = d.negate();
+        }
+
+        let mut ead = mul(d, &self.challenge)?;
+        if let Some(a) = self.ctx.coefficient(&pubkey)? {
+            ead = mul(ead, &a)?;
+        }
+
+        let mut k2b = mul(k2, &self.nonce_coefficient)?;
+        let signed = add(k1, &k2b).and_then(|s| add(s, &ead));
+        for key in [&mut k1, &mut k2, &mut k2b, &mut d] {
+            key.non_secure_erase();
+        }
+        Ok(PartialSignature(signed?))
+    }
+
+    /// Check one signer's partial signature against their public nonce and key
+    ///
+    /// Lets a faulty or malicious signer be identified before aggregation.
+    pub fn partial_sig_verify(
+        &self,
+        partial: &PartialSignature,
+        public_nonce: &PublicNonce,
+        pubkey: &PublicKey,
+    ) -> EscrowResult<()> {
+        if !self.ctx.contains(pubkey) {
+            return Err(EscrowError::crypto(
+                "Signer is not part of the MuSig2 key set",
+            ));
+        }
+        let secp = Secp256k1::verification_only();
+        let invalid = || EscrowError::crypto("MuSig2 partial signature is invalid");
+
+        // Re = R1 + b * R2, negated along with the final nonce
+        let r2 = public_nonce
+            .r2
+            .mul_tweak(&secp, &Scalar::from(self.nonce_coefficient))
+            .map_err(|_| invalid())?;
+        let mut effective_nonce =
+            PublicKey::combine_keys(&[&public_nonce.r1, &r2]).map_err(|_| invalid())?;
+        if !has_even_y(&self.final_nonce) {
+            effective_nonce = effective_nonce.negate(&secp);
+        }
+
+        // e * a * g * gacc * P
+        let mut ea = self.challenge;
+        if let Some(a) = self.ctx.coefficient(pubkey)? {
+            ea = mul(ea, &a)?;
+        }
+        let mut key_term = pubkey
+            .mul_tweak(&secp, &Scalar::from(ea))
+            .map_err(|_| invalid())?;
+        if self.ctx.negates_key() {
+            key_term = key_term.negate(&secp);
+        }
+
+        let expected =
+            PublicKey::combine_keys(&[&effective_nonce, &key_term]).map_err(|_| invalid())?;
+        if PublicKey::from_secret_key(&Secp256k1::signing_only(), &partial.0) != expected {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+
+    /// Combine all partial signatures into a BIP-340 signature and verify it
+    pub fn aggregate(&self, partials: &[PartialSignature]) -> EscrowResult<schnorr::Signature> {
+        let mut s: Option<SecretKey> = None;
+        for partial in partials {
+            s = Some(match s {
+                Some(sum) => add(sum, &partial.0)?,
+                None => partial.0,
+            });
+        }
+
+        if let Some(tacc) = self.ctx.tacc {
+            let mut etacc = mul(tacc, &self.challenge)?;
+            if !has_even_y(&self.ctx.aggregated) {
+                etacc = etacc.negate();
+            }
+            s = Some(match s {
+                Some(sum) => add(sum, &etacc)?,
+                None => etacc,
+            });
+        }
+
+        let s = s.ok_or_else(|| EscrowError::crypto("No partial signatures to aggregate"))?;
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.final_nonce.x_only_public_key().0.serialize());
+        bytes[32..].copy_from_slice(&s.secret_bytes());
+        let signature = schnorr::Signature::from_slice(&bytes)
+            .map_err(|e| EscrowError::crypto(format!("Invalid aggregated signature: {}", e)))?;
+
+        let message = secp256k1::Message::from_digest(self.message);
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, &message, &self.ctx.x_only_public_key())
+            .map_err(|_| EscrowError::crypto("Aggregated MuSig2 signature is invalid"))?;
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(hex_key: &str) -> PublicKey {
+        PublicKey::from_slice(&hex::decode(hex_key).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_key_aggregation_vector() {
+        // BIP-327 key_agg_vectors.json, valid case 0
+        let keys = vec![
+            pubkey("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            pubkey("03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659"),
+            pubkey("023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66"),
+        ];
+        let ctx = KeyAggContext::new(keys).unwrap();
+        assert_eq!(
+            hex::encode_upper(ctx.x_only_public_key().serialize()),
+            "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C"
+        );
+    }
+
+    /// Inputs shared by BIP-327 sign_verify_vectors.json and tweak_vectors.json
+    struct SignVectors {
+        secret_key: SecretKey,
+        pubkeys: Vec<PublicKey>,
+        public_nonces: Vec<PublicNonce>,
+        aggregated_nonce: PublicNonce,
+        message: [u8; 32],
+    }
+
+    impl SignVectors {
+        fn new() -> Self {
+            let nonce = |hex_nonce: &str| {
+                PublicNonce::from_slice(&hex::decode(hex_nonce).unwrap()).unwrap()
+            };
+            Self {
+                secret_key: SecretKey::from_slice(
+                    &hex::decode(
+                        "7FB9E0E687ADA1EEBF7ECFE2F21E73EBDB51A7D450948DFE8D76D7F2D1007671",
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                pubkeys: vec![
+                    pubkey("03935F972DA013F80AE011890FA89B67A27B7BE6CCB24D3274D18B2D4067F261A9"),
+                    pubkey("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+                    pubkey("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA661"),
+                ],
+                public_nonces: vec![
+                    nonce(
+                        "0337C87821AFD50A8644D820A8F3E02E499C931865C2360FB43D0A0D20DAFE07EA0287BF891D2A6DEAEBADC909352AA9405D1428C15F4B75F04DAE642A95C2548480",
+                    ),
+                    nonce(
+                        "0279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F817980279BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798",
+                    ),
+                    nonce(
+                        "032DE2662628C90B03F5E720284EB52FF7D71F4284F627B68A853D78C78E1FFE9303E4C5524E83FFE1493B9077CF1CA6BEB2090C93D930321071AD40B2F44E599046",
+                    ),
+                ],
+                aggregated_nonce: nonce(
+                    "028465FCF0BBDBCF443AABCCE533D42B4B5A10966AC09A49655E8C42DAAB8FCD61037496A3CC86926D452CAFCFD55D25972CA1675D549310DE296BFF42F72EEEA8C9",
+                ),
+                message: hex::decode(
+                    "F95466D086770E689964664219266FE5ED215C92AE20BAB5C9D79ADDDDF3C0CF",
+                )
+                .unwrap()
+                .try_into()
+                .unwrap(),
+            }
+        }
+
+        /// The vectors' fixed secret nonce, whose public nonce is `public_nonces[0]`
+        fn secret_nonce(&self) -> SecretNonce {
+            let bytes = hex::decode("508B81A611F100A6B2B6B29656590898AF488BCF2E1F55CF22E5CFB84421FE61FA27FD49B1D50085B481285E1CA205D55C82CC1B31FF5CD54A489829355901F7").unwrap();
+            SecretNonce {
+                k1: bytes[..32].try_into().unwrap(),
+                k2: bytes[32..].try_into().unwrap(),
+            }
+        }
+
+        /// Sign as the vectors' signer and check the partial signature both ways
+        fn sign(&self, ctx: &KeyAggContext, expected: &str) {
+            let session = SigningSession::new(ctx, &self.aggregated_nonce, self.message).unwrap();
+            let partial = session
+                .partial_sign(self.secret_nonce(), &self.secret_key)
+                .unwrap();
+            assert_eq!(hex::encode_upper(partial.serialize()), expected);
+            let signer = &self.pubkeys[0];
+            assert!(
+                session
+                    .partial_sig_verify(&partial, &self.public_nonces[0], signer)
+                    .is_ok()
+            );
+            assert!(
+                session
+                    .partial_sig_verify(&partial, &self.public_nonces[1], signer)
+                    .is_err()
+            );
+            assert!(
+                session
+                    .partial_sig_verify(&partial, &self.public_nonces[0], &self.pubkeys[1])
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_sign_verify_vectors() {
+        let vectors = SignVectors::new();
+        let secp = Secp256k1::new();
+        assert_eq!(
+            PublicKey::from_secret_key(&secp, &vectors.secret_key),
+            vectors.pubkeys[0]
+        );
+        let (k1, _) = vectors.secret_nonce().keys().unwrap();
+        assert_eq!(
+            PublicKey::from_secret_key(&secp, &k1),
+            vectors.public_nonces[0].r1
+        );
+        assert_eq!(
+            aggregate_nonces(&vectors.public_nonces).unwrap(),
+            vectors.aggregated_nonce
+        );
+
+        // The signer's key at each position of the key list
+        for (order, expected) in [
+            (
+                [0, 1, 2],
+                "012ABBCB52B3016AC03AD82395A1A415C48B93DEF78718E62A7A90052FE224FB",
+            ),
+            (
+                [1, 0, 2],
+                "9FF2F7AAA856150CC8819254218D3ADEEB0535269051897724F9DB3789513A52",
+            ),
+            (
+                [1, 2, 0],
+                "FA23C359F6FAC4E7796BB93BC9F0532A95468C539BA20FF86D7C76ED92227900",
+            ),
+        ] {
+            let keys = order.iter().map(|&i| vectors.pubkeys[i]).collect();
+            vectors.sign(&KeyAggContext::new(keys).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_tweak_vectors() {
+        // Same inputs, but the third key differs from sign_verify_vectors.json
+        let mut vectors = SignVectors::new();
+        vectors.pubkeys[2] =
+            pubkey("02DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659");
+        let tweak = |hex_tweak: &str| {
+            Scalar::from_be_bytes(hex::decode(hex_tweak).unwrap().try_into().unwrap()).unwrap()
+        };
+        let tweaks = [
+            tweak("E8F791FF9225A2AF0102AFFF4A9A723D9612A682A25EBE79802B263CDFCD83BB"),
+            tweak("AE2EA797CC0FE72AC5B97B97F3C6957D7E4199A167A58EB08BCAFFDA70AC0455"),
+            tweak("F52ECBC565B3D8BEA2DFD5B75A4F457E54369809322E4120831626F290FA87E0"),
+            tweak("1969AD73CC177FA0B4FCED6DF1F7BF9907E665FDE9BA196A74FED0A3CF5AEF9D"),
+        ];
+        let keys = vec![vectors.pubkeys[1], vectors.pubkeys[2], vectors.pubkeys[0]];
+
+        // Whether each successive tweak is x-only
+        for (xonly, expected) in [
+            (
+                &[true][..],
+                "E28A5C66E61E178C2BA19DB77B6CF9F7E2F0F56C17918CD13135E60CC848FE91",
+            ),
+            (
+                &[false],
+                "38B0767798252F21BF5702C48028B095428320F73A4B14DB1E25DE58543D2D2D",
+            ),
+            (
+                &[false, true],
+                "408A0A21C4A0F5DACAF9646AD6EB6FECD7F7A11F03ED1F48DFFF2185BC2C2408",
+            ),
+            (
+                &[false, false, true, true],
+                "45ABD206E61E3DF2EC9E264A6FEC8292141A633C28586388235541F9ADE75435",
+            ),
+            (
+                &[true, false, true, false],
+                "B255FDCAC27B40C7CE7848E2D3B7BF5EA0ED756DA81565AC804CCCA3E1D5D239",
+            ),
+        ] {
+            let mut ctx = KeyAggContext::new(keys.clone()).unwrap();
+            for (tweak, &xonly) in tweaks.iter().zip(xonly) {
+                ctx = if xonly {
+                    ctx.with_xonly_tweak(*tweak)
+                } else {
+                    ctx.with_plain_tweak(*tweak)
+                }
+                .unwrap();
+            }
+            vectors.sign(&ctx, expected);
+        }
+    }
+
+    #[test]
+    fn test_tweaked_two_party_signature() {
+        let secp = Secp256k1::new();
+        let alice = SecretKey::from_slice(&[0x11; 32]).unwrap();
+        let bob = SecretKey::from_slice(&[0x22; 32]).unwrap();
+        let keys = vec![
+            PublicKey::from_secret_key(&secp, &alice),
+            PublicKey::from_secret_key(&secp, &bob),
+        ];
+
+        let tweak = Scalar::from_be_bytes([0x33; 32]).unwrap();
+        let ctx = KeyAggContext::new(keys.clone())
+            .unwrap()
+            .with_xonly_tweak(tweak)
+            .unwrap();
+
+        let message = [0x44; 32];
+        let (alice_secnonce, alice_nonce) = generate_nonce().unwrap();
+        let (bob_secnonce, bob_nonce) = generate_nonce().unwrap();
+        let aggregated = aggregate_nonces(&[alice_nonce, bob_nonce]).unwrap();
+        assert_eq!(
+            PublicNonce::from_slice(&aggregated.serialize()).unwrap(),
+            aggregated
+        );
+
+        let session = SigningSession::new(&ctx, &aggregated, message).unwrap();
+        let alice_partial = session.partial_sign(alice_secnonce, &alice).unwrap();
+        let bob_partial = session.partial_sign(bob_secnonce, &bob).unwrap();
+        assert!(
+            session
+                .partial_sig_verify(&alice_partial, &alice_nonce, &keys[0])
+                .is_ok()
+        );
+        assert!(
+            session
+                .partial_sig_verify(&alice_partial, &bob_nonce, &keys[0])
+                .is_err()
+        );
+
+        // `aggregate` verifies the result against the tweaked key
+        assert!(session.aggregate(&[alice_partial, bob_partial]).is_ok());
+        assert!(session.aggregate(&[alice_partial]).is_err());
+    }
+}
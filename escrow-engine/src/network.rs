@@ -0,0 +1,161 @@
+//! Network - Bitcoin network selection and per-network validation
+//!
+//! The node runs against a single Bitcoin network. Everything that encodes
+//! the network (BOLT11 prefixes, on-chain addresses, Boltz endpoints) is
+//! derived from that setting here, and inputs from another network are
+//! rejected before any funds move.
+
+use crate::{EscrowResult, error::EscrowError};
+use bitcoin::Address;
+use std::str::FromStr;
+
+pub use bitcoin::Network;
+
+/// BOLT11 human-readable prefix for a network
+pub fn bolt11_prefix(network: Network) -> &'static str {
+    match network {
+        Network::Bitcoin => "lnbc",
+        Network::Testnet => "lntb",
+        Network::Signet => "lntbs",
+        Network::Regtest => "lnbcrt",
+        _ => "lnbc",
+    }
+}
+
+/// Determine the network a BOLT11 invoice was issued for
+///
+/// Returns `None` if the string is not a BOLT11 invoice or uses an unknown
+/// currency prefix.
+pub fn invoice_network(invoice: &str) -> Option<Network> {
+    let invoice = invoice.trim().to_lowercase();
+    let invoice = invoice.strip_prefix("lightning:").unwrap_or(&invoice);
+    let rest = invoice.strip_prefix("ln")?;
+
+    // The currency is the alphabetic run before the (optional) amount
+    let currency: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    match currency.as_str() {
+        "bc" => Some(Network::Bitcoin),
+        "tb" => Some(Network::Testnet),
+        "tbs" => Some(Network::Signet),
+        "bcrt" => Some(Network::Regtest),
+        _ => None,
+    }
+}
+
+/// Validate that a BOLT11 invoice belongs to the expected network
+pub fn validate_invoice_network(invoice: &str, network: Network) -> EscrowResult<()> {
+    match invoice_network(invoice) {
+        Some(invoice_network) if invoice_network == network => Ok(()),
+        Some(invoice_network) => Err(EscrowError::invoice(format!(
+            "Invoice is for {} but the node runs on {}",
+            invoice_network, network
+        ))),
+        None => Err(EscrowError::invoice("Not a valid BOLT11 invoice")),
+    }
+}
+
+/// Parse an on-chain address, requiring it to match the network
+pub fn parse_address(address: &str, network: Network) -> EscrowResult<Address> {
+    let unchecked = Address::from_str(address.trim())
+        .map_err(|e| EscrowError::payment(format!("Invalid bitcoin address: {}", e)))?;
+
+    unchecked.require_network(network).map_err(|_| {
+        EscrowError::payment(format!(
+            "Address {} is not valid for {}",
+            address.trim(),
+            network
+        ))
+    })
+}
+
+/// Validate a worker payout destination against the network
+///
+/// Accepts BOLT11 invoices, on-chain addresses, LNURLs and Lightning
+/// addresses (`user@domain`); the latter two carry no network and are
+/// resolved at payout time.
+pub fn validate_payout_destination(destination: &str, network: Network) -> EscrowResult<()> {
+    let destination = destination.trim();
+    let lower = destination.to_lowercase();
+
+    if destination.is_empty() {
+        return Err(EscrowError::payment("Payout destination cannot be empty"));
+    }
+
+    if destination.contains('@') || lower.starts_with("lnurl") {
+        return Ok(());
+    }
+
+    if lower.starts_with("ln") || lower.starts_with("lightning:") {
+        return validate_invoice_network(destination, network);
+    }
+
+    parse_address(destination, network).map(|_| ())
+}
+
+/// Whether a payout destination can be paid more than once
+///
+/// BOLT11 invoices are single-use and carry a fixed amount; on-chain
+/// addresses, LNURLs and Lightning addresses accept any number of payments.
+pub fn is_reusable_destination(destination: &str) -> bool {
+    let lower = destination.trim().to_lowercase();
+    let lower = lower.strip_prefix("lightning:").unwrap_or(&lower);
+    destination.contains('@') || lower.starts_with("lnurl") || !lower.starts_with("ln")
+}
+
+/// Public Boltz API endpoint for a network
+///
+/// Boltz does not operate on signet; regtest points at the default port of
+/// a local Boltz backend.
+pub fn default_boltz_api_url(network: Network) -> Option<&'static str> {
+    match network {
+        Network::Bitcoin => Some("https://api.boltz.exchange"),
+        Network::Testnet => Some("https://api.testnet.boltz.exchange"),
+        Network::Regtest => Some("http://localhost:9001"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invoice_network_prefixes() {
+        assert_eq!(invoice_network("lnbc500u1pvjluez"), Some(Network::Bitcoin));
+        assert_eq!(invoice_network("lntb500u1pvjluez"), Some(Network::Testnet));
+        assert_eq!(invoice_network("lntbs500u1pvjluez"), Some(Network::Signet));
+        assert_eq!(invoice_network("LNBCRT1pvjluez"), Some(Network::Regtest));
+        assert_eq!(
+            invoice_network("lightning:lnbc1pvjluez"),
+            Some(Network::Bitcoin)
+        );
+        assert_eq!(invoice_network("lnxy1pvjluez"), None);
+
+        for network in [
+            Network::Bitcoin,
+            Network::Testnet,
+            Network::Signet,
+            Network::Regtest,
+        ] {
+            let invoice = format!("{}100u1pvjluez", bolt11_prefix(network));
+            assert!(validate_invoice_network(&invoice, network).is_ok());
+        }
+        assert!(validate_invoice_network("lnbc100u1pvjluez", Network::Regtest).is_err());
+    }
+
+    #[test]
+    fn test_payout_destination_network() {
+        let mainnet = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+        let testnet = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        assert!(validate_payout_destination(mainnet, Network::Bitcoin).is_ok());
+        assert!(validate_payout_destination(mainnet, Network::Testnet).is_err());
+        assert!(validate_payout_destination(testnet, Network::Signet).is_ok());
+        assert!(validate_payout_destination("lntb1pvjluez", Network::Bitcoin).is_err());
+        assert!(validate_payout_destination("worker@example.com", Network::Regtest).is_ok());
+        assert!(validate_payout_destination("not an address", Network::Bitcoin).is_err());
+    }
+}
@@ -6,23 +6,43 @@
 
 use crate::{
     EscrowResult,
+    backup::BackupStatus,
     engine::{EscrowEngine, EscrowEngineConfig, LiquidityInfo, NodeInfo},
     error::EscrowError,
-    models::{Dispute, EscrowEvent, Funding, FundingMode, Reputation, Task, TaskState, User},
+    models::{
+        Dispute, EscrowEvent, FiatAmount, Funding, FundingMode, Reputation, Task, TaskState, User,
+    },
+    network::Network,
     nostr_publisher::{NostrPublisher, NostrPublisherConfig},
-    payment_coordinator::{PaymentCoordinator, PaymentCoordinatorConfig},
+    payment_coordinator::{
+        ChainStatusChange, EscrowParties, FeeQuote, KeyPathContribution, MultisigSettlement,
+        OnchainPayout, PaymentCoordinator, PaymentCoordinatorConfig, PaymentResponse, SwapRefund,
+        SwapRefundRequest, SwapStatusChange,
+    },
+    proof_archive::{ArchivedProof, ProofArchive, ProofArchiveConfig, RetrievedProof},
+    proof_verifier::ProofVerifier,
     reputation_indexer::{ReputationIndexer, ReputationIndexerConfig},
+    settlement_scheduler::{SettlementBatchResult, SettlementScheduler, SettlementSchedulerConfig},
+    signed_action::{ActionAuth, ActionMessage},
     task_manager::{TaskManager, TaskManagerConfig},
-    verification_service::{VerificationService, VerificationServiceConfig},
+    verification_service::{
+        self, CompletionVerificationResult, VerificationService, VerificationServiceConfig,
+    },
+    webhook_dispatcher::{
+        WebhookDeliveryAttempt, WebhookDispatcher, WebhookDispatcherConfig, WebhookEndpoint,
+    },
 };
 use chrono::{DateTime, Utc};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::mpsc, task::JoinHandle};
 use tracing::{info, warn};
 use uuid::Uuid;
 
 /// Configuration for the escrow node
 #[derive(Debug, Clone)]
 pub struct EscrowNodeConfig {
+    /// Bitcoin network (overrides the network of the component configs)
+    pub network: Network,
     /// Task manager configuration
     pub task_config: TaskManagerConfig,
     /// Escrow engine configuration
@@ -35,17 +55,27 @@ pub struct EscrowNodeConfig {
     pub nostr_config: NostrPublisherConfig,
     /// Reputation indexer configuration
     pub reputation_config: ReputationIndexerConfig,
+    /// Webhook dispatcher configuration
+    pub webhook_config: WebhookDispatcherConfig,
+    /// Settlement scheduler configuration
+    pub settlement_config: SettlementSchedulerConfig,
+    /// Proof archive configuration; submitted proofs are archived when set
+    pub proof_archive_config: Option<ProofArchiveConfig>,
 }
 
 impl Default for EscrowNodeConfig {
     fn default() -> Self {
         Self {
+            network: Network::Bitcoin,
             task_config: TaskManagerConfig::default(),
             escrow_config: EscrowEngineConfig::default(),
             payment_config: PaymentCoordinatorConfig::default(),
             verification_config: VerificationServiceConfig::default(),
             nostr_config: NostrPublisherConfig::default(),
             reputation_config: ReputationIndexerConfig::default(),
+            webhook_config: WebhookDispatcherConfig::default(),
+            settlement_config: SettlementSchedulerConfig::default(),
+            proof_archive_config: None,
         }
     }
 }
@@ -64,6 +94,10 @@ pub struct EscrowNode {
     nostr_publisher: Arc<NostrPublisher>,
     /// Reputation indexer for user scoring
     reputation_indexer: Arc<ReputationIndexer>,
+    /// Webhook dispatcher for marketplace notifications
+    webhook_dispatcher: Arc<WebhookDispatcher>,
+    /// Scheduler for batched settlement
+    settlement_scheduler: Arc<SettlementScheduler>,
 }
 
 /// Task creation request
@@ -75,6 +109,8 @@ pub struct CreateTaskRequest {
     pub employer_pubkey: String,
     pub deadline: Option<DateTime<Utc>>,
     pub metadata: Option<serde_json::Value>,
+    /// Fiat price of the task; `reward_sats` is ignored when set
+    pub reward_fiat: Option<FiatAmount>,
 }
 
 /// Task funding request
@@ -83,6 +119,12 @@ pub struct FundTaskRequest {
     pub task_id: Uuid,
     pub employer_pubkey: String,
     pub mode: FundingMode,
+    /// Employer's on-chain address for refunds of on-chain fundings
+    pub refund_address: Option<String>,
+    /// Employer and worker keys, for multisig escrow
+    pub escrow_parties: Option<EscrowParties>,
+    /// Employer's signature over `ActionMessage::fund`
+    pub auth: ActionAuth,
 }
 
 /// Task claiming request
@@ -91,6 +133,8 @@ pub struct ClaimTaskRequest {
     pub task_id: Uuid,
     pub worker_pubkey: String,
     pub worker_invoice: String,
+    /// Worker's signature over `ActionMessage::claim`
+    pub auth: ActionAuth,
 }
 
 /// Proof submission request
@@ -100,8 +144,10 @@ pub struct SubmitProofRequest {
     pub worker_pubkey: String,
     pub proof_url: String,
     pub proof_hash: String,
-    pub nostr_event_id: String,
-    pub nostr_signature: String,
+    /// Worker's signed NIP-01 proof event, as JSON
+    pub proof_event: String,
+    /// Worker's signature over `ActionMessage::submit_proof`
+    pub auth: ActionAuth,
 }
 
 /// Task verification request
@@ -111,7 +157,8 @@ pub struct VerifyTaskRequest {
     pub verifier_pubkey: String,
     pub approved: bool,
     pub reason: String,
-    pub signature: String,
+    /// Employer's signature over `ActionMessage::verify`
+    pub auth: ActionAuth,
 }
 
 /// Task information response
@@ -121,6 +168,8 @@ pub struct TaskInfo {
     pub funding: Option<Funding>,
     pub events: Vec<EscrowEvent>,
     pub reputation: Option<Reputation>,
+    /// Automated verification of the proof, advisory unless auto-approved
+    pub completion: Option<CompletionVerificationResult>,
 }
 
 /// User tasks response
@@ -139,8 +188,32 @@ pub struct ReputationStatsResponse {
 
 impl EscrowNode {
     /// Create a new escrow node with all components initialized
-    pub async fn new(config: EscrowNodeConfig) -> EscrowResult<Self> {
-        info!("Initializing escrow node with all components");
+    pub async fn new(mut config: EscrowNodeConfig) -> EscrowResult<Self> {
+        info!(
+            "Initializing escrow node with all components on {}",
+            config.network
+        );
+
+        // A single network setting drives every component
+        config.escrow_config.network = config.network;
+        config.payment_config.network = config.network;
+
+        // The engine-level webhook URL acts as a catch-all endpoint
+        let mut webhook_config = config.webhook_config;
+        if let Some(ref url) = config.escrow_config.webhook_url {
+            let secret = config.escrow_config.webhook_secret.clone().ok_or_else(|| {
+                EscrowError::config("webhook_secret is required when webhook_url is set")
+            })?;
+            webhook_config.endpoints.push(WebhookEndpoint {
+                id: "default".to_string(),
+                marketplace_id: None,
+                url: url.clone(),
+                secret,
+                event_types: Vec::new(),
+            });
+        }
+        let webhook_dispatcher: Arc<WebhookDispatcher> =
+            Arc::new(WebhookDispatcher::new(webhook_config)?);
 
         // Initialize escrow engine (LDK)
         let escrow_engine: Arc<EscrowEngine> =
@@ -151,20 +224,30 @@ impl EscrowNode {
             Arc::new(NostrPublisher::new(config.nostr_config).await?);
         let reputation_indexer: Arc<ReputationIndexer> =
             Arc::new(ReputationIndexer::new(config.reputation_config));
-        let payment_coordinator: Arc<PaymentCoordinator> =
-            Arc::new(PaymentCoordinator::new(config.payment_config));
+        let payment_coordinator: Arc<PaymentCoordinator> = Arc::new(
+            PaymentCoordinator::new(config.payment_config)
+                .with_escrow_engine(escrow_engine.clone()),
+        );
+        let settlement_scheduler: Arc<SettlementScheduler> =
+            Arc::new(SettlementScheduler::new(config.settlement_config));
 
         // Initialize task manager
-        let task_manager = Arc::new(
-            TaskManager::new(
-                config.task_config,
-                escrow_engine.clone(),
-                verification_service.clone(),
-                nostr_publisher.clone(),
-                reputation_indexer.clone(),
-            )
-            .await?,
-        );
+        let mut task_manager = TaskManager::new(
+            config.task_config,
+            escrow_engine.clone(),
+            verification_service.clone(),
+            nostr_publisher.clone(),
+            reputation_indexer.clone(),
+            webhook_dispatcher.clone(),
+            settlement_scheduler.clone(),
+        )
+        .await?
+        .with_payment_coordinator(payment_coordinator.clone());
+        if let Some(archive_config) = config.proof_archive_config {
+            task_manager =
+                task_manager.with_proof_archive(Arc::new(ProofArchive::new(archive_config).await?));
+        }
+        let task_manager = Arc::new(task_manager);
 
         info!("Escrow node initialized successfully");
 
@@ -175,6 +258,8 @@ impl EscrowNode {
             verification_service,
             nostr_publisher,
             reputation_indexer,
+            webhook_dispatcher,
+            settlement_scheduler,
         })
     }
 
@@ -187,23 +272,305 @@ impl EscrowNode {
             employer_pubkey: request.employer_pubkey,
             deadline: request.deadline,
             metadata: request.metadata,
+            reward_fiat: request.reward_fiat,
         };
 
         self.task_manager.create_task(task_request).await
     }
 
-    /// Fund a task with a hold invoice
-    pub async fn fund_task(
+    /// Fund a task through the requested payment mode
+    ///
+    /// Falls back to other supported modes when the requested one fails; the
+    /// returned payment's `mode` is the one used.
+    pub async fn fund_task(&self, request: FundTaskRequest) -> EscrowResult<PaymentResponse> {
+        self.task_manager
+            .fund_task(task_funding_request(request))
+            .await
+    }
+
+    /// Fund a task through a Boltz swap (`OnchainSubmarine` / `OnchainReverse`)
+    ///
+    /// The task is funded once the swap completes; run `spawn_swap_monitor`
+    /// (or feed updates to `apply_swap_status_change`) to follow it.
+    /// If the requested swap cannot be created, the other swap direction is
+    /// tried; the returned payment's `mode` is the one used.
+    pub async fn fund_task_with_swap(
         &self,
         request: FundTaskRequest,
-    ) -> EscrowResult<crate::models::HoldInvoiceData> {
-        let fund_request = crate::task_manager::FundTaskRequest {
+    ) -> EscrowResult<PaymentResponse> {
+        if !matches!(
+            request.mode,
+            FundingMode::OnchainSubmarine | FundingMode::OnchainReverse
+        ) {
+            return Err(EscrowError::task_validation(format!(
+                "{:?} is not a swap funding mode",
+                request.mode
+            )));
+        }
+
+        self.task_manager
+            .fund_task_with_fallbacks(
+                task_funding_request(request),
+                &[FundingMode::OnchainSubmarine, FundingMode::OnchainReverse],
+            )
+            .await
+    }
+
+    /// Apply a swap state change to the funded task
+    pub async fn apply_swap_status_change(&self, change: &SwapStatusChange) -> EscrowResult<Task> {
+        self.task_manager.apply_swap_status(change).await
+    }
+
+    /// Refund the on-chain lockup of a submarine swap that did not fund its task
+    ///
+    /// Only the employer can trigger the refund, signing
+    /// `ActionMessage::cancel` with the refund address, and only while the
+    /// swap has not completed. Without Boltz's cooperation the returned
+    /// transaction is timelocked until `spendable_at_height` and may need
+    /// manual broadcast.
+    pub async fn refund_swap_funding(
+        &self,
+        task_id: Uuid,
+        employer_pubkey: &str,
+        refund_address: Option<String>,
+        fee_rate_sat_vb: u64,
+        auth: &ActionAuth,
+    ) -> EscrowResult<SwapRefund> {
+        let task = self.task_manager.get_task(task_id).await?;
+        if !verification_service::same_pubkey(&task.employer_pubkey, employer_pubkey) {
+            return Err(EscrowError::task_validation(
+                "Only task creator can refund task",
+            ));
+        }
+
+        let funding_id = task
+            .funding_id
+            .ok_or_else(|| EscrowError::task_validation("Task has no funding"))?;
+        let funding = self.task_manager.get_funding(funding_id).await?;
+        let swap_id = match (&funding.mode, &funding.swap_id) {
+            (FundingMode::OnchainSubmarine, Some(swap_id)) => swap_id.clone(),
+            _ => {
+                return Err(EscrowError::task_validation(
+                    "Task is not funded through a submarine swap",
+                ));
+            }
+        };
+        if !matches!(task.state, TaskState::Draft | TaskState::PendingFunding) {
+            return Err(EscrowError::task_validation(format!(
+                "Swap funding of a {:?} task cannot be refunded on-chain",
+                task.state
+            )));
+        }
+        self.task_manager
+            .authorize_action(
+                employer_pubkey,
+                &ActionMessage::cancel(task.id, refund_address.as_deref()),
+                auth,
+            )
+            .await?;
+
+        let refund = self
+            .payment_coordinator
+            .refund_submarine_swap(SwapRefundRequest {
+                swap_id,
+                refund_address,
+                fee_rate_sat_vb,
+                lockup_tx_hex: None,
+            })
+            .await?;
+
+        self.task_manager.record_swap_refund(&refund).await?;
+
+        Ok(refund)
+    }
+
+    /// Record confirmations of an on-chain payout's claim transaction
+    ///
+    /// The task is marked paid once the claim has enough confirmations.
+    pub async fn record_payout_confirmations(
+        &self,
+        swap_id: &str,
+        confirmations: u32,
+    ) -> EscrowResult<Task> {
+        self.task_manager
+            .record_payout_confirmations(swap_id, confirmations)
+            .await
+    }
+
+    /// Retry a failed or unclaimed on-chain payout
+    pub async fn retry_onchain_payout(&self, task_id: Uuid) -> EscrowResult<OnchainPayout> {
+        self.task_manager.retry_onchain_payout(task_id).await
+    }
+
+    /// Fund a Cashu task with the employer's token
+    ///
+    /// The token must be locked as described by the funding's lockup script,
+    /// and the submission signed by the employer as a Cashu funding action.
+    pub async fn submit_cashu_token(
+        &self,
+        task_id: Uuid,
+        employer_pubkey: &str,
+        token: &str,
+        auth: &ActionAuth,
+    ) -> EscrowResult<Task> {
+        self.task_manager
+            .submit_cashu_token(task_id, employer_pubkey, token, auth)
+            .await
+    }
+
+    /// Finalise the pending release or refund of a task's multisig escrow
+    /// from the parties' signed PSBTs
+    pub async fn finalize_escrow_settlement(
+        &self,
+        task_id: Uuid,
+        signed_psbts: &[String],
+    ) -> EscrowResult<MultisigSettlement> {
+        self.task_manager
+            .finalize_escrow_settlement(task_id, signed_psbts)
+            .await
+    }
+
+    /// Finalise the pending cooperative release or refund of a task's
+    /// Taproot escrow from the parties' MuSig2 contributions
+    pub async fn finalize_cooperative_escrow_settlement(
+        &self,
+        task_id: Uuid,
+        contributions: &[KeyPathContribution],
+    ) -> EscrowResult<MultisigSettlement> {
+        self.task_manager
+            .finalize_cooperative_escrow_settlement(task_id, contributions)
+            .await
+    }
+
+    /// Spawn the background task following Boltz swap updates
+    pub fn spawn_swap_monitor(&self) -> JoinHandle<()> {
+        let payment_coordinator = self.payment_coordinator.clone();
+        let task_manager = self.task_manager.clone();
+        let (changes_tx, mut changes_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let monitor = payment_coordinator.run_swap_monitor(changes_tx);
+            let apply = async {
+                while let Some(change) = changes_rx.recv().await {
+                    if let Err(e) = task_manager.apply_swap_status(&change).await {
+                        warn!("Failed to apply swap update for {}: {}", change.swap_id, e);
+                    }
+                }
+            };
+            tokio::join!(monitor, apply);
+        })
+    }
+
+    /// Catch up with the chain and record confirmations and reorgs
+    ///
+    /// Escrow funding and payout claim confirmations are recorded on their
+    /// tasks.
+    pub async fn sync_chain(&self) -> EscrowResult<Vec<ChainStatusChange>> {
+        let changes = self.payment_coordinator.sync_chain().await?;
+        for change in &changes {
+            apply_chain_change(&self.task_manager, change).await;
+        }
+        Ok(changes)
+    }
+
+    /// Spawn the background task following escrow fundings and payout claims
+    /// on-chain
+    ///
+    /// Returns `None` when no chain source is configured.
+    pub fn spawn_chain_watcher(&self) -> Option<JoinHandle<()>> {
+        if !self.payment_coordinator.has_chain_watcher() {
+            return None;
+        }
+
+        let payment_coordinator = self.payment_coordinator.clone();
+        let task_manager = self.task_manager.clone();
+        let (changes_tx, mut changes_rx) = mpsc::unbounded_channel();
+
+        Some(tokio::spawn(async move {
+            let watcher = payment_coordinator.run_chain_watcher(changes_tx);
+            let apply = async {
+                while let Some(change) = changes_rx.recv().await {
+                    apply_chain_change(&task_manager, &change).await;
+                }
+            };
+            tokio::join!(watcher, apply);
+        }))
+    }
+
+    /// Claim a funded task for work
+    pub async fn claim_task(&self, request: ClaimTaskRequest) -> EscrowResult<Task> {
+        let claim_request = crate::task_manager::ClaimTaskRequest {
             task_id: request.task_id,
-            employer_pubkey: request.employer_pubkey,
-            mode: request.mode,
+            worker_pubkey: request.worker_pubkey,
+            worker_invoice: request.worker_invoice,
+            auth: request.auth,
         };
 
-        self.task_manager.fund_task(fund_request).await
+        self.task_manager.claim_task(claim_request).await
+    }
+
+    /// Record a payment received for a task's hold invoice
+    pub async fn process_invoice_payment(
+        &self,
+        invoice_hash: &str,
+        amount_received_sats: u64,
+    ) -> EscrowResult<Task> {
+        self.task_manager
+            .process_invoice_payment(invoice_hash, amount_received_sats)
+            .await
+    }
+
+    /// Record one HTLC of a multi-part payment for a task's hold invoice
+    pub async fn process_htlc_part(
+        &self,
+        invoice_hash: &str,
+        htlc_id: &str,
+        amount_sats: u64,
+    ) -> EscrowResult<Task> {
+        self.task_manager
+            .process_htlc_part(invoice_hash, htlc_id, amount_sats)
+            .await
+    }
+
+    /// Fail back multi-part payments whose remaining parts never arrived
+    pub async fn expire_incomplete_payments(&self) -> EscrowResult<Vec<Uuid>> {
+        self.task_manager.expire_incomplete_payments().await
+    }
+
+    /// Spawn a background loop failing back timed-out multi-part payments
+    ///
+    /// Incomplete sets are checked twice per `mpp_timeout_secs`, so a set is
+    /// failed back at most half a timeout late.
+    pub fn spawn_payment_expiry(&self) -> JoinHandle<()> {
+        let period = Duration::from_millis((self.escrow_engine.mpp_timeout_secs() * 500).max(1000));
+        let task_manager = self.task_manager.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = task_manager.expire_incomplete_payments().await {
+                    warn!("Failed to expire incomplete payments: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Return tasks whose funding invoice expired unpaid to `Draft`
+    pub async fn expire_unpaid_fundings(&self) -> EscrowResult<Vec<Uuid>> {
+        self.task_manager.expire_unpaid_fundings().await
+    }
+
+    /// Refund a funded but unclaimed task to the employer, authorised by
+    /// their signature over `ActionMessage::cancel`
+    pub async fn refund_task(
+        &self,
+        task_id: Uuid,
+        employer_pubkey: &str,
+        auth: &ActionAuth,
+    ) -> EscrowResult<Task> {
+        self.task_manager
+            .refund_task(task_id, employer_pubkey, auth)
+            .await
     }
 
     /// Submit proof of work completion
@@ -213,12 +580,44 @@ impl EscrowNode {
             worker_pubkey: request.worker_pubkey,
             proof_url: request.proof_url,
             proof_hash: request.proof_hash,
-            nostr_event_id: request.nostr_event_id,
-            nostr_signature: request.nostr_signature,
+            proof_event: request.proof_event,
+            auth: request.auth,
         };
         self.task_manager.submit_proof(submit_proof_request).await
     }
 
+    /// Read a task's archived proof, e.g. to settle a dispute
+    ///
+    /// Open to the task's parties and the configured arbitrators, who sign
+    /// `ActionMessage::retrieve_proof`.
+    pub async fn retrieve_archived_proof(
+        &self,
+        task_id: Uuid,
+        requester_pubkey: &str,
+        auth: &ActionAuth,
+    ) -> EscrowResult<RetrievedProof> {
+        self.task_manager
+            .retrieve_archived_proof(task_id, requester_pubkey, auth)
+            .await
+    }
+
+    /// Archived proof of a task, without its content
+    pub async fn get_archived_proof(&self, task_id: Uuid) -> EscrowResult<Option<ArchivedProof>> {
+        self.task_manager.get_archived_proof(task_id).await
+    }
+
+    /// Prune archived proofs past the retention period
+    pub async fn prune_proof_archive(&self) -> EscrowResult<Vec<Uuid>> {
+        self.task_manager.prune_proof_archive().await
+    }
+
+    /// Register the automated proof verifier for tasks of `task_type`
+    pub async fn register_proof_verifier(&self, task_type: &str, verifier: Arc<dyn ProofVerifier>) {
+        self.verification_service
+            .register_verifier(task_type, verifier)
+            .await;
+    }
+
     /// Verify task completion and approve for payment
     pub async fn verify_task(&self, request: VerifyTaskRequest) -> EscrowResult<Task> {
         let verify_request = crate::task_manager::VerifyTaskRequest {
@@ -226,12 +625,38 @@ impl EscrowNode {
             verifier_pubkey: request.verifier_pubkey,
             approved: request.approved,
             reason: request.reason,
-            signature: request.signature,
+            auth: request.auth,
         };
 
         self.task_manager.verify_task(verify_request).await
     }
 
+    /// Settle all tasks queued by the settlement scheduler
+    pub async fn run_settlement_batch(&self) -> EscrowResult<Vec<SettlementBatchResult>> {
+        self.task_manager.run_settlement_batch().await
+    }
+
+    /// Spawn a background loop settling queued tasks on the configured cadence
+    ///
+    /// Returns `None` when batched settlement is disabled.
+    pub fn spawn_settlement_scheduler(&self) -> Option<JoinHandle<()>> {
+        if !self.settlement_scheduler.is_enabled() {
+            return None;
+        }
+
+        let interval_secs = self.settlement_scheduler.interval_secs();
+        let task_manager = self.task_manager.clone();
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                interval.tick().await;
+                if let Err(e) = task_manager.run_settlement_batch().await {
+                    warn!("Settlement batch failed: {}", e);
+                }
+            }
+        }))
+    }
+
     /// Get task information with related data
     pub async fn get_task_info(&self, task_id: Uuid) -> EscrowResult<TaskInfo> {
         let task = self.task_manager.get_task(task_id).await?;
@@ -249,12 +674,14 @@ impl EscrowNode {
             .get_reputation(&task.employer_pubkey)
             .await
             .ok();
+        let completion = self.task_manager.get_completion_result(task_id).await;
 
         Ok(TaskInfo {
             task,
             funding,
             events,
             reputation,
+            completion,
         })
     }
 
@@ -288,9 +715,25 @@ impl EscrowNode {
         self.payment_coordinator.get_supported_modes(amount_sats)
     }
 
-    /// Calculate estimated fees for a payment
-    pub fn calculate_payment_fees(&self, amount_sats: u64, mode: FundingMode) -> u64 {
-        self.payment_coordinator.calculate_fees(amount_sats, mode)
+    /// Quote fees, limits and timing of a payment
+    ///
+    /// Live fee sources are used where available, with the static fee
+    /// schedule as fallback.
+    pub async fn calculate_payment_fees(&self, amount_sats: u64, mode: FundingMode) -> FeeQuote {
+        self.payment_coordinator.quote_fee(amount_sats, mode).await
+    }
+
+    /// Quote fees, limits and timing of every payment mode
+    pub async fn quote_payment_fees(&self, amount_sats: u64) -> Vec<FeeQuote> {
+        self.payment_coordinator.quote_fees(amount_sats).await
+    }
+
+    /// Get recorded webhook delivery attempts for the events of a task
+    pub async fn get_webhook_delivery_attempts(
+        &self,
+        task_id: Uuid,
+    ) -> Vec<WebhookDeliveryAttempt> {
+        self.webhook_dispatcher.get_task_attempts(task_id).await
     }
 
     /// Get node liquidity information
@@ -303,6 +746,11 @@ impl EscrowNode {
         self.escrow_engine.get_node_info().await
     }
 
+    /// Get channel monitor backup freshness
+    pub async fn get_backup_status(&self) -> EscrowResult<BackupStatus> {
+        self.escrow_engine.get_backup_status().await
+    }
+
     /// Health check for the escrow node
     pub async fn health_check(&self) -> EscrowResult<NodeHealth> {
         // Check if all components are healthy
@@ -313,6 +761,16 @@ impl EscrowNode {
             issues.push(format!("LDK node error: {}", e));
         }
 
+        // Check channel monitor backups (EDGE_CASES #16)
+        match self.escrow_engine.get_backup_status().await {
+            Ok(status) if !status.fresh => issues.push(format!(
+                "Channel backup stale: stale={:?} missing={:?} failed_sinks={:?}",
+                status.stale_channels, status.missing_channels, status.failed_sinks
+            )),
+            Ok(_) | Err(EscrowError::Config(_)) => {}
+            Err(e) => issues.push(format!("Channel backup error: {}", e)),
+        }
+
         // Check reputation indexer
         if let Err(e) = self.reputation_indexer.get_reputation_stats().await {
             issues.push(format!("Reputation indexer error: {}", e));
@@ -361,9 +819,140 @@ impl Default for EscrowNode {
     }
 }
 
+/// Record a payout claim's or escrow funding's new confirmation depth on its task
+async fn apply_chain_change(task_manager: &TaskManager, change: &ChainStatusChange) {
+    let Some(swap_id) = &change.payout_swap_id else {
+        if let Err(e) = task_manager
+            .record_escrow_confirmations(&change.update, change.reorged)
+            .await
+        {
+            warn!(
+                "Failed to record confirmations of escrow {}: {}",
+                change.update.funding_id, e
+            );
+        }
+        return;
+    };
+    let confirmations = change.update.confirmations.unwrap_or_default();
+    if let Err(e) = task_manager
+        .record_payout_confirmations(swap_id, confirmations)
+        .await
+    {
+        warn!(
+            "Failed to record confirmations of payout {}: {}",
+            swap_id, e
+        );
+    }
+}
+
+fn task_funding_request(request: FundTaskRequest) -> crate::task_manager::FundTaskRequest {
+    crate::task_manager::FundTaskRequest {
+        task_id: request.task_id,
+        employer_pubkey: request.employer_pubkey,
+        mode: request.mode,
+        refund_address: request.refund_address,
+        escrow_parties: request.escrow_parties,
+        auth: request.auth,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signed_action::new_nonce;
+    use crate::verification_service::{
+        NostrEvent,
+        tests::{nostr_pubkey, proof_event},
+    };
+    use nostr_sdk::ToBech32;
+    use secp256k1::SecretKey;
+
+    fn employer_key() -> SecretKey {
+        SecretKey::from_slice(&[0x11; 32]).unwrap()
+    }
+
+    fn worker_key() -> SecretKey {
+        SecretKey::from_slice(&[0x12; 32]).unwrap()
+    }
+
+    fn employer() -> String {
+        nostr_pubkey(&employer_key())
+    }
+
+    fn worker() -> String {
+        nostr_pubkey(&worker_key())
+    }
+
+    /// Funding signed by the employer
+    fn funding(
+        task_id: Uuid,
+        mode: FundingMode,
+        escrow_parties: Option<EscrowParties>,
+    ) -> FundTaskRequest {
+        FundTaskRequest {
+            task_id,
+            employer_pubkey: employer(),
+            mode,
+            refund_address: None,
+            escrow_parties,
+            auth: ActionMessage::fund(task_id, mode, None, escrow_parties.as_ref())
+                .sign(&employer_key())
+                .unwrap(),
+        }
+    }
+
+    /// Claim signed by the worker
+    fn claim(task_id: Uuid, destination: &str) -> ClaimTaskRequest {
+        ClaimTaskRequest {
+            task_id,
+            worker_pubkey: worker(),
+            worker_invoice: destination.to_string(),
+            auth: ActionMessage::claim(task_id, destination)
+                .sign(&worker_key())
+                .unwrap(),
+        }
+    }
+
+    /// Proof submission signed by the worker
+    fn proof_request(task_id: Uuid) -> SubmitProofRequest {
+        proof_submission(task_id, "https://example.com/proof.png", &"a".repeat(64))
+    }
+
+    /// Submission of the proof at `proof_url`, signed by the worker
+    fn proof_submission(task_id: Uuid, proof_url: &str, proof_hash: &str) -> SubmitProofRequest {
+        let proof_event = proof_event(&worker_key(), task_id, proof_url, proof_hash);
+        let event: NostrEvent = serde_json::from_str(&proof_event).unwrap();
+        SubmitProofRequest {
+            task_id,
+            worker_pubkey: worker(),
+            proof_url: proof_url.to_string(),
+            auth: ActionMessage::submit_proof(task_id, proof_url, proof_hash, &event.id)
+                .sign(&worker_key())
+                .unwrap(),
+            proof_hash: proof_hash.to_string(),
+            proof_event,
+        }
+    }
+
+    /// Approval signed by the employer
+    fn approval(task_id: Uuid) -> VerifyTaskRequest {
+        VerifyTaskRequest {
+            task_id,
+            verifier_pubkey: employer(),
+            approved: true,
+            reason: "Looks good".to_string(),
+            auth: ActionMessage::verify(task_id, true, "Looks good")
+                .sign(&employer_key())
+                .unwrap(),
+        }
+    }
+
+    /// Cancellation signed by `key`
+    fn cancellation(key: &SecretKey, task_id: Uuid, refund_address: Option<&str>) -> ActionAuth {
+        ActionMessage::cancel(task_id, refund_address)
+            .sign(key)
+            .unwrap()
+    }
 
     #[tokio::test]
     async fn test_node_initialization() {
@@ -383,9 +972,10 @@ mod tests {
             title: "Test Task".to_string(),
             description: Some("Test description".to_string()),
             reward_sats: 50000,
-            employer_pubkey: "employer_pubkey".to_string(),
+            employer_pubkey: employer(),
             deadline: None,
             metadata: None,
+            reward_fiat: None,
         };
 
         let task = node.create_task(request).await.unwrap();
@@ -393,4 +983,1515 @@ mod tests {
         assert_eq!(task.reward_sats, 50000);
         assert_eq!(task.state, TaskState::Draft);
     }
+
+    #[tokio::test]
+    async fn test_funding_records_received_amount() {
+        let config = EscrowNodeConfig::default();
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Test Task".to_string(),
+                description: None,
+                reward_sats: 50000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::LightningHold, None))
+            .await
+            .unwrap();
+
+        // Partial payment is refused and audited
+        assert!(
+            node.process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), 45000)
+                .await
+                .is_err()
+        );
+
+        let task = node
+            .process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), 50200)
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Funded);
+
+        let info = node.get_task_info(task.id).await.unwrap();
+        let funding = info.funding.unwrap();
+        assert_eq!(funding.amount_received_sats, Some(50200));
+
+        let rejected = info
+            .events
+            .iter()
+            .find(|e| e.event_type == "invoice.payment_rejected")
+            .unwrap();
+        assert_eq!(rejected.amount_sats, Some(45000));
+
+        // Only the employer's signature cancels the funding
+        let forged = cancellation(&worker_key(), task.id, None);
+        assert!(
+            node.refund_task(task.id, &employer(), &forged)
+                .await
+                .is_err()
+        );
+        let task = node
+            .refund_task(
+                task.id,
+                &employer(),
+                &cancellation(&employer_key(), task.id, None),
+            )
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Refunded);
+        let info = node.get_task_info(task.id).await.unwrap();
+        let cancelled = info
+            .events
+            .iter()
+            .find(|e| e.event_type == "invoice.cancelled")
+            .unwrap();
+        assert_eq!(cancelled.amount_sats, Some(50200));
+    }
+
+    #[tokio::test]
+    async fn test_disputed_task_is_not_refunded_by_employer() {
+        let node = EscrowNode::new(EscrowNodeConfig::default()).await.unwrap();
+        let task = claimed_task_with_proof(&node, 20000, "worker@example.com").await;
+
+        let reason = "Incomplete";
+        let task = node
+            .verify_task(VerifyTaskRequest {
+                task_id: task.id,
+                verifier_pubkey: employer(),
+                approved: false,
+                reason: reason.to_string(),
+                auth: ActionMessage::verify(task.id, false, reason)
+                    .sign(&employer_key())
+                    .unwrap(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Disputed);
+
+        // The employer's own signature cannot bypass arbitration
+        assert!(matches!(
+            node.refund_task(
+                task.id,
+                &employer(),
+                &cancellation(&employer_key(), task.id, None),
+            )
+            .await,
+            Err(EscrowError::TaskValidation(_))
+        ));
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Disputed);
+        assert!(
+            !info
+                .events
+                .iter()
+                .any(|e| e.event_type == "invoice.cancelled")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_network_selection() {
+        let config = EscrowNodeConfig {
+            network: Network::Regtest,
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Regtest Task".to_string(),
+                description: None,
+                reward_sats: 20000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::LightningHold, None))
+            .await
+            .unwrap();
+        assert!(payment.invoice.unwrap().starts_with("lnbcrt"));
+        node.process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), 20000)
+            .await
+            .unwrap();
+
+        // Mainnet invoices and addresses are refused on regtest
+        for destination in [
+            "lnbc200u1pvjluez",
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq",
+        ] {
+            let result = node.claim_task(claim(task.id, destination)).await;
+            assert!(matches!(result, Err(EscrowError::TaskValidation(_))));
+        }
+
+        let task = node
+            .claim_task(claim(task.id, "lnbcrt200u1pvjluez"))
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Claimed);
+
+        // Boltz has no signet deployment
+        let signet = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Signet,
+            ..PaymentCoordinatorConfig::default()
+        });
+        assert!(
+            !signet
+                .get_supported_modes(200_000)
+                .contains(&FundingMode::OnchainSubmarine)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_swap_funding_lifecycle() {
+        use crate::boltz::SwapUpdate;
+        use crate::swap_script::tests::submarine_swap_fixture;
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use bitcoin::Address;
+        use serde_json::json;
+        use std::str::FromStr;
+
+        let boltz_public_key = secp256k1::PublicKey::from_secret_key(
+            &secp256k1::Secp256k1::new(),
+            &secp256k1::SecretKey::from_slice(&[0x07; 32]).unwrap(),
+        );
+        let server = MockHttpServer::start(move |request| match request.method.as_str() {
+            "GET" => MockResponse::json(
+                200,
+                json!({ "BTC": { "BTC": {
+                    "hash": "sub_hash",
+                    "rate": 1,
+                    "limits": { "minimal": 10000, "maximal": 25000000 },
+                    "fees": { "percentage": 0.1, "minerFees": 300 }
+                }}}),
+            ),
+            _ => {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let refund_key =
+                    secp256k1::PublicKey::from_str(body["refundPublicKey"].as_str().unwrap())
+                        .unwrap();
+                let (tree, address) =
+                    submarine_swap_fixture(&boltz_public_key, &refund_key, 850_000);
+                let address =
+                    Address::from_script(&address.script_pubkey(), Network::Bitcoin).unwrap();
+                MockResponse::json(
+                    201,
+                    json!({
+                        "id": format!("swap_{}", uuid::Uuid::new_v4()),
+                        "address": address.to_string(),
+                        "claimPublicKey": boltz_public_key.to_string(),
+                        "swapTree": tree,
+                        "timeoutBlockHeight": 850000,
+                        "expectedAmount": 50350
+                    }),
+                )
+            }
+        })
+        .await;
+        let config = EscrowNodeConfig {
+            payment_config: PaymentCoordinatorConfig {
+                boltz_api_url: Some(server.url()),
+                ..PaymentCoordinatorConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let mut swaps = Vec::new();
+        for _ in 0..2 {
+            let task = node
+                .create_task(CreateTaskRequest {
+                    title: "Swap Task".to_string(),
+                    description: None,
+                    reward_sats: 50000,
+                    employer_pubkey: employer(),
+                    deadline: None,
+                    metadata: None,
+                    reward_fiat: None,
+                })
+                .await
+                .unwrap();
+            let payment = node
+                .fund_task_with_swap(funding(task.id, FundingMode::OnchainSubmarine, None))
+                .await
+                .unwrap();
+            swaps.push((
+                task.id,
+                payment.swap_id.unwrap(),
+                payment.invoice_hash.unwrap(),
+            ));
+        }
+
+        // Boltz pays the engine's hold invoice, which is released at once so
+        // the swap can complete
+        let task = node
+            .process_invoice_payment(&swaps[0].2, 50000)
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Funded);
+        assert!(
+            node.escrow_engine
+                .get_invoice_status(&swaps[0].2)
+                .await
+                .is_err()
+        );
+
+        // Drive the first swap to completion and fail the second
+        let updates = [
+            (&swaps[0].1, "transaction.mempool"),
+            (&swaps[0].1, "invoice.paid"),
+            (&swaps[1].1, "transaction.mempool"),
+            (&swaps[1].1, "swap.expired"),
+        ];
+        for (swap_id, status) in updates {
+            let change = node
+                .payment_coordinator
+                .apply_swap_update(&SwapUpdate {
+                    id: swap_id.clone(),
+                    status: status.to_string(),
+                    failure_reason: None,
+                    transaction: None,
+                })
+                .await
+                .unwrap();
+            node.apply_swap_status_change(&change).await.unwrap();
+        }
+
+        let funded = node.get_task_info(swaps[0].0).await.unwrap();
+        assert_eq!(funded.task.state, TaskState::Funded);
+        let funding = funded.funding.unwrap();
+        assert_eq!(funding.status, crate::models::FundingStatus::Accepted);
+        assert_eq!(
+            funding.external_metadata.unwrap()["swap"]["state"],
+            "InvoicePaid"
+        );
+
+        let expired = node.get_task_info(swaps[1].0).await.unwrap();
+        assert_eq!(expired.task.state, TaskState::Draft);
+        assert!(
+            expired
+                .events
+                .iter()
+                .any(|e| e.event_type == "swap.expired")
+        );
+
+        // Only the employer can refund, and never a completed swap
+        let cancel = |task_id| cancellation(&employer_key(), task_id, None);
+        assert!(matches!(
+            node.refund_swap_funding(swaps[1].0, "someone_else", None, 2, &cancel(swaps[1].0))
+                .await,
+            Err(EscrowError::TaskValidation(_))
+        ));
+        assert!(matches!(
+            node.refund_swap_funding(swaps[0].0, &employer(), None, 2, &cancel(swaps[0].0))
+                .await,
+            Err(EscrowError::TaskValidation(_))
+        ));
+        // The signature binds the refund address
+        let auth = cancel(swaps[1].0);
+        assert!(matches!(
+            node.refund_swap_funding(
+                swaps[1].0,
+                &employer(),
+                Some("bcrt1qattacker".to_string()),
+                2,
+                &auth
+            )
+            .await,
+            Err(EscrowError::ProofVerification(_))
+        ));
+        // No refund address was given for the expired swap
+        assert!(matches!(
+            node.refund_swap_funding(swaps[1].0, &employer(), None, 2, &auth)
+                .await,
+            Err(EscrowError::Payment(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_onchain_payout() {
+        use crate::boltz::{SwapTransaction, SwapUpdate};
+        use crate::payment_coordinator::PayoutState;
+        use crate::swap_script::tests::{lockup_transaction, reverse_swap_fixture};
+        use crate::test_utils::{MockHttpServer, MockResponse, bolt11_invoice};
+        use bitcoin::{Address, Transaction, consensus::encode};
+        use secp256k1::{PublicKey, Secp256k1, SecretKey};
+        use serde_json::json;
+        use sha2::{Digest, Sha256};
+        use std::{
+            str::FromStr,
+            sync::{Arc, Mutex},
+        };
+
+        let boltz_key = SecretKey::from_slice(&[0x08; 32]).unwrap();
+        let boltz_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &boltz_key);
+        // Lockup address and preimage hash of the created swap
+        type SwapLockup = Option<(Address, [u8; 32])>;
+        let lockup: Arc<Mutex<SwapLockup>> = Arc::default();
+        let broadcast: Arc<Mutex<Vec<String>>> = Arc::default();
+
+        let (lockup_address, broadcasts) = (lockup.clone(), broadcast.clone());
+        let server = MockHttpServer::start(move |request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/v2/swap/reverse") => MockResponse::json(
+                    200,
+                    json!({ "BTC": { "BTC": {
+                        "hash": "rev_hash",
+                        "rate": 1,
+                        "limits": { "minimal": 10000, "maximal": 25000000 },
+                        "fees": { "percentage": 0.25, "minerFees": { "lockup": 250, "claim": 150 } }
+                    }}}),
+                ),
+                ("POST", "/v2/swap/reverse") => {
+                    let claim_key =
+                        PublicKey::from_str(body["claimPublicKey"].as_str().unwrap()).unwrap();
+                    let preimage_hash: [u8; 32] =
+                        hex::decode(body["preimageHash"].as_str().unwrap())
+                            .unwrap()
+                            .try_into()
+                            .unwrap();
+                    let (tree, address) = reverse_swap_fixture(
+                        &claim_key,
+                        &boltz_public_key,
+                        &preimage_hash,
+                        850_000,
+                    );
+                    *lockup_address.lock().unwrap() = Some((address.clone(), preimage_hash));
+                    MockResponse::json(
+                        201,
+                        json!({
+                            "id": "rev123",
+                            "invoice": bolt11_invoice("lnbcrt", &preimage_hash, 60000),
+                            "swapTree": tree,
+                            "lockupAddress": address.to_string(),
+                            "refundPublicKey": boltz_public_key.to_string(),
+                            "timeoutBlockHeight": 850000,
+                            "onchainAmount": 59450
+                        }),
+                    )
+                }
+                ("POST", "/v2/chain/BTC/transaction") => {
+                    broadcasts
+                        .lock()
+                        .unwrap()
+                        .push(body["hex"].as_str().unwrap().to_string());
+                    MockResponse::json(201, json!({ "id": "claim_txid" }))
+                }
+                _ => MockResponse::json(404, json!({ "error": "not found" })),
+            }
+        })
+        .await;
+        let config = EscrowNodeConfig {
+            network: Network::Regtest,
+            payment_config: PaymentCoordinatorConfig {
+                boltz_api_url: Some(server.url()),
+                ..PaymentCoordinatorConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let worker_address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        let task = claimed_task_with_proof(&node, 60000, worker_address).await;
+        let task = node.verify_task(approval(task.id)).await.unwrap();
+
+        // The swap invoice is paid, but the task is not paid until the claim confirms
+        assert_eq!(task.state, TaskState::Verified);
+        let info = node.get_task_info(task.id).await.unwrap();
+        let payout = &info.funding.unwrap().external_metadata.unwrap()["payout"];
+        assert_eq!(payout["state"], "InvoicePaid");
+        assert_eq!(payout["invoice_amount_sats"], 60000);
+        assert!(info.events.iter().any(|e| e.event_type == "payout.created"));
+
+        // Confirmations of an unclaimed payout are refused
+        assert!(node.record_payout_confirmations("rev123", 6).await.is_err());
+
+        // Boltz's lockup confirms and the claim is broadcast to the worker
+        let (address, preimage_hash) = lockup.lock().unwrap().clone().unwrap();
+        let lockup_tx = lockup_transaction(&address, 59450);
+        let change = node
+            .payment_coordinator
+            .apply_swap_update(&SwapUpdate {
+                id: "rev123".to_string(),
+                status: "transaction.confirmed".to_string(),
+                failure_reason: None,
+                transaction: Some(SwapTransaction {
+                    id: lockup_tx.txid().to_string(),
+                    hex: Some(encode::serialize_hex(&lockup_tx)),
+                }),
+            })
+            .await
+            .unwrap();
+        node.apply_swap_status_change(&change).await.unwrap();
+
+        let claims = broadcast.lock().unwrap().clone();
+        assert_eq!(claims.len(), 1);
+        let claim: Transaction = encode::deserialize(&hex::decode(&claims[0]).unwrap()).unwrap();
+        assert_eq!(claim.input[0].previous_output.txid, lockup_tx.txid());
+        assert_eq!(claim.output[0].script_pubkey.to_string(), {
+            let worker: Address<_> = worker_address.parse().unwrap();
+            worker.assume_checked().script_pubkey().to_string()
+        });
+        // The claim reveals the preimage of the swap invoice
+        let revealed: [u8; 32] = Sha256::digest(&claim.input[0].witness[1]).into();
+        assert_eq!(revealed, preimage_hash);
+
+        let payout = node.payment_coordinator.get_payout("rev123").await.unwrap();
+        assert_eq!(payout.state, PayoutState::Claimed);
+        assert_eq!(payout.claimed_sats, Some(claim.output[0].value.to_sat()));
+
+        let task = node.record_payout_confirmations("rev123", 2).await.unwrap();
+        assert_eq!(task.state, TaskState::Verified);
+        let task = node.record_payout_confirmations("rev123", 6).await.unwrap();
+        assert_eq!(task.state, TaskState::Paid);
+
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert!(info.events.iter().any(|e| e.event_type == "payout.claimed"));
+        assert!(
+            info.events
+                .iter()
+                .any(|e| e.event_type == "settlement.completed")
+        );
+        assert_eq!(
+            info.funding.unwrap().external_metadata.unwrap()["payout"]["state"],
+            "Confirmed"
+        );
+
+        // A reorg drops the claim back to unconfirmed; the task stays paid
+        let task = node.record_payout_confirmations("rev123", 0).await.unwrap();
+        assert_eq!(task.state, TaskState::Paid);
+        let payout = node.payment_coordinator.get_payout("rev123").await.unwrap();
+        assert_eq!(payout.state, PayoutState::Claimed);
+    }
+
+    /// Create, fund, pay, claim and submit proof for a task
+    async fn claimed_task_with_proof(
+        node: &EscrowNode,
+        reward_sats: i64,
+        destination: &str,
+    ) -> Task {
+        let task = claimed_task(node, reward_sats, destination).await;
+        node.submit_proof(proof_request(task.id)).await.unwrap()
+    }
+
+    /// Create, fund, pay and claim a task
+    async fn claimed_task(node: &EscrowNode, reward_sats: i64, destination: &str) -> Task {
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Batch Task".to_string(),
+                description: None,
+                reward_sats,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::LightningHold, None))
+            .await
+            .unwrap();
+        node.process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), reward_sats as u64)
+            .await
+            .unwrap();
+        node.claim_task(claim(task.id, destination)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_payment_expiry() {
+        let config = EscrowNodeConfig {
+            escrow_config: EscrowEngineConfig {
+                mpp_timeout_secs: 0,
+                ..EscrowEngineConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "MPP Task".to_string(),
+                description: None,
+                reward_sats: 20000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::LightningHold, None))
+            .await
+            .unwrap();
+        node.process_htlc_part(payment.invoice_hash.as_deref().unwrap(), "htlc_1", 10000)
+            .await
+            .unwrap();
+
+        // The background loop fails back the incomplete set without being asked
+        let expiry = node.spawn_payment_expiry();
+        let mut timed_out = false;
+        for _ in 0..50 {
+            let info = node.get_task_info(task.id).await.unwrap();
+            timed_out = info
+                .events
+                .iter()
+                .any(|e| e.event_type == "invoice.htlcs_timed_out");
+            if timed_out {
+                assert_eq!(
+                    info.funding.unwrap().status,
+                    crate::models::FundingStatus::Created
+                );
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        expiry.abort();
+        assert!(timed_out);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_on_transition() {
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use crate::webhook_dispatcher::{
+            EVENT_TYPE_HEADER, SIGNATURE_HEADER, verify_signature_header,
+        };
+
+        let server =
+            MockHttpServer::start(|_| MockResponse::json(200, serde_json::json!({ "ok": true })))
+                .await;
+        let config = EscrowNodeConfig {
+            webhook_config: WebhookDispatcherConfig {
+                endpoints: vec![WebhookEndpoint {
+                    id: "marketplace".to_string(),
+                    marketplace_id: None,
+                    url: server.url(),
+                    secret: "whsec_test".to_string(),
+                    event_types: vec![
+                        "task.claimed".to_string(),
+                        "settlement.completed".to_string(),
+                    ],
+                }],
+                ..WebhookDispatcherConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let task = claimed_task_with_proof(&node, 20000, "worker@example.com").await;
+        node.verify_task(approval(task.id)).await.unwrap();
+
+        // Delivery runs in the background
+        let mut attempts = Vec::new();
+        for _ in 0..50 {
+            attempts = node.get_webhook_delivery_attempts(task.id).await;
+            if attempts.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts.iter().all(|attempt| attempt.success));
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 2);
+        let request = requests
+            .iter()
+            .find(|request| request.header(EVENT_TYPE_HEADER) == Some("task.claimed"))
+            .unwrap();
+        assert_eq!(request.header(EVENT_TYPE_HEADER), Some("task.claimed"));
+        let header = request.header(SIGNATURE_HEADER).unwrap();
+        assert!(verify_signature_header("whsec_test", header, &request.body_text(), 300).is_ok());
+        let body: serde_json::Value = serde_json::from_str(&request.body_text()).unwrap();
+        assert_eq!(body["task_id"], serde_json::json!(task.id));
+        assert_eq!(
+            body["data"]["task_state"],
+            serde_json::json!(TaskState::Claimed)
+        );
+
+        // Settlement details go out without the preimage
+        let request = requests
+            .iter()
+            .find(|request| request.header(EVENT_TYPE_HEADER) == Some("settlement.completed"))
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.body_text()).unwrap();
+        assert_eq!(body["data"]["metadata"]["amount_sats"], 20000);
+        assert!(!request.body_text().contains("preimage"));
+    }
+
+    #[tokio::test]
+    async fn test_batched_settlement() {
+        let config = EscrowNodeConfig {
+            settlement_config: SettlementSchedulerConfig {
+                enabled: true,
+                ..SettlementSchedulerConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let first = claimed_task_with_proof(&node, 20000, "worker@example.com").await;
+        let second = claimed_task_with_proof(&node, 30000, "worker@example.com").await;
+        let other = claimed_task_with_proof(&node, 10000, "other@example.com").await;
+        // A single-use invoice is never paid the sum of several tasks
+        let invoice = "lnbc100u1pvjluez";
+        let invoiced = claimed_task_with_proof(&node, 10000, invoice).await;
+        let reinvoiced = claimed_task_with_proof(&node, 10000, invoice).await;
+
+        for task in [&first, &second, &other, &invoiced, &reinvoiced] {
+            let verified = node.verify_task(approval(task.id)).await.unwrap();
+            assert_eq!(verified.state, TaskState::Verified);
+        }
+
+        let results = node.run_settlement_batch().await.unwrap();
+        assert_eq!(results.len(), 4);
+        let worker_batch = results
+            .iter()
+            .find(|r| r.destination == "worker@example.com")
+            .unwrap();
+        assert_eq!(worker_batch.total_paid_sats, 50000);
+        assert_eq!(worker_batch.paid_task_ids.len(), 2);
+        let invoice_batches: Vec<_> = results
+            .iter()
+            .filter(|r| r.destination == invoice)
+            .collect();
+        assert_eq!(invoice_batches.len(), 2);
+        assert!(
+            invoice_batches
+                .iter()
+                .all(|r| r.total_paid_sats == 10000 && r.paid_task_ids.len() == 1)
+        );
+
+        for task in [&first, &second, &other, &invoiced, &reinvoiced] {
+            let info = node.get_task_info(task.id).await.unwrap();
+            assert_eq!(info.task.state, TaskState::Paid);
+            let settled = info
+                .events
+                .iter()
+                .find(|e| e.event_type == "settlement.completed")
+                .unwrap();
+            assert_eq!(settled.amount_sats, Some(task.reward_sats));
+        }
+
+        assert!(node.run_settlement_batch().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batched_settlement_completion_failure() {
+        use crate::models::InvoiceSettlementData;
+        use crate::settlement_scheduler::PendingSettlement;
+
+        let config = EscrowNodeConfig {
+            settlement_config: SettlementSchedulerConfig {
+                enabled: true,
+                ..SettlementSchedulerConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        // Queued ahead of a verified task, but its task cannot be marked paid
+        let stuck = claimed_task(&node, 10000, "worker@example.com").await;
+        let funding = node.get_task_info(stuck.id).await.unwrap().funding.unwrap();
+        node.settlement_scheduler
+            .enqueue(PendingSettlement {
+                task_id: stuck.id,
+                funding_id: funding.id,
+                hold_invoice_id: funding.hold_invoice_id.clone().unwrap_or_default(),
+                destination: "worker@example.com".to_string(),
+                queued_at: Utc::now(),
+                released: Some(InvoiceSettlementData {
+                    invoice_hash: funding.invoice_hash.clone().unwrap_or_default(),
+                    preimage: "00".repeat(32),
+                    amount_sats: 10000,
+                    settled_at: Utc::now(),
+                }),
+                paid: None,
+            })
+            .await;
+        let task = claimed_task_with_proof(&node, 20000, "worker@example.com").await;
+        node.verify_task(approval(task.id)).await.unwrap();
+
+        // The rest of the batch still completes; the failed task stays queued
+        let results = node.run_settlement_batch().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].total_paid_sats, 30000);
+        assert_eq!(results[0].paid_task_ids, vec![task.id]);
+        assert!(results[0].error.is_some());
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Paid);
+        assert!(node.settlement_scheduler.is_queued(stuck.id).await);
+        assert!(!node.settlement_scheduler.is_queued(task.id).await);
+
+        // Retrying does not pay the stuck task a second time
+        let results = node.run_settlement_batch().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].payout_id, None);
+        assert_eq!(results[0].total_paid_sats, 0);
+        assert!(results[0].paid_task_ids.is_empty());
+        assert!(node.settlement_scheduler.is_queued(stuck.id).await);
+    }
+
+    #[tokio::test]
+    async fn test_multisig_escrow_task() {
+        use crate::payment_coordinator::{EscrowOutput, PaymentStatus, PaymentStatusUpdate};
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use bitcoin::{Psbt, consensus::encode};
+        use secp256k1::{PublicKey, Secp256k1, SecretKey};
+        use serde_json::json;
+        use std::str::FromStr;
+
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/v2/chain/BTC/transaction" => MockResponse::json(201, json!({ "id": "txid" })),
+            _ => MockResponse::json(404, json!({ "error": "not found" })),
+        })
+        .await;
+        let config = EscrowNodeConfig {
+            network: Network::Regtest,
+            payment_config: PaymentCoordinatorConfig {
+                network: Network::Regtest,
+                boltz_api_url: Some(server.url()),
+                ..PaymentCoordinatorConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let secp = Secp256k1::new();
+        let employer_key = SecretKey::from_slice(&[0x31; 32]).unwrap();
+        let worker_key = SecretKey::from_slice(&[0x32; 32]).unwrap();
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Escrow Task".to_string(),
+                description: None,
+                reward_sats: 100000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(
+                task.id,
+                FundingMode::OnchainMultisig,
+                Some(EscrowParties {
+                    employer: PublicKey::from_secret_key(&secp, &employer_key),
+                    worker: PublicKey::from_secret_key(&secp, &worker_key),
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(payment.mode, FundingMode::OnchainMultisig);
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::PendingFunding);
+        let funding = info.funding.unwrap();
+        assert_eq!(funding.provider, "multisig");
+        assert_eq!(funding.onchain_address, payment.onchain_address);
+        assert!(funding.lockup_script.is_some());
+
+        // The funding transaction confirms
+        let address =
+            crate::network::parse_address(&payment.onchain_address.unwrap(), Network::Regtest)
+                .unwrap();
+        let funding_tx = crate::swap_script::tests::lockup_transaction(&address, 100_000);
+        node.payment_coordinator
+            .watch_multisig_funding(payment.funding_id, &encode::serialize_hex(&funding_tx))
+            .await
+            .unwrap();
+        apply_chain_change(
+            &node.task_manager,
+            &ChainStatusChange {
+                payout_swap_id: None,
+                update: PaymentStatusUpdate {
+                    funding_id: payment.funding_id,
+                    status: PaymentStatus::Confirmed,
+                    confirmations: Some(1),
+                    transaction_id: Some(funding_tx.txid().to_string()),
+                    failure_reason: None,
+                },
+                reorged: false,
+            },
+        )
+        .await;
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Funded);
+        assert!(info.events.iter().any(|e| e.event_type == "escrow.funded"));
+
+        // The escrow can only be released on-chain
+        let worker_address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        assert!(matches!(
+            node.claim_task(claim(task.id, "lnbcrt200u1pvjluez")).await,
+            Err(EscrowError::TaskValidation(_))
+        ));
+        node.claim_task(claim(task.id, worker_address))
+            .await
+            .unwrap();
+        node.submit_proof(proof_request(task.id)).await.unwrap();
+        let task = node.verify_task(approval(task.id)).await.unwrap();
+
+        // Approval prepares the release for both parties to sign
+        assert_eq!(task.state, TaskState::Verified);
+        let info = node.get_task_info(task.id).await.unwrap();
+        let prepared = &info.funding.unwrap().external_metadata.unwrap()["escrow_settlement"];
+        let mut psbt = Psbt::from_str(prepared["psbt"].as_str().unwrap()).unwrap();
+        let EscrowOutput::P2wsh(escrow) = node
+            .payment_coordinator
+            .get_multisig_funding(payment.funding_id)
+            .await
+            .unwrap()
+            .escrow
+        else {
+            panic!("small escrows use P2WSH");
+        };
+        escrow.sign_psbt(&mut psbt, &employer_key).unwrap();
+        escrow.sign_psbt(&mut psbt, &worker_key).unwrap();
+
+        let settlement = node
+            .finalize_escrow_settlement(task.id, &[psbt.to_string()])
+            .await
+            .unwrap();
+        assert!(settlement.broadcast);
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Paid);
+        let settled = info
+            .events
+            .iter()
+            .find(|e| e.event_type == "settlement.completed")
+            .unwrap();
+        assert_eq!(
+            settled.amount_sats,
+            Some(100_000 - settlement.fee_sats as i64)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multisig_escrow_reorg() {
+        use crate::chain_watcher::ChainEvent;
+        use crate::models::FundingStatus;
+        use bitcoin::consensus::encode;
+        use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+        let node = EscrowNode::new(EscrowNodeConfig {
+            network: Network::Regtest,
+            payment_config: PaymentCoordinatorConfig {
+                network: Network::Regtest,
+                ..PaymentCoordinatorConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        })
+        .await
+        .unwrap();
+        let secp = Secp256k1::new();
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Escrow Task".to_string(),
+                description: None,
+                reward_sats: 100000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(
+                task.id,
+                FundingMode::OnchainMultisig,
+                Some(EscrowParties {
+                    employer: PublicKey::from_secret_key(
+                        &secp,
+                        &SecretKey::from_slice(&[0x31; 32]).unwrap(),
+                    ),
+                    worker: PublicKey::from_secret_key(
+                        &secp,
+                        &SecretKey::from_slice(&[0x32; 32]).unwrap(),
+                    ),
+                }),
+            ))
+            .await
+            .unwrap();
+        let address =
+            crate::network::parse_address(&payment.onchain_address.unwrap(), Network::Regtest)
+                .unwrap();
+        let funding_tx = crate::swap_script::tests::lockup_transaction(&address, 100_000);
+        let watch_id = crate::payment_coordinator::funding_watch_id(payment.funding_id);
+        let confirmed = ChainEvent::Confirmed {
+            watch_id: watch_id.clone(),
+            txid: funding_tx.txid(),
+            tx_hex: encode::serialize_hex(&funding_tx),
+            block_height: 101,
+            confirmations: 1,
+        };
+        let sync = |event: ChainEvent| {
+            let node = &node;
+            async move {
+                let change = node
+                    .payment_coordinator
+                    .apply_chain_event(&event)
+                    .await
+                    .unwrap();
+                apply_chain_change(&node.task_manager, &change).await;
+            }
+        };
+
+        sync(confirmed.clone()).await;
+        let worker_address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        node.claim_task(claim(task.id, worker_address))
+            .await
+            .unwrap();
+
+        // The funding block is reorged out: nothing can be released until it is re-mined
+        sync(ChainEvent::Reorged {
+            watch_id,
+            txid: funding_tx.txid(),
+            block_height: 101,
+        })
+        .await;
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::PendingFunding);
+        assert_eq!(info.task.worker_pubkey, Some(worker()));
+        let funding = info.funding.unwrap();
+        assert_eq!(funding.status, FundingStatus::Pending);
+        assert_eq!(funding.amount_received_sats, None);
+        assert!(info.events.iter().any(|e| e.event_type == "escrow.reorged"));
+        assert!(node.submit_proof(proof_request(task.id)).await.is_err());
+
+        // Re-included in the new chain: the claim resumes
+        sync(confirmed).await;
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Claimed);
+        assert_eq!(info.funding.unwrap().status, FundingStatus::Accepted);
+        node.submit_proof(proof_request(task.id)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cashu_funded_task() {
+        use crate::cashu::{self, P2pkConditions, Token, tests::MockMint};
+        use crate::payment_coordinator::CashuLock;
+        use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+        let mint = MockMint::start(100).await;
+        let config = EscrowNodeConfig {
+            payment_config: PaymentCoordinatorConfig {
+                cashu_mint_url: Some(mint.url()),
+                ..PaymentCoordinatorConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let secp = Secp256k1::new();
+        let employer = employer();
+        let ecash_key =
+            PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[0x42; 32]).unwrap());
+        let task = node
+            .create_task(CreateTaskRequest {
+                title: "Ecash Task".to_string(),
+                description: None,
+                reward_sats: 10000,
+                employer_pubkey: employer.clone(),
+                deadline: None,
+                metadata: None,
+                reward_fiat: None,
+            })
+            .await
+            .unwrap();
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::Cashu, None))
+            .await
+            .unwrap();
+        assert_eq!(payment.mode, FundingMode::Cashu);
+        let lock: CashuLock = serde_json::from_str(&payment.lockup_script.unwrap()).unwrap();
+
+        // The employer's wallet locks the reward to the escrow
+        let token = mint.locked_token(
+            10_000,
+            &P2pkConditions {
+                pubkey: cashu::parse_pubkey(&lock.pubkey).unwrap(),
+                locktime: Some(lock.locktime),
+                refund_keys: vec![cashu::parse_pubkey(&employer).unwrap()],
+            },
+        );
+        let stranger = SecretKey::from_slice(&[0x14; 32]).unwrap();
+        let forged = ActionMessage::fund(task.id, FundingMode::Cashu, None, None)
+            .sign(&stranger)
+            .unwrap();
+        assert!(
+            node.submit_cashu_token(task.id, &nostr_pubkey(&stranger), &token, &forged)
+                .await
+                .is_err()
+        );
+        assert!(
+            node.submit_cashu_token(task.id, &employer, &token, &forged)
+                .await
+                .is_err()
+        );
+        // The employer may sign in as npub what they created the task as in hex
+        let employer_npub = nostr_sdk::PublicKey::from_hex(&employer)
+            .unwrap()
+            .to_bech32()
+            .unwrap();
+        let submission = ActionMessage::fund(task.id, FundingMode::Cashu, None, None)
+            .sign(&employer_key())
+            .unwrap();
+        let task = node
+            .submit_cashu_token(task.id, &employer_npub, &token, &submission)
+            .await
+            .unwrap();
+        assert_eq!(task.state, TaskState::Funded);
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.funding.unwrap().provider, "cashu");
+        assert!(info.events.iter().any(|e| e.event_type == "cashu.funded"));
+
+        // Ecash is only handed over locked to the worker's key
+        assert!(matches!(
+            node.claim_task(claim(task.id, "lnbcrt200u1pvjluez")).await,
+            Err(EscrowError::TaskValidation(_))
+        ));
+        node.claim_task(claim(task.id, &ecash_key.to_string()))
+            .await
+            .unwrap();
+
+        // Each action must be signed by the party taking it
+        let mut forged = proof_request(task.id);
+        forged.proof_event = proof_event(
+            &employer_key(),
+            task.id,
+            &forged.proof_url,
+            &forged.proof_hash,
+        );
+        assert!(node.submit_proof(forged).await.is_err());
+        let proof = proof_request(task.id);
+        let task = node.submit_proof(proof.clone()).await.unwrap();
+        assert_eq!(task.proof_nostr_event.as_ref(), Some(&proof.proof_event));
+        let mut forged = approval(task.id);
+        forged.auth = ActionMessage::verify(task.id, true, "Looks good")
+            .sign(&worker_key())
+            .unwrap();
+        assert!(node.verify_task(forged).await.is_err());
+        let task = node.verify_task(approval(task.id)).await.unwrap();
+
+        assert_eq!(task.state, TaskState::Paid);
+        let info = node.get_task_info(task.id).await.unwrap();
+        let settlement = &info.funding.unwrap().external_metadata.unwrap()["cashu_settlement"];
+        let paid = Token::decode(settlement["token"].as_str().unwrap()).unwrap();
+        assert_eq!(paid.amount(), 9_999);
+        assert!(paid.proofs().all(|proof| {
+            P2pkConditions::from_secret(&proof.secret)
+                .unwrap()
+                .unwrap()
+                .pubkey
+                == ecash_key
+        }));
+        let settled = info
+            .events
+            .iter()
+            .find(|e| e.event_type == "settlement.completed")
+            .unwrap();
+        assert_eq!(settled.amount_sats, Some(9_999));
+    }
+
+    #[tokio::test]
+    async fn test_proofs_archived_for_disputes() {
+        use crate::proof_archive::ProofRetention;
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use sha2::{Digest, Sha256};
+
+        const REPORT: &[u8] = b"# Report\n\nAll tests pass.\n";
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/report.md" => MockResponse::bytes(200, "text/markdown", REPORT),
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let archive_dir = std::env::temp_dir().join(format!("escrow-proofs-{}", Uuid::new_v4()));
+        let arbitrator_key = SecretKey::from_slice(&[0x13; 32]).unwrap();
+        let config = EscrowNodeConfig {
+            verification_config: VerificationServiceConfig {
+                allow_private_proof_hosts: true,
+                ..VerificationServiceConfig::default()
+            },
+            proof_archive_config: Some(ProofArchiveConfig {
+                archive_dir: archive_dir.clone(),
+                retention: ProofRetention::AfterClose { days: 0 },
+                arbitrator_pubkeys: vec![nostr_pubkey(&arbitrator_key)],
+            }),
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+        let task = claimed_task(&node, 20000, "worker@example.com").await;
+        let url = format!("{}/report.md", server.url());
+        let hash = hex::encode(Sha256::digest(REPORT));
+
+        // The declared hash must match what the URL serves
+        let mismatched = proof_submission(task.id, &url, &"a".repeat(64));
+        assert!(node.submit_proof(mismatched).await.is_err());
+        node.submit_proof(proof_submission(task.id, &url, &hash))
+            .await
+            .unwrap();
+        let archived = node.get_archived_proof(task.id).await.unwrap().unwrap();
+        assert_eq!(
+            (archived.content_hash.as_str(), archived.size),
+            (hash.as_str(), REPORT.len() as u64)
+        );
+
+        // Parties and the arbitrator can read the copy, with a signed request
+        let retrieve = |key: &SecretKey| ActionMessage::retrieve_proof(task.id).sign(key).unwrap();
+        for key in [employer_key(), worker_key(), arbitrator_key] {
+            let proof = node
+                .retrieve_archived_proof(task.id, &nostr_pubkey(&key), &retrieve(&key))
+                .await
+                .unwrap();
+            assert_eq!(proof.data, REPORT);
+        }
+        let stranger = SecretKey::from_slice(&[0x14; 32]).unwrap();
+        assert!(
+            node.retrieve_archived_proof(task.id, &nostr_pubkey(&stranger), &retrieve(&stranger))
+                .await
+                .is_err()
+        );
+        assert!(
+            node.retrieve_archived_proof(task.id, &employer(), &retrieve(&worker_key()))
+                .await
+                .is_err()
+        );
+
+        // Kept while the task is open, pruned once it is paid
+        assert!(node.prune_proof_archive().await.unwrap().is_empty());
+        node.verify_task(approval(task.id)).await.unwrap();
+        assert_eq!(node.prune_proof_archive().await.unwrap(), vec![task.id]);
+        assert!(node.get_archived_proof(task.id).await.unwrap().is_none());
+
+        std::fs::remove_dir_all(&archive_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_automated_proof_verification() {
+        use crate::proof_verifier::{ProofVerifierConfig, RegexVerifier};
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use sha2::{Digest, Sha256};
+
+        const REPORT: &[u8] = b"# Report\n\n42 passed, 0 failed\n";
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/report.md" => MockResponse::bytes(200, "text/markdown", REPORT),
+            // Passes the check, but is not what the worker signed
+            "/swapped.md" => MockResponse::bytes(200, "text/markdown", "# Report\n\n0 failed\n"),
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let config = EscrowNodeConfig {
+            verification_config: VerificationServiceConfig {
+                allow_private_proof_hosts: true,
+                proof_verifiers: std::collections::HashMap::from([(
+                    "test-run".to_string(),
+                    ProofVerifierConfig::Regex {
+                        pattern: r"\b0 failed\b".to_string(),
+                    },
+                )]),
+                ..VerificationServiceConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+        node.register_proof_verifier("release", Arc::new(RegexVerifier::new("^v\\d").unwrap()))
+            .await;
+        let hash = hex::encode(Sha256::digest(REPORT));
+
+        let submit_to = |path: &str, metadata: serde_json::Value| {
+            let node = &node;
+            let (url, hash) = (format!("{}{}", server.url(), path), hash.clone());
+            async move {
+                let task = node
+                    .create_task(CreateTaskRequest {
+                        title: "Fix the tests".to_string(),
+                        description: None,
+                        reward_sats: 20000,
+                        employer_pubkey: employer(),
+                        deadline: None,
+                        metadata: Some(metadata),
+                        reward_fiat: None,
+                    })
+                    .await
+                    .unwrap();
+                let payment = node
+                    .fund_task(funding(task.id, FundingMode::LightningHold, None))
+                    .await
+                    .unwrap();
+                node.process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), 20000)
+                    .await
+                    .unwrap();
+                node.claim_task(claim(task.id, "worker@example.com"))
+                    .await
+                    .unwrap();
+                node.submit_proof(proof_submission(task.id, &url, &hash))
+                    .await
+                    .unwrap()
+            }
+        };
+        let submit = |metadata| submit_to("/report.md", metadata);
+
+        // Without opting in the result is only advice for the employer
+        let advised = submit(serde_json::json!({ "task_type": "test-run" })).await;
+        assert_eq!(advised.state, TaskState::Claimed);
+        let completion = node
+            .get_task_info(advised.id)
+            .await
+            .unwrap()
+            .completion
+            .unwrap();
+        assert!(completion.approved);
+        assert_eq!(completion.verification_method, "regex");
+
+        // Opted in, a passing proof approves and pays the task
+        let approved =
+            submit(serde_json::json!({ "task_type": "test-run", "auto_approve": true })).await;
+        assert_eq!(approved.state, TaskState::Paid);
+        assert_eq!(approved.verified_by.as_deref(), Some("automated:regex"));
+
+        // A failing proof stays with the employer, whatever the opt-in
+        let rejected =
+            submit(serde_json::json!({ "task_type": "release", "auto_approve": true })).await;
+        assert_eq!(rejected.state, TaskState::Claimed);
+        let completion = node
+            .get_task_info(rejected.id)
+            .await
+            .unwrap()
+            .completion
+            .unwrap();
+        assert!(!completion.approved);
+
+        // Content that differs from the signed hash is never approved
+        let swapped = submit_to(
+            "/swapped.md",
+            serde_json::json!({ "task_type": "test-run", "auto_approve": true }),
+        )
+        .await;
+        assert_eq!(swapped.state, TaskState::Claimed);
+        let completion = node
+            .get_task_info(swapped.id)
+            .await
+            .unwrap()
+            .completion
+            .unwrap();
+        assert!(!completion.approved);
+        assert!(
+            completion.feedback.contains(&hash),
+            "{}",
+            completion.feedback
+        );
+
+        // Untyped tasks are not checked
+        let untyped = submit(serde_json::json!({ "auto_approve": true })).await;
+        assert!(
+            node.get_task_info(untyped.id)
+                .await
+                .unwrap()
+                .completion
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_acceptance_criteria() {
+        use crate::test_utils::{MockHttpServer, MockResponse};
+        use sha2::{Digest, Sha256};
+
+        const RESULTS: &[u8] = br#"{"commit": "4f2a9c1", "tests": ["parse", "settle"]}"#;
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/results.json" => MockResponse::bytes(200, "application/json", RESULTS),
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let config = EscrowNodeConfig {
+            verification_config: VerificationServiceConfig {
+                allow_private_proof_hosts: true,
+                ..VerificationServiceConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+        let create = |criteria: serde_json::Value| {
+            node.create_task(CreateTaskRequest {
+                title: "Add tests".to_string(),
+                description: None,
+                reward_sats: 20000,
+                employer_pubkey: employer(),
+                deadline: None,
+                metadata: Some(serde_json::json!({ "acceptance_criteria": criteria })),
+                reward_fiat: None,
+            })
+        };
+
+        // Malformed criteria are refused up front
+        let malformed =
+            create(serde_json::json!([{ "type": "min_count", "field": "tests" }])).await;
+        assert!(matches!(malformed, Err(EscrowError::TaskValidation(_))));
+
+        let task = create(serde_json::json!([
+            { "type": "file_type", "allowed": ["txt"] },
+            { "type": "size", "max_bytes": 4096 },
+            { "type": "required_fields", "fields": ["commit"] },
+            { "type": "min_count", "field": "tests", "min": 3 },
+        ]))
+        .await
+        .unwrap();
+        let payment = node
+            .fund_task(funding(task.id, FundingMode::LightningHold, None))
+            .await
+            .unwrap();
+        node.process_invoice_payment(payment.invoice_hash.as_deref().unwrap(), 20000)
+            .await
+            .unwrap();
+        node.claim_task(claim(task.id, "worker@example.com"))
+            .await
+            .unwrap();
+        let url = format!("{}/results.json", server.url());
+        let hash = hex::encode(Sha256::digest(RESULTS));
+        node.submit_proof(proof_submission(task.id, &url, &hash))
+            .await
+            .unwrap();
+
+        let completion = node
+            .get_task_info(task.id)
+            .await
+            .unwrap()
+            .completion
+            .unwrap();
+        assert!(!completion.approved);
+        assert_eq!(completion.score, 75);
+        assert_eq!(
+            completion.feedback,
+            "3 of 4 acceptance criteria met; min_count failed: Found 2"
+        );
+        let report = completion.criteria.unwrap();
+        let passed: Vec<bool> = report.results.iter().map(|result| result.passed).collect();
+        assert_eq!(passed, [true, true, true, false]);
+
+        // The report travels with the employer's verification
+        node.verify_task(approval(task.id)).await.unwrap();
+        let events = node.get_task_info(task.id).await.unwrap().events;
+        for event_type in ["proof.auto_verified", "proof.verified"] {
+            let event = events
+                .iter()
+                .find(|event| event.event_type == event_type)
+                .unwrap();
+            let attached = &event.metadata.as_ref().unwrap()["criteria"];
+            assert_eq!(
+                attached,
+                &serde_json::to_value(&report).unwrap(),
+                "{}",
+                event_type
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_actions_cannot_be_replayed() {
+        let node = EscrowNode::new(EscrowNodeConfig::default()).await.unwrap();
+        let task = claimed_task_with_proof(&node, 20000, "worker@example.com").await;
+
+        // The worker may submit proof again, but not resend a request
+        let proof = proof_request(task.id);
+        node.submit_proof(proof.clone()).await.unwrap();
+        let replayed = node.submit_proof(proof).await.unwrap_err();
+        assert!(replayed.to_string().contains("already used"));
+
+        // A signed approval is only accepted while fresh
+        let mut stale = approval(task.id);
+        stale.auth = ActionMessage::verify(task.id, true, "Looks good").sign_with(
+            &employer_key(),
+            new_nonce().unwrap(),
+            Utc::now().timestamp() - 600,
+        );
+        assert!(node.verify_task(stale).await.is_err());
+
+        // Approving is signed differently from rejecting
+        let mut flipped = approval(task.id);
+        flipped.approved = false;
+        assert!(node.verify_task(flipped).await.is_err());
+        let task = node.verify_task(approval(task.id)).await.unwrap();
+        assert_eq!(task.state, TaskState::Paid);
+    }
+
+    #[tokio::test]
+    async fn test_fiat_reward_locked_at_funding() {
+        use crate::price_oracle::{PriceSourceConfig, RequotePolicy};
+        use std::collections::HashMap;
+
+        let config = EscrowNodeConfig {
+            task_config: TaskManagerConfig {
+                price_sources: vec![PriceSourceConfig::Fixed {
+                    prices: HashMap::from([("USD".to_string(), 100_000.0)]),
+                }],
+                requote_policy: RequotePolicy::KeepRate { max_age_secs: 600 },
+                ..TaskManagerConfig::default()
+            },
+            escrow_config: EscrowEngineConfig {
+                invoice_expiry_secs: 0,
+                ..EscrowEngineConfig::default()
+            },
+            ..EscrowNodeConfig::default()
+        };
+        let node = EscrowNode::new(config).await.unwrap();
+
+        let mut request = CreateTaskRequest {
+            title: "Fiat Task".to_string(),
+            description: None,
+            reward_sats: 0,
+            employer_pubkey: employer(),
+            deadline: None,
+            metadata: None,
+            reward_fiat: Some(FiatAmount {
+                currency: "EUR".to_string(),
+                amount_cents: 5_000,
+            }),
+        };
+        // No source prices EUR
+        assert!(node.create_task(request.clone()).await.is_err());
+        request.reward_fiat = Some(FiatAmount {
+            currency: "USD".to_string(),
+            amount_cents: 5_000,
+        });
+        let task = node.create_task(request).await.unwrap();
+        assert_eq!((task.currency.as_str(), task.reward_sats), ("USD", 50_000));
+
+        let fund = || funding(task.id, FundingMode::LightningHold, None);
+        node.fund_task(fund()).await.unwrap();
+        let info = node.get_task_info(task.id).await.unwrap();
+        let first = info.funding.unwrap();
+        let quote = first.fiat_quote.clone().unwrap();
+        assert_eq!(
+            (quote.amount_sats, quote.btc_price, quote.source.as_str()),
+            (50_000, 100_000.0, "fixed")
+        );
+        assert_eq!(first.amount_sats, 50_000);
+
+        // The invoice expires unpaid; funding again keeps the recent rate
+        assert_eq!(node.expire_unpaid_fundings().await.unwrap(), vec![first.id]);
+        let info = node.get_task_info(task.id).await.unwrap();
+        assert_eq!(info.task.state, TaskState::Draft);
+        assert!(
+            info.events
+                .iter()
+                .any(|e| e.event_type == "invoice.expired")
+        );
+
+        node.fund_task(fund()).await.unwrap();
+        let second = node.get_task_info(task.id).await.unwrap().funding.unwrap();
+        assert_ne!(second.id, first.id);
+        assert_eq!(second.fiat_quote, Some(quote));
+    }
 }
@@ -2,37 +2,141 @@
 //!
 //! This module handles routing payments through different payment rails
 //! (Lightning, on-chain) and integrates with external services like Boltz
-//! for submarine swaps when needed.
+//! for submarine swaps when needed. Failed submarine swaps are refunded
+//! cooperatively with Boltz, or through the timeout script path when Boltz
+//! is unavailable. On-chain payouts go through Boltz reverse swaps whose
+//! lockup is claimed to the worker's address with our preimage. Multisig
+//! escrow locks funds in a 2-of-3 output settled through PSBTs; large escrows
+//! use a Taproot output whose cooperative close is a MuSig2 key-path spend.
+//! With a chain source configured, escrow fundings and payout claims are
+//! followed on-chain through confirmations and reorgs. Cashu fundings are
+//! ecash of a configured mint locked to a fresh escrow key, swapped at the
+//! mint for proofs locked to the worker (or back to the employer).
 
 use crate::{
+    boltz::{
+        BoltzClient, CreateReverseSwapRequest, CreateSubmarineSwapRequest, RefundSignatureRequest,
+        ReverseSwapResponse, SwapKind, SwapTree, SwapUpdate, BTC,
+    },
+    cashu::{self, CashuMintClient, P2pkConditions, PreparedOutput, Proof, ProofState, Token},
+    chain_source::{ChainSource, ChainSourceConfig},
+    chain_watcher::{ChainEvent, ChainWatcher, ChainWatcherConfig, WatchTarget},
+    engine::EscrowEngine,
     error::EscrowError,
+    fee_estimator::{self, FeeEstimator, FeeSourceConfig, RoutingFeeProber, StaticFeeSchedule},
     models::{FundingMode},
+    multisig_escrow::{MultisigEscrow, MultisigKeys, MultisigOutcome},
+    musig::{self, PartialSignature, PublicNonce, SigningSession},
+    network::{self, Network},
+    swap_script::{RefundPath, SpendPath, SwapScript, UnsignedSwapSpend},
+    taproot_escrow::{EscrowLeaf, TaprootEscrow},
     EscrowResult,
 };
+use bitcoin::{consensus::encode, Address, OutPoint, Psbt, Transaction, TxOut};
 use chrono::{DateTime, Utc};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
 use serde::{Deserialize, Serialize};
-// std collections not needed here
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{mpsc, RwLock};
+
+/// Rough virtual size of a payout claim through the swap's claim leaf
+const CLAIM_VSIZE: u64 = 140;
+
+/// Rough virtual size of a two-output multisig escrow settlement
+const MULTISIG_SETTLEMENT_VSIZE: u64 = 220;
+
+/// Chain watch id prefixes
+const FUNDING_WATCH_PREFIX: &str = "funding:";
+const PAYOUT_WATCH_PREFIX: &str = "payout:";
+
+/// Average block interval
+const BLOCK_INTERVAL_SECS: u64 = 600;
+
+/// Expected time for a Lightning payment to settle
+const LIGHTNING_ETA_SECS: u64 = 60;
 
 /// Configuration for the payment coordinator
 #[derive(Debug, Clone)]
 pub struct PaymentCoordinatorConfig {
-    /// Boltz API configuration
-    pub boltz_api_url: String,
+    /// Bitcoin network for invoices, addresses and swaps
+    pub network: Network,
+    /// Boltz API override (defaults to the public endpoint for `network`)
+    pub boltz_api_url: Option<String>,
+    /// Timeout for Boltz API requests in seconds
+    pub boltz_timeout_secs: u64,
+    /// Swap status polling interval when the Boltz websocket is unavailable
+    pub swap_poll_interval_secs: u64,
     /// Default payment timeout in seconds
     pub payment_timeout_secs: u64,
     /// Maximum retry attempts for failed payments
     pub max_retry_attempts: u32,
+    /// Delay before the first retry of a transient failure, doubled for each further retry
+    pub retry_backoff_ms: u64,
     /// Enable fallback payment methods
     pub enable_fallbacks: bool,
+    /// Modes tried, in order, when the preferred mode fails
+    pub fallback_order: Vec<FundingMode>,
+    /// Confirmations of a payout claim before the payout counts as final
+    pub payout_confirmations: u32,
+    /// Fee rate for payout claim transactions when no fee source answers (sat/vB)
+    pub claim_fee_rate_sat_vb: u64,
+    /// Fee rate for multisig escrow settlements when no fee source answers (sat/vB)
+    pub multisig_fee_rate_sat_vb: u64,
+    /// Escrows of at least this amount use a Taproot output (`None` keeps all on P2WSH)
+    pub taproot_escrow_min_sats: Option<u64>,
+    /// Blocks after funding before the employer can reclaim a Taproot escrow alone
+    pub escrow_refund_delay_blocks: u16,
+    /// On-chain fee rate sources, tried in order
+    pub fee_sources: Vec<FeeSourceConfig>,
+    /// Confirmation target for on-chain fee estimates (blocks)
+    pub confirmation_target_blocks: u16,
+    /// Fees and limits quoted when live sources are unavailable
+    pub static_fees: StaticFeeSchedule,
+    /// Block source for confirmation tracking (`None` disables the chain watcher)
+    pub chain_source: Option<ChainSourceConfig>,
+    /// Chain watcher configuration
+    pub chain_watcher: ChainWatcherConfig,
+    /// Cashu mint whose ecash is accepted as funding (`None` disables Cashu)
+    pub cashu_mint_url: Option<String>,
+    /// Seconds after funding before the employer can reclaim Cashu tokens alone
+    pub cashu_locktime_secs: u64,
 }
 
 impl Default for PaymentCoordinatorConfig {
     fn default() -> Self {
         Self {
-            boltz_api_url: "https://api.boltz.exchange".to_string(),
+            network: Network::Bitcoin,
+            boltz_api_url: None,
+            boltz_timeout_secs: 30,
+            swap_poll_interval_secs: 30,
             payment_timeout_secs: 300, // 5 minutes
             max_retry_attempts: 3,
+            retry_backoff_ms: 500,
             enable_fallbacks: true,
+            fallback_order: vec![
+                FundingMode::LightningHold,
+                FundingMode::OnchainSubmarine,
+                FundingMode::OnchainReverse,
+                FundingMode::OnchainMultisig,
+            ],
+            payout_confirmations: 6,
+            claim_fee_rate_sat_vb: 2,
+            multisig_fee_rate_sat_vb: 2,
+            taproot_escrow_min_sats: Some(1_000_000),
+            escrow_refund_delay_blocks: 4320, // ~30 days
+            fee_sources: Vec::new(),
+            confirmation_target_blocks: 6,
+            static_fees: StaticFeeSchedule::default(),
+            chain_source: None,
+            chain_watcher: ChainWatcherConfig::default(),
+            cashu_mint_url: None,
+            cashu_locktime_secs: 2_592_000, // ~30 days
         }
     }
 }
@@ -40,6 +144,187 @@ impl Default for PaymentCoordinatorConfig {
 /// Main payment coordinator
 pub struct PaymentCoordinator {
     config: PaymentCoordinatorConfig,
+    /// Boltz client (`None` when Boltz is unavailable on the network)
+    boltz: Option<BoltzClient>,
+    /// Key material for created swaps (in production, this would be encrypted storage)
+    swap_secrets: RwLock<HashMap<String, SwapSecrets>>,
+    /// Lockup details of created submarine swaps, needed to refund them
+    submarine_swaps: RwLock<HashMap<String, SubmarineSwapDetails>>,
+    /// Lockup details of created reverse swaps, needed to claim them
+    reverse_swaps: RwLock<HashMap<String, ReverseSwapDetails>>,
+    /// On-chain payouts (swap_id -> payout)
+    payouts: RwLock<HashMap<String, OnchainPayout>>,
+    /// Multisig escrows (funding_id -> escrow)
+    multisig_escrows: RwLock<HashMap<uuid::Uuid, MultisigFunding>>,
+    /// Arbitrator keys of multisig escrows (in production, this would be encrypted storage)
+    arbitrator_keys: RwLock<HashMap<uuid::Uuid, SecretKey>>,
+    /// Swaps being tracked (swap_id -> state)
+    tracked_swaps: RwLock<HashMap<String, TrackedSwap>>,
+    /// On-chain fee rate sources, tried in order
+    fee_estimators: RwLock<Vec<Arc<dyn FeeEstimator>>>,
+    /// Lightning routing fee source
+    routing_prober: RwLock<Option<Arc<dyn RoutingFeeProber>>>,
+    /// Watcher for escrow fundings and payout claims (`None` without a chain source)
+    chain_watcher: Option<Arc<ChainWatcher>>,
+    /// Escrow engine issuing hold invoices (`None` returns placeholder invoices)
+    escrow_engine: Option<Arc<EscrowEngine>>,
+    /// Cashu mint client (`None` when no mint is configured)
+    cashu_mint: Option<CashuMintClient>,
+    /// Cashu escrows (funding_id -> escrow)
+    cashu_escrows: RwLock<HashMap<uuid::Uuid, CashuFunding>>,
+    /// Escrow keys of Cashu fundings (in production, this would be encrypted storage)
+    cashu_keys: RwLock<HashMap<uuid::Uuid, SecretKey>>,
+}
+
+/// Secrets needed to refund or claim a swap
+#[derive(Debug, Clone)]
+pub struct SwapSecrets {
+    /// Refund key (submarine) or claim key (reverse)
+    pub secret_key: SecretKey,
+    /// Preimage of the reverse swap invoice
+    pub preimage: Option<[u8; 32]>,
+}
+
+/// Lockup details of a submarine swap
+#[derive(Debug, Clone)]
+pub struct SubmarineSwapDetails {
+    pub swap_id: String,
+    pub lockup_address: String,
+    pub expected_amount_sats: u64,
+    pub claim_public_key: Option<PublicKey>,
+    pub swap_tree: Option<SwapTree>,
+    pub timeout_block_height: u32,
+    /// Where refunds go unless the refund request names another address
+    pub refund_address: Option<String>,
+}
+
+/// Lockup details of a reverse swap
+#[derive(Debug, Clone)]
+pub struct ReverseSwapDetails {
+    pub swap_id: String,
+    pub lockup_address: String,
+    /// Boltz invoice whose payment makes Boltz lock the funds
+    pub invoice: String,
+    pub invoice_amount_sats: u64,
+    pub onchain_amount_sats: u64,
+    pub refund_public_key: Option<PublicKey>,
+    pub swap_tree: Option<SwapTree>,
+    pub timeout_block_height: u32,
+}
+
+/// Progress of an on-chain payout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayoutState {
+    /// Reverse swap created, its invoice not yet paid
+    Created,
+    /// Invoice paid from the escrow, awaiting Boltz's lockup
+    InvoicePaid,
+    /// Claim transaction broadcast to the worker's address
+    Claimed,
+    /// Claim transaction has enough confirmations
+    Confirmed,
+    /// Swap failed before the claim; the invoice payment is returned
+    Failed,
+}
+
+/// On-chain payout of a task reward through a reverse swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnchainPayout {
+    pub swap_id: String,
+    pub task_id: uuid::Uuid,
+    /// Worker's payout address
+    pub address: String,
+    /// Boltz invoice paid from the escrow
+    pub invoice: String,
+    pub invoice_amount_sats: u64,
+    /// Amount Boltz locks on-chain (before the claim fee)
+    pub onchain_amount_sats: u64,
+    pub state: PayoutState,
+    pub claim_txid: Option<String>,
+    pub claim_tx_hex: Option<String>,
+    /// Amount received by the worker
+    pub claimed_sats: Option<u64>,
+    pub confirmations: u32,
+    pub failure_reason: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 2-of-3 multisig escrow of a task's funding
+#[derive(Debug, Clone)]
+pub struct MultisigFunding {
+    pub funding_id: uuid::Uuid,
+    pub task_id: uuid::Uuid,
+    pub escrow: EscrowOutput,
+    pub amount_sats: u64,
+    /// Escrow output, once the funding transaction is seen
+    pub funding_output: Option<(OutPoint, TxOut)>,
+    /// Confirmations of the funding transaction
+    pub confirmations: u32,
+    /// Settlement awaiting signatures
+    pub pending_settlement: Option<(MultisigOutcome, Psbt)>,
+    pub settlement_txid: Option<String>,
+}
+
+/// Output locking a multisig escrow
+#[derive(Debug, Clone)]
+pub enum EscrowOutput {
+    /// 2-of-3 P2WSH multisig
+    P2wsh(Box<MultisigEscrow>),
+    /// Taproot output with a MuSig2 key path for cooperative closes
+    Taproot(Box<TaprootEscrow>),
+}
+
+impl EscrowOutput {
+    /// Participant keys
+    pub fn keys(&self) -> &MultisigKeys {
+        match self {
+            EscrowOutput::P2wsh(escrow) => escrow.keys(),
+            EscrowOutput::Taproot(escrow) => escrow.keys(),
+        }
+    }
+
+    /// Escrow address the employer funds
+    pub fn address(&self) -> Address {
+        match self {
+            EscrowOutput::P2wsh(escrow) => escrow.address().clone(),
+            EscrowOutput::Taproot(escrow) => escrow.address(),
+        }
+    }
+
+    /// Find the output of `tx` paying this escrow
+    pub fn find_funding(&self, tx: &Transaction) -> Option<(OutPoint, TxOut)> {
+        match self {
+            EscrowOutput::P2wsh(escrow) => escrow.find_funding(tx),
+            EscrowOutput::Taproot(escrow) => escrow.find_funding(tx),
+        }
+    }
+}
+
+/// A party's MuSig2 contribution to a cooperative Taproot escrow close
+#[derive(Debug, Clone, Copy)]
+pub struct KeyPathContribution {
+    pub signer: PublicKey,
+    pub public_nonce: PublicNonce,
+    pub partial_signature: PartialSignature,
+}
+
+/// Employer and worker keys of a multisig escrow
+#[derive(Debug, Clone, Copy)]
+pub struct EscrowParties {
+    pub employer: PublicKey,
+    pub worker: PublicKey,
+}
+
+/// Finalised settlement of a multisig escrow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigSettlement {
+    pub funding_id: uuid::Uuid,
+    pub outcome: MultisigOutcome,
+    pub txid: String,
+    pub tx_hex: String,
+    pub fee_sats: u64,
+    /// Whether the transaction was broadcast; if not, it must be broadcast manually
+    pub broadcast: bool,
 }
 
 /// Payment request for funding a task
@@ -50,6 +335,10 @@ pub struct PaymentRequest {
     pub preferred_mode: FundingMode,
     pub payer_pubkey: String,
     pub description: String,
+    /// Payer's on-chain address for refunds of failed submarine swaps
+    pub refund_address: Option<String>,
+    /// Participant keys for multisig escrow
+    pub escrow_parties: Option<EscrowParties>,
 }
 
 /// Payment response containing funding details
@@ -59,9 +348,241 @@ pub struct PaymentResponse {
     pub mode: FundingMode,
     pub invoice: Option<String>,
     pub onchain_address: Option<String>,
+    pub invoice_hash: Option<String>,
+    /// Hold invoice escrowing a Lightning payment
+    pub hold_invoice_id: Option<String>,
     pub swap_id: Option<String>,
+    /// Swap script tree (JSON) committed to by the on-chain address
+    pub lockup_script: Option<String>,
+    /// Block height after which the lockup can be refunded unilaterally
+    pub timeout_block: Option<u32>,
     pub expires_at: Option<DateTime<Utc>>,
     pub estimated_fees_sats: u64,
+    /// Attempts made before this payment was created, the last one being its own
+    pub attempts: Vec<PaymentAttempt>,
+}
+
+/// One attempt at creating a payment through a mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentAttempt {
+    pub mode: FundingMode,
+    /// Attempt number within the mode, starting at 1
+    pub attempt: u32,
+    /// Why the attempt failed (`None` if it succeeded)
+    pub error: Option<String>,
+}
+
+/// Confirmation change of a watched escrow funding or payout claim
+#[derive(Debug, Clone)]
+pub struct ChainStatusChange {
+    /// Payout swap whose claim transaction changed (`None` for escrow fundings)
+    pub payout_swap_id: Option<String>,
+    pub update: PaymentStatusUpdate,
+    /// Whether the transaction left the best chain
+    pub reorged: bool,
+}
+
+/// Where a fee quote's figures come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeeSource {
+    /// Live fee sources (fee estimators, Boltz, route probing)
+    Live,
+    /// The static fee schedule
+    Static,
+}
+
+/// Fees, limits and expected timing of funding through a payment mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeQuote {
+    pub mode: FundingMode,
+    pub amount_sats: u64,
+    pub fee_sats: u64,
+    /// On-chain fee rate the quote assumes (sat/vB)
+    pub fee_rate_sat_vb: Option<u64>,
+    pub min_amount_sats: u64,
+    pub max_amount_sats: Option<u64>,
+    /// Whether the rail is available for `amount_sats`
+    pub supported: bool,
+    /// Expected time until the funds are usable
+    pub eta_secs: u64,
+    pub source: FeeSource,
+}
+
+/// Request to refund a failed submarine swap
+#[derive(Debug, Clone)]
+pub struct SwapRefundRequest {
+    pub swap_id: String,
+    /// Refund destination (defaults to the address given at creation)
+    pub refund_address: Option<String>,
+    pub fee_rate_sat_vb: u64,
+    /// Raw lockup transaction, if Boltz has not reported it
+    pub lockup_tx_hex: Option<String>,
+}
+
+/// Signed refund of a submarine swap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapRefund {
+    pub swap_id: String,
+    pub path: RefundPath,
+    pub txid: String,
+    pub tx_hex: String,
+    pub amount_sats: u64,
+    pub fee_sats: u64,
+    /// Whether the transaction was broadcast; if not, it must be broadcast
+    /// manually (from `spendable_at_height` for timeout refunds)
+    pub broadcast: bool,
+    pub spendable_at_height: Option<u32>,
+}
+
+/// Lifecycle state of a Boltz swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapState {
+    /// Swap created, awaiting the lockup transaction
+    Created,
+    /// Lockup transaction seen in the mempool
+    Mempool,
+    /// Lockup transaction confirmed
+    Confirmed,
+    /// Lightning side completed (invoice paid or settled)
+    InvoicePaid,
+    /// Swap failed (lockup failed, invoice could not be paid, ...)
+    Failed,
+    /// Swap expired before completing
+    Expired,
+    /// Locked funds returned on-chain
+    Refunded,
+}
+
+impl SwapState {
+    /// Map a Boltz status string to a swap state
+    ///
+    /// Intermediate statuses that do not change the escrow's view of the
+    /// swap (e.g. `invoice.set`, `invoice.pending`) map to `None`.
+    pub fn from_boltz(status: &str) -> Option<Self> {
+        match status {
+            "swap.created" => Some(Self::Created),
+            "transaction.mempool" => Some(Self::Mempool),
+            "transaction.confirmed" => Some(Self::Confirmed),
+            "invoice.paid" | "invoice.settled" | "transaction.claim.pending"
+            | "transaction.claimed" => Some(Self::InvoicePaid),
+            "invoice.failedToPay" | "transaction.lockupFailed" | "transaction.failed" => {
+                Some(Self::Failed)
+            }
+            "swap.expired" | "invoice.expired" => Some(Self::Expired),
+            "transaction.refunded" => Some(Self::Refunded),
+            _ => None,
+        }
+    }
+
+    /// Check if no further transitions are possible
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::InvoicePaid | Self::Refunded)
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Created => 0,
+            Self::Mempool => 1,
+            Self::Confirmed => 2,
+            Self::InvoicePaid | Self::Failed | Self::Expired => 3,
+            Self::Refunded => 4,
+        }
+    }
+
+    /// Check whether moving to `next` is a valid forward transition
+    ///
+    /// Updates can arrive twice or out of order (websocket and polling both
+    /// report), so anything that does not move the swap forward is rejected.
+    pub fn can_transition_to(&self, next: SwapState) -> bool {
+        match self {
+            Self::InvoicePaid | Self::Refunded => false,
+            Self::Failed | Self::Expired => next == Self::Refunded,
+            _ => next.rank() > self.rank(),
+        }
+    }
+}
+
+/// A swap tracked by the coordinator
+#[derive(Debug, Clone)]
+pub struct TrackedSwap {
+    pub swap_id: String,
+    pub funding_id: uuid::Uuid,
+    pub state: SwapState,
+    pub boltz_status: String,
+    pub transaction_id: Option<String>,
+    /// Raw lockup transaction, once reported
+    pub transaction_hex: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TrackedSwap {
+    /// Whether the swap can still change state
+    ///
+    /// Failed or expired swaps stay watched only while funds are locked
+    /// on-chain, since they may still be refunded.
+    pub fn needs_monitoring(&self) -> bool {
+        match self.state {
+            SwapState::Failed | SwapState::Expired => self.transaction_id.is_some(),
+            state => !state.is_terminal(),
+        }
+    }
+}
+
+/// A swap state transition
+#[derive(Debug, Clone)]
+pub struct SwapStatusChange {
+    pub swap_id: String,
+    pub funding_id: uuid::Uuid,
+    pub previous_state: SwapState,
+    pub state: SwapState,
+    pub boltz_status: String,
+    pub failure_reason: Option<String>,
+    pub transaction_id: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Spending conditions the ecash of a Cashu funding must be locked to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CashuLock {
+    pub mint_url: String,
+    /// Escrow key the proofs are locked to
+    pub pubkey: String,
+    /// Employer's key, able to reclaim the proofs from `locktime`
+    pub refund_pubkey: String,
+    /// Unix time from which the employer can reclaim the proofs alone
+    pub locktime: u64,
+}
+
+/// Cashu escrow of a task's funding
+#[derive(Debug, Clone)]
+pub struct CashuFunding {
+    pub funding_id: uuid::Uuid,
+    pub task_id: uuid::Uuid,
+    pub lock: CashuLock,
+    pub amount_sats: u64,
+    /// Proofs received from the employer (empty until funded)
+    pub proofs: Vec<Proof>,
+    pub settlement: Option<CashuSettlement>,
+}
+
+impl CashuFunding {
+    /// Amount of the received proofs
+    pub fn funded_sats(&self) -> u64 {
+        self.proofs.iter().map(|proof| proof.amount).sum()
+    }
+}
+
+/// Cashu escrow swapped for proofs locked to its recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CashuSettlement {
+    /// Key the new proofs are locked to
+    pub recipient_pubkey: String,
+    /// Token of the new proofs, spendable by the recipient's key only
+    pub token: String,
+    pub amount_sats: u64,
+    /// Mint input fee taken from the escrow
+    pub fee_sats: u64,
+    pub settled_at: DateTime<Utc>,
 }
 
 /// Payment status update
@@ -89,35 +610,212 @@ pub enum PaymentStatus {
     Cancelled,
 }
 
-/// Boltz submarine swap request
-#[derive(Debug, Clone, Serialize)]
-struct BoltzSubmarineSwapRequest {
-    amount: u64,
-    from: String, // "BTC" for on-chain to Lightning
-    to: String,   // "BTC" for Lightning to on-chain
-    invoice: Option<String>,
-    refund_address: Option<String>,
-}
-
-/// Boltz swap response
-#[derive(Debug, Clone, Deserialize)]
-struct BoltzSwapResponse {
-    id: String,
-    invoice: Option<String>,
-    address: Option<String>,
-    expected_amount: u64,
-    timeout_block_height: u32,
-    redeem_script: Option<String>,
-}
-
 impl PaymentCoordinator {
     /// Create a new payment coordinator
     pub fn new(config: PaymentCoordinatorConfig) -> Self {
-        Self { config }
+        let boltz_url = config
+            .boltz_api_url
+            .clone()
+            .or_else(|| network::default_boltz_api_url(config.network).map(str::to_string));
+        let boltz = boltz_url.and_then(|url| {
+            BoltzClient::new(url, Duration::from_secs(config.boltz_timeout_secs))
+                .inspect_err(|e| warn!("Boltz client unavailable: {}", e))
+                .ok()
+        });
+
+        let fee_estimators = config
+            .fee_sources
+            .iter()
+            .filter_map(|source| {
+                source
+                    .build(Duration::from_secs(config.boltz_timeout_secs))
+                    .inspect_err(|e| warn!("Fee source unavailable: {}", e))
+                    .ok()
+            })
+            .collect();
+        let chain_watcher = config.chain_source.as_ref().and_then(|source| {
+            source
+                .build(Duration::from_secs(config.boltz_timeout_secs))
+                .inspect_err(|e| warn!("Chain source unavailable: {}", e))
+                .ok()
+                .map(|source| Arc::new(ChainWatcher::new(config.chain_watcher.clone(), source)))
+        });
+
+        let cashu_mint = config.cashu_mint_url.as_ref().and_then(|url| {
+            CashuMintClient::new(url.clone(), Duration::from_secs(config.boltz_timeout_secs))
+                .inspect_err(|e| warn!("Cashu mint unavailable: {}", e))
+                .ok()
+        });
+
+        Self {
+            config,
+            boltz,
+            swap_secrets: RwLock::new(HashMap::new()),
+            submarine_swaps: RwLock::new(HashMap::new()),
+            reverse_swaps: RwLock::new(HashMap::new()),
+            payouts: RwLock::new(HashMap::new()),
+            multisig_escrows: RwLock::new(HashMap::new()),
+            arbitrator_keys: RwLock::new(HashMap::new()),
+            tracked_swaps: RwLock::new(HashMap::new()),
+            fee_estimators: RwLock::new(fee_estimators),
+            routing_prober: RwLock::new(None),
+            chain_watcher,
+            escrow_engine: None,
+            cashu_mint,
+            cashu_escrows: RwLock::new(HashMap::new()),
+            cashu_keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Issue Lightning payments as hold invoices of `engine`
+    pub fn with_escrow_engine(mut self, engine: Arc<EscrowEngine>) -> Self {
+        self.escrow_engine = Some(engine);
+        self
+    }
+
+    /// Follow the chain through `source` instead of the configured chain source
+    pub fn with_chain_source(mut self, source: Arc<dyn ChainSource>) -> Self {
+        self.chain_watcher = Some(Arc::new(ChainWatcher::new(self.config.chain_watcher.clone(), source)));
+        self
+    }
+
+    /// Whether escrow fundings and payout claims are followed on-chain
+    pub fn has_chain_watcher(&self) -> bool {
+        self.chain_watcher.is_some()
     }
 
     /// Create a payment for task funding
+    ///
+    /// Tries the preferred mode first, retrying transient failures up to
+    /// `max_retry_attempts` times. If it still fails and fallbacks are
+    /// enabled, the supported modes of `fallback_order` are tried in turn.
     pub async fn create_payment(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
+        let fallbacks = self.config.fallback_order.clone();
+        self.create_payment_with_fallbacks(request, &fallbacks).await
+    }
+
+    /// Create a payment, falling back only to `fallbacks` (in order)
+    ///
+    /// A request the preferred mode cannot take at all (say, a multisig
+    /// escrow without participant keys) fails without fallback. Fallback
+    /// modes that cannot take the request or amount are skipped. When
+    /// several modes were tried and all failed, the error lists every
+    /// attempt.
+    pub async fn create_payment_with_fallbacks(
+        &self,
+        request: PaymentRequest,
+        fallbacks: &[FundingMode],
+    ) -> EscrowResult<PaymentResponse> {
+        self.check_request(&request, request.preferred_mode)?;
+
+        let mut modes = vec![request.preferred_mode];
+        if self.config.enable_fallbacks {
+            let supported = self.get_supported_modes(request.amount_sats);
+            for &mode in fallbacks {
+                if !modes.contains(&mode)
+                    && supported.contains(&mode)
+                    && self.check_request(&request, mode).is_ok()
+                {
+                    modes.push(mode);
+                }
+            }
+        }
+
+        let mut attempts = Vec::new();
+        let mut last_error = None;
+        for &mode in &modes {
+            let mut attempt = 1;
+            loop {
+                let result = self
+                    .create_payment_via(PaymentRequest {
+                        preferred_mode: mode,
+                        ..request.clone()
+                    })
+                    .await;
+                match result {
+                    Ok(mut response) => {
+                        attempts.push(PaymentAttempt {
+                            mode,
+                            attempt,
+                            error: None,
+                        });
+                        if mode != request.preferred_mode {
+                            info!(
+                                "Task {} funded through fallback {:?} instead of {:?}",
+                                request.task_id, mode, request.preferred_mode
+                            );
+                        }
+                        response.attempts = attempts;
+                        return Ok(response);
+                    }
+                    Err(e) => {
+                        warn!("{:?} payment attempt {} for task {} failed: {}", mode, attempt, request.task_id, e);
+                        attempts.push(PaymentAttempt {
+                            mode,
+                            attempt,
+                            error: Some(e.to_string()),
+                        });
+                        let retry = e.is_transient() && attempt <= self.config.max_retry_attempts;
+                        last_error = Some(e);
+                        if !retry {
+                            break;
+                        }
+                    }
+                }
+
+                let backoff = self.config.retry_backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                attempt += 1;
+            }
+        }
+
+        match last_error {
+            Some(e) if modes.len() == 1 => Err(e),
+            _ => Err(EscrowError::payment(format!(
+                "All payment modes failed: {}",
+                attempts
+                    .iter()
+                    .map(|attempt| format!(
+                        "{:?} #{}: {}",
+                        attempt.mode,
+                        attempt.attempt,
+                        attempt.error.as_deref().unwrap_or_default()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))),
+        }
+    }
+
+    /// Check that `mode` can take `request` at all
+    fn check_request(&self, request: &PaymentRequest, mode: FundingMode) -> EscrowResult<()> {
+        match mode {
+            FundingMode::OnchainSubmarine => {
+                if let Some(refund_address) = &request.refund_address {
+                    network::parse_address(refund_address, self.config.network)?;
+                }
+            }
+            FundingMode::OnchainMultisig => {
+                let parties = request
+                    .escrow_parties
+                    .as_ref()
+                    .ok_or_else(|| EscrowError::payment("Multisig escrow needs employer and worker keys"))?;
+                if parties.employer == parties.worker {
+                    return Err(EscrowError::payment("Employer and worker keys must differ"));
+                }
+            }
+            FundingMode::Cashu => {
+                self.cashu_mint()?;
+                // The payer's key is the refund key of the locked proofs
+                cashu::parse_pubkey(&request.payer_pubkey)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Create a payment through `request.preferred_mode` only
+    async fn create_payment_via(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
         match request.preferred_mode {
             FundingMode::LightningHold => {
                 self.create_lightning_payment(request).await
@@ -134,15 +832,35 @@ impl PaymentCoordinator {
             FundingMode::OnchainMultisig => {
                 self.create_multisig_payment(request).await
             }
+            FundingMode::Cashu => {
+                self.create_cashu_payment(request).await
+            }
         }
     }
 
     /// Get payment status
     pub async fn get_payment_status(&self, funding_id: uuid::Uuid) -> EscrowResult<PaymentStatus> {
-        // In production, this would query the actual payment status from LDK/Boltz/blockchain
+        if let Some(escrow) = self.get_cashu_funding(funding_id).await {
+            return Ok(match (escrow.proofs.is_empty(), &escrow.settlement) {
+                (_, Some(_)) => PaymentStatus::Completed,
+                (false, None) => PaymentStatus::Confirmed,
+                (true, None) => PaymentStatus::Pending,
+            });
+        }
+        if let Some(escrow) = self.get_multisig_funding(funding_id).await {
+            return Ok(match (&escrow.funding_output, &escrow.settlement_txid) {
+                (_, Some(_)) => PaymentStatus::Completed,
+                (Some(_), None) => PaymentStatus::Confirmed,
+                (None, None) => PaymentStatus::Pending,
+            });
+        }
 
-        // For demo purposes, simulate status checking
-        Ok(PaymentStatus::Pending)
+        // Lightning payments are tracked by the escrow engine; swaps are tracked here
+        Ok(self
+            .find_tracked_swap(funding_id)
+            .await
+            .map(|swap| payment_status_for(swap.state))
+            .unwrap_or(PaymentStatus::Pending))
     }
 
     /// Cancel a payment
@@ -154,188 +872,2875 @@ impl PaymentCoordinator {
     }
 
     /// Create Lightning payment (hold invoice)
+    ///
+    /// Without an escrow engine a placeholder invoice is returned.
     async fn create_lightning_payment(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
-        // In production, this would integrate with LDK to create hold invoices
-        // For demo purposes, we'll simulate the response
-
-        let invoice = format!(
-            "lnbc{}u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w",
-            request.amount_sats
-        );
+        let mode = request.preferred_mode;
+        let (invoice, invoice_hash, hold_invoice_id, expires_at) = match &self.escrow_engine {
+            Some(engine) => {
+                let hold = engine
+                    .create_hold_invoice(request.amount_sats, request.description.clone(), request.task_id.to_string())
+                    .await?;
+                (hold.invoice, Some(hold.invoice_hash), Some(hold.hold_invoice_id), hold.expires_at)
+            }
+            None => (
+                self.placeholder_invoice(request.amount_sats),
+                None,
+                None,
+                Utc::now() + chrono::Duration::seconds(self.config.payment_timeout_secs as i64),
+            ),
+        };
 
         Ok(PaymentResponse {
             funding_id: uuid::Uuid::new_v4(),
-            mode: FundingMode::LightningHold,
+            mode,
             invoice: Some(invoice),
             onchain_address: None,
+            invoice_hash,
+            hold_invoice_id,
             swap_id: None,
-            expires_at: Some(Utc::now() + chrono::Duration::seconds(self.config.payment_timeout_secs as i64)),
-            estimated_fees_sats: (request.amount_sats * 1) / 1000, // 0.1% fee
+            lockup_script: None,
+            timeout_block: None,
+            expires_at: Some(expires_at),
+            estimated_fees_sats: self.calculate_fees(request.amount_sats, mode),
+            attempts: Vec::new(),
         })
     }
 
     /// Create submarine swap (on-chain to Lightning)
+    ///
+    /// Boltz pays a hold invoice issued by the escrow engine, so submarine
+    /// swaps need one configured.
     async fn create_submarine_swap(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
-        // In production, this would:
-        // 1. Create a submarine swap request to Boltz
-        // 2. Get the on-chain address and hold invoice
-        // 3. Return both for the payer to choose
-
-        let swap_request = BoltzSubmarineSwapRequest {
-            amount: request.amount_sats,
-            from: "BTC".to_string(),
-            to: "BTC".to_string(),
-            invoice: None, // Will be created by Boltz
-            refund_address: None,
-        };
+        let boltz = self.boltz_client()?;
+        let engine = self
+            .escrow_engine
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("Submarine swaps need an escrow engine to issue the swap invoice"))?;
+
+        // Check the amount against Boltz's current limits
+        let pair = boltz.get_submarine_pair().await?;
+        if request.amount_sats < pair.limits.minimal || request.amount_sats > pair.limits.maximal {
+            return Err(EscrowError::payment(format!(
+                "Amount {} sats is outside Boltz submarine limits ({}-{})",
+                request.amount_sats, pair.limits.minimal, pair.limits.maximal
+            )));
+        }
+
+        if let Some(refund_address) = &request.refund_address {
+            network::parse_address(refund_address, self.config.network)?;
+        }
+
+        // Boltz pays this invoice once the payer's on-chain lockup confirms
+        let hold = engine
+            .create_hold_invoice(request.amount_sats, request.description.clone(), request.task_id.to_string())
+            .await?;
+        let refund_key = random_secret_key()?;
+        let refund_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &refund_key);
+
+        let swap = boltz
+            .create_submarine_swap(&CreateSubmarineSwapRequest {
+                from: BTC.to_string(),
+                to: BTC.to_string(),
+                invoice: hold.invoice.clone(),
+                refund_public_key: refund_public_key.to_string(),
+                pair_hash: Some(pair.hash),
+                referral_id: None,
+            })
+            .await?;
+
+        // Never hand out a lockup address for another network, or one we
+        // could not refund from
+        let lockup_address = network::parse_address(&swap.address, self.config.network)?;
+        let claim_public_key = swap
+            .claim_public_key
+            .as_deref()
+            .ok_or_else(|| EscrowError::external_api("Boltz did not return a claim key"))
+            .and_then(|key| {
+                PublicKey::from_str(key)
+                    .map_err(|e| EscrowError::external_api(format!("Invalid Boltz claim key: {}", e)))
+            })?;
+        let tree = swap
+            .swap_tree
+            .as_ref()
+            .ok_or_else(|| EscrowError::external_api("Boltz did not return a swap tree"))?;
+        SwapScript::submarine(&claim_public_key, &refund_public_key, tree, swap.timeout_block_height)?
+            .verify_address(&lockup_address)?;
 
-        // Simulate API call to Boltz
-        let swap_response = self.call_boltz_api("/v2/swap/submarine", swap_request).await?;
+        self.swap_secrets.write().await.insert(
+            swap.id.clone(),
+            SwapSecrets {
+                secret_key: refund_key,
+                preimage: None,
+            },
+        );
+        self.submarine_swaps.write().await.insert(
+            swap.id.clone(),
+            SubmarineSwapDetails {
+                swap_id: swap.id.clone(),
+                lockup_address: swap.address.clone(),
+                expected_amount_sats: swap.expected_amount,
+                claim_public_key: Some(claim_public_key),
+                swap_tree: swap.swap_tree.clone(),
+                timeout_block_height: swap.timeout_block_height,
+                refund_address: request.refund_address.clone(),
+            },
+        );
+
+        info!("Created Boltz submarine swap {} for task {}", swap.id, request.task_id);
 
         Ok(PaymentResponse {
             funding_id: uuid::Uuid::new_v4(),
             mode: FundingMode::OnchainSubmarine,
-            invoice: swap_response.invoice,
-            onchain_address: swap_response.address,
-            swap_id: Some(swap_response.id),
+            invoice: Some(hold.invoice),
+            onchain_address: Some(swap.address),
+            invoice_hash: Some(hold.invoice_hash),
+            hold_invoice_id: Some(hold.hold_invoice_id),
+            swap_id: Some(swap.id),
+            lockup_script: swap.swap_tree.as_ref().map(lockup_script_json),
+            timeout_block: Some(swap.timeout_block_height),
             expires_at: Some(Utc::now() + chrono::Duration::seconds(self.config.payment_timeout_secs as i64)),
-            estimated_fees_sats: (request.amount_sats * 5) / 1000, // 0.5% fee for submarine swaps
+            estimated_fees_sats: swap.expected_amount.saturating_sub(request.amount_sats),
+            attempts: Vec::new(),
         })
     }
 
     /// Create reverse swap (Lightning to on-chain)
     async fn create_reverse_swap(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
-        // In production, this would:
-        // 1. Create a hold invoice
-        // 2. Create a reverse swap request to Boltz
-        // 3. Return the invoice and on-chain address
-
-        let invoice = format!(
-            "lnbc{}u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w",
-            request.amount_sats
-        );
+        let swap = self.open_reverse_swap(request.amount_sats, &request.description).await?;
 
-        let swap_request = BoltzSubmarineSwapRequest {
-            amount: request.amount_sats,
-            from: "BTC".to_string(),
-            to: "BTC".to_string(),
-            invoice: Some(invoice.clone()),
-            refund_address: None,
-        };
-
-        // Simulate API call to Boltz
-        let swap_response = self.call_boltz_api("/v2/swap/reverse", swap_request).await?;
+        info!("Created Boltz reverse swap {} for task {}", swap.id, request.task_id);
 
         Ok(PaymentResponse {
             funding_id: uuid::Uuid::new_v4(),
             mode: FundingMode::OnchainReverse,
-            invoice: Some(invoice),
-            onchain_address: swap_response.address,
-            swap_id: Some(swap_response.id),
+            invoice: Some(swap.invoice),
+            onchain_address: Some(swap.lockup_address),
+            invoice_hash: None,
+            hold_invoice_id: None,
+            swap_id: Some(swap.id),
+            lockup_script: swap.swap_tree.as_ref().map(lockup_script_json),
+            timeout_block: Some(swap.timeout_block_height),
             expires_at: Some(Utc::now() + chrono::Duration::seconds(self.config.payment_timeout_secs as i64)),
-            estimated_fees_sats: (request.amount_sats * 3) / 1000, // 0.3% fee for reverse swaps
+            estimated_fees_sats: request.amount_sats.saturating_sub(swap.onchain_amount),
+            attempts: Vec::new(),
         })
     }
 
+    /// Create a Boltz reverse swap for an invoice of `amount_sats`
+    async fn open_reverse_swap(&self, amount_sats: u64, description: &str) -> EscrowResult<ReverseSwapResponse> {
+        let boltz = self.boltz_client()?;
+
+        let pair = boltz.get_reverse_pair().await?;
+        if amount_sats < pair.limits.minimal || amount_sats > pair.limits.maximal {
+            return Err(EscrowError::payment(format!(
+                "Amount {} sats is outside Boltz reverse limits ({}-{})",
+                amount_sats, pair.limits.minimal, pair.limits.maximal
+            )));
+        }
+
+        // We hold the preimage; Boltz locks funds on-chain against its hash
+        let preimage = random_bytes()?;
+        let preimage_hash: [u8; 32] = Sha256::digest(preimage).into();
+        let claim_key = random_secret_key()?;
+        let claim_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &claim_key);
+
+        let swap = boltz
+            .create_reverse_swap(&CreateReverseSwapRequest {
+                from: BTC.to_string(),
+                to: BTC.to_string(),
+                preimage_hash: hex::encode(preimage_hash),
+                claim_public_key: claim_public_key.to_string(),
+                invoice_amount: Some(amount_sats),
+                onchain_amount: None,
+                pair_hash: Some(pair.hash),
+                address: None,
+                description: Some(description.to_string()),
+            })
+            .await?;
+
+        // Never pay for a lockup we could not claim
+        network::validate_invoice_network(&swap.invoice, self.config.network)?;
+        verify_swap_invoice(&swap.invoice, &preimage_hash, amount_sats)?;
+        let lockup_address = network::parse_address(&swap.lockup_address, self.config.network)?;
+        let refund_public_key = swap
+            .refund_public_key
+            .as_deref()
+            .ok_or_else(|| EscrowError::external_api("Boltz did not return a refund key"))
+            .and_then(|key| {
+                PublicKey::from_str(key)
+                    .map_err(|e| EscrowError::external_api(format!("Invalid Boltz refund key: {}", e)))
+            })?;
+        let tree = swap
+            .swap_tree
+            .as_ref()
+            .ok_or_else(|| EscrowError::external_api("Boltz did not return a swap tree"))?;
+        SwapScript::reverse(
+            &refund_public_key,
+            &claim_public_key,
+            &preimage_hash,
+            tree,
+            swap.timeout_block_height,
+        )?
+        .verify_address(&lockup_address)?;
+
+        self.swap_secrets.write().await.insert(
+            swap.id.clone(),
+            SwapSecrets {
+                secret_key: claim_key,
+                preimage: Some(preimage),
+            },
+        );
+        self.reverse_swaps.write().await.insert(
+            swap.id.clone(),
+            ReverseSwapDetails {
+                swap_id: swap.id.clone(),
+                lockup_address: swap.lockup_address.clone(),
+                invoice: swap.invoice.clone(),
+                invoice_amount_sats: amount_sats,
+                onchain_amount_sats: swap.onchain_amount,
+                refund_public_key: Some(refund_public_key),
+                swap_tree: swap.swap_tree.clone(),
+                timeout_block_height: swap.timeout_block_height,
+            },
+        );
+
+        Ok(swap)
+    }
+
     /// Create multisig payment (on-chain escrow)
+    ///
+    /// Locks the funds in a 2-of-3 P2WSH output of the employer, the worker
+    /// and a fresh arbitrator key held by the coordinator. Amounts from
+    /// `taproot_escrow_min_sats` up get a Taproot output instead: employer
+    /// and worker close it cooperatively through the MuSig2 key path.
     async fn create_multisig_payment(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
-        // In production, this would:
-        // 1. Generate multisig address requiring 2-of-3 signatures
-        // 2. Create funding transaction
-        // 3. Set up escrow contract
+        let parties = request
+            .escrow_parties
+            .ok_or_else(|| EscrowError::payment("Multisig escrow needs employer and worker keys"))?;
+        if parties.employer == parties.worker {
+            return Err(EscrowError::payment("Employer and worker keys must differ"));
+        }
 
-        let multisig_address = format!("bc1q multisig_address_for_{}", request.amount_sats);
+        let arbitrator_key = random_secret_key()?;
+        let keys = MultisigKeys {
+            employer: parties.employer,
+            worker: parties.worker,
+            arbitrator: PublicKey::from_secret_key(&Secp256k1::new(), &arbitrator_key),
+        };
+        let use_taproot = self
+            .config
+            .taproot_escrow_min_sats
+            .is_some_and(|min_sats| request.amount_sats >= min_sats);
+        let (escrow, lockup_script) = if use_taproot {
+            let escrow = TaprootEscrow::new(keys, self.config.escrow_refund_delay_blocks, self.config.network)?;
+            let leaf = |leaf| hex::encode(escrow.leaf_script(leaf).as_bytes());
+            let lockup_script = serde_json::json!({
+                "internalKey": escrow.internal_key().to_string(),
+                "arbitratorEmployerLeaf": leaf(EscrowLeaf::ArbitratorEmployer),
+                "arbitratorWorkerLeaf": leaf(EscrowLeaf::ArbitratorWorker),
+                "timeoutRefundLeaf": leaf(EscrowLeaf::TimeoutRefund),
+            })
+            .to_string();
+            (EscrowOutput::Taproot(Box::new(escrow)), lockup_script)
+        } else {
+            let escrow = MultisigEscrow::new(keys, self.config.network);
+            let witness_script = hex::encode(escrow.witness_script().as_bytes());
+            (EscrowOutput::P2wsh(Box::new(escrow)), witness_script)
+        };
+        let funding_id = uuid::Uuid::new_v4();
+        let address = escrow.address().to_string();
+        let escrow_script_pubkey = escrow.address().script_pubkey();
+
+        self.arbitrator_keys.write().await.insert(funding_id, arbitrator_key);
+        self.multisig_escrows.write().await.insert(
+            funding_id,
+            MultisigFunding {
+                funding_id,
+                task_id: request.task_id,
+                escrow,
+                amount_sats: request.amount_sats,
+                funding_output: None,
+                confirmations: 0,
+                pending_settlement: None,
+                settlement_txid: None,
+            },
+        );
+        self.watch_chain(
+            funding_watch_id(funding_id),
+            WatchTarget::Script(escrow_script_pubkey),
+        )
+        .await;
+
+        info!("Created multisig escrow {} for task {}", address, request.task_id);
 
         Ok(PaymentResponse {
-            funding_id: uuid::Uuid::new_v4(),
+            funding_id,
             mode: FundingMode::OnchainMultisig,
             invoice: None,
-            onchain_address: Some(multisig_address),
+            onchain_address: Some(address),
+            invoice_hash: None,
+            hold_invoice_id: None,
             swap_id: None,
+            lockup_script: Some(lockup_script),
+            timeout_block: None,
             expires_at: Some(Utc::now() + chrono::Duration::hours(24)), // Longer timeout for multisig
-            estimated_fees_sats: (request.amount_sats * 1) / 100, // 1% fee for multisig setup
+            estimated_fees_sats: self.calculate_fees(request.amount_sats, FundingMode::OnchainMultisig),
+            attempts: Vec::new(),
         })
     }
 
-    /// Simulate API call to Boltz exchange
-    async fn call_boltz_api<T: Serialize>(
-        &self,
-        endpoint: &str,
-        _request: T,
-    ) -> EscrowResult<BoltzSwapResponse> {
-        // In production, this would make actual HTTP calls to Boltz API
-
-        // Simulate response for demo
-        Ok(BoltzSwapResponse {
-            id: format!("boltz_swap_{}", uuid::Uuid::new_v4()),
-            invoice: Some(format!("lnbc123u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w")),
-            address: Some(format!("bc1qaddress_for_swap_{}", uuid::Uuid::new_v4())),
-            expected_amount: 100000,
-            timeout_block_height: 800000,
-            redeem_script: Some("redeem_script_placeholder".to_string()),
-        })
+    /// Get a multisig escrow by its funding id
+    pub async fn get_multisig_funding(&self, funding_id: uuid::Uuid) -> Option<MultisigFunding> {
+        self.multisig_escrows.read().await.get(&funding_id).cloned()
     }
 
-    /// Monitor payment status (in production, this would be webhook handlers)
-    pub async fn monitor_payment(&self, funding_id: uuid::Uuid) -> EscrowResult<PaymentStatusUpdate> {
-        // In production, this would:
-        // 1. Check LDK for Lightning payment status
-        // 2. Check blockchain for on-chain confirmations
-        // 3. Check Boltz for swap status
-
-        // For demo, simulate monitoring
-        Ok(PaymentStatusUpdate {
-            funding_id,
-            status: PaymentStatus::Pending,
-            confirmations: None,
-            transaction_id: None,
-            failure_reason: None,
-        })
-    }
+    /// Check whether `tx_hex` funds a multisig escrow
+    ///
+    /// Returns the escrow once a transaction paying at least the requested
+    /// amount to its address is seen, and `None` for unrelated transactions.
+    pub async fn watch_multisig_funding(
+        &self,
+        funding_id: uuid::Uuid,
+        tx_hex: &str,
+    ) -> EscrowResult<Option<MultisigFunding>> {
+        let tx = decode_transaction(tx_hex)?;
+        let mut escrows = self.multisig_escrows.write().await;
+        let escrow = escrows
+            .get_mut(&funding_id)
+            .ok_or_else(|| EscrowError::payment(format!("Unknown multisig escrow {}", funding_id)))?;
 
-    /// Get supported payment modes for an amount
-    pub fn get_supported_modes(&self, amount_sats: u64) -> Vec<FundingMode> {
-        let mut modes = vec![FundingMode::LightningHold, FundingMode::LightningStandard];
+        let Some((outpoint, output)) = escrow.escrow.find_funding(&tx) else {
+            return Ok(None);
+        };
+        if output.value.to_sat() < escrow.amount_sats {
+            return Err(EscrowError::payment(format!(
+                "Escrow funded with {} sats, expected {}",
+                output.value.to_sat(),
+                escrow.amount_sats
+            )));
+        }
 
-        if amount_sats >= 10000 { // Minimum for submarine swaps
-            modes.push(FundingMode::OnchainSubmarine);
+        if escrow.funding_output.is_none() {
+            info!("Multisig escrow {} funded in {}", funding_id, outpoint);
+            escrow.funding_output = Some((outpoint, output));
         }
+        Ok(Some(escrow.clone()))
+    }
 
-        if amount_sats >= 50000 { // Minimum for reverse swaps
-            modes.push(FundingMode::OnchainReverse);
+    /// Start settling a funded multisig escrow
+    ///
+    /// Returns the PSBT (base64) for the parties to sign. Arbitrated
+    /// outcomes come pre-signed with the arbitrator key, so one party's
+    /// signature completes them; releases and refunds need both parties,
+    /// which for Taproot escrows means a MuSig2 signature over the key path
+    /// (see `finalize_cooperative_settlement`). Timeout refunds need only
+    /// the employer, once the refund delay has passed.
+    pub async fn prepare_multisig_settlement(
+        &self,
+        funding_id: uuid::Uuid,
+        outcome: MultisigOutcome,
+    ) -> EscrowResult<String> {
+        let mut escrows = self.multisig_escrows.write().await;
+        let escrow = escrows
+            .get_mut(&funding_id)
+            .ok_or_else(|| EscrowError::payment(format!("Unknown multisig escrow {}", funding_id)))?;
+        if escrow.settlement_txid.is_some() {
+            return Err(EscrowError::payment(format!("Multisig escrow {} is already settled", funding_id)));
         }
+        let (outpoint, output) = escrow
+            .funding_output
+            .clone()
+            .ok_or_else(|| EscrowError::payment(format!("Multisig escrow {} is not funded", funding_id)))?;
 
-        if amount_sats >= 100000 { // Minimum for multisig
-            modes.push(FundingMode::OnchainMultisig);
+        let (fee_rate, _) = self.fee_rate_or(self.config.multisig_fee_rate_sat_vb).await;
+        let mut psbt = match &escrow.escrow {
+            EscrowOutput::P2wsh(multisig) => multisig.settlement_psbt(outpoint, &output, &outcome, fee_rate)?,
+            EscrowOutput::Taproot(taproot) => taproot.settlement_psbt(outpoint, &output, &outcome, fee_rate)?,
+        };
+        if matches!(outcome, MultisigOutcome::Arbitrated { .. }) {
+            let arbitrator_key = self
+                .arbitrator_keys
+                .read()
+                .await
+                .get(&funding_id)
+                .copied()
+                .ok_or_else(|| EscrowError::payment(format!("No arbitrator key for escrow {}", funding_id)))?;
+            match &escrow.escrow {
+                EscrowOutput::P2wsh(multisig) => multisig.sign_psbt(&mut psbt, &arbitrator_key)?,
+                EscrowOutput::Taproot(taproot) => {
+                    // Either party may complete the split
+                    for leaf in [EscrowLeaf::ArbitratorEmployer, EscrowLeaf::ArbitratorWorker] {
+                        taproot.sign_leaf(&mut psbt, leaf, &arbitrator_key)?;
+                    }
+                }
+            }
         }
 
-        modes
+        let encoded = psbt.to_string();
+        escrow.pending_settlement = Some((outcome, psbt));
+        Ok(encoded)
     }
 
-    /// Calculate estimated fees for a payment mode
-    pub fn calculate_fees(&self, amount_sats: u64, mode: FundingMode) -> u64 {
-        match mode {
-            FundingMode::LightningHold | FundingMode::LightningStandard => {
-                (amount_sats * 1) / 1000 // 0.1% for Lightning
-            }
-            FundingMode::OnchainSubmarine => {
-                (amount_sats * 5) / 1000 // 0.5% for submarine swaps
-            }
-            FundingMode::OnchainReverse => {
-                (amount_sats * 3) / 1000 // 0.3% for reverse swaps
-            }
-            FundingMode::OnchainMultisig => {
-                (amount_sats * 1) / 100 // 1% for multisig setup
+    /// Finalise a multisig settlement from the parties' signed PSBTs
+    ///
+    /// Every signature is checked against the escrow keys before the
+    /// transaction is broadcast.
+    pub async fn finalize_multisig_settlement(
+        &self,
+        funding_id: uuid::Uuid,
+        signed_psbts: &[String],
+    ) -> EscrowResult<MultisigSettlement> {
+        let escrow = self
+            .get_multisig_funding(funding_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown multisig escrow {}", funding_id)))?;
+        let (outcome, psbt) = escrow.pending_settlement.clone().ok_or_else(|| {
+            EscrowError::payment(format!("Multisig escrow {} has no pending settlement", funding_id))
+        })?;
+        let signed = signed_psbts
+            .iter()
+            .map(|encoded| {
+                Psbt::from_str(encoded.trim())
+                    .map_err(|e| EscrowError::payment(format!("Invalid PSBT: {}", e)))
+            })
+            .collect::<EscrowResult<Vec<_>>>()?;
+
+        let transaction = match &escrow.escrow {
+            EscrowOutput::P2wsh(multisig) => multisig.finalize(psbt, signed)?,
+            EscrowOutput::Taproot(_) if is_key_path(&outcome) => {
+                return Err(EscrowError::payment(format!(
+                    "Taproot escrow {} closes cooperatively with a MuSig2 signature",
+                    funding_id
+                )));
             }
+            EscrowOutput::Taproot(taproot) => taproot.finalize(psbt, signed)?,
+        };
+        self.complete_multisig_settlement(escrow, outcome, transaction).await
+    }
+
+    /// Finalise a cooperative release or refund of a Taproot escrow
+    ///
+    /// The employer and worker sign the key-path sighash of the pending
+    /// settlement with MuSig2; each partial signature is checked against its
+    /// signer, then they are aggregated and verified against the escrow
+    /// output key before broadcast.
+    pub async fn finalize_cooperative_settlement(
+        &self,
+        funding_id: uuid::Uuid,
+        contributions: &[KeyPathContribution],
+    ) -> EscrowResult<MultisigSettlement> {
+        let escrow = self
+            .get_multisig_funding(funding_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown multisig escrow {}", funding_id)))?;
+        let EscrowOutput::Taproot(taproot) = &escrow.escrow else {
+            return Err(EscrowError::payment(format!("Escrow {} has no key path", funding_id)));
+        };
+        let (outcome, psbt) = escrow.pending_settlement.clone().ok_or_else(|| {
+            EscrowError::payment(format!("Multisig escrow {} has no pending settlement", funding_id))
+        })?;
+        if !is_key_path(&outcome) {
+            return Err(EscrowError::payment(format!(
+                "{:?} settlements of escrow {} go through the script path",
+                outcome, funding_id
+            )));
+        }
+
+        let nonces: Vec<PublicNonce> = contributions.iter().map(|c| c.public_nonce).collect();
+        let partials: Vec<PartialSignature> = contributions.iter().map(|c| c.partial_signature).collect();
+        let aggregated_nonce = musig::aggregate_nonces(&nonces)?;
+        let session = SigningSession::new(taproot.key_agg(), &aggregated_nonce, taproot.key_spend_sighash(&psbt)?)?;
+        for contribution in contributions {
+            session
+                .partial_sig_verify(&contribution.partial_signature, &contribution.public_nonce, &contribution.signer)
+                .map_err(|e| EscrowError::payment(format!("Rejected signature of {}: {}", contribution.signer, e)))?;
         }
+        let signature = session.aggregate(&partials)?;
+        let transaction = taproot.finalize_key_path(psbt, &signature)?;
+        self.complete_multisig_settlement(escrow, outcome, transaction).await
     }
-}
 
-impl Default for PaymentCoordinator {
-    fn default() -> Self {
-        Self::new(PaymentCoordinatorConfig::default())
+    /// Broadcast a finalised escrow settlement and mark the escrow settled
+    async fn complete_multisig_settlement(
+        &self,
+        escrow: MultisigFunding,
+        outcome: MultisigOutcome,
+        transaction: Transaction,
+    ) -> EscrowResult<MultisigSettlement> {
+        let funding_id = escrow.funding_id;
+        let input_sats = escrow
+            .funding_output
+            .as_ref()
+            .map(|(_, output)| output.value.to_sat())
+            .unwrap_or_default();
+        let tx_hex = encode::serialize_hex(&transaction);
+        let broadcast = self.broadcast_transaction(&tx_hex).await;
+        let txid = transaction.txid().to_string();
+
+        if let Some(escrow) = self.multisig_escrows.write().await.get_mut(&funding_id) {
+            escrow.pending_settlement = None;
+            escrow.settlement_txid = Some(txid.clone());
+        }
+
+        let paid_sats: u64 = transaction.output.iter().map(|o| o.value.to_sat()).sum();
+        info!(
+            "Finalised {:?} settlement {} of multisig escrow {} (broadcast: {})",
+            outcome, txid, funding_id, broadcast
+        );
+
+        Ok(MultisigSettlement {
+            funding_id,
+            outcome,
+            txid,
+            tx_hex,
+            fee_sats: input_sats - paid_sats,
+            broadcast,
+        })
     }
-}
 
-use tracing::info;
-use uuid::Uuid;
+    /// Create a Cashu payment (ecash escrow)
+    ///
+    /// The employer sends the reward as ecash of the configured mint locked
+    /// to a fresh escrow key, with their own key (the payer key) as refund
+    /// key from `cashu_locktime_secs` on. The lock is returned as the
+    /// payment's lockup script; the token is taken by `accept_cashu_token`.
+    async fn create_cashu_payment(&self, request: PaymentRequest) -> EscrowResult<PaymentResponse> {
+        let mint = self.cashu_mint()?;
+        let refund_pubkey = cashu::parse_pubkey(&request.payer_pubkey)?;
+        let escrow_key = random_secret_key()?;
+        let locktime = Utc::now() + chrono::Duration::seconds(self.config.cashu_locktime_secs as i64);
+        let lock = CashuLock {
+            mint_url: mint.mint_url().to_string(),
+            pubkey: PublicKey::from_secret_key(&Secp256k1::new(), &escrow_key).to_string(),
+            refund_pubkey: refund_pubkey.to_string(),
+            locktime: locktime.timestamp() as u64,
+        };
+        let funding_id = uuid::Uuid::new_v4();
+
+        self.cashu_keys.write().await.insert(funding_id, escrow_key);
+        self.cashu_escrows.write().await.insert(
+            funding_id,
+            CashuFunding {
+                funding_id,
+                task_id: request.task_id,
+                lock: lock.clone(),
+                amount_sats: request.amount_sats,
+                proofs: Vec::new(),
+                settlement: None,
+            },
+        );
+
+        info!("Created Cashu escrow {} for task {}", lock.pubkey, request.task_id);
+
+        Ok(PaymentResponse {
+            funding_id,
+            mode: FundingMode::Cashu,
+            invoice: None,
+            onchain_address: None,
+            invoice_hash: None,
+            hold_invoice_id: None,
+            swap_id: None,
+            lockup_script: Some(serde_json::to_string(&lock)?),
+            timeout_block: None,
+            expires_at: Some(Utc::now() + chrono::Duration::seconds(self.config.payment_timeout_secs as i64)),
+            estimated_fees_sats: self.calculate_fees(request.amount_sats, FundingMode::Cashu),
+            attempts: Vec::new(),
+        })
+    }
+
+    /// Get a Cashu escrow by its funding id
+    pub async fn get_cashu_funding(&self, funding_id: uuid::Uuid) -> Option<CashuFunding> {
+        self.cashu_escrows.read().await.get(&funding_id).cloned()
+    }
+
+    /// Take the employer's token as the funding of a Cashu escrow
+    ///
+    /// Every proof must come from the configured mint, be locked to the
+    /// escrow key with the employer's refund key and a locktime no earlier
+    /// than agreed, carry a DLEQ proof of a sat keyset and be unspent. The
+    /// proofs are kept as they are: until the locktime only the escrow can
+    /// spend them, so settle well before it.
+    pub async fn accept_cashu_token(&self, funding_id: uuid::Uuid, token: &str) -> EscrowResult<CashuFunding> {
+        let mint = self.cashu_mint()?;
+        let token = Token::decode(token)?;
+        let escrow = self
+            .get_cashu_funding(funding_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown Cashu escrow {}", funding_id)))?;
+        if !escrow.proofs.is_empty() {
+            return Err(EscrowError::payment(format!("Cashu escrow {} is already funded", funding_id)));
+        }
+
+        if token.mint_url()? != mint.mint_url() {
+            return Err(EscrowError::payment(format!(
+                "Token is from mint {}, expected {}",
+                token.mint_url()?,
+                mint.mint_url()
+            )));
+        }
+        if token.unit.as_deref().is_some_and(|unit| unit != cashu::UNIT_SAT) {
+            return Err(EscrowError::payment("Only sat tokens are accepted"));
+        }
+
+        let escrow_pubkey = cashu::parse_pubkey(&escrow.lock.pubkey)?;
+        let refund_pubkey = cashu::parse_pubkey(&escrow.lock.refund_pubkey)?;
+        let keysets = mint.get_keysets().await?;
+        let mut keys = HashMap::new();
+        for proof in token.proofs() {
+            let locked = P2pkConditions::from_secret(&proof.secret)?.is_some_and(|conditions| {
+                conditions.pubkey == escrow_pubkey
+                    && conditions.refund_keys.contains(&refund_pubkey)
+                    && conditions.locktime.is_some_and(|locktime| locktime >= escrow.lock.locktime)
+            });
+            if !locked {
+                return Err(EscrowError::payment("Token is not locked to the escrow's conditions"));
+            }
+
+            if !keys.contains_key(&proof.id) {
+                if !keysets
+                    .iter()
+                    .any(|keyset| keyset.id == proof.id && keyset.unit == cashu::UNIT_SAT)
+                {
+                    return Err(EscrowError::payment(format!("Unknown keyset {}", proof.id)));
+                }
+                keys.insert(proof.id.clone(), mint.get_keyset(&proof.id).await?);
+            }
+            let mint_key = keys[&proof.id]
+                .keys
+                .get(&proof.amount)
+                .ok_or_else(|| EscrowError::payment(format!("Keyset {} has no {} sat key", proof.id, proof.amount)))?;
+            cashu::verify_proof_dleq(proof, mint_key)?;
+        }
+
+        let proofs: Vec<Proof> = token.proofs().cloned().collect();
+        if mint
+            .check_state(&proofs)
+            .await?
+            .iter()
+            .any(|state| *state != ProofState::Unspent)
+        {
+            return Err(EscrowError::payment("Token is already spent"));
+        }
+        if token.amount() < escrow.amount_sats {
+            return Err(EscrowError::payment(format!(
+                "Token carries {} sats, expected {}",
+                token.amount(),
+                escrow.amount_sats
+            )));
+        }
+
+        let mut escrows = self.cashu_escrows.write().await;
+        let escrow = escrows
+            .get_mut(&funding_id)
+            .ok_or_else(|| EscrowError::payment(format!("Unknown Cashu escrow {}", funding_id)))?;
+        if !escrow.proofs.is_empty() {
+            return Err(EscrowError::payment(format!("Cashu escrow {} is already funded", funding_id)));
+        }
+        escrow.proofs = proofs;
+
+        info!("Cashu escrow {} funded with {} sats", funding_id, escrow.funded_sats());
+        Ok(escrow.clone())
+    }
+
+    /// Pay a funded Cashu escrow out to `recipient` (a Cashu public key)
+    ///
+    /// The proofs are swapped at the mint for proofs locked to the
+    /// recipient's key, less the mint's input fee. Only the recipient can
+    /// spend the returned token, so it can be handed out in the clear.
+    pub async fn settle_cashu_escrow(&self, funding_id: uuid::Uuid, recipient: &str) -> EscrowResult<CashuSettlement> {
+        let mint = self.cashu_mint()?;
+        let recipient_pubkey = cashu::parse_pubkey(recipient)?;
+        let escrow = self
+            .get_cashu_funding(funding_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown Cashu escrow {}", funding_id)))?;
+        if escrow.settlement.is_some() {
+            return Err(EscrowError::payment(format!("Cashu escrow {} is already settled", funding_id)));
+        }
+        if escrow.proofs.is_empty() {
+            return Err(EscrowError::payment(format!("Cashu escrow {} is not funded", funding_id)));
+        }
+        if Utc::now().timestamp() as u64 >= escrow.lock.locktime {
+            warn!("Cashu escrow {} is past its locktime; the employer may have reclaimed it", funding_id);
+        }
+        let escrow_key = self
+            .cashu_keys
+            .read()
+            .await
+            .get(&funding_id)
+            .copied()
+            .ok_or_else(|| EscrowError::payment(format!("No escrow key for Cashu escrow {}", funding_id)))?;
+
+        let keysets = mint.get_keysets().await?;
+        let fee_ppk: u64 = escrow
+            .proofs
+            .iter()
+            .filter_map(|proof| keysets.iter().find(|keyset| keyset.id == proof.id))
+            .map(|keyset| keyset.input_fee_ppk)
+            .sum();
+        let fee_sats = fee_ppk.div_ceil(1000);
+        let amount_sats = escrow.funded_sats().saturating_sub(fee_sats);
+        if amount_sats == 0 {
+            return Err(EscrowError::payment("Cashu escrow does not cover the mint's input fee"));
+        }
+        let keyset_id = keysets
+            .iter()
+            .find(|keyset| keyset.active && keyset.unit == cashu::UNIT_SAT)
+            .map(|keyset| keyset.id.clone())
+            .ok_or_else(|| EscrowError::external_api("Cashu mint has no active sat keyset"))?;
+        let keyset = mint.get_keyset(&keyset_id).await?;
+
+        let conditions = P2pkConditions {
+            pubkey: recipient_pubkey,
+            locktime: None,
+            refund_keys: Vec::new(),
+        };
+        let outputs = cashu::split_amount(amount_sats)
+            .into_iter()
+            .map(|amount| PreparedOutput::new(amount, &keyset.id, conditions.to_secret()?))
+            .collect::<EscrowResult<Vec<_>>>()?;
+        let messages: Vec<_> = outputs.iter().map(|output| output.message.clone()).collect();
+        let inputs: Vec<Proof> = escrow
+            .proofs
+            .iter()
+            .cloned()
+            .map(|mut proof| {
+                cashu::sign_p2pk(&mut proof, &escrow_key);
+                proof
+            })
+            .collect();
+
+        let signatures = mint.swap(&inputs, &messages).await?;
+        let proofs = outputs
+            .into_iter()
+            .zip(&signatures)
+            .map(|(output, signature)| {
+                let mint_key = keyset.keys.get(&signature.amount).ok_or_else(|| {
+                    EscrowError::external_api(format!("Keyset {} has no {} sat key", keyset.id, signature.amount))
+                })?;
+                output.unblind(signature, mint_key)
+            })
+            .collect::<EscrowResult<Vec<_>>>()?;
+
+        let settlement = CashuSettlement {
+            recipient_pubkey: recipient_pubkey.to_string(),
+            token: Token::new(mint.mint_url(), proofs, None).encode()?,
+            amount_sats,
+            fee_sats,
+            settled_at: Utc::now(),
+        };
+        if let Some(escrow) = self.cashu_escrows.write().await.get_mut(&funding_id) {
+            escrow.settlement = Some(settlement.clone());
+        }
+
+        info!("Settled Cashu escrow {}: {} sats to {}", funding_id, amount_sats, recipient_pubkey);
+        Ok(settlement)
+    }
+
+    /// Return a funded Cashu escrow to the employer's refund key
+    pub async fn refund_cashu_escrow(&self, funding_id: uuid::Uuid) -> EscrowResult<CashuSettlement> {
+        let escrow = self
+            .get_cashu_funding(funding_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown Cashu escrow {}", funding_id)))?;
+        self.settle_cashu_escrow(funding_id, &escrow.lock.refund_pubkey).await
+    }
+
+    /// Placeholder BOLT11 invoice on the configured network
+    fn placeholder_invoice(&self, amount_sats: u64) -> String {
+        // In production, this would be a hold invoice created through LDK
+        format!(
+            "{}{}u1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdpl2pkx2ctnv5sxxmmwwd5kgetjypeh2ursdae8g6twvus8g6rfwvs8qun0dfjkxaq8rkx3yf5tcsyz3d73gafnh3cax9rn449d9p5uxz9ezhhypd0elx87sjle52x86fux2ypatgddc6k63n7erqz25le42c4u4ecky03ylcqca784w",
+            network::bolt11_prefix(self.config.network),
+            amount_sats
+        )
+    }
+
+    /// Get the Boltz client, if Boltz is available on the configured network
+    pub fn boltz_client(&self) -> EscrowResult<&BoltzClient> {
+        self.boltz.as_ref().ok_or_else(|| {
+            EscrowError::config(format!("Boltz swaps are not available on {}", self.config.network))
+        })
+    }
+
+    /// Get the Cashu mint client, if a mint is configured
+    pub fn cashu_mint(&self) -> EscrowResult<&CashuMintClient> {
+        self.cashu_mint
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("No Cashu mint configured"))
+    }
+
+    /// Get the secrets of a swap created by this coordinator
+    pub async fn get_swap_secrets(&self, swap_id: &str) -> Option<SwapSecrets> {
+        self.swap_secrets.read().await.get(swap_id).cloned()
+    }
+
+    /// Get the lockup details of a submarine swap created by this coordinator
+    pub async fn get_submarine_swap(&self, swap_id: &str) -> Option<SubmarineSwapDetails> {
+        self.submarine_swaps.read().await.get(swap_id).cloned()
+    }
+
+    /// Refund the on-chain lockup of a failed submarine swap
+    ///
+    /// Boltz is asked to co-sign a key-path refund first. If it will not (the
+    /// swap is not refundable yet, or Boltz is down), the refund is signed
+    /// through the timeout leaf instead; that transaction is broadcast once
+    /// the timeout block height is reached and otherwise returned for manual
+    /// broadcast.
+    pub async fn refund_submarine_swap(&self, request: SwapRefundRequest) -> EscrowResult<SwapRefund> {
+        let swap_id = request.swap_id.as_str();
+        let details = self
+            .get_submarine_swap(swap_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown submarine swap {}", swap_id)))?;
+        let secrets = self
+            .get_swap_secrets(swap_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("No refund key for swap {}", swap_id)))?;
+
+        let tracked = self.get_tracked_swap(swap_id).await;
+        if let Some(swap) = &tracked
+            && matches!(swap.state, SwapState::InvoicePaid | SwapState::Refunded)
+        {
+            return Err(EscrowError::payment(format!(
+                "Swap {} is {:?} and cannot be refunded",
+                swap_id, swap.state
+            )));
+        }
+
+        let destination = request
+            .refund_address
+            .as_ref()
+            .or(details.refund_address.as_ref())
+            .ok_or_else(|| EscrowError::payment(format!("No refund address for swap {}", swap_id)))?;
+        let destination = network::parse_address(destination, self.config.network)?;
+
+        let (Some(claim_public_key), Some(tree)) = (&details.claim_public_key, &details.swap_tree)
+        else {
+            return Err(EscrowError::payment(format!(
+                "Swap {} has no script tree to refund from",
+                swap_id
+            )));
+        };
+        let refund_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secrets.secret_key);
+        let script = SwapScript::submarine(
+            claim_public_key,
+            &refund_public_key,
+            tree,
+            details.timeout_block_height,
+        )?;
+
+        let lockup_hex = match request
+            .lockup_tx_hex
+            .clone()
+            .or_else(|| tracked.and_then(|swap| swap.transaction_hex))
+        {
+            Some(hex) => hex,
+            None => self
+                .boltz_client()?
+                .get_lockup_transaction(SwapKind::Submarine, swap_id)
+                .await?
+                .hex,
+        };
+        let lockup_tx = decode_transaction(&lockup_hex)?;
+
+        let unsigned = UnsignedSwapSpend::new(
+            &script,
+            &lockup_tx,
+            &destination,
+            request.fee_rate_sat_vb,
+            SpendPath::KeyPath,
+        )?;
+        let input_sats = unsigned.input_sats();
+
+        let (transaction, path) = match self
+            .sign_cooperative_refund(swap_id, &script, unsigned, &secrets.secret_key)
+            .await
+        {
+            Ok(transaction) => (transaction, RefundPath::Cooperative),
+            Err(e) => {
+                warn!("Cooperative refund of swap {} failed, using timeout path: {}", swap_id, e);
+                let transaction = UnsignedSwapSpend::new(
+                    &script,
+                    &lockup_tx,
+                    &destination,
+                    request.fee_rate_sat_vb,
+                    SpendPath::RefundLeaf,
+                )?
+                .sign_script_path(&script, &secrets.secret_key)?;
+                (transaction, RefundPath::Timeout)
+            }
+        };
+
+        let tx_hex = encode::serialize_hex(&transaction);
+        let broadcastable = match path {
+            RefundPath::Cooperative => true,
+            RefundPath::Timeout => self
+                .current_block_height()
+                .await
+                .is_some_and(|height| height >= details.timeout_block_height),
+        };
+        let broadcast = broadcastable && self.broadcast_transaction(&tx_hex).await;
+
+        let amount_sats = transaction.output[0].value.to_sat();
+        let refund = SwapRefund {
+            swap_id: swap_id.to_string(),
+            path,
+            txid: transaction.txid().to_string(),
+            tx_hex,
+            amount_sats,
+            fee_sats: input_sats - amount_sats,
+            broadcast,
+            spendable_at_height: (path == RefundPath::Timeout).then_some(details.timeout_block_height),
+        };
+
+        info!(
+            "Signed {:?} refund {} for swap {} (broadcast: {})",
+            refund.path, refund.txid, swap_id, refund.broadcast
+        );
+
+        Ok(refund)
+    }
+
+    /// Sign a key-path refund together with Boltz (MuSig2)
+    async fn sign_cooperative_refund(
+        &self,
+        swap_id: &str,
+        script: &SwapScript,
+        unsigned: UnsignedSwapSpend,
+        refund_key: &SecretKey,
+    ) -> EscrowResult<Transaction> {
+        let boltz = self.boltz_client()?;
+        let sighash = unsigned.key_spend_sighash()?;
+        let (secret_nonce, public_nonce) = musig::generate_nonce()?;
+
+        let response = boltz
+            .get_refund_signature(
+                swap_id,
+                &RefundSignatureRequest {
+                    pub_nonce: hex::encode(public_nonce.serialize()),
+                    transaction: encode::serialize_hex(unsigned.transaction()),
+                    index: 0,
+                },
+            )
+            .await?;
+
+        let decode = |value: &str| {
+            hex::decode(value)
+                .map_err(|e| EscrowError::external_api(format!("Invalid Boltz signature data: {}", e)))
+        };
+        let boltz_nonce = PublicNonce::from_slice(&decode(&response.pub_nonce)?)?;
+        let boltz_partial = PartialSignature::from_slice(&decode(&response.partial_signature)?)?;
+
+        let aggregated_nonce = musig::aggregate_nonces(&[boltz_nonce, public_nonce])?;
+        let session = SigningSession::new(script.key_agg(), &aggregated_nonce, sighash)?;
+        // Boltz's key comes first in the key set
+        session
+            .partial_sig_verify(&boltz_partial, &boltz_nonce, &script.key_agg().pubkeys()[0])
+            .map_err(|e| EscrowError::external_api(format!("Invalid Boltz partial signature: {}", e)))?;
+        let partial = session.partial_sign(secret_nonce, refund_key)?;
+        let signature = session.aggregate(&[boltz_partial, partial])?;
+
+        Ok(unsigned.with_key_spend_signature(&signature))
+    }
+
+    async fn current_block_height(&self) -> Option<u32> {
+        let boltz = self.boltz_client().ok()?;
+        boltz
+            .get_block_height()
+            .await
+            .inspect_err(|e| warn!("Failed to get block height: {}", e))
+            .ok()
+    }
+
+    async fn broadcast_transaction(&self, tx_hex: &str) -> bool {
+        let Ok(boltz) = self.boltz_client() else {
+            return false;
+        };
+        boltz
+            .broadcast_transaction(tx_hex)
+            .await
+            .inspect_err(|e| warn!("Failed to broadcast transaction: {}", e))
+            .is_ok()
+    }
+
+    /// Start an on-chain payout of `amount_sats` to `address`
+    ///
+    /// Creates a reverse swap whose invoice must be paid from the escrow;
+    /// once Boltz's lockup confirms, `advance_payout` claims it to `address`.
+    pub async fn create_onchain_payout(
+        &self,
+        task_id: uuid::Uuid,
+        funding_id: uuid::Uuid,
+        amount_sats: u64,
+        address: &str,
+    ) -> EscrowResult<OnchainPayout> {
+        network::parse_address(address, self.config.network)?;
+        let swap = self
+            .open_reverse_swap(amount_sats, &format!("Payout for task {}", task_id))
+            .await?;
+
+        let payout = OnchainPayout {
+            swap_id: swap.id.clone(),
+            task_id,
+            address: address.to_string(),
+            invoice: swap.invoice,
+            invoice_amount_sats: amount_sats,
+            onchain_amount_sats: swap.onchain_amount,
+            state: PayoutState::Created,
+            claim_txid: None,
+            claim_tx_hex: None,
+            claimed_sats: None,
+            confirmations: 0,
+            failure_reason: None,
+            updated_at: Utc::now(),
+        };
+        self.payouts.write().await.insert(swap.id.clone(), payout.clone());
+        self.track_swap(&swap.id, funding_id).await;
+
+        info!("Created payout swap {} for task {} to {}", swap.id, task_id, address);
+
+        Ok(payout)
+    }
+
+    /// Claim the lockup of a reverse swap that funded a task to `address`
+    ///
+    /// The payer paid the swap invoice, so the lockup is ours: it is claimed
+    /// like a payout (and followed through confirmations as one) to the
+    /// worker on settlement or to the employer on refund. This must happen
+    /// before the swap's timeout block, after which Boltz can take it back.
+    pub async fn claim_funding_swap(
+        &self,
+        task_id: uuid::Uuid,
+        swap_id: &str,
+        address: &str,
+    ) -> EscrowResult<OnchainPayout> {
+        network::parse_address(address, self.config.network)?;
+        let details = self
+            .reverse_swaps
+            .read()
+            .await
+            .get(swap_id)
+            .cloned()
+            .ok_or_else(|| EscrowError::payment(format!("Unknown reverse swap {}", swap_id)))?;
+
+        {
+            let mut payouts = self.payouts.write().await;
+            match payouts.get(swap_id) {
+                // A lockup is only ever claimed for the payout it was opened for
+                Some(payout) if payout.task_id != task_id || payout.address != address => {
+                    return Err(EscrowError::payment(format!(
+                        "Lockup of swap {} belongs to another payout",
+                        swap_id
+                    )));
+                }
+                Some(payout) if payout.state != PayoutState::InvoicePaid => {
+                    return Err(EscrowError::payment(format!(
+                        "Lockup of swap {} is already {:?}",
+                        swap_id, payout.state
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    payouts.insert(
+                        swap_id.to_string(),
+                        OnchainPayout {
+                            swap_id: swap_id.to_string(),
+                            task_id,
+                            address: address.to_string(),
+                            invoice: details.invoice,
+                            invoice_amount_sats: details.invoice_amount_sats,
+                            onchain_amount_sats: details.onchain_amount_sats,
+                            state: PayoutState::InvoicePaid,
+                            claim_txid: None,
+                            claim_tx_hex: None,
+                            claimed_sats: None,
+                            confirmations: 0,
+                            failure_reason: None,
+                            updated_at: Utc::now(),
+                        },
+                    );
+                }
+            }
+        }
+
+        self.claim_payout(swap_id).await
+    }
+
+    /// Get an on-chain payout by its swap id
+    pub async fn get_payout(&self, swap_id: &str) -> Option<OnchainPayout> {
+        self.payouts.read().await.get(swap_id).cloned()
+    }
+
+    /// Record that a payout's swap invoice was paid from the escrow
+    pub async fn mark_payout_invoice_paid(&self, swap_id: &str) -> EscrowResult<OnchainPayout> {
+        self.update_payout(swap_id, |payout| {
+            if payout.state == PayoutState::Created {
+                payout.state = PayoutState::InvoicePaid;
+            }
+        })
+        .await
+    }
+
+    /// Advance a payout on a status change of its reverse swap
+    ///
+    /// Claims the lockup once Boltz's lockup transaction confirms and marks
+    /// the payout failed if the swap fails first. Returns the payout if it
+    /// changed.
+    pub async fn advance_payout(&self, change: &SwapStatusChange) -> EscrowResult<Option<OnchainPayout>> {
+        let Some(payout) = self.get_payout(&change.swap_id).await else {
+            return Ok(None);
+        };
+
+        match change.state {
+            SwapState::Confirmed if payout.state == PayoutState::InvoicePaid => {
+                self.claim_payout(&change.swap_id).await.map(Some)
+            }
+            SwapState::Failed | SwapState::Expired | SwapState::Refunded
+                if matches!(payout.state, PayoutState::Created | PayoutState::InvoicePaid) =>
+            {
+                let reason = change
+                    .failure_reason
+                    .clone()
+                    .unwrap_or_else(|| change.boltz_status.clone());
+                self.update_payout(&change.swap_id, |payout| {
+                    payout.state = PayoutState::Failed;
+                    payout.failure_reason = Some(reason);
+                })
+                .await
+                .map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Claim a payout's lockup to the worker's address
+    ///
+    /// Spends the claim leaf with our preimage, so Boltz's cooperation is not
+    /// needed. The payout only counts as claimed once the transaction is
+    /// broadcast.
+    pub async fn claim_payout(&self, swap_id: &str) -> EscrowResult<OnchainPayout> {
+        let payout = self
+            .get_payout(swap_id)
+            .await
+            .ok_or_else(|| EscrowError::payment(format!("Unknown payout swap {}", swap_id)))?;
+        if payout.state != PayoutState::InvoicePaid {
+            return Err(EscrowError::payment(format!(
+                "Payout {} is {:?} and cannot be claimed",
+                swap_id, payout.state
+            )));
+        }
+
+        let details = self
+            .reverse_swaps
+            .read()
+            .await
+            .get(swap_id)
+            .cloned()
+            .ok_or_else(|| EscrowError::payment(format!("Unknown reverse swap {}", swap_id)))?;
+        let secrets = self.get_swap_secrets(swap_id).await;
+        let Some((claim_key, preimage)) = secrets.and_then(|s| Some((s.secret_key, s.preimage?))) else {
+            return Err(EscrowError::payment(format!("No claim secrets for swap {}", swap_id)));
+        };
+        let (Some(refund_public_key), Some(tree)) = (&details.refund_public_key, &details.swap_tree) else {
+            return Err(EscrowError::payment(format!(
+                "Swap {} has no script tree to claim from",
+                swap_id
+            )));
+        };
+
+        let claim_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &claim_key);
+        let preimage_hash: [u8; 32] = Sha256::digest(preimage).into();
+        let script = SwapScript::reverse(
+            refund_public_key,
+            &claim_public_key,
+            &preimage_hash,
+            tree,
+            details.timeout_block_height,
+        )?;
+
+        let boltz = self.boltz_client()?;
+        let lockup_hex = match self.get_tracked_swap(swap_id).await.and_then(|swap| swap.transaction_hex) {
+            Some(hex) => hex,
+            None => boltz.get_lockup_transaction(SwapKind::Reverse, swap_id).await?.hex,
+        };
+        let lockup_tx = decode_transaction(&lockup_hex)?;
+        let destination = network::parse_address(&payout.address, self.config.network)?;
+        let (fee_rate, _) = self.fee_rate_or(self.config.claim_fee_rate_sat_vb).await;
+
+        let transaction = UnsignedSwapSpend::new(
+            &script,
+            &lockup_tx,
+            &destination,
+            fee_rate,
+            SpendPath::ClaimLeaf(preimage),
+        )?
+        .sign_script_path(&script, &claim_key)?;
+        let tx_hex = encode::serialize_hex(&transaction);
+        boltz.broadcast_transaction(&tx_hex).await?;
+
+        self.watch_chain(payout_watch_id(swap_id), WatchTarget::Transaction(transaction.txid()))
+            .await;
+        let txid = transaction.txid().to_string();
+        let claimed_sats = transaction.output[0].value.to_sat();
+        info!("Claimed payout swap {} in {} ({} sats)", swap_id, txid, claimed_sats);
+
+        self.update_payout(swap_id, |payout| {
+            payout.state = PayoutState::Claimed;
+            payout.claim_txid = Some(txid);
+            payout.claim_tx_hex = Some(tx_hex);
+            payout.claimed_sats = Some(claimed_sats);
+        })
+        .await
+    }
+
+    /// Record the confirmations of a payout's claim transaction
+    ///
+    /// The payout becomes `Confirmed` once `payout_confirmations` is reached,
+    /// and drops back to `Claimed` if a reorg takes it below that again.
+    pub async fn update_payout_confirmations(
+        &self,
+        swap_id: &str,
+        confirmations: u32,
+    ) -> EscrowResult<OnchainPayout> {
+        let required = self.config.payout_confirmations;
+        let payout = self
+            .update_payout(swap_id, |payout| {
+                if payout.claim_txid.is_none() {
+                    return;
+                }
+                payout.confirmations = confirmations;
+                if payout.state == PayoutState::Claimed && confirmations >= required {
+                    payout.state = PayoutState::Confirmed;
+                } else if payout.state == PayoutState::Confirmed && confirmations < required {
+                    warn!(
+                        "Payout claim {} reorged to {} confirmation(s)",
+                        payout.claim_txid.as_deref().unwrap_or_default(),
+                        confirmations
+                    );
+                    payout.state = PayoutState::Claimed;
+                }
+            })
+            .await?;
+
+        if payout.claim_txid.is_none() {
+            return Err(EscrowError::payment(format!("Payout {} has not been claimed", swap_id)));
+        }
+        Ok(payout)
+    }
+
+    async fn update_payout(
+        &self,
+        swap_id: &str,
+        update: impl FnOnce(&mut OnchainPayout),
+    ) -> EscrowResult<OnchainPayout> {
+        let mut payouts = self.payouts.write().await;
+        let payout = payouts
+            .get_mut(swap_id)
+            .ok_or_else(|| EscrowError::payment(format!("Unknown payout swap {}", swap_id)))?;
+        update(payout);
+        payout.updated_at = Utc::now();
+        Ok(payout.clone())
+    }
+
+    /// Monitor payment status
+    pub async fn monitor_payment(&self, funding_id: uuid::Uuid) -> EscrowResult<PaymentStatusUpdate> {
+        if let Some(escrow) = self.get_multisig_funding(funding_id).await {
+            return Ok(PaymentStatusUpdate {
+                funding_id,
+                status: self.get_payment_status(funding_id).await?,
+                confirmations: escrow.funding_output.as_ref().map(|_| escrow.confirmations),
+                transaction_id: escrow.funding_output.map(|(outpoint, _)| outpoint.txid.to_string()),
+                failure_reason: None,
+            });
+        }
+
+        let Some(swap) = self.find_tracked_swap(funding_id).await else {
+            return Ok(PaymentStatusUpdate {
+                funding_id,
+                status: PaymentStatus::Pending,
+                confirmations: None,
+                transaction_id: None,
+                failure_reason: None,
+            });
+        };
+
+        let confirmations = match swap.state {
+            SwapState::Created => None,
+            SwapState::Mempool => Some(0),
+            _ => swap.transaction_id.as_ref().map(|_| 1),
+        };
+        let failure_reason = matches!(swap.state, SwapState::Failed | SwapState::Expired)
+            .then(|| swap.boltz_status.clone());
+
+        Ok(PaymentStatusUpdate {
+            funding_id,
+            status: payment_status_for(swap.state),
+            confirmations,
+            transaction_id: swap.transaction_id,
+            failure_reason,
+        })
+    }
+
+    /// Start tracking a swap's lifecycle
+    pub async fn track_swap(&self, swap_id: &str, funding_id: uuid::Uuid) {
+        self.tracked_swaps.write().await.insert(
+            swap_id.to_string(),
+            TrackedSwap {
+                swap_id: swap_id.to_string(),
+                funding_id,
+                state: SwapState::Created,
+                boltz_status: "swap.created".to_string(),
+                transaction_id: None,
+                transaction_hex: None,
+                updated_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Get a tracked swap
+    pub async fn get_tracked_swap(&self, swap_id: &str) -> Option<TrackedSwap> {
+        self.tracked_swaps.read().await.get(swap_id).cloned()
+    }
+
+    /// Apply a Boltz status update to a tracked swap
+    ///
+    /// Returns the transition if the swap moved forward; duplicate, stale and
+    /// unknown updates are ignored.
+    pub async fn apply_swap_update(&self, update: &SwapUpdate) -> Option<SwapStatusChange> {
+        let mut swaps = self.tracked_swaps.write().await;
+        let swap = swaps.get_mut(&update.id)?;
+
+        // The first transaction reported for a swap is its lockup
+        if swap.transaction_hex.is_none() {
+            swap.transaction_hex = update.transaction.as_ref().and_then(|tx| tx.hex.clone());
+        }
+
+        let state = SwapState::from_boltz(&update.status)?;
+        if !swap.state.can_transition_to(state) {
+            return None;
+        }
+
+        let change = SwapStatusChange {
+            swap_id: swap.swap_id.clone(),
+            funding_id: swap.funding_id,
+            previous_state: swap.state,
+            state,
+            boltz_status: update.status.clone(),
+            failure_reason: update.failure_reason.clone(),
+            transaction_id: update.transaction.as_ref().map(|tx| tx.id.clone()),
+            changed_at: Utc::now(),
+        };
+
+        swap.state = state;
+        swap.boltz_status = update.status.clone();
+        if change.transaction_id.is_some() {
+            swap.transaction_id = change.transaction_id.clone();
+        }
+        swap.updated_at = change.changed_at;
+
+        info!(
+            "Swap {} moved {:?} -> {:?} ({})",
+            change.swap_id, change.previous_state, change.state, change.boltz_status
+        );
+
+        Some(change)
+    }
+
+    /// Poll Boltz for the status of every non-terminal tracked swap
+    pub async fn poll_tracked_swaps(&self) -> EscrowResult<Vec<SwapStatusChange>> {
+        let boltz = self.boltz_client()?;
+
+        let mut changes = Vec::new();
+        for swap_id in self.active_swap_ids().await {
+            let status = match boltz.get_swap_status(&swap_id).await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Failed to poll swap {}: {}", swap_id, e);
+                    continue;
+                }
+            };
+
+            let update = SwapUpdate {
+                id: swap_id,
+                status: status.status,
+                failure_reason: status.failure_reason,
+                transaction: status.transaction,
+            };
+            changes.extend(self.apply_swap_update(&update).await);
+        }
+
+        Ok(changes)
+    }
+
+    /// Follow swap updates until `changes` is closed
+    ///
+    /// Consumes the Boltz websocket; while it is unavailable, swaps are polled
+    /// every `swap_poll_interval_secs` and the websocket is retried.
+    pub async fn run_swap_monitor(&self, changes: mpsc::UnboundedSender<SwapStatusChange>) {
+        if let Err(e) = self.boltz_client() {
+            warn!("Swap monitor not started: {}", e);
+            return;
+        }
+        let poll_interval = Duration::from_secs(self.config.swap_poll_interval_secs.max(1));
+
+        while !changes.is_closed() {
+            match self.stream_swap_updates(&changes, poll_interval).await {
+                Ok(()) => info!("Boltz websocket closed"),
+                Err(e) => warn!("Boltz websocket unavailable, polling swaps: {}", e),
+            }
+
+            match self.poll_tracked_swaps().await {
+                Ok(polled) => {
+                    for change in polled {
+                        if changes.send(change).is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => warn!("Swap polling failed: {}", e),
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Forward websocket updates until the socket closes or fails
+    async fn stream_swap_updates(
+        &self,
+        changes: &mpsc::UnboundedSender<SwapStatusChange>,
+        resubscribe_interval: Duration,
+    ) -> EscrowResult<()> {
+        let mut stream = self.boltz_client()?.connect_swap_updates().await?;
+        let mut subscribed = HashSet::new();
+        let mut ticker = tokio::time::interval(resubscribe_interval);
+
+        loop {
+            // Pick up swaps created since the last subscription
+            let new_ids: Vec<String> = self
+                .active_swap_ids()
+                .await
+                .into_iter()
+                .filter(|id| !subscribed.contains(id))
+                .collect();
+            if !new_ids.is_empty() {
+                stream.subscribe(&new_ids).await?;
+                subscribed.extend(new_ids);
+            }
+
+            tokio::select! {
+                _ = ticker.tick() => {}
+                updates = stream.next_updates() => {
+                    let Some(updates) = updates? else {
+                        return Ok(());
+                    };
+                    for update in updates {
+                        if let Some(change) = self.apply_swap_update(&update).await
+                            && changes.send(change).is_err()
+                        {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn active_swap_ids(&self) -> Vec<String> {
+        self.tracked_swaps
+            .read()
+            .await
+            .values()
+            .filter(|swap| swap.needs_monitoring())
+            .map(|swap| swap.swap_id.clone())
+            .collect()
+    }
+
+    async fn find_tracked_swap(&self, funding_id: uuid::Uuid) -> Option<TrackedSwap> {
+        self.tracked_swaps
+            .read()
+            .await
+            .values()
+            .find(|swap| swap.funding_id == funding_id)
+            .cloned()
+    }
+
+    /// Catch up with the chain and apply what changed
+    ///
+    /// Escrow fundings are confirmed or rolled back here. All changes are
+    /// returned for the task manager to record on their tasks (see
+    /// `TaskManager::record_escrow_confirmations` and
+    /// `TaskManager::record_payout_confirmations`).
+    pub async fn sync_chain(&self) -> EscrowResult<Vec<ChainStatusChange>> {
+        let watcher = self
+            .chain_watcher
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("No chain source configured"))?;
+
+        let mut changes = Vec::new();
+        for event in watcher.sync().await? {
+            if let Some(change) = self.apply_chain_event(&event).await {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Follow the chain until `changes` is closed
+    pub async fn run_chain_watcher(&self, changes: mpsc::UnboundedSender<ChainStatusChange>) {
+        let Some(watcher) = &self.chain_watcher else {
+            warn!("Chain watcher not started: no chain source configured");
+            return;
+        };
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+
+        let watch = watcher.run(events_tx);
+        let apply = async {
+            // Dropping the receiver stops the watcher
+            while let Some(event) = events_rx.recv().await {
+                if let Some(change) = self.apply_chain_event(&event).await
+                    && changes.send(change).is_err()
+                {
+                    break;
+                }
+            }
+        };
+        tokio::join!(watch, apply);
+    }
+
+    async fn watch_chain(&self, watch_id: String, target: WatchTarget) {
+        if let Some(watcher) = &self.chain_watcher {
+            watcher.watch(watch_id, target).await;
+        }
+    }
+
+    pub(crate) async fn apply_chain_event(&self, event: &ChainEvent) -> Option<ChainStatusChange> {
+        let (ChainEvent::Confirmed { watch_id, txid, .. } | ChainEvent::Reorged { watch_id, txid, .. }) = event;
+        let (confirmations, reorged) = match event {
+            ChainEvent::Confirmed { confirmations, .. } => (*confirmations, false),
+            ChainEvent::Reorged { .. } => (0, true),
+        };
+        let failure_reason = reorged.then(|| "blockchain_reorg".to_string());
+
+        if let Some(swap_id) = watch_id.strip_prefix(PAYOUT_WATCH_PREFIX) {
+            let payout = self.get_payout(swap_id).await?;
+            if payout.claim_txid != Some(txid.to_string()) {
+                return None;
+            }
+            if reorged {
+                warn!("Payout claim {} of swap {} left the best chain", txid, swap_id);
+            }
+            let funding_id = self.tracked_swaps.read().await.get(swap_id)?.funding_id;
+            return Some(ChainStatusChange {
+                payout_swap_id: Some(swap_id.to_string()),
+                update: PaymentStatusUpdate {
+                    funding_id,
+                    status: if confirmations > 0 {
+                        PaymentStatus::Confirmed
+                    } else {
+                        PaymentStatus::Pending
+                    },
+                    confirmations: Some(confirmations),
+                    transaction_id: Some(txid.to_string()),
+                    failure_reason,
+                },
+                reorged,
+            });
+        }
+
+        let funding_id = watch_id
+            .strip_prefix(FUNDING_WATCH_PREFIX)
+            .and_then(|id| uuid::Uuid::parse_str(id).ok())?;
+        if let ChainEvent::Confirmed { tx_hex, .. } = event {
+            match self.watch_multisig_funding(funding_id, tx_hex).await {
+                Ok(Some(_)) => {}
+                Ok(None) => return None,
+                Err(e) => {
+                    warn!("Ignoring transaction {} for escrow {}: {}", txid, funding_id, e);
+                    return None;
+                }
+            }
+        }
+
+        {
+            let mut escrows = self.multisig_escrows.write().await;
+            let escrow = escrows.get_mut(&funding_id)?;
+            // Only the transaction that funded the escrow counts
+            if escrow.funding_output.as_ref().map(|(outpoint, _)| outpoint.txid) != Some(*txid) {
+                return None;
+            }
+            escrow.confirmations = confirmations;
+            if reorged {
+                warn!("Funding {} of escrow {} left the best chain", txid, funding_id);
+                if escrow.settlement_txid.is_some() {
+                    // The settlement spends the funding and confirms once it is re-included
+                    warn!("Escrow {} was already settled", funding_id);
+                } else {
+                    escrow.funding_output = None;
+                    escrow.pending_settlement = None;
+                }
+            }
+        }
+
+        let mut update = self.monitor_payment(funding_id).await.ok()?;
+        update.confirmations = Some(confirmations);
+        update.transaction_id = Some(txid.to_string());
+        update.failure_reason = failure_reason;
+        Some(ChainStatusChange {
+            payout_swap_id: None,
+            update,
+            reorged,
+        })
+    }
+
+    /// Register an additional on-chain fee rate source
+    pub async fn add_fee_estimator(&self, estimator: Arc<dyn FeeEstimator>) {
+        info!("Registered fee estimator: {}", estimator.name());
+        self.fee_estimators.write().await.push(estimator);
+    }
+
+    /// Set the Lightning routing fee source
+    pub async fn set_routing_prober(&self, prober: Arc<dyn RoutingFeeProber>) {
+        *self.routing_prober.write().await = Some(prober);
+    }
+
+    /// Current fee rate (sat/vB) for the confirmation target
+    ///
+    /// Returns the first estimate from the registered sources, or `None`
+    /// when none of them answers.
+    pub async fn estimate_fee_rate(&self) -> Option<u64> {
+        let target = self.config.confirmation_target_blocks;
+        for estimator in self.fee_estimators.read().await.iter() {
+            match estimator.estimate_fee_rate(target).await {
+                Ok(rate) if rate.is_finite() && rate > 0.0 => return Some((rate.ceil() as u64).max(1)),
+                Ok(rate) => warn!("Fee estimator {} returned unusable rate {}", estimator.name(), rate),
+                Err(e) => warn!("Fee estimator {} failed: {}", estimator.name(), e),
+            }
+        }
+        None
+    }
+
+    /// Quote fees, limits and timing of every payment mode for an amount
+    pub async fn quote_fees(&self, amount_sats: u64) -> Vec<FeeQuote> {
+        let mut quotes = Vec::new();
+        for mode in [
+            FundingMode::LightningHold,
+            FundingMode::LightningStandard,
+            FundingMode::OnchainSubmarine,
+            FundingMode::OnchainReverse,
+            FundingMode::OnchainMultisig,
+            FundingMode::Cashu,
+        ] {
+            quotes.push(self.quote_fee(amount_sats, mode).await);
+        }
+        quotes
+    }
+
+    /// Quote fees, limits and timing of a payment mode for an amount
+    ///
+    /// Uses live fee rates, Boltz pair fees and limits, and route probing
+    /// where available, and the static fee schedule for anything that is not.
+    pub async fn quote_fee(&self, amount_sats: u64, mode: FundingMode) -> FeeQuote {
+        let schedule = &self.config.static_fees;
+        let block_secs = self.config.confirmation_target_blocks as u64 * BLOCK_INTERVAL_SECS;
+        let mut quote = FeeQuote {
+            mode,
+            amount_sats,
+            fee_sats: self.calculate_fees(amount_sats, mode),
+            fee_rate_sat_vb: None,
+            min_amount_sats: 1,
+            max_amount_sats: None,
+            supported: self.get_supported_modes(amount_sats).contains(&mode),
+            eta_secs: block_secs,
+            source: FeeSource::Static,
+        };
+
+        match mode {
+            FundingMode::LightningHold | FundingMode::LightningStandard => {
+                quote.eta_secs = LIGHTNING_ETA_SECS;
+                let prober = self.routing_prober.read().await.clone();
+                if let Some(prober) = prober {
+                    match prober.probe_routing_fee(None, amount_sats).await {
+                        Ok(fee_sats) => {
+                            quote.fee_sats = fee_sats;
+                            quote.source = FeeSource::Live;
+                        }
+                        Err(e) => warn!("Routing fee probe failed: {}", e),
+                    }
+                }
+            }
+            FundingMode::OnchainSubmarine | FundingMode::OnchainReverse => {
+                let (kind, static_min) = if mode == FundingMode::OnchainSubmarine {
+                    (SwapKind::Submarine, schedule.submarine_min_sats)
+                } else {
+                    (SwapKind::Reverse, schedule.reverse_min_sats)
+                };
+                quote.min_amount_sats = static_min;
+                let (claim_rate, live_rate) = self.fee_rate_or(self.config.claim_fee_rate_sat_vb).await;
+                // Reverse swaps also pay for our claim transaction
+                let claim_sats = if kind == SwapKind::Reverse {
+                    quote.fee_rate_sat_vb = Some(claim_rate);
+                    CLAIM_VSIZE * claim_rate
+                } else {
+                    0
+                };
+
+                if let Some(boltz) = &self.boltz {
+                    match tokio::try_join!(boltz.get_fees(kind), boltz.get_limits(kind)) {
+                        Ok((fees, limits)) => {
+                            quote.fee_sats = fees.total_for(amount_sats) + claim_sats;
+                            quote.min_amount_sats = limits.minimal_sats;
+                            quote.max_amount_sats = Some(limits.maximal_sats);
+                            quote.supported = (limits.minimal_sats..=limits.maximal_sats).contains(&amount_sats);
+                            quote.source = if live_rate || kind == SwapKind::Submarine {
+                                FeeSource::Live
+                            } else {
+                                FeeSource::Static
+                            };
+                        }
+                        Err(e) => warn!("Boltz fee lookup failed: {}", e),
+                    }
+                }
+            }
+            FundingMode::OnchainMultisig => {
+                let (fee_rate, live_rate) = self.fee_rate_or(self.config.multisig_fee_rate_sat_vb).await;
+                quote.fee_sats = MULTISIG_SETTLEMENT_VSIZE * fee_rate;
+                quote.fee_rate_sat_vb = Some(fee_rate);
+                quote.min_amount_sats = schedule.multisig_min_sats;
+                if live_rate {
+                    quote.source = FeeSource::Live;
+                }
+            }
+            FundingMode::Cashu => {
+                // Settlement swaps one input per denomination of the amount
+                quote.eta_secs = LIGHTNING_ETA_SECS;
+                if let Some(mint) = &self.cashu_mint {
+                    match mint.get_keysets().await {
+                        Ok(keysets) => {
+                            let fee_ppk = keysets
+                                .iter()
+                                .find(|keyset| keyset.active && keyset.unit == cashu::UNIT_SAT)
+                                .map_or(0, |keyset| keyset.input_fee_ppk);
+                            let inputs = cashu::split_amount(amount_sats).len() as u64;
+                            quote.fee_sats = (inputs * fee_ppk).div_ceil(1000);
+                            quote.source = FeeSource::Live;
+                        }
+                        Err(e) => warn!("Cashu mint fee lookup failed: {}", e),
+                    }
+                }
+            }
+        }
+
+        quote
+    }
+
+    /// Get supported payment modes for an amount, from the static fee schedule
+    pub fn get_supported_modes(&self, amount_sats: u64) -> Vec<FundingMode> {
+        let schedule = &self.config.static_fees;
+        let mut modes = vec![FundingMode::LightningHold, FundingMode::LightningStandard];
+
+        let boltz_available = self.boltz.is_some();
+
+        if boltz_available && amount_sats >= schedule.submarine_min_sats {
+            modes.push(FundingMode::OnchainSubmarine);
+        }
+
+        if boltz_available && amount_sats >= schedule.reverse_min_sats {
+            modes.push(FundingMode::OnchainReverse);
+        }
+
+        if amount_sats >= schedule.multisig_min_sats {
+            modes.push(FundingMode::OnchainMultisig);
+        }
+
+        if self.cashu_mint.is_some() {
+            modes.push(FundingMode::Cashu);
+        }
+
+        modes
+    }
+
+    /// Calculate estimated fees for a payment mode from the static fee schedule
+    pub fn calculate_fees(&self, amount_sats: u64, mode: FundingMode) -> u64 {
+        let schedule = &self.config.static_fees;
+        match mode {
+            FundingMode::LightningHold | FundingMode::LightningStandard => {
+                fee_estimator::ppm_fee(amount_sats, schedule.lightning_fee_ppm)
+            }
+            FundingMode::OnchainSubmarine => fee_estimator::ppm_fee(amount_sats, schedule.submarine_fee_ppm),
+            FundingMode::OnchainReverse => fee_estimator::ppm_fee(amount_sats, schedule.reverse_fee_ppm),
+            FundingMode::OnchainMultisig => MULTISIG_SETTLEMENT_VSIZE * self.config.multisig_fee_rate_sat_vb,
+            // Mint input fees are only known from the mint's keysets
+            FundingMode::Cashu => 0,
+        }
+    }
+
+    /// Live fee rate, or `fallback` when no source answers; the flag tells which
+    async fn fee_rate_or(&self, fallback_sat_vb: u64) -> (u64, bool) {
+        match self.estimate_fee_rate().await {
+            Some(rate) => (rate, true),
+            None => (fallback_sat_vb, false),
+        }
+    }
+}
+
+/// Decode a hex-encoded transaction
+fn decode_transaction(tx_hex: &str) -> EscrowResult<Transaction> {
+    hex::decode(tx_hex.trim())
+        .ok()
+        .and_then(|bytes| encode::deserialize(&bytes).ok())
+        .ok_or_else(|| EscrowError::payment("Invalid lockup transaction"))
+}
+
+/// Check that a reverse swap invoice pays for our preimage and amount
+///
+/// Boltz only locks funds on-chain against the invoice's payment hash, so an
+/// invoice for another hash or amount must never be paid.
+fn verify_swap_invoice(invoice: &str, preimage_hash: &[u8; 32], amount_sats: u64) -> EscrowResult<()> {
+    let invoice = lightning_invoice::Bolt11Invoice::from_str(invoice.trim())
+        .map_err(|e| EscrowError::external_api(format!("Invalid Boltz invoice: {}", e)))?;
+
+    let payment_hash: &[u8] = invoice.payment_hash().as_ref();
+    if payment_hash != preimage_hash {
+        return Err(EscrowError::external_api("Boltz invoice does not pay for our preimage hash"));
+    }
+    if invoice.amount_milli_satoshis() != Some(amount_sats * 1000) {
+        return Err(EscrowError::external_api(format!(
+            "Boltz invoice is for {:?} msat instead of {} sats",
+            invoice.amount_milli_satoshis(),
+            amount_sats
+        )));
+    }
+    Ok(())
+}
+
+/// Serialized swap tree stored as the funding's lockup script
+fn lockup_script_json(tree: &SwapTree) -> String {
+    serde_json::to_string(tree).unwrap_or_default()
+}
+
+/// Chain watch of a multisig escrow's address
+pub(crate) fn funding_watch_id(funding_id: uuid::Uuid) -> String {
+    format!("{}{}", FUNDING_WATCH_PREFIX, funding_id)
+}
+
+/// Chain watch of a payout's claim transaction
+fn payout_watch_id(swap_id: &str) -> String {
+    format!("{}{}", PAYOUT_WATCH_PREFIX, swap_id)
+}
+
+/// Whether a Taproot escrow settles `outcome` through the MuSig2 key path
+fn is_key_path(outcome: &MultisigOutcome) -> bool {
+    matches!(outcome, MultisigOutcome::Release { .. } | MultisigOutcome::Refund { .. })
+}
+
+/// Payment status reported for a swap state
+fn payment_status_for(state: SwapState) -> PaymentStatus {
+    match state {
+        SwapState::Created | SwapState::Mempool => PaymentStatus::Pending,
+        SwapState::Confirmed => PaymentStatus::Confirmed,
+        SwapState::InvoicePaid => PaymentStatus::Completed,
+        SwapState::Failed | SwapState::Expired => PaymentStatus::Failed,
+        SwapState::Refunded => PaymentStatus::Cancelled,
+    }
+}
+
+/// 32 bytes from the OS random number generator
+fn random_bytes() -> EscrowResult<[u8; 32]> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| EscrowError::crypto(format!("Random number generation failed: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Fresh random secp256k1 secret key
+fn random_secret_key() -> EscrowResult<SecretKey> {
+    SecretKey::from_slice(&random_bytes()?)
+        .map_err(|e| EscrowError::crypto(format!("Invalid secret key: {}", e)))
+}
+
+impl Default for PaymentCoordinator {
+    fn default() -> Self {
+        Self::new(PaymentCoordinatorConfig::default())
+    }
+}
+
+use tracing::{info, warn};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain_source::InMemoryChain;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use serde_json::json;
+
+    /// Escrow engine issuing the hold invoices of a test coordinator
+    async fn escrow_engine(network: Network) -> std::sync::Arc<EscrowEngine> {
+        let config = crate::engine::EscrowEngineConfig {
+            network,
+            ..Default::default()
+        };
+        std::sync::Arc::new(EscrowEngine::new(config).await.unwrap())
+    }
+
+    #[test]
+    fn test_swap_state_transitions() {
+        assert_eq!(SwapState::from_boltz("transaction.mempool"), Some(SwapState::Mempool));
+        assert_eq!(SwapState::from_boltz("invoice.settled"), Some(SwapState::InvoicePaid));
+        assert_eq!(SwapState::from_boltz("invoice.set"), None);
+
+        assert!(SwapState::Created.can_transition_to(SwapState::Confirmed));
+        assert!(!SwapState::Confirmed.can_transition_to(SwapState::Mempool));
+        assert!(!SwapState::Mempool.can_transition_to(SwapState::Mempool));
+        assert!(SwapState::Confirmed.can_transition_to(SwapState::Failed));
+        assert!(SwapState::Expired.can_transition_to(SwapState::Refunded));
+        assert!(!SwapState::Failed.can_transition_to(SwapState::InvoicePaid));
+        assert!(!SwapState::InvoicePaid.can_transition_to(SwapState::Refunded));
+    }
+
+    #[tokio::test]
+    async fn test_swap_monitor_websocket() {
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let subscribe = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            assert!(subscribe.contains("swap.update") && subscribe.contains("sub123"));
+
+            // Duplicate and stale updates are delivered too
+            for status in [
+                "transaction.mempool",
+                "transaction.mempool",
+                "transaction.confirmed",
+                "transaction.mempool",
+                "invoice.paid",
+            ] {
+                let update = json!({
+                    "event": "update",
+                    "channel": "swap.update",
+                    "args": [{ "id": "sub123", "status": status, "transaction": { "id": "txid1" } }]
+                });
+                ws.send(Message::Text(update.to_string())).await.unwrap();
+            }
+            let _ = ws.next().await;
+        });
+
+        let coordinator = std::sync::Arc::new(PaymentCoordinator::new(PaymentCoordinatorConfig {
+            boltz_api_url: Some(url),
+            ..PaymentCoordinatorConfig::default()
+        }));
+        let funding_id = Uuid::new_v4();
+        coordinator.track_swap("sub123", funding_id).await;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let monitor = coordinator.clone();
+        let handle = tokio::spawn(async move { monitor.run_swap_monitor(tx).await });
+
+        let mut states = Vec::new();
+        for _ in 0..3 {
+            let change = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            states.push(change.state);
+        }
+        handle.abort();
+
+        assert_eq!(
+            states,
+            vec![SwapState::Mempool, SwapState::Confirmed, SwapState::InvoicePaid]
+        );
+        let status = coordinator.monitor_payment(funding_id).await.unwrap();
+        assert_eq!(status.status, PaymentStatus::Completed);
+        assert_eq!(status.transaction_id.as_deref(), Some("txid1"));
+    }
+
+    #[tokio::test]
+    async fn test_swap_monitor_polling_fallback() {
+        // Plain HTTP server: the websocket upgrade fails, so swaps are polled
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/v2/swap/sub123" => MockResponse::json(
+                200,
+                json!({ "status": "transaction.lockupFailed", "failureReason": "amount too low" }),
+            ),
+            _ => MockResponse::json(404, json!({ "error": "not found" })),
+        })
+        .await;
+        let coordinator = std::sync::Arc::new(PaymentCoordinator::new(PaymentCoordinatorConfig {
+            boltz_api_url: Some(server.url()),
+            swap_poll_interval_secs: 1,
+            ..PaymentCoordinatorConfig::default()
+        }));
+        coordinator.track_swap("sub123", Uuid::new_v4()).await;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let monitor = coordinator.clone();
+        let handle = tokio::spawn(async move { monitor.run_swap_monitor(tx).await });
+        let change = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        handle.abort();
+
+        assert_eq!(change.state, SwapState::Failed);
+        assert_eq!(change.failure_reason.as_deref(), Some("amount too low"));
+        // No lockup transaction, so nothing left to watch
+        assert!(!coordinator.get_tracked_swap("sub123").await.unwrap().needs_monitoring());
+    }
+
+    #[tokio::test]
+    async fn test_submarine_swap_through_boltz() {
+        use crate::swap_script::tests::submarine_swap_fixture;
+
+        let boltz_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &SecretKey::from_slice(&[0x07; 32]).unwrap());
+        let server = MockHttpServer::start(move |request| match request.method.as_str() {
+            "GET" => MockResponse::json(
+                200,
+                json!({ "BTC": { "BTC": {
+                    "hash": "sub_hash",
+                    "rate": 1,
+                    "limits": { "minimal": 50000, "maximal": 25000000 },
+                    "fees": { "percentage": 0.1, "minerFees": 300 }
+                }}}),
+            ),
+            _ => {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let refund_key = PublicKey::from_str(body["refundPublicKey"].as_str().unwrap()).unwrap();
+                let (tree, address) = submarine_swap_fixture(&boltz_public_key, &refund_key, 850_000);
+                let address = Address::from_script(&address.script_pubkey(), Network::Bitcoin).unwrap();
+                MockResponse::json(
+                    201,
+                    json!({
+                        "id": "sub123",
+                        "address": address.to_string(),
+                        "claimPublicKey": boltz_public_key.to_string(),
+                        "swapTree": tree,
+                        "timeoutBlockHeight": 850000,
+                        "expectedAmount": 100400
+                    }),
+                )
+            }
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            boltz_api_url: Some(server.url()),
+            ..PaymentCoordinatorConfig::default()
+        })
+        .with_escrow_engine(escrow_engine(Network::Bitcoin).await);
+
+        let request = PaymentRequest {
+            task_id: Uuid::new_v4(),
+            amount_sats: 100_000,
+            preferred_mode: FundingMode::OnchainSubmarine,
+            payer_pubkey: "payer".to_string(),
+            description: "Task funding".to_string(),
+            refund_address: None,
+            escrow_parties: None,
+        };
+        let response = coordinator.create_payment(request.clone()).await.unwrap();
+        assert_eq!(response.swap_id.as_deref(), Some("sub123"));
+        assert_eq!(response.estimated_fees_sats, 400);
+        assert!(coordinator.get_swap_secrets("sub123").await.is_some());
+
+        // Boltz is asked to pay the engine's hold invoice
+        let requests = server.requests().await;
+        let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert!(response.hold_invoice_id.is_some());
+        assert_eq!(body["invoice"].as_str(), response.invoice.as_deref());
+
+        // The refund key sent to Boltz matches the stored secret
+        let secrets = coordinator.get_swap_secrets("sub123").await.unwrap();
+        let expected = PublicKey::from_secret_key(&Secp256k1::new(), &secrets.secret_key);
+        assert_eq!(body["refundPublicKey"], expected.to_string());
+
+        // Below the pair minimum no swap is created; the payment falls back
+        // to Lightning without retrying
+        let small = PaymentRequest {
+            amount_sats: 10_000,
+            ..request.clone()
+        };
+        let response = coordinator.create_payment(small).await.unwrap();
+        assert_eq!(response.mode, FundingMode::LightningHold);
+        assert_eq!(response.attempts.len(), 2);
+        assert_eq!(response.attempts[0].mode, FundingMode::OnchainSubmarine);
+        assert!(response.attempts[0].error.as_deref().unwrap().contains("outside Boltz submarine limits"));
+        assert!(response.attempts[1].error.is_none());
+        assert_eq!(server.requests().await.len(), 3);
+
+        // A lockup Boltz does not prove with its keys and swap tree is refused
+        let unverifiable = MockHttpServer::start(|request| match request.method.as_str() {
+            "GET" => MockResponse::json(
+                200,
+                json!({ "BTC": { "BTC": {
+                    "hash": "sub_hash",
+                    "rate": 1,
+                    "limits": { "minimal": 50000, "maximal": 25000000 },
+                    "fees": { "percentage": 0.1, "minerFees": 300 }
+                }}}),
+            ),
+            _ => MockResponse::json(
+                201,
+                json!({
+                    "id": "sub456",
+                    "address": "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+                    "timeoutBlockHeight": 850000,
+                    "expectedAmount": 100400
+                }),
+            ),
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            boltz_api_url: Some(unverifiable.url()),
+            enable_fallbacks: false,
+            ..PaymentCoordinatorConfig::default()
+        })
+        .with_escrow_engine(escrow_engine(Network::Bitcoin).await);
+        assert!(matches!(
+            coordinator.create_payment(request.clone()).await,
+            Err(EscrowError::ExternalApi(_))
+        ));
+        assert!(coordinator.get_swap_secrets("sub456").await.is_none());
+
+        // Without an engine there is no invoice for Boltz to pay
+        let offline = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            boltz_api_url: Some(server.url()),
+            enable_fallbacks: false,
+            ..PaymentCoordinatorConfig::default()
+        });
+        assert!(matches!(
+            offline.create_payment(request).await,
+            Err(EscrowError::Config(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_reverse_swap_invoice_checked() {
+        use crate::{swap_script::tests::reverse_swap_fixture, test_utils::bolt11_invoice};
+        use std::sync::{Arc, Mutex};
+
+        let boltz_public_key = PublicKey::from_secret_key(&Secp256k1::new(), &SecretKey::from_slice(&[0x08; 32]).unwrap());
+        // Payment hash and amount Boltz puts in its invoice, if not ours
+        type InvoiceTerms = (Option<[u8; 32]>, Option<u64>);
+        let tampered: Arc<Mutex<InvoiceTerms>> = Arc::default();
+
+        let invoice_terms = tampered.clone();
+        let server = MockHttpServer::start(move |request| match request.method.as_str() {
+            "GET" => MockResponse::json(
+                200,
+                json!({ "BTC": { "BTC": {
+                    "hash": "rev_hash",
+                    "rate": 1,
+                    "limits": { "minimal": 10000, "maximal": 25000000 },
+                    "fees": { "percentage": 0.25, "minerFees": { "lockup": 250, "claim": 150 } }
+                }}}),
+            ),
+            _ => {
+                let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+                let claim_key = PublicKey::from_str(body["claimPublicKey"].as_str().unwrap()).unwrap();
+                let preimage_hash: [u8; 32] =
+                    hex::decode(body["preimageHash"].as_str().unwrap()).unwrap().try_into().unwrap();
+                let (tree, address) = reverse_swap_fixture(&claim_key, &boltz_public_key, &preimage_hash, 850_000);
+                let (payment_hash, amount_sats) = *invoice_terms.lock().unwrap();
+                MockResponse::json(
+                    201,
+                    json!({
+                        "id": "rev123",
+                        "invoice": bolt11_invoice(
+                            "lnbcrt",
+                            &payment_hash.unwrap_or(preimage_hash),
+                            amount_sats.unwrap_or(100_000)
+                        ),
+                        "swapTree": tree,
+                        "lockupAddress": address.to_string(),
+                        "refundPublicKey": boltz_public_key.to_string(),
+                        "timeoutBlockHeight": 850000,
+                        "onchainAmount": 99350
+                    }),
+                )
+            }
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            boltz_api_url: Some(server.url()),
+            enable_fallbacks: false,
+            retry_backoff_ms: 0,
+            ..PaymentCoordinatorConfig::default()
+        });
+        let request = PaymentRequest {
+            task_id: Uuid::new_v4(),
+            amount_sats: 100_000,
+            preferred_mode: FundingMode::OnchainReverse,
+            payer_pubkey: "payer".to_string(),
+            description: "Task funding".to_string(),
+            refund_address: None,
+            escrow_parties: None,
+        };
+
+        // An invoice for another payment hash or amount is never handed out
+        *tampered.lock().unwrap() = (Some([0x33; 32]), None);
+        assert!(matches!(
+            coordinator.create_payment(request.clone()).await,
+            Err(EscrowError::ExternalApi(msg)) if msg.contains("preimage hash")
+        ));
+        *tampered.lock().unwrap() = (None, Some(150_000));
+        assert!(matches!(
+            coordinator.create_payment(request.clone()).await,
+            Err(EscrowError::ExternalApi(msg)) if msg.contains("msat")
+        ));
+        assert!(coordinator.get_swap_secrets("rev123").await.is_none());
+
+        *tampered.lock().unwrap() = (None, None);
+        let response = coordinator.create_payment(request.clone()).await.unwrap();
+        assert_eq!(response.swap_id.as_deref(), Some("rev123"));
+
+        // The lockup is claimed for one payout only; it is never re-targeted
+        let address = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+        let _ = coordinator.claim_funding_swap(request.task_id, "rev123", address).await;
+        let payout = coordinator.get_payout("rev123").await.unwrap();
+        assert_eq!(payout.task_id, request.task_id);
+        for (task_id, address) in [
+            (Uuid::new_v4(), address),
+            (request.task_id, "bcrt1q6rz28mcfaxtmd6v789l9rrlrusdprr9pz3cppk"),
+        ] {
+            assert!(matches!(
+                coordinator.claim_funding_swap(task_id, "rev123", address).await,
+                Err(EscrowError::Payment(msg)) if msg.contains("another payout")
+            ));
+        }
+        assert_eq!(coordinator.get_payout("rev123").await.unwrap().address, address);
+    }
+
+    #[tokio::test]
+    async fn test_payment_fallback_after_retries() {
+        let server = MockHttpServer::start(|_| MockResponse::json(503, json!({ "error": "maintenance" }))).await;
+        let config = PaymentCoordinatorConfig {
+            boltz_api_url: Some(server.url()),
+            max_retry_attempts: 2,
+            retry_backoff_ms: 0,
+            fallback_order: vec![FundingMode::OnchainReverse, FundingMode::LightningHold],
+            ..PaymentCoordinatorConfig::default()
+        };
+        let request = PaymentRequest {
+            task_id: Uuid::new_v4(),
+            amount_sats: 100_000,
+            preferred_mode: FundingMode::OnchainSubmarine,
+            payer_pubkey: "payer".to_string(),
+            description: "Task funding".to_string(),
+            refund_address: None,
+            escrow_parties: None,
+        };
+
+        // Each Boltz mode is retried, then Lightning takes over
+        let coordinator = PaymentCoordinator::new(config.clone()).with_escrow_engine(escrow_engine(Network::Bitcoin).await);
+        let response = coordinator.create_payment(request.clone()).await.unwrap();
+        assert_eq!(response.mode, FundingMode::LightningHold);
+        let tried: Vec<(FundingMode, u32)> = response
+            .attempts
+            .iter()
+            .map(|attempt| (attempt.mode, attempt.attempt))
+            .collect();
+        assert_eq!(
+            tried,
+            vec![
+                (FundingMode::OnchainSubmarine, 1),
+                (FundingMode::OnchainSubmarine, 2),
+                (FundingMode::OnchainSubmarine, 3),
+                (FundingMode::OnchainReverse, 1),
+                (FundingMode::OnchainReverse, 2),
+                (FundingMode::OnchainReverse, 3),
+                (FundingMode::LightningHold, 1),
+            ]
+        );
+
+        // Only Boltz modes allowed: every attempt is reported
+        let err = coordinator
+            .create_payment_with_fallbacks(request.clone(), &[FundingMode::OnchainReverse])
+            .await
+            .unwrap_err();
+        assert!(matches!(&err, EscrowError::Payment(msg) if msg.contains("OnchainReverse #3")));
+
+        // Without fallbacks the preferred mode's own error comes back
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            enable_fallbacks: false,
+            ..config
+        })
+        .with_escrow_engine(escrow_engine(Network::Bitcoin).await);
+        assert!(matches!(
+            coordinator.create_payment(request).await,
+            Err(EscrowError::ExternalApi(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_submarine_swap_refund() {
+        use crate::{
+            boltz::SwapTransaction,
+            swap_script::tests::{lockup_transaction, submarine_swap_fixture},
+        };
+        use bitcoin::{
+            hashes::Hash,
+            sighash::{Prevouts, SighashCache, TapSighashType},
+        };
+        use std::sync::{
+            Arc, Mutex,
+            atomic::{AtomicBool, Ordering},
+        };
+
+        let secp = Secp256k1::new();
+        let boltz_key = SecretKey::from_slice(&[0x07; 32]).unwrap();
+        let boltz_public_key = PublicKey::from_secret_key(&secp, &boltz_key);
+        let refund_public_key: Arc<Mutex<Option<PublicKey>>> = Arc::default();
+        let lockup: Arc<Mutex<Option<Transaction>>> = Arc::default();
+        let cooperative = Arc::new(AtomicBool::new(true));
+
+        let (user_key, lockup_tx, boltz_up) = (refund_public_key.clone(), lockup.clone(), cooperative.clone());
+        let server = MockHttpServer::start(move |request| {
+            let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap_or_default();
+            match (request.method.as_str(), request.path.as_str()) {
+                ("GET", "/v2/swap/submarine") => MockResponse::json(
+                    200,
+                    json!({ "BTC": { "BTC": {
+                        "hash": "sub_hash",
+                        "rate": 1,
+                        "limits": { "minimal": 10000, "maximal": 25000000 },
+                        "fees": { "percentage": 0.1, "minerFees": 300 }
+                    }}}),
+                ),
+                ("POST", "/v2/swap/submarine") => {
+                    let refund_key = PublicKey::from_str(body["refundPublicKey"].as_str().unwrap()).unwrap();
+                    *user_key.lock().unwrap() = Some(refund_key);
+                    let (tree, address) = submarine_swap_fixture(&boltz_public_key, &refund_key, 850_000);
+                    MockResponse::json(
+                        201,
+                        json!({
+                            "id": "sub123",
+                            "address": address.to_string(),
+                            "claimPublicKey": boltz_public_key.to_string(),
+                            "swapTree": tree,
+                            "timeoutBlockHeight": 850000,
+                            "expectedAmount": 100400
+                        }),
+                    )
+                }
+                ("POST", "/v2/swap/submarine/sub123/refund") => {
+                    if !boltz_up.load(Ordering::SeqCst) {
+                        return MockResponse::json(500, json!({ "error": "service unavailable" }));
+                    }
+
+                    // Co-sign as Boltz
+                    let refund_key = user_key.lock().unwrap().unwrap();
+                    let (tree, _) = submarine_swap_fixture(&boltz_public_key, &refund_key, 850_000);
+                    let script = SwapScript::submarine(&boltz_public_key, &refund_key, &tree, 850_000).unwrap();
+                    let refund_tx: Transaction =
+                        encode::deserialize(&hex::decode(body["transaction"].as_str().unwrap()).unwrap())
+                            .unwrap();
+                    let prevout = lockup_tx.lock().unwrap().clone().unwrap().output[0].clone();
+                    let sighash = SighashCache::new(&refund_tx)
+                        .taproot_key_spend_signature_hash(0, &Prevouts::All(&[&prevout]), TapSighashType::Default)
+                        .unwrap();
+
+                    let user_nonce =
+                        PublicNonce::from_slice(&hex::decode(body["pubNonce"].as_str().unwrap()).unwrap()).unwrap();
+                    let (secret_nonce, public_nonce) = musig::generate_nonce().unwrap();
+                    let aggregated = musig::aggregate_nonces(&[public_nonce, user_nonce]).unwrap();
+                    let session =
+                        SigningSession::new(script.key_agg(), &aggregated, sighash.to_byte_array()).unwrap();
+                    let partial = session.partial_sign(secret_nonce, &boltz_key).unwrap();
+                    MockResponse::json(
+                        200,
+                        json!({
+                            "pubNonce": hex::encode(public_nonce.serialize()),
+                            "partialSignature": hex::encode(partial.serialize())
+                        }),
+                    )
+                }
+                ("GET", "/v2/chain/heights") => MockResponse::json(200, json!({ "BTC": 849000 })),
+                ("POST", "/v2/chain/BTC/transaction") => MockResponse::json(201, json!({ "id": "txid" })),
+                _ => MockResponse::json(404, json!({ "error": "not found" })),
+            }
+        })
+        .await;
+
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            boltz_api_url: Some(server.url()),
+            ..PaymentCoordinatorConfig::default()
+        })
+        .with_escrow_engine(escrow_engine(Network::Regtest).await);
+        let response = coordinator
+            .create_payment(PaymentRequest {
+                task_id: Uuid::new_v4(),
+                amount_sats: 100_000,
+                preferred_mode: FundingMode::OnchainSubmarine,
+                payer_pubkey: "payer".to_string(),
+                description: "Task funding".to_string(),
+                refund_address: Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string()),
+                escrow_parties: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.timeout_block, Some(850000));
+        assert!(response.lockup_script.unwrap().contains("refundLeaf"));
+
+        // The payer locks funds, then the swap fails
+        let address = network::parse_address(&response.onchain_address.unwrap(), Network::Regtest).unwrap();
+        let lockup_tx = lockup_transaction(&address, 100_400);
+        *lockup.lock().unwrap() = Some(lockup_tx.clone());
+        coordinator.track_swap("sub123", response.funding_id).await;
+        coordinator
+            .apply_swap_update(&SwapUpdate {
+                id: "sub123".to_string(),
+                status: "transaction.lockupFailed".to_string(),
+                failure_reason: None,
+                transaction: Some(SwapTransaction {
+                    id: lockup_tx.txid().to_string(),
+                    hex: Some(encode::serialize_hex(&lockup_tx)),
+                }),
+            })
+            .await
+            .unwrap();
+
+        let request = SwapRefundRequest {
+            swap_id: "sub123".to_string(),
+            refund_address: None,
+            fee_rate_sat_vb: 2,
+            lockup_tx_hex: None,
+        };
+
+        // Cooperative key-path refund, broadcast immediately
+        let refund = coordinator.refund_submarine_swap(request.clone()).await.unwrap();
+        assert_eq!(refund.path, RefundPath::Cooperative);
+        assert!(refund.broadcast);
+        assert_eq!(refund.spendable_at_height, None);
+        assert_eq!(refund.amount_sats + refund.fee_sats, 100_400);
+        let refund_tx: Transaction = encode::deserialize(&hex::decode(&refund.tx_hex).unwrap()).unwrap();
+        assert_eq!(refund_tx.input[0].witness.len(), 1);
+        let sighash = SighashCache::new(&refund_tx)
+            .taproot_key_spend_signature_hash(0, &Prevouts::All(&[&lockup_tx.output[0]]), TapSighashType::Default)
+            .unwrap();
+        let signature = secp256k1::schnorr::Signature::from_slice(&refund_tx.input[0].witness[0]).unwrap();
+        let output_key = secp256k1::XOnlyPublicKey::from_slice(&address.script_pubkey().as_bytes()[2..]).unwrap();
+        assert!(secp
+            .verify_schnorr(&signature, &secp256k1::Message::from_digest(sighash.to_byte_array()), &output_key)
+            .is_ok());
+
+        // Boltz down: timeout refund, held until the timeout height
+        cooperative.store(false, Ordering::SeqCst);
+        let refund = coordinator.refund_submarine_swap(request).await.unwrap();
+        assert_eq!(refund.path, RefundPath::Timeout);
+        assert!(!refund.broadcast);
+        assert_eq!(refund.spendable_at_height, Some(850000));
+        let refund_tx: Transaction = encode::deserialize(&hex::decode(&refund.tx_hex).unwrap()).unwrap();
+        assert_eq!(refund_tx.lock_time.to_consensus_u32(), 850000);
+        assert_eq!(refund_tx.input[0].witness.len(), 3);
+
+        let broadcasts = server
+            .requests()
+            .await
+            .iter()
+            .filter(|r| r.path == "/v2/chain/BTC/transaction")
+            .count();
+        assert_eq!(broadcasts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_multisig_escrow_settlement() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/v2/chain/BTC/transaction" => MockResponse::json(201, json!({ "id": "txid" })),
+            _ => MockResponse::json(404, json!({ "error": "not found" })),
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            boltz_api_url: Some(server.url()),
+            ..PaymentCoordinatorConfig::default()
+        });
+
+        let secp = Secp256k1::new();
+        let employer_key = SecretKey::from_slice(&[0x21; 32]).unwrap();
+        let worker_key = SecretKey::from_slice(&[0x22; 32]).unwrap();
+        let mut request = PaymentRequest {
+            task_id: Uuid::new_v4(),
+            amount_sats: 100_000,
+            preferred_mode: FundingMode::OnchainMultisig,
+            payer_pubkey: "payer".to_string(),
+            description: "Task funding".to_string(),
+            refund_address: None,
+            escrow_parties: None,
+        };
+        assert!(coordinator.create_payment(request.clone()).await.is_err());
+
+        request.escrow_parties = Some(EscrowParties {
+            employer: PublicKey::from_secret_key(&secp, &employer_key),
+            worker: PublicKey::from_secret_key(&secp, &worker_key),
+        });
+        let response = coordinator.create_payment(request).await.unwrap();
+        let funding_id = response.funding_id;
+        let address = network::parse_address(&response.onchain_address.unwrap(), Network::Regtest).unwrap();
+        assert!(response.lockup_script.unwrap().ends_with("53ae")); // OP_3 OP_CHECKMULTISIG
+
+        let outcome = MultisigOutcome::Arbitrated {
+            worker_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+            worker_sats: 70_000,
+            employer_address: address.to_string(),
+        };
+        assert!(coordinator
+            .prepare_multisig_settlement(funding_id, outcome.clone())
+            .await
+            .is_err());
+
+        // Unrelated and underfunding transactions are told apart from the funding
+        let funding_tx = crate::swap_script::tests::lockup_transaction(&address, 100_000);
+        let unrelated = crate::swap_script::tests::lockup_transaction(
+            &network::parse_address("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080", Network::Regtest).unwrap(),
+            100_000,
+        );
+        let short = crate::swap_script::tests::lockup_transaction(&address, 99_999);
+        assert!(coordinator
+            .watch_multisig_funding(funding_id, &encode::serialize_hex(&unrelated))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(coordinator
+            .watch_multisig_funding(funding_id, &encode::serialize_hex(&short))
+            .await
+            .is_err());
+        coordinator
+            .watch_multisig_funding(funding_id, &encode::serialize_hex(&funding_tx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Confirmed);
+
+        // The arbitrator pre-signs; the worker's signature completes the settlement
+        let psbt = coordinator
+            .prepare_multisig_settlement(funding_id, outcome)
+            .await
+            .unwrap();
+        let EscrowOutput::P2wsh(escrow) = coordinator.get_multisig_funding(funding_id).await.unwrap().escrow else {
+            panic!("small escrows use P2WSH");
+        };
+        let mut signed = Psbt::from_str(&psbt).unwrap();
+        assert_eq!(signed.inputs[0].partial_sigs.len(), 1);
+        escrow.sign_psbt(&mut signed, &worker_key).unwrap();
+
+        let settlement = coordinator
+            .finalize_multisig_settlement(funding_id, &[signed.to_string()])
+            .await
+            .unwrap();
+        assert!(settlement.broadcast);
+        let tx: Transaction = encode::deserialize(&hex::decode(&settlement.tx_hex).unwrap()).unwrap();
+        assert_eq!(tx.input[0].previous_output.txid, funding_tx.txid());
+        assert_eq!(tx.output[0].value.to_sat(), 70_000);
+        assert_eq!(tx.output[1].value.to_sat() + settlement.fee_sats, 30_000);
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_taproot_escrow_cooperative_close() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/v2/chain/BTC/transaction" => MockResponse::json(201, json!({ "id": "txid" })),
+            _ => MockResponse::json(404, json!({ "error": "not found" })),
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            boltz_api_url: Some(server.url()),
+            ..PaymentCoordinatorConfig::default()
+        });
+
+        let secp = Secp256k1::new();
+        let employer_key = SecretKey::from_slice(&[0x21; 32]).unwrap();
+        let worker_key = SecretKey::from_slice(&[0x22; 32]).unwrap();
+        let response = coordinator
+            .create_payment(PaymentRequest {
+                task_id: Uuid::new_v4(),
+                amount_sats: 5_000_000,
+                preferred_mode: FundingMode::OnchainMultisig,
+                payer_pubkey: "payer".to_string(),
+                description: "Task funding".to_string(),
+                refund_address: None,
+                escrow_parties: Some(EscrowParties {
+                    employer: PublicKey::from_secret_key(&secp, &employer_key),
+                    worker: PublicKey::from_secret_key(&secp, &worker_key),
+                }),
+            })
+            .await
+            .unwrap();
+        let funding_id = response.funding_id;
+        let address = network::parse_address(&response.onchain_address.unwrap(), Network::Regtest).unwrap();
+        assert!(address.to_string().starts_with("bcrt1p"));
+
+        let funding_tx = crate::swap_script::tests::lockup_transaction(&address, 5_000_000);
+        coordinator
+            .watch_multisig_funding(funding_id, &encode::serialize_hex(&funding_tx))
+            .await
+            .unwrap()
+            .unwrap();
+        let psbt = coordinator
+            .prepare_multisig_settlement(
+                funding_id,
+                MultisigOutcome::Release {
+                    worker_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        // Key-path outcomes cannot be finalised from signed PSBTs
+        assert!(coordinator
+            .finalize_multisig_settlement(funding_id, std::slice::from_ref(&psbt))
+            .await
+            .is_err());
+
+        // Employer and worker exchange nonces and sign the key-path sighash
+        let EscrowOutput::Taproot(escrow) = coordinator.get_multisig_funding(funding_id).await.unwrap().escrow else {
+            panic!("large escrows use Taproot");
+        };
+        let sighash = escrow.key_spend_sighash(&Psbt::from_str(&psbt).unwrap()).unwrap();
+        let (employer_secnonce, employer_nonce) = musig::generate_nonce().unwrap();
+        let (worker_secnonce, worker_nonce) = musig::generate_nonce().unwrap();
+        let aggregated = musig::aggregate_nonces(&[employer_nonce, worker_nonce]).unwrap();
+        let session = SigningSession::new(escrow.key_agg(), &aggregated, sighash).unwrap();
+        let employer = KeyPathContribution {
+            signer: PublicKey::from_secret_key(&secp, &employer_key),
+            public_nonce: employer_nonce,
+            partial_signature: session.partial_sign(employer_secnonce, &employer_key).unwrap(),
+        };
+        let worker = KeyPathContribution {
+            signer: PublicKey::from_secret_key(&secp, &worker_key),
+            public_nonce: worker_nonce,
+            partial_signature: session.partial_sign(worker_secnonce, &worker_key).unwrap(),
+        };
+        assert!(coordinator
+            .finalize_cooperative_settlement(funding_id, &[employer])
+            .await
+            .is_err());
+        // A partial signature attributed to the wrong party is rejected
+        let misattributed = KeyPathContribution { signer: worker.signer, ..employer };
+        assert!(coordinator
+            .finalize_cooperative_settlement(funding_id, &[misattributed, worker])
+            .await
+            .is_err());
+
+        let settlement = coordinator
+            .finalize_cooperative_settlement(funding_id, &[employer, worker])
+            .await
+            .unwrap();
+        assert!(settlement.broadcast);
+        let tx: Transaction = encode::deserialize(&hex::decode(&settlement.tx_hex).unwrap()).unwrap();
+        assert_eq!(tx.input[0].witness.len(), 1);
+        assert_eq!(tx.output[0].value.to_sat() + settlement.fee_sats, 5_000_000);
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_fee_quotes_live_and_static() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/fee-estimates" => MockResponse::json(200, json!({ "1": 40.0, "6": 12.3, "144": 2.0 })),
+            "/v2/swap/reverse" => MockResponse::json(200, json!({ "BTC": { "BTC": {
+                "hash": "rev_hash",
+                "rate": 1,
+                "limits": { "minimal": 25_000, "maximal": 25_000_000 },
+                "fees": { "percentage": 0.25, "minerFees": { "lockup": 250, "claim": 150 } }
+            }}})),
+            _ => MockResponse::json(503, json!({ "error": "unavailable" })),
+        })
+        .await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            boltz_api_url: Some(server.url()),
+            fee_sources: vec![
+                FeeSourceConfig::Bitcoind {
+                    rpc_url: format!("{}/rpc", server.url()),
+                    rpc_user: None,
+                    rpc_password: None,
+                },
+                FeeSourceConfig::Esplora { base_url: server.url() },
+            ],
+            ..PaymentCoordinatorConfig::default()
+        });
+
+        // bitcoind is down, Esplora answers
+        assert_eq!(coordinator.estimate_fee_rate().await, Some(13));
+        let quotes = coordinator.quote_fees(100_000).await;
+        let reverse = quotes.iter().find(|q| q.mode == FundingMode::OnchainReverse).unwrap();
+        assert_eq!(reverse.source, FeeSource::Live);
+        assert_eq!(reverse.fee_sats, 650 + CLAIM_VSIZE * 13);
+        assert_eq!(reverse.min_amount_sats, 25_000);
+        assert!(reverse.supported);
+        let multisig = quotes.iter().find(|q| q.mode == FundingMode::OnchainMultisig).unwrap();
+        assert_eq!(multisig.fee_sats, MULTISIG_SETTLEMENT_VSIZE * 13);
+
+        // Boltz submarine pairs are down: static schedule
+        let submarine = quotes.iter().find(|q| q.mode == FundingMode::OnchainSubmarine).unwrap();
+        assert_eq!(submarine.source, FeeSource::Static);
+        assert_eq!(submarine.fee_sats, 500);
+        let lightning = quotes.iter().find(|q| q.mode == FundingMode::LightningHold).unwrap();
+        assert_eq!((lightning.fee_sats, lightning.source), (100, FeeSource::Static));
+
+        // A probed route replaces the static Lightning fee
+        struct FixedProbe;
+        #[async_trait::async_trait]
+        impl RoutingFeeProber for FixedProbe {
+            async fn probe_routing_fee(&self, _destination: Option<&str>, _amount_sats: u64) -> EscrowResult<u64> {
+                Ok(7)
+            }
+        }
+        coordinator.set_routing_prober(Arc::new(FixedProbe)).await;
+        let lightning = coordinator.quote_fee(100_000, FundingMode::LightningHold).await;
+        assert_eq!((lightning.fee_sats, lightning.source), (7, FeeSource::Live));
+
+        // Without any fee source the configured fallback rate applies
+        let offline = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            ..PaymentCoordinatorConfig::default()
+        });
+        let multisig = offline.quote_fee(100_000, FundingMode::OnchainMultisig).await;
+        assert_eq!(multisig.source, FeeSource::Static);
+        assert_eq!(multisig.fee_rate_sat_vb, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_escrow_funding_through_reorg() {
+        let chain = Arc::new(InMemoryChain::new());
+        chain.mine_empty(10).await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            network: Network::Regtest,
+            ..PaymentCoordinatorConfig::default()
+        })
+        .with_chain_source(chain.clone());
+        assert!(coordinator.sync_chain().await.unwrap().is_empty());
+
+        let secp = Secp256k1::new();
+        let response = coordinator
+            .create_payment(PaymentRequest {
+                task_id: Uuid::new_v4(),
+                amount_sats: 100_000,
+                preferred_mode: FundingMode::OnchainMultisig,
+                payer_pubkey: "payer".to_string(),
+                description: "Task funding".to_string(),
+                refund_address: None,
+                escrow_parties: Some(EscrowParties {
+                    employer: PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[0x21; 32]).unwrap()),
+                    worker: PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[0x22; 32]).unwrap()),
+                }),
+            })
+            .await
+            .unwrap();
+        let funding_id = response.funding_id;
+        let address = network::parse_address(&response.onchain_address.unwrap(), Network::Regtest).unwrap();
+        let funding_tx = crate::swap_script::tests::lockup_transaction(&address, 100_000);
+        let outcome = MultisigOutcome::Release {
+            worker_address: "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string(),
+        };
+
+        chain.mine(vec![funding_tx.clone()]).await;
+        chain.mine_empty(2).await;
+        let changes = coordinator.sync_chain().await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].update.status, PaymentStatus::Confirmed);
+        assert_eq!(changes[0].update.confirmations, Some(3));
+        let status = coordinator.monitor_payment(funding_id).await.unwrap();
+        assert_eq!(status.confirmations, Some(3));
+        assert_eq!(status.transaction_id, Some(funding_tx.txid().to_string()));
+        coordinator
+            .prepare_multisig_settlement(funding_id, outcome.clone())
+            .await
+            .unwrap();
+
+        // The funding block is reorged out: the escrow waits for funding again
+        chain.reorg(3, vec![Vec::new(); 4]).await.unwrap();
+        let changes = coordinator.sync_chain().await.unwrap();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].reorged);
+        assert_eq!(changes[0].update.status, PaymentStatus::Pending);
+        assert_eq!(changes[0].update.failure_reason.as_deref(), Some("blockchain_reorg"));
+        let escrow = coordinator.get_multisig_funding(funding_id).await.unwrap();
+        assert!(escrow.funding_output.is_none() && escrow.pending_settlement.is_none());
+        assert!(coordinator
+            .prepare_multisig_settlement(funding_id, outcome.clone())
+            .await
+            .is_err());
+
+        // Re-included in the new chain
+        chain.mine(vec![funding_tx]).await;
+        let changes = coordinator.sync_chain().await.unwrap();
+        assert_eq!(changes[0].update.confirmations, Some(1));
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Confirmed);
+        coordinator
+            .prepare_multisig_settlement(funding_id, outcome)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cashu_escrow_refund() {
+        use crate::cashu::tests::MockMint;
+
+        let mint = MockMint::start(100).await;
+        let coordinator = PaymentCoordinator::new(PaymentCoordinatorConfig {
+            cashu_mint_url: Some(mint.url()),
+            ..PaymentCoordinatorConfig::default()
+        });
+        assert!(coordinator.get_supported_modes(10_000).contains(&FundingMode::Cashu));
+
+        let employer = PublicKey::from_secret_key(&Secp256k1::new(), &SecretKey::from_slice(&[0x61; 32]).unwrap());
+        let mut request = PaymentRequest {
+            task_id: Uuid::new_v4(),
+            amount_sats: 10_000,
+            preferred_mode: FundingMode::Cashu,
+            payer_pubkey: "payer".to_string(),
+            description: "Task funding".to_string(),
+            refund_address: None,
+            escrow_parties: None,
+        };
+        // The payer key is the refund key, so it must be a public key
+        assert!(coordinator.create_payment(request.clone()).await.is_err());
+        request.payer_pubkey = employer.to_string();
+        let response = coordinator.create_payment(request).await.unwrap();
+        let funding_id = response.funding_id;
+        let lock: CashuLock = serde_json::from_str(&response.lockup_script.unwrap()).unwrap();
+        assert_eq!(lock.refund_pubkey, employer.to_string());
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Pending);
+
+        // Ecash the employer could take back early is refused
+        let mut conditions = P2pkConditions {
+            pubkey: cashu::parse_pubkey(&lock.pubkey).unwrap(),
+            locktime: Some(lock.locktime - 1),
+            refund_keys: vec![employer],
+        };
+        let early = mint.locked_token(10_000, &conditions);
+        assert!(coordinator.accept_cashu_token(funding_id, &early).await.is_err());
+
+        conditions.locktime = Some(lock.locktime);
+        assert!(
+            coordinator
+                .accept_cashu_token(funding_id, &mint.locked_token(9_999, &conditions))
+                .await
+                .is_err()
+        );
+        let token = mint.locked_token(10_000, &conditions);
+        let escrow = coordinator.accept_cashu_token(funding_id, &token).await.unwrap();
+        assert_eq!(escrow.funded_sats(), 10_000);
+        assert!(coordinator.accept_cashu_token(funding_id, &token).await.is_err());
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Confirmed);
+
+        // 5 inputs at 100 ppk cost 1 sat
+        let refund = coordinator.refund_cashu_escrow(funding_id).await.unwrap();
+        assert_eq!((refund.amount_sats, refund.fee_sats), (9_999, 1));
+        let refunded = Token::decode(&refund.token).unwrap();
+        assert_eq!(refunded.amount(), 9_999);
+        assert!(refunded.proofs().all(|proof| {
+            P2pkConditions::from_secret(&proof.secret).unwrap().unwrap().pubkey == employer
+        }));
+        assert_eq!(coordinator.get_payment_status(funding_id).await.unwrap(), PaymentStatus::Completed);
+        assert!(coordinator.settle_cashu_escrow(funding_id, &lock.pubkey).await.is_err());
+    }
+}
@@ -0,0 +1,382 @@
+//! Price Oracle - BTC exchange rates for fiat-denominated rewards
+//!
+//! Tasks priced in a fiat currency are converted to sats when they are
+//! funded. Rates come from pluggable sources (a fixed-rate table for tests
+//! and local setups, the Coinbase and Kraken public APIs) tried in order;
+//! the rate used is snapshotted on the funding together with its source.
+
+use crate::{
+    EscrowResult,
+    error::EscrowError,
+    fee_estimator::http_client,
+    models::{FiatAmount, FiatQuote},
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, de::DeserializeOwned};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tracing::warn;
+
+/// Sats per bitcoin
+const SATS_PER_BTC: f64 = 100_000_000.0;
+
+/// Source of BTC exchange rates
+#[async_trait]
+pub trait PriceOracle: Send + Sync {
+    /// Human-readable source name
+    fn name(&self) -> &str;
+
+    /// Price of one bitcoin in `currency` (ISO 4217 code, e.g. "USD")
+    async fn btc_price(&self, currency: &str) -> EscrowResult<f64>;
+}
+
+/// Configured exchange rate source
+#[derive(Debug, Clone)]
+pub enum PriceSourceConfig {
+    /// Fixed prices per currency
+    Fixed { prices: HashMap<String, f64> },
+    /// Coinbase API base URL (`https://api.coinbase.com`)
+    Coinbase { base_url: String },
+    /// Kraken API base URL (`https://api.kraken.com`)
+    Kraken { base_url: String },
+}
+
+impl PriceSourceConfig {
+    /// Build the oracle for this source
+    pub fn build(&self, timeout: Duration) -> EscrowResult<Arc<dyn PriceOracle>> {
+        Ok(match self {
+            PriceSourceConfig::Fixed { prices } => {
+                let oracle = prices
+                    .iter()
+                    .fold(FixedRateOracle::new(), |oracle, (currency, price)| {
+                        oracle.with_price(currency, *price)
+                    });
+                Arc::new(oracle)
+            }
+            PriceSourceConfig::Coinbase { base_url } => {
+                Arc::new(CoinbasePriceOracle::new(base_url.clone(), timeout)?)
+            }
+            PriceSourceConfig::Kraken { base_url } => {
+                Arc::new(KrakenPriceOracle::new(base_url.clone(), timeout)?)
+            }
+        })
+    }
+}
+
+/// How a fiat task is priced again after its invoice expired unpaid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequotePolicy {
+    /// Convert at a fresh rate
+    #[default]
+    Requote,
+    /// Keep the previous rate while it is younger than `max_age_secs`
+    KeepRate { max_age_secs: u64 },
+}
+
+impl RequotePolicy {
+    /// Whether a new funding may reuse `previous`
+    pub fn keeps(&self, previous: &FiatQuote) -> bool {
+        match self {
+            RequotePolicy::Requote => false,
+            RequotePolicy::KeepRate { max_age_secs } => {
+                (Utc::now() - previous.quoted_at).num_seconds() < *max_age_secs as i64
+            }
+        }
+    }
+}
+
+/// Sats buying `amount` at `btc_price`, rounded up
+pub fn fiat_to_sats(amount: &FiatAmount, btc_price: f64) -> EscrowResult<u64> {
+    if !btc_price.is_finite() || btc_price <= 0.0 {
+        return Err(EscrowError::external_api(format!(
+            "Unusable BTC price {}",
+            btc_price
+        )));
+    }
+    let sats = (amount.amount_cents as f64 / 100.0 / btc_price * SATS_PER_BTC).ceil();
+    if sats < 1.0 {
+        return Err(EscrowError::task_validation(format!(
+            "{} is less than a sat",
+            amount
+        )));
+    }
+    Ok(sats as u64)
+}
+
+/// Quote `amount` in sats from the first source that answers
+pub async fn quote(
+    oracles: &[Arc<dyn PriceOracle>],
+    amount: &FiatAmount,
+) -> EscrowResult<FiatQuote> {
+    for oracle in oracles {
+        match oracle.btc_price(&amount.currency).await {
+            Ok(price) if price.is_finite() && price > 0.0 => {
+                return Ok(FiatQuote {
+                    amount: amount.clone(),
+                    amount_sats: fiat_to_sats(amount, price)?,
+                    btc_price: price,
+                    source: oracle.name().to_string(),
+                    quoted_at: Utc::now(),
+                });
+            }
+            Ok(price) => warn!(
+                "Price oracle {} returned unusable price {}",
+                oracle.name(),
+                price
+            ),
+            Err(e) => warn!("Price oracle {} failed: {}", oracle.name(), e),
+        }
+    }
+    Err(EscrowError::external_api(format!(
+        "No price source for BTC/{}",
+        amount.currency
+    )))
+}
+
+/// Oracle returning fixed prices, as a local stand-in for live sources
+#[derive(Debug, Clone, Default)]
+pub struct FixedRateOracle {
+    prices: HashMap<String, f64>,
+}
+
+impl FixedRateOracle {
+    /// Create an oracle without any prices
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Price one bitcoin at `price` units of `currency`
+    pub fn with_price(mut self, currency: &str, price: f64) -> Self {
+        self.prices.insert(currency.to_uppercase(), price);
+        self
+    }
+}
+
+#[async_trait]
+impl PriceOracle for FixedRateOracle {
+    fn name(&self) -> &str {
+        "fixed"
+    }
+
+    async fn btc_price(&self, currency: &str) -> EscrowResult<f64> {
+        self.prices
+            .get(&currency.to_uppercase())
+            .copied()
+            .ok_or_else(|| {
+                EscrowError::external_api(format!("No fixed price for BTC/{}", currency))
+            })
+    }
+}
+
+/// Oracle backed by Coinbase's spot price endpoint
+pub struct CoinbasePriceOracle {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+/// Body of `/v2/prices/{pair}/spot`
+#[derive(Debug, Deserialize)]
+struct CoinbaseSpot {
+    data: CoinbasePrice,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoinbasePrice {
+    amount: String,
+}
+
+impl CoinbasePriceOracle {
+    /// Create an oracle for the Coinbase API at `base_url`
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: http_client(timeout)?,
+        })
+    }
+}
+
+#[async_trait]
+impl PriceOracle for CoinbasePriceOracle {
+    fn name(&self) -> &str {
+        "coinbase"
+    }
+
+    async fn btc_price(&self, currency: &str) -> EscrowResult<f64> {
+        let url = format!(
+            "{}/v2/prices/BTC-{}/spot",
+            self.base_url,
+            currency.to_uppercase()
+        );
+        let spot: CoinbaseSpot = get_json(&self.http, &url, "Coinbase").await?;
+        spot.data
+            .amount
+            .parse()
+            .map_err(|e| EscrowError::external_api(format!("Invalid Coinbase price: {}", e)))
+    }
+}
+
+/// Oracle backed by Kraken's public ticker
+pub struct KrakenPriceOracle {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+/// Body of `/0/public/Ticker`
+#[derive(Debug, Deserialize)]
+struct KrakenTicker {
+    #[serde(default)]
+    error: Vec<String>,
+    #[serde(default)]
+    result: HashMap<String, KrakenPair>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KrakenPair {
+    /// Last trade: price, lot volume
+    c: Vec<String>,
+}
+
+impl KrakenPriceOracle {
+    /// Create an oracle for the Kraken API at `base_url`
+    pub fn new(base_url: impl Into<String>, timeout: Duration) -> EscrowResult<Self> {
+        Ok(Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: http_client(timeout)?,
+        })
+    }
+}
+
+#[async_trait]
+impl PriceOracle for KrakenPriceOracle {
+    fn name(&self) -> &str {
+        "kraken"
+    }
+
+    async fn btc_price(&self, currency: &str) -> EscrowResult<f64> {
+        // Kraken calls bitcoin XBT and answers under its own pair name
+        // (XXBTZUSD for XBTUSD), so take the only pair in the result
+        let url = format!(
+            "{}/0/public/Ticker?pair=XBT{}",
+            self.base_url,
+            currency.to_uppercase()
+        );
+        let ticker: KrakenTicker = get_json(&self.http, &url, "Kraken").await?;
+        if !ticker.error.is_empty() {
+            return Err(EscrowError::external_api(format!(
+                "Kraken error: {}",
+                ticker.error.join(", ")
+            )));
+        }
+        ticker
+            .result
+            .values()
+            .next()
+            .and_then(|pair| pair.c.first())
+            .ok_or_else(|| EscrowError::external_api("Kraken returned no ticker"))?
+            .parse()
+            .map_err(|e| EscrowError::external_api(format!("Invalid Kraken price: {}", e)))
+    }
+}
+
+async fn get_json<T: DeserializeOwned>(
+    http: &reqwest::Client,
+    url: &str,
+    service: &str,
+) -> EscrowResult<T> {
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| EscrowError::external_api(format!("{} request failed: {}", service, e)))?;
+    if !response.status().is_success() {
+        return Err(EscrowError::external_api(format!(
+            "{} {} returned {}",
+            service,
+            url,
+            response.status().as_u16()
+        )));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| EscrowError::external_api(format!("Invalid {} response: {}", service, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use serde_json::json;
+
+    fn usd(amount_cents: i64) -> FiatAmount {
+        FiatAmount {
+            currency: "USD".to_string(),
+            amount_cents,
+        }
+    }
+
+    #[test]
+    fn test_fiat_to_sats() {
+        // $50 at $100,000/BTC
+        assert_eq!(fiat_to_sats(&usd(5_000), 100_000.0).unwrap(), 50_000);
+        // Rounded up to whole sats
+        assert_eq!(fiat_to_sats(&usd(1), 30_000.0).unwrap(), 34);
+        assert!(fiat_to_sats(&usd(5_000), 0.0).is_err());
+        assert!(fiat_to_sats(&usd(5_000), f64::NAN).is_err());
+
+        let quote = FiatQuote {
+            amount: usd(5_000),
+            amount_sats: 50_000,
+            btc_price: 100_000.0,
+            source: "fixed".to_string(),
+            quoted_at: Utc::now() - chrono::Duration::minutes(10),
+        };
+        assert!(!RequotePolicy::Requote.keeps(&quote));
+        assert!(RequotePolicy::KeepRate { max_age_secs: 3600 }.keeps(&quote));
+        assert!(!RequotePolicy::KeepRate { max_age_secs: 60 }.keeps(&quote));
+    }
+
+    #[tokio::test]
+    async fn test_http_sources_in_order() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/v2/prices/BTC-USD/spot" => MockResponse::json(200, json!({
+                "data": { "amount": "100000.00", "base": "BTC", "currency": "USD" }
+            })),
+            "/0/public/Ticker?pair=XBTEUR" => MockResponse::json(200, json!({
+                "error": [],
+                "result": { "XXBTZEUR": { "a": ["80001.0", "1", "1.0"], "c": ["80000.0", "0.01"] } }
+            })),
+            _ => MockResponse::json(404, json!({ "errors": [{ "id": "not_found" }] })),
+        })
+        .await;
+        let timeout = Duration::from_secs(5);
+        let coinbase = PriceSourceConfig::Coinbase {
+            base_url: server.url(),
+        }
+        .build(timeout)
+        .unwrap();
+        let kraken = PriceSourceConfig::Kraken {
+            base_url: server.url(),
+        }
+        .build(timeout)
+        .unwrap();
+        assert_eq!(coinbase.btc_price("usd").await.unwrap(), 100_000.0);
+        assert!(coinbase.btc_price("EUR").await.is_err());
+        assert_eq!(kraken.btc_price("EUR").await.unwrap(), 80_000.0);
+
+        // Coinbase has no EUR price here, so Kraken's is used
+        let oracles = vec![coinbase, kraken];
+        let eur = FiatAmount {
+            currency: "EUR".to_string(),
+            amount_cents: 4_000,
+        };
+        let quote = quote(&oracles, &eur).await.unwrap();
+        assert_eq!(
+            (quote.amount_sats, quote.source.as_str()),
+            (50_000, "kraken")
+        );
+
+        let offline: Vec<Arc<dyn PriceOracle>> = vec![Arc::new(FixedRateOracle::new())];
+        assert!(super::quote(&offline, &usd(100)).await.is_err());
+    }
+}
@@ -0,0 +1,458 @@
+//! Proof Archive - Escrow-owned copies of submitted proofs
+//!
+//! A proof URL can go dead before the employer or an arbitrator looks at it
+//! (EDGE_CASES #20, "Worker Loses Access to Proof"). When a proof is
+//! submitted, its content is stored in a local blob store keyed by SHA-256,
+//! so identical proofs are stored once and every read can be checked
+//! against its key. An index maps each task to its latest proof. Only the
+//! task's parties and the configured arbitrators may read a copy, and copies
+//! of closed tasks are pruned according to the retention policy.
+
+use crate::{
+    EscrowResult, error::EscrowError, models::Task, proof_fetcher::FetchedProof,
+    verification_service,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Index file in the archive directory
+const INDEX_FILE: &str = "index.json";
+
+/// Configuration for the proof archive
+#[derive(Debug, Clone)]
+pub struct ProofArchiveConfig {
+    /// Directory holding the blobs and the index
+    pub archive_dir: PathBuf,
+    /// How long copies are kept
+    pub retention: ProofRetention,
+    /// Arbitrators allowed to read any archived proof (hex or npub)
+    pub arbitrator_pubkeys: Vec<String>,
+}
+
+impl Default for ProofArchiveConfig {
+    fn default() -> Self {
+        Self {
+            archive_dir: PathBuf::from("/var/lib/escrow/proofs"),
+            retention: ProofRetention::default(),
+            arbitrator_pubkeys: Vec::new(),
+        }
+    }
+}
+
+/// How long archived proofs are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofRetention {
+    /// Never prune
+    KeepForever,
+    /// Prune `days` after the task was paid or refunded; open and disputed
+    /// tasks keep their proof
+    AfterClose { days: u64 },
+}
+
+impl Default for ProofRetention {
+    fn default() -> Self {
+        ProofRetention::AfterClose { days: 90 }
+    }
+}
+
+/// Archived copy of a task's proof
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedProof {
+    pub task_id: Uuid,
+    /// Hex SHA-256 of the content, and the key of its blob
+    pub content_hash: String,
+    pub size: u64,
+    pub content_type: String,
+    /// URL the proof was downloaded from
+    pub proof_url: String,
+    pub employer_pubkey: String,
+    pub worker_pubkey: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Archived proof with its content
+#[derive(Debug, Clone)]
+pub struct RetrievedProof {
+    pub proof: ArchivedProof,
+    pub data: Vec<u8>,
+}
+
+/// Latest archived proof per task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ArchiveIndex {
+    proofs: BTreeMap<Uuid, ArchivedProof>,
+}
+
+/// Content-addressed store of submitted proofs
+pub struct ProofArchive {
+    config: ProofArchiveConfig,
+    index: RwLock<ArchiveIndex>,
+}
+
+impl ProofArchive {
+    /// Open the archive, loading its index from disk
+    pub async fn new(config: ProofArchiveConfig) -> EscrowResult<Self> {
+        let index = match tokio::fs::read(config.archive_dir.join(INDEX_FILE)).await {
+            Ok(data) => serde_json::from_slice(&data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ArchiveIndex::default(),
+            Err(e) => {
+                return Err(EscrowError::integration(format!(
+                    "Proof archive index read failed: {}",
+                    e
+                )));
+            }
+        };
+
+        info!(
+            "Proof archive at {} ({} proof(s))",
+            config.archive_dir.display(),
+            index.proofs.len()
+        );
+
+        Ok(Self {
+            config,
+            index: RwLock::new(index),
+        })
+    }
+
+    /// Store the downloaded proof of `task`, replacing any earlier one
+    pub async fn archive(
+        &self,
+        task: &Task,
+        proof_url: &str,
+        proof: &FetchedProof,
+    ) -> EscrowResult<ArchivedProof> {
+        let content_hash = hex::encode(Sha256::digest(&proof.data));
+        if !content_hash.eq_ignore_ascii_case(&proof.content_hash) {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof content hashes to {}, not {}",
+                content_hash, proof.content_hash
+            )));
+        }
+
+        let mut index = self.index.write().await;
+        if !tokio::fs::try_exists(self.blob_path(&content_hash))
+            .await
+            .unwrap_or(false)
+        {
+            self.write_file(self.blob_path(&content_hash), &proof.data)
+                .await?;
+        }
+
+        let archived = ArchivedProof {
+            task_id: task.id,
+            content_hash,
+            size: proof.data.len() as u64,
+            content_type: proof.content_type.mime_type().to_string(),
+            proof_url: proof_url.to_string(),
+            employer_pubkey: task.employer_pubkey.clone(),
+            worker_pubkey: task.worker_pubkey.clone(),
+            archived_at: Utc::now(),
+        };
+        let replaced = index.proofs.insert(task.id, archived.clone());
+        self.save_index(&index).await?;
+        if let Some(replaced) = replaced {
+            self.remove_unreferenced(&index, &replaced.content_hash)
+                .await;
+        }
+
+        info!(
+            "Archived proof {} ({} bytes) for task {}",
+            archived.content_hash, archived.size, task.id
+        );
+
+        Ok(archived)
+    }
+
+    /// Archived proof of a task, without its content
+    pub async fn get(&self, task_id: Uuid) -> Option<ArchivedProof> {
+        self.index.read().await.proofs.get(&task_id).cloned()
+    }
+
+    /// Whether `pubkey` may read `proof`: the task's parties and arbitrators
+    pub fn can_access(&self, proof: &ArchivedProof, pubkey: &str) -> bool {
+        std::iter::once(&proof.employer_pubkey)
+            .chain(proof.worker_pubkey.as_ref())
+            .chain(&self.config.arbitrator_pubkeys)
+            .any(|allowed| verification_service::same_pubkey(allowed, pubkey))
+    }
+
+    /// Read a task's archived proof on behalf of `requester`
+    ///
+    /// The content is checked against its hash, so a damaged copy is never
+    /// handed out as evidence.
+    pub async fn retrieve(&self, task_id: Uuid, requester: &str) -> EscrowResult<RetrievedProof> {
+        let proof = self.get(task_id).await.ok_or_else(|| {
+            EscrowError::task_validation(format!("No archived proof for task {}", task_id))
+        })?;
+        if !self.can_access(&proof, requester) {
+            return Err(EscrowError::task_validation(
+                "Only the task's parties and arbitrators can read its proof",
+            ));
+        }
+
+        let data = tokio::fs::read(self.blob_path(&proof.content_hash))
+            .await
+            .map_err(|e| EscrowError::integration(format!("Proof archive read failed: {}", e)))?;
+        if hex::encode(Sha256::digest(&data)) != proof.content_hash {
+            return Err(EscrowError::integration(format!(
+                "Archived proof {} is corrupted",
+                proof.content_hash
+            )));
+        }
+
+        Ok(RetrievedProof { proof, data })
+    }
+
+    /// Drop the proofs of tasks closed longer ago than the retention period
+    ///
+    /// `closed_at` holds the paid or refunded tasks and when they closed;
+    /// tasks missing from it are still open. Blobs are deleted once no task
+    /// references them.
+    pub async fn prune(&self, closed_at: &HashMap<Uuid, DateTime<Utc>>) -> EscrowResult<Vec<Uuid>> {
+        let ProofRetention::AfterClose { days } = self.config.retention else {
+            return Ok(Vec::new());
+        };
+        let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+
+        let mut index = self.index.write().await;
+        let expired: Vec<Uuid> = index
+            .proofs
+            .keys()
+            .filter(|task_id| {
+                closed_at
+                    .get(task_id)
+                    .is_some_and(|closed| *closed < cutoff)
+            })
+            .copied()
+            .collect();
+        if expired.is_empty() {
+            return Ok(expired);
+        }
+
+        let removed: Vec<ArchivedProof> = expired
+            .iter()
+            .filter_map(|task_id| index.proofs.remove(task_id))
+            .collect();
+        self.save_index(&index).await?;
+        for proof in removed {
+            self.remove_unreferenced(&index, &proof.content_hash).await;
+        }
+
+        info!("Pruned {} archived proof(s)", expired.len());
+
+        Ok(expired)
+    }
+
+    /// Path of the blob for a content hash, fanned out by its first byte
+    fn blob_path(&self, content_hash: &str) -> PathBuf {
+        self.config
+            .archive_dir
+            .join("blobs")
+            .join(&content_hash[..2])
+            .join(content_hash)
+    }
+
+    /// Delete a blob no archived proof refers to any more
+    async fn remove_unreferenced(&self, index: &ArchiveIndex, content_hash: &str) {
+        if index
+            .proofs
+            .values()
+            .any(|proof| proof.content_hash == content_hash)
+        {
+            return;
+        }
+        if let Err(e) = tokio::fs::remove_file(self.blob_path(content_hash)).await {
+            warn!("Failed to delete archived proof {}: {}", content_hash, e);
+        }
+    }
+
+    async fn save_index(&self, index: &ArchiveIndex) -> EscrowResult<()> {
+        let data = serde_json::to_vec_pretty(index)?;
+        self.write_file(self.config.archive_dir.join(INDEX_FILE), &data)
+            .await
+    }
+
+    /// Write through a temporary file so readers never see a torn write
+    async fn write_file(&self, path: PathBuf, data: &[u8]) -> EscrowResult<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                EscrowError::integration(format!("Proof archive mkdir failed: {}", e))
+            })?;
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, data)
+            .await
+            .map_err(|e| EscrowError::integration(format!("Proof archive write failed: {}", e)))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| EscrowError::integration(format!("Proof archive rename failed: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_fetcher::ContentType;
+    use crate::verification_service::tests::nostr_pubkey;
+    use secp256k1::SecretKey;
+
+    fn pubkey(byte: u8) -> String {
+        nostr_pubkey(&SecretKey::from_slice(&[byte; 32]).unwrap())
+    }
+
+    fn employer() -> String {
+        pubkey(0x21)
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("escrow-proofs-{}", Uuid::new_v4()))
+    }
+
+    fn fetched(data: &[u8]) -> FetchedProof {
+        FetchedProof {
+            content_hash: hex::encode(Sha256::digest(data)),
+            size: data.len() as u64,
+            content_type: ContentType::sniff(data),
+            data: data.to_vec(),
+        }
+    }
+
+    fn task(worker: &str) -> Task {
+        let mut task = Task::new("Proof Task".to_string(), None, 10_000, employer(), None);
+        task.worker_pubkey = Some(worker.to_string());
+        task
+    }
+
+    #[tokio::test]
+    async fn test_archive_and_access() {
+        let (worker, other_worker, arbitrator) = (pubkey(0x22), pubkey(0x23), pubkey(0x24));
+        let config = ProofArchiveConfig {
+            archive_dir: temp_dir(),
+            arbitrator_pubkeys: vec![arbitrator.clone()],
+            ..ProofArchiveConfig::default()
+        };
+        let archive = ProofArchive::new(config.clone()).await.unwrap();
+        let (first, second) = (task(&worker), task(&other_worker));
+        let report = fetched(b"All tests pass.\n");
+
+        let archived = archive
+            .archive(&first, "https://example.com/a.txt", &report)
+            .await
+            .unwrap();
+        assert_eq!(
+            (archived.size, archived.content_type.as_str()),
+            (16, "text/plain")
+        );
+        // The same content is stored once
+        archive
+            .archive(&second, "https://example.com/b.txt", &report)
+            .await
+            .unwrap();
+        let blobs = std::fs::read_dir(
+            config
+                .archive_dir
+                .join("blobs")
+                .join(&report.content_hash[..2]),
+        )
+        .unwrap()
+        .count();
+        assert_eq!(blobs, 1);
+
+        // Parties and arbitrators only
+        for reader in [employer(), worker, arbitrator] {
+            assert_eq!(
+                archive.retrieve(first.id, &reader).await.unwrap().data,
+                report.data
+            );
+        }
+        assert!(archive.retrieve(first.id, &other_worker).await.is_err());
+        assert!(archive.retrieve(Uuid::new_v4(), &employer()).await.is_err());
+
+        let mut forged = fetched(b"All tests pass.\n");
+        forged.data = b"Nothing works.\n".to_vec();
+        assert!(
+            archive
+                .archive(&first, "https://example.com/a.txt", &forged)
+                .await
+                .is_err()
+        );
+
+        // The index survives a restart, and damaged copies are refused
+        let reopened = ProofArchive::new(config.clone()).await.unwrap();
+        assert_eq!(reopened.get(first.id).await, Some(archived.clone()));
+        std::fs::write(reopened.blob_path(&archived.content_hash), b"tampered").unwrap();
+        assert!(matches!(
+            reopened.retrieve(first.id, &employer()).await,
+            Err(EscrowError::Integration(_))
+        ));
+
+        std::fs::remove_dir_all(&config.archive_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let config = ProofArchiveConfig {
+            archive_dir: temp_dir(),
+            retention: ProofRetention::AfterClose { days: 30 },
+            ..ProofArchiveConfig::default()
+        };
+        let archive = ProofArchive::new(config.clone()).await.unwrap();
+        let worker = pubkey(0x22);
+        let (old, recent, open) = (task(&worker), task(&worker), task(&worker));
+        let shared = fetched(b"shared proof");
+        let own = fetched(b"old proof");
+        archive
+            .archive(&old, "https://example.com/old.txt", &own)
+            .await
+            .unwrap();
+        archive
+            .archive(&recent, "https://example.com/recent.txt", &shared)
+            .await
+            .unwrap();
+        archive
+            .archive(&open, "https://example.com/open.txt", &shared)
+            .await
+            .unwrap();
+
+        let closed_at = HashMap::from([
+            (old.id, Utc::now() - chrono::Duration::days(31)),
+            (recent.id, Utc::now() - chrono::Duration::days(1)),
+        ]);
+        assert_eq!(archive.prune(&closed_at).await.unwrap(), vec![old.id]);
+        assert!(archive.get(old.id).await.is_none());
+        assert!(!archive.blob_path(&own.content_hash).exists());
+        assert!(archive.retrieve(recent.id, &employer()).await.is_ok());
+        assert!(archive.retrieve(open.id, &employer()).await.is_ok());
+
+        // Resubmitting releases the earlier blob
+        archive
+            .archive(&open, "https://example.com/open.txt", &own)
+            .await
+            .unwrap();
+        archive
+            .archive(&recent, "https://example.com/recent.txt", &own)
+            .await
+            .unwrap();
+        assert!(!archive.blob_path(&shared.content_hash).exists());
+
+        let kept = ProofArchive::new(ProofArchiveConfig {
+            retention: ProofRetention::KeepForever,
+            ..config.clone()
+        })
+        .await
+        .unwrap();
+        let long_ago = HashMap::from([(open.id, Utc::now() - chrono::Duration::days(3650))]);
+        assert!(kept.prune(&long_ago).await.unwrap().is_empty());
+
+        std::fs::remove_dir_all(&config.archive_dir).unwrap();
+    }
+}
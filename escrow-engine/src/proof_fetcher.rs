@@ -0,0 +1,488 @@
+//! Proof Fetcher - Downloads and fingerprints submitted proofs
+//!
+//! Proofs are streamed from their URL with a size cap, hashed with SHA-256
+//! as they arrive, and typed by their leading bytes rather than by the file
+//! name in the URL. Only public hosts are contacted: every address a host
+//! resolves to is checked before connecting, the connection is pinned to the
+//! checked address, and redirects are followed by hand so each hop is
+//! checked the same way.
+
+use crate::{EscrowResult, error::EscrowError};
+use reqwest::{Url, redirect};
+use sha2::{Digest, Sha256};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+
+/// Redirects followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// Leading bytes kept for content sniffing
+const SNIFF_LEN: usize = 512;
+
+/// Configuration for proof downloads
+#[derive(Debug, Clone)]
+pub struct ProofFetcherConfig {
+    /// Maximum proof size in bytes
+    pub max_size_bytes: usize,
+    /// Timeout for the whole download in seconds
+    pub timeout_secs: u64,
+    /// Allow hosts on loopback and private networks (local setups only)
+    pub allow_private_hosts: bool,
+}
+
+impl Default for ProofFetcherConfig {
+    fn default() -> Self {
+        Self {
+            max_size_bytes: 10 * 1024 * 1024, // 10MB
+            timeout_secs: 30,
+            allow_private_hosts: false,
+        }
+    }
+}
+
+/// Downloaded proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedProof {
+    /// Hex SHA-256 of the content
+    pub content_hash: String,
+    pub size: u64,
+    /// Content type sniffed from the content
+    pub content_type: ContentType,
+    pub data: Vec<u8>,
+}
+
+/// Content types recognised in proofs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Pdf,
+    Text,
+    Unknown,
+}
+
+impl ContentType {
+    /// Sniff the type from the leading bytes of the content
+    ///
+    /// Anything that is not one of the known binary formats counts as text
+    /// when it is UTF-8 without control characters besides whitespace.
+    pub fn sniff(head: &[u8]) -> Self {
+        if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+            ContentType::Png
+        } else if head.starts_with(&[0xff, 0xd8, 0xff]) {
+            ContentType::Jpeg
+        } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+            ContentType::Gif
+        } else if head.len() >= 12 && head.starts_with(b"RIFF") && &head[8..12] == b"WEBP" {
+            ContentType::Webp
+        } else if head.starts_with(b"%PDF-") {
+            ContentType::Pdf
+        } else if is_text(head) {
+            ContentType::Text
+        } else {
+            ContentType::Unknown
+        }
+    }
+
+    /// MIME type of the content
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            ContentType::Png => "image/png",
+            ContentType::Jpeg => "image/jpeg",
+            ContentType::Gif => "image/gif",
+            ContentType::Webp => "image/webp",
+            ContentType::Pdf => "application/pdf",
+            ContentType::Text => "text/plain",
+            ContentType::Unknown => "application/octet-stream",
+        }
+    }
+
+    /// File extensions content of this type goes by
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ContentType::Png => &["png"],
+            ContentType::Jpeg => &["jpg", "jpeg"],
+            ContentType::Gif => &["gif"],
+            ContentType::Webp => &["webp"],
+            ContentType::Pdf => &["pdf"],
+            ContentType::Text => &["txt", "md"],
+            ContentType::Unknown => &[],
+        }
+    }
+}
+
+/// Whether `head` reads as text, allowing a character cut off at the end
+fn is_text(head: &[u8]) -> bool {
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&head[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+}
+
+/// Whether `ip` is reachable on the public internet
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(embedded) => is_public_ipv4(embedded),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+/// IPv4 address that an IPv6 address reaches, when it embeds one
+///
+/// Mapped (`::ffff:0:0/96`), IPv4-compatible (`::/96`), NAT64
+/// (`64:ff9b::/96`) and 6to4 (`2002::/16`) addresses all lead to the IPv4
+/// host they embed, so they are only as public as that host.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let [.., a, b, c, d] = ip.octets();
+    match ip.segments() {
+        [0, 0, 0, 0, 0, 0xffff, _, _]
+        | [0, 0, 0, 0, 0, 0, _, _]
+        | [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        [0x2002, high, low, ..] => {
+            let [a, b] = high.to_be_bytes();
+            let [c, d] = low.to_be_bytes();
+            Some(Ipv4Addr::new(a, b, c, d))
+        }
+        _ => None,
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // Shared address space (100.64.0.0/10)
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments (192.0.0.0/24)
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking (198.18.0.0/15)
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved (240.0.0.0/4) and "this network" (0.0.0.0/8)
+        || a >= 240
+        || a == 0)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let [first, second, third, ..] = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local (fc00::/7) and link-local (fe80::/10)
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        // Local-use NAT64 (64:ff9b:1::/48), which may embed any IPv4 address
+        || (first == 0x64 && second == 0xff9b && third == 1)
+        // Documentation (2001:db8::/32)
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+/// Downloads proofs from public hosts
+pub struct ProofFetcher {
+    config: ProofFetcherConfig,
+}
+
+impl ProofFetcher {
+    /// Create a new proof fetcher
+    pub fn new(config: ProofFetcherConfig) -> Self {
+        Self { config }
+    }
+
+    /// Download the proof at `url`, hashing and typing it
+    pub async fn fetch(&self, url: &str) -> EscrowResult<FetchedProof> {
+        let (url, response) = self.follow(url).await?;
+        if !response.status().is_success() {
+            return Err(EscrowError::external_api(format!(
+                "Proof {} returned {}",
+                url,
+                response.status().as_u16()
+            )));
+        }
+        self.read(response).await
+    }
+
+    /// HTTP status the proof at `url` answers with, without reading it
+    pub async fn status(&self, url: &str) -> EscrowResult<u16> {
+        let (_, response) = self.follow(url).await?;
+        Ok(response.status().as_u16())
+    }
+
+    /// Request `url`, following redirects, until a final response
+    async fn follow(&self, url: &str) -> EscrowResult<(Url, reqwest::Response)> {
+        let mut url = Url::parse(url.trim())
+            .map_err(|e| EscrowError::proof_verification(format!("Invalid proof URL: {}", e)))?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            let response = self.get(&url).await?;
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| {
+                        EscrowError::external_api("Proof redirect without a location")
+                    })?;
+                url = url.join(location).map_err(|e| {
+                    EscrowError::external_api(format!("Invalid proof redirect: {}", e))
+                })?;
+                continue;
+            }
+            return Ok((url, response));
+        }
+
+        Err(EscrowError::external_api(format!(
+            "Proof URL redirected more than {} times",
+            MAX_REDIRECTS
+        )))
+    }
+
+    /// Request `url` from a checked address of its host
+    async fn get(&self, url: &Url) -> EscrowResult<reqwest::Response> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(EscrowError::proof_verification(
+                "Proof URL must use HTTP/HTTPS",
+            ));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| EscrowError::proof_verification("Proof URL has no host"))?;
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| EscrowError::proof_verification("Proof URL has no port"))?;
+
+        // IPv6 literals come bracketed in URLs
+        let lookup_host = host.trim_start_matches('[').trim_end_matches(']');
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((lookup_host, port))
+            .await
+            .map_err(|e| {
+                EscrowError::external_api(format!("Cannot resolve proof host {}: {}", host, e))
+            })?
+            .collect();
+        let address = *addresses.first().ok_or_else(|| {
+            EscrowError::external_api(format!("Proof host {} has no address", host))
+        })?;
+        if !self.config.allow_private_hosts
+            && let Some(private) = addresses
+                .iter()
+                .find(|address| !is_public_address(address.ip()))
+        {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof host {} resolves to non-public address {}",
+                host,
+                private.ip()
+            )));
+        }
+
+        // Connect to the checked address, not whatever a second lookup returns
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.config.timeout_secs))
+            .redirect(redirect::Policy::none())
+            .resolve(lookup_host, address)
+            .build()
+            .map_err(|e| {
+                EscrowError::external_api(format!("Failed to build HTTP client: {}", e))
+            })?;
+        client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Proof request failed: {}", e)))
+    }
+
+    /// Stream the body, stopping as soon as it exceeds the size cap
+    async fn read(&self, mut response: reqwest::Response) -> EscrowResult<FetchedProof> {
+        let max_size = self.config.max_size_bytes as u64;
+        let too_large = || {
+            EscrowError::proof_verification(format!(
+                "Proof exceeds maximum size of {} bytes",
+                max_size
+            ))
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_size)
+        {
+            return Err(too_large());
+        }
+
+        let mut hasher = Sha256::new();
+        let mut data = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| EscrowError::external_api(format!("Failed to read proof: {}", e)))?
+        {
+            if (data.len() + chunk.len()) as u64 > max_size {
+                return Err(too_large());
+            }
+            hasher.update(&chunk);
+            data.extend_from_slice(&chunk);
+        }
+
+        Ok(FetchedProof {
+            content_hash: hex::encode(hasher.finalize()),
+            size: data.len() as u64,
+            content_type: ContentType::sniff(&data[..data.len().min(SNIFF_LEN)]),
+            data,
+        })
+    }
+}
+
+impl Default for ProofFetcher {
+    fn default() -> Self {
+        Self::new(ProofFetcherConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_content_sniffing_and_addresses() {
+        assert_eq!(ContentType::sniff(PNG), ContentType::Png);
+        assert_eq!(
+            ContentType::sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"),
+            ContentType::Jpeg
+        );
+        assert_eq!(ContentType::sniff(b"%PDF-1.7\n"), ContentType::Pdf);
+        assert_eq!(
+            ContentType::sniff(b"RIFF\x24\0\0\0WEBPVP8 "),
+            ContentType::Webp
+        );
+        assert_eq!(
+            ContentType::sniff("# Proof\n\nDone ✓".as_bytes()),
+            ContentType::Text
+        );
+        // A character cut off by the sniffing window is still text
+        assert_eq!(
+            ContentType::sniff(&"✓".as_bytes()[..2]),
+            ContentType::Unknown
+        );
+        assert_eq!(ContentType::sniff(&"a✓".as_bytes()[..3]), ContentType::Text);
+        assert_eq!(ContentType::sniff(b"MZ\x90\0\x03\0"), ContentType::Unknown);
+        assert_eq!(ContentType::sniff(b""), ContentType::Unknown);
+
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "192.0.0.170",
+            "198.18.0.1",
+            "198.19.255.255",
+            // IPv4 hosts reached through IPv6
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "64:ff9b:1::a00:1",
+            "2002:a00:1::1",
+            "2002:7f00:1::",
+        ] {
+            assert!(!is_public_address(private.parse().unwrap()), "{}", private);
+        }
+        for public in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "198.20.0.1",
+            "2606:4700:4700::1111",
+            "64:ff9b::1.1.1.1",
+            "2002:101:101::1",
+        ] {
+            assert!(is_public_address(public.parse().unwrap()), "{}", public);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_streams_and_guards() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            // Served under a misleading name and type
+            "/proof.txt" => MockResponse::bytes(200, "text/plain", PNG),
+            "/moved" => {
+                MockResponse::bytes(302, "text/plain", "").with_header("location", "/proof.txt")
+            }
+            "/large.png" => MockResponse::bytes(200, "image/png", vec![0u8; 2048]),
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+
+        // Local setups may opt in to private hosts
+        let fetcher = ProofFetcher::new(ProofFetcherConfig {
+            max_size_bytes: 1024,
+            allow_private_hosts: true,
+            ..ProofFetcherConfig::default()
+        });
+        let proof = fetcher
+            .fetch(&format!("{}/proof.txt", server.url()))
+            .await
+            .unwrap();
+        assert_eq!(proof.content_type, ContentType::Png);
+        assert_eq!(proof.size, PNG.len() as u64);
+        assert_eq!(proof.content_hash, hex::encode(Sha256::digest(PNG)));
+        assert_eq!(proof.data, PNG);
+        assert_eq!(
+            fetcher
+                .fetch(&format!("{}/moved", server.url()))
+                .await
+                .unwrap(),
+            proof
+        );
+        assert!(
+            fetcher
+                .fetch(&format!("{}/large.png", server.url()))
+                .await
+                .is_err()
+        );
+        assert!(
+            fetcher
+                .fetch(&format!("{}/missing", server.url()))
+                .await
+                .is_err()
+        );
+        assert!(fetcher.fetch("ftp://example.com/proof.png").await.is_err());
+
+        // By default the server's loopback address is refused before any request
+        let requests = server.requests().await.len();
+        let fetcher = ProofFetcher::default();
+        let localhost = server.url().replace("127.0.0.1", "localhost");
+        for url in [
+            format!("{}/proof.txt", server.url()),
+            format!("{}/proof.txt", localhost),
+        ] {
+            assert!(matches!(
+                fetcher.fetch(&url).await,
+                Err(EscrowError::ProofVerification(_))
+            ));
+        }
+        assert_eq!(server.requests().await.len(), requests);
+    }
+}
@@ -0,0 +1,594 @@
+//! Proof Verifier - Automated checks of submitted proofs
+//!
+//! Verifiers are registered per task type, taken from the `task_type` key of
+//! the task metadata, and judge whether a proof shows the task was done.
+//! Verifiers only ever see content that hashes to what the worker signed.
+//! Their results are advisory for the employer unless the task opted in to
+//! auto-approval with `auto_approve: true` in its metadata.
+
+use crate::{
+    EscrowResult,
+    error::EscrowError,
+    models::Task,
+    proof_fetcher::{FetchedProof, ProofFetcher, ProofFetcherConfig},
+    verification_service::CompletionVerificationResult,
+};
+use async_trait::async_trait;
+use chrono::Utc;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Automated check of a submitted proof
+#[async_trait]
+pub trait ProofVerifier: Send + Sync {
+    /// Verifier name for logs and verification results
+    fn name(&self) -> &str;
+
+    /// Check the proof at `proof_url`, whose content `proof` has already
+    /// been checked against the hash the worker signed
+    async fn verify(
+        &self,
+        task: &Task,
+        proof_url: &str,
+        proof: &FetchedProof,
+    ) -> EscrowResult<CompletionVerificationResult>;
+}
+
+/// Configured proof verifier
+#[derive(Debug, Clone)]
+pub enum ProofVerifierConfig {
+    /// Content hash matches the declared and expected hashes
+    HashMatch,
+    /// Text content matches a regular expression
+    Regex { pattern: String },
+    /// JSON content matches a JSON Schema
+    JsonSchema { schema: Value },
+    /// Proof URL answers with the given HTTP status
+    HttpStatus { expected_status: u16 },
+}
+
+impl ProofVerifierConfig {
+    /// Build the verifier, fetching with `fetcher_config` where it needs to
+    pub fn build(
+        &self,
+        fetcher_config: &ProofFetcherConfig,
+    ) -> EscrowResult<Arc<dyn ProofVerifier>> {
+        Ok(match self {
+            ProofVerifierConfig::HashMatch => Arc::new(HashMatchVerifier),
+            ProofVerifierConfig::Regex { pattern } => Arc::new(RegexVerifier::new(pattern)?),
+            ProofVerifierConfig::JsonSchema { schema } => {
+                Arc::new(JsonSchemaVerifier::new(schema.clone()))
+            }
+            ProofVerifierConfig::HttpStatus { expected_status } => {
+                Arc::new(HttpStatusVerifier::new(
+                    ProofFetcher::new(fetcher_config.clone()),
+                    *expected_status,
+                ))
+            }
+        })
+    }
+}
+
+/// Task type a task is verified as
+pub fn task_type(task: &Task) -> Option<&str> {
+    task.metadata.as_ref()?.get("task_type")?.as_str()
+}
+
+/// Whether the employer lets a positive automated result approve the task
+pub fn auto_approve(task: &Task) -> bool {
+    task.metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("auto_approve"))
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+/// Result of a pass/fail check
+fn verdict(verifier: &str, approved: bool, feedback: String) -> CompletionVerificationResult {
+    CompletionVerificationResult {
+        approved,
+        score: if approved { 100 } else { 0 },
+        feedback,
+        verification_method: verifier.to_string(),
+        verified_at: Utc::now(),
+        verifier_notes: None,
+        criteria: None,
+    }
+}
+
+/// Approves proofs whose content hashes to what the task expects
+///
+/// The content must match the hash the worker declared and, when the
+/// employer set one, the `expected_proof_hash` of the task metadata.
+#[derive(Debug, Clone, Default)]
+pub struct HashMatchVerifier;
+
+#[async_trait]
+impl ProofVerifier for HashMatchVerifier {
+    fn name(&self) -> &str {
+        "hash_match"
+    }
+
+    async fn verify(
+        &self,
+        task: &Task,
+        _proof_url: &str,
+        proof: &FetchedProof,
+    ) -> EscrowResult<CompletionVerificationResult> {
+        let expected = task
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get("expected_proof_hash"))
+            .and_then(Value::as_str);
+        let hashes: Vec<&str> = task
+            .proof_hash
+            .as_deref()
+            .into_iter()
+            .chain(expected)
+            .collect();
+        if hashes.is_empty() {
+            return Ok(verdict(
+                self.name(),
+                false,
+                "No proof hash to match".to_string(),
+            ));
+        }
+
+        Ok(
+            match hashes
+                .iter()
+                .find(|hash| !hash.eq_ignore_ascii_case(&proof.content_hash))
+            {
+                Some(hash) => verdict(
+                    self.name(),
+                    false,
+                    format!(
+                        "Proof content hashes to {}, not {}",
+                        proof.content_hash, hash
+                    ),
+                ),
+                None => verdict(self.name(), true, "Proof content hash matches".to_string()),
+            },
+        )
+    }
+}
+
+/// Approves text proofs matching a regular expression
+#[derive(Debug, Clone)]
+pub struct RegexVerifier {
+    pattern: Regex,
+}
+
+impl RegexVerifier {
+    /// Create a verifier for `pattern`
+    pub fn new(pattern: &str) -> EscrowResult<Self> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| EscrowError::config(format!("Invalid proof pattern: {}", e)))?;
+        Ok(Self { pattern })
+    }
+}
+
+#[async_trait]
+impl ProofVerifier for RegexVerifier {
+    fn name(&self) -> &str {
+        "regex"
+    }
+
+    async fn verify(
+        &self,
+        _task: &Task,
+        _proof_url: &str,
+        proof: &FetchedProof,
+    ) -> EscrowResult<CompletionVerificationResult> {
+        let Ok(text) = std::str::from_utf8(&proof.data) else {
+            return Ok(verdict(
+                self.name(),
+                false,
+                format!("Proof is {}, not text", proof.content_type.mime_type()),
+            ));
+        };
+
+        Ok(if self.pattern.is_match(text) {
+            verdict(self.name(), true, format!("Proof matches {}", self.pattern))
+        } else {
+            verdict(
+                self.name(),
+                false,
+                format!("Proof does not match {}", self.pattern),
+            )
+        })
+    }
+}
+
+/// Approves JSON proofs matching a JSON Schema
+///
+/// Supports the commonly used subset of JSON Schema: `type`, `enum`,
+/// `const`, `required`, `properties`, `additionalProperties: false`,
+/// `items`, `minItems`/`maxItems`, `minLength`/`maxLength`, `pattern` and
+/// `minimum`/`maximum`. Other keywords are ignored.
+#[derive(Debug, Clone)]
+pub struct JsonSchemaVerifier {
+    schema: Value,
+}
+
+impl JsonSchemaVerifier {
+    /// Create a verifier for `schema`
+    pub fn new(schema: Value) -> Self {
+        Self { schema }
+    }
+}
+
+#[async_trait]
+impl ProofVerifier for JsonSchemaVerifier {
+    fn name(&self) -> &str {
+        "json_schema"
+    }
+
+    async fn verify(
+        &self,
+        _task: &Task,
+        _proof_url: &str,
+        proof: &FetchedProof,
+    ) -> EscrowResult<CompletionVerificationResult> {
+        let value: Value = match serde_json::from_slice(&proof.data) {
+            Ok(value) => value,
+            Err(e) => {
+                return Ok(verdict(
+                    self.name(),
+                    false,
+                    format!("Proof is not JSON: {}", e),
+                ));
+            }
+        };
+
+        let mut errors = Vec::new();
+        validate_schema(&self.schema, &value, "$", &mut errors);
+        if errors.is_empty() {
+            return Ok(verdict(
+                self.name(),
+                true,
+                "Proof matches the schema".to_string(),
+            ));
+        }
+        let mut result = verdict(
+            self.name(),
+            false,
+            format!("Proof does not match the schema: {}", errors[0]),
+        );
+        result.verifier_notes = Some(errors.join("; "));
+        Ok(result)
+    }
+}
+
+/// Collect the violations of `schema` by `value` found at `path`
+fn validate_schema(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let schema = match schema {
+        Value::Bool(false) => return errors.push(format!("{} is not allowed", path)),
+        Value::Object(schema) => schema,
+        _ => return,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| has_type(value, name)) {
+            // A value of the wrong type has nothing else worth checking
+            return errors.push(format!("{} must be of type {}", path, types.join(" or ")));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        errors.push(format!(
+            "{} must be one of {}",
+            path,
+            Value::Array(allowed.clone())
+        ));
+    }
+    if let Some(constant) = schema.get("const")
+        && constant != value
+    {
+        errors.push(format!("{} must be {}", path, constant));
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(name) = name.as_str()
+                    && !object.contains_key(name)
+                {
+                    errors.push(format!("{}.{} is required", path, name));
+                }
+            }
+            for (name, property) in object {
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => validate_schema(
+                        property_schema,
+                        property,
+                        &format!("{}.{}", path, name),
+                        errors,
+                    ),
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        errors.push(format!("{}.{} is not allowed", path, name))
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+                && (items.len() as u64) < min
+            {
+                errors.push(format!("{} must have at least {} items", path, min));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+                && items.len() as u64 > max
+            {
+                errors.push(format!("{} must have at most {} items", path, max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_schema(item_schema, item, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+                && length < min
+            {
+                errors.push(format!("{} must be at least {} characters", path, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+                && length > max
+            {
+                errors.push(format!("{} must be at most {} characters", path, max));
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                match Regex::new(pattern) {
+                    Ok(regex) if regex.is_match(text) => {}
+                    Ok(_) => errors.push(format!("{} must match {}", path, pattern)),
+                    Err(e) => errors.push(format!("{} has an invalid pattern: {}", path, e)),
+                }
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+                && number < min
+            {
+                errors.push(format!("{} must be at least {}", path, min));
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+                && number > max
+            {
+                errors.push(format!("{} must be at most {}", path, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `value` is of the JSON Schema type `name`
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
+
+/// Approves proofs whose URL answers with an expected HTTP status
+///
+/// Useful when the proof is a deployed page or endpoint rather than a file.
+pub struct HttpStatusVerifier {
+    fetcher: ProofFetcher,
+    expected_status: u16,
+}
+
+impl HttpStatusVerifier {
+    /// Create a verifier expecting `expected_status` from proof URLs
+    pub fn new(fetcher: ProofFetcher, expected_status: u16) -> Self {
+        Self {
+            fetcher,
+            expected_status,
+        }
+    }
+}
+
+#[async_trait]
+impl ProofVerifier for HttpStatusVerifier {
+    fn name(&self) -> &str {
+        "http_status"
+    }
+
+    async fn verify(
+        &self,
+        _task: &Task,
+        proof_url: &str,
+        _proof: &FetchedProof,
+    ) -> EscrowResult<CompletionVerificationResult> {
+        let status = self.fetcher.status(proof_url).await?;
+        let approved = status == self.expected_status;
+        Ok(verdict(
+            self.name(),
+            approved,
+            format!(
+                "Proof URL answered {} (expected {})",
+                status, self.expected_status
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_fetcher::ContentType;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use serde_json::json;
+    use sha2::{Digest, Sha256};
+
+    fn proof(data: &[u8]) -> FetchedProof {
+        FetchedProof {
+            content_hash: hex::encode(Sha256::digest(data)),
+            size: data.len() as u64,
+            content_type: ContentType::sniff(data),
+            data: data.to_vec(),
+        }
+    }
+
+    fn task(proof_hash: Option<String>, metadata: Option<Value>) -> Task {
+        let mut task = Task::new(
+            "Task".to_string(),
+            Some("Description".to_string()),
+            1000,
+            "employer".to_string(),
+            None,
+        );
+        task.proof_hash = proof_hash;
+        task.metadata = metadata;
+        task
+    }
+
+    #[tokio::test]
+    async fn test_content_verifiers() {
+        let report = proof(br#"{"tests": 42, "failures": 0, "status": "passed"}"#);
+        let url = "https://example.com/report.json";
+
+        // Hashes: declared only, declared and expected, mismatching expected
+        let hash_match = HashMatchVerifier;
+        let declared = task(Some(report.content_hash.to_uppercase()), None);
+        assert!(
+            hash_match
+                .verify(&declared, url, &report)
+                .await
+                .unwrap()
+                .approved
+        );
+        let expected = task(
+            Some(report.content_hash.clone()),
+            Some(json!({ "expected_proof_hash": report.content_hash })),
+        );
+        assert!(
+            hash_match
+                .verify(&expected, url, &report)
+                .await
+                .unwrap()
+                .approved
+        );
+        let wrong = task(
+            Some(report.content_hash.clone()),
+            Some(json!({ "expected_proof_hash": "00".repeat(32) })),
+        );
+        let result = hash_match.verify(&wrong, url, &report).await.unwrap();
+        assert!(!result.approved);
+        assert_eq!(result.score, 0);
+        let undeclared = hash_match.verify(&task(None, None), url, &report).await;
+        assert!(!undeclared.unwrap().approved);
+
+        let any = task(None, None);
+        let regex = RegexVerifier::new(r#""failures": 0\b"#).unwrap();
+        assert!(regex.verify(&any, url, &report).await.unwrap().approved);
+        assert!(
+            !regex
+                .verify(&any, url, &proof(b"1 failure"))
+                .await
+                .unwrap()
+                .approved
+        );
+        assert!(
+            !regex
+                .verify(&any, url, &proof(b"\x89PNG\r\n\x1a\n"))
+                .await
+                .unwrap()
+                .approved
+        );
+        assert!(RegexVerifier::new("(unclosed").is_err());
+
+        let schema = JsonSchemaVerifier::new(json!({
+            "type": "object",
+            "required": ["tests", "status"],
+            "properties": {
+                "tests": { "type": "integer", "minimum": 1 },
+                "failures": { "const": 0 },
+                "status": { "enum": ["passed"] },
+            },
+        }));
+        let result = schema.verify(&any, url, &report).await.unwrap();
+        assert!(result.approved, "{}", result.feedback);
+        let failing = proof(br#"{"tests": 0, "failures": 3}"#);
+        let result = schema.verify(&any, url, &failing).await.unwrap();
+        assert!(!result.approved);
+        let notes = result.verifier_notes.unwrap();
+        for violation in [
+            "$.status is required",
+            "$.tests must be at least 1",
+            "$.failures must be 0",
+        ] {
+            assert!(notes.contains(violation), "{}", notes);
+        }
+        assert!(
+            !schema
+                .verify(&any, url, &proof(b"not json"))
+                .await
+                .unwrap()
+                .approved
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_status_verifier() {
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/deployed" => MockResponse::bytes(200, "text/html", "<h1>Live</h1>"),
+            "/moved" => {
+                MockResponse::bytes(301, "text/plain", "").with_header("location", "/deployed")
+            }
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let config = ProofVerifierConfig::HttpStatus {
+            expected_status: 200,
+        };
+        let verifier = config
+            .build(&ProofFetcherConfig {
+                allow_private_hosts: true,
+                ..ProofFetcherConfig::default()
+            })
+            .unwrap();
+
+        // The status is what counts, not the content
+        let any = task(None, None);
+        let page = proof(b"<h1>Live</h1>");
+        for (path, approved) in [("/deployed", true), ("/moved", true), ("/gone", false)] {
+            let result = verifier
+                .verify(&any, &format!("{}{}", server.url(), path), &page)
+                .await
+                .unwrap();
+            assert_eq!(result.approved, approved, "{}", path);
+            assert_eq!(result.verification_method, "http_status");
+        }
+
+        // The fetcher's private-address guard applies to status checks too
+        let guarded = config.build(&ProofFetcherConfig::default()).unwrap();
+        let url = format!("{}/deployed", server.url());
+        assert!(guarded.verify(&any, &url, &page).await.is_err());
+    }
+}
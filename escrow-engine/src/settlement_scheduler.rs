@@ -0,0 +1,176 @@
+//! Settlement Scheduler - Batches settlement of verified tasks
+//!
+//! Instead of settling each verified task synchronously, tasks can be queued
+//! and settled on a fixed cadence. Queued settlements are grouped by payout
+//! destination so that several tasks paying the same worker are released
+//! individually (each hold invoice settles atomically) but paid out in a
+//! single aggregated payment. Single-use BOLT11 invoices are never grouped.
+
+use crate::{models::InvoiceSettlementData, network};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Configuration for the settlement scheduler
+#[derive(Debug, Clone)]
+pub struct SettlementSchedulerConfig {
+    /// Queue verified tasks instead of settling them immediately
+    pub enabled: bool,
+    /// Seconds between settlement runs
+    pub interval_secs: u64,
+    /// Maximum number of tasks aggregated into one payout
+    pub max_batch_size: usize,
+}
+
+impl Default for SettlementSchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 300, // 5 minutes
+            max_batch_size: 50,
+        }
+    }
+}
+
+/// A verified task waiting for settlement
+#[derive(Debug, Clone)]
+pub struct PendingSettlement {
+    pub task_id: Uuid,
+    pub funding_id: Uuid,
+    pub hold_invoice_id: String,
+    pub destination: String,
+    pub queued_at: DateTime<Utc>,
+    /// Set once the hold invoice has been released, so a failed payout is
+    /// retried without settling the invoice twice
+    pub released: Option<InvoiceSettlementData>,
+    /// Batch and payout ids once paid out, so a failed completion is retried
+    /// without paying twice
+    pub paid: Option<(Uuid, String)>,
+}
+
+/// Group of pending settlements sharing a payout destination
+#[derive(Debug, Clone)]
+pub struct SettlementBatch {
+    pub id: Uuid,
+    pub destination: String,
+    pub settlements: Vec<PendingSettlement>,
+}
+
+/// Outcome of processing a settlement batch
+#[derive(Debug, Clone)]
+pub struct SettlementBatchResult {
+    pub batch_id: Uuid,
+    pub destination: String,
+    pub paid_task_ids: Vec<Uuid>,
+    pub total_paid_sats: u64,
+    pub payout_id: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Queue of verified tasks awaiting batched settlement
+pub struct SettlementScheduler {
+    config: SettlementSchedulerConfig,
+    /// In-memory queue (in production, this would be a database table)
+    queue: RwLock<Vec<PendingSettlement>>,
+}
+
+impl SettlementScheduler {
+    /// Create a new settlement scheduler
+    pub fn new(config: SettlementSchedulerConfig) -> Self {
+        Self {
+            config,
+            queue: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Whether verified tasks should be queued rather than settled inline
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Seconds between settlement runs
+    pub fn interval_secs(&self) -> u64 {
+        self.config.interval_secs
+    }
+
+    /// Queue a settlement (idempotent per task)
+    pub async fn enqueue(&self, settlement: PendingSettlement) -> bool {
+        let mut queue = self.queue.write().await;
+        if queue.iter().any(|p| p.task_id == settlement.task_id) {
+            return false;
+        }
+        queue.push(settlement);
+        true
+    }
+
+    /// Put settlements back on the queue after a failed run
+    pub async fn requeue(&self, settlements: Vec<PendingSettlement>) {
+        let mut queue = self.queue.write().await;
+        for settlement in settlements {
+            if !queue.iter().any(|p| p.task_id == settlement.task_id) {
+                queue.push(settlement);
+            }
+        }
+    }
+
+    /// Number of queued settlements
+    pub async fn pending_count(&self) -> usize {
+        self.queue.read().await.len()
+    }
+
+    /// Check whether a task is waiting for settlement
+    pub async fn is_queued(&self, task_id: Uuid) -> bool {
+        self.queue.read().await.iter().any(|p| p.task_id == task_id)
+    }
+
+    /// Drain the queue into batches grouped by payout destination
+    ///
+    /// Settlements keep their queue order within a destination, and groups
+    /// larger than `max_batch_size` are split into several batches. Each
+    /// settlement paying a BOLT11 invoice gets a batch of its own.
+    pub async fn take_batches(&self) -> Vec<SettlementBatch> {
+        let pending: Vec<PendingSettlement> = self.queue.write().await.drain(..).collect();
+
+        let mut batches = Vec::new();
+        let mut order: Vec<String> = Vec::new();
+        let mut groups: HashMap<String, Vec<PendingSettlement>> = HashMap::new();
+        for settlement in pending {
+            if !network::is_reusable_destination(&settlement.destination) {
+                batches.push(SettlementBatch {
+                    id: Uuid::new_v4(),
+                    destination: settlement.destination.clone(),
+                    settlements: vec![settlement],
+                });
+                continue;
+            }
+            if !groups.contains_key(&settlement.destination) {
+                order.push(settlement.destination.clone());
+            }
+            groups
+                .entry(settlement.destination.clone())
+                .or_default()
+                .push(settlement);
+        }
+
+        let max_batch_size = self.config.max_batch_size.max(1);
+        for destination in order {
+            let settlements = groups.remove(&destination).unwrap_or_default();
+            for chunk in settlements.chunks(max_batch_size) {
+                batches.push(SettlementBatch {
+                    id: Uuid::new_v4(),
+                    destination: destination.clone(),
+                    settlements: chunk.to_vec(),
+                });
+            }
+        }
+
+        batches
+    }
+}
+
+impl Default for SettlementScheduler {
+    fn default() -> Self {
+        Self::new(SettlementSchedulerConfig::default())
+    }
+}
@@ -0,0 +1,361 @@
+//! Signed Actions - Canonical messages authorising task actions
+//!
+//! Every action a party takes on a task (funding, claiming, submitting
+//! proof, verifying or disputing, cancelling, reading its archived proof)
+//! carries a BIP-340 signature over the SHA-256 hash of a canonical message
+//! binding the task, the action and its parameters to a nonce and a
+//! timestamp. Like a NIP-01 event
+//! serialisation, the message is a compact JSON array:
+//!
+//! `["escrow-action",1,"<action>","<task id>",{<params by key>},"<nonce>",<unix time>]`
+//!
+//! Clients build the same message with `ActionMessage` and sign it with
+//! `ActionMessage::sign`. The escrow rejects stale timestamps and nonces it
+//! has already seen from the signer.
+
+use crate::{
+    EscrowResult, error::EscrowError, models::FundingMode, payment_coordinator::EscrowParties,
+    verification_service,
+};
+use chrono::Utc;
+use secp256k1::{Keypair, Message, Secp256k1, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Version of the canonical message format
+pub const ACTION_MESSAGE_VERSION: u32 = 1;
+
+/// First element of every canonical message
+const ACTION_MESSAGE_TAG: &str = "escrow-action";
+
+/// How far a timestamp may run ahead of our clock
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+
+/// Action a party takes on a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskAction {
+    Fund,
+    Claim,
+    SubmitProof,
+    Verify,
+    Dispute,
+    Cancel,
+    RetrieveProof,
+}
+
+impl TaskAction {
+    /// Name of the action in canonical messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskAction::Fund => "fund",
+            TaskAction::Claim => "claim",
+            TaskAction::SubmitProof => "submit_proof",
+            TaskAction::Verify => "verify",
+            TaskAction::Dispute => "dispute",
+            TaskAction::Cancel => "cancel",
+            TaskAction::RetrieveProof => "retrieve_proof",
+        }
+    }
+}
+
+/// Signature authorising an action, sent along with its request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActionAuth {
+    /// Unique per signer; hex of 16 random bytes from `new_nonce`
+    pub nonce: String,
+    /// Unix time the action was signed at
+    pub timestamp: i64,
+    /// BIP-340 signature over the SHA-256 hash of the canonical message
+    pub signature: String,
+}
+
+/// Action on a task with the parameters it is authorised for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActionMessage {
+    pub action: TaskAction,
+    pub task_id: Uuid,
+    pub params: BTreeMap<String, String>,
+}
+
+impl ActionMessage {
+    /// Action without parameters
+    pub fn new(action: TaskAction, task_id: Uuid) -> Self {
+        Self {
+            action,
+            task_id,
+            params: BTreeMap::new(),
+        }
+    }
+
+    /// Bind a parameter of the action
+    pub fn with_param(mut self, key: &str, value: impl ToString) -> Self {
+        self.params.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Fund the task through `mode`, refunding on-chain to `refund_address`
+    /// and locking multisig escrow to `escrow_parties`
+    pub fn fund(
+        task_id: Uuid,
+        mode: FundingMode,
+        refund_address: Option<&str>,
+        escrow_parties: Option<&EscrowParties>,
+    ) -> Self {
+        let mut message =
+            Self::new(TaskAction::Fund, task_id).with_param("mode", format!("{:?}", mode));
+        if let Some(address) = refund_address {
+            message = message.with_param("refund_address", address);
+        }
+        if let Some(parties) = escrow_parties {
+            message = message
+                .with_param("employer_key", parties.employer)
+                .with_param("worker_key", parties.worker);
+        }
+        message
+    }
+
+    /// Claim the task, to be paid out to `payout_destination`
+    pub fn claim(task_id: Uuid, payout_destination: &str) -> Self {
+        Self::new(TaskAction::Claim, task_id).with_param("payout_destination", payout_destination)
+    }
+
+    /// Submit proof of the task's completion
+    pub fn submit_proof(
+        task_id: Uuid,
+        proof_url: &str,
+        proof_hash: &str,
+        nostr_event_id: &str,
+    ) -> Self {
+        Self::new(TaskAction::SubmitProof, task_id)
+            .with_param("proof_url", proof_url)
+            .with_param("proof_hash", proof_hash)
+            .with_param("nostr_event_id", nostr_event_id)
+    }
+
+    /// Approve the task's proof, or reject it and open a dispute
+    pub fn verify(task_id: Uuid, approved: bool, reason: &str) -> Self {
+        let action = if approved {
+            TaskAction::Verify
+        } else {
+            TaskAction::Dispute
+        };
+        Self::new(action, task_id).with_param("reason", reason)
+    }
+
+    /// Cancel the task's funding and refund the employer, on-chain to
+    /// `refund_address` when one is given
+    pub fn cancel(task_id: Uuid, refund_address: Option<&str>) -> Self {
+        let message = Self::new(TaskAction::Cancel, task_id);
+        match refund_address {
+            Some(address) => message.with_param("refund_address", address),
+            None => message,
+        }
+    }
+
+    /// Read the task's archived proof
+    pub fn retrieve_proof(task_id: Uuid) -> Self {
+        Self::new(TaskAction::RetrieveProof, task_id)
+    }
+
+    /// Canonical message to sign for `nonce` and `timestamp`
+    pub fn canonical(&self, nonce: &str, timestamp: i64) -> String {
+        serde_json::json!([
+            ACTION_MESSAGE_TAG,
+            ACTION_MESSAGE_VERSION,
+            self.action.as_str(),
+            self.task_id.to_string(),
+            self.params,
+            nonce,
+            timestamp,
+        ])
+        .to_string()
+    }
+
+    /// Sign the action now with a fresh nonce
+    pub fn sign(&self, secret_key: &SecretKey) -> EscrowResult<ActionAuth> {
+        Ok(self.sign_with(secret_key, new_nonce()?, Utc::now().timestamp()))
+    }
+
+    /// Sign the action for a given nonce and timestamp
+    pub fn sign_with(&self, secret_key: &SecretKey, nonce: String, timestamp: i64) -> ActionAuth {
+        let secp = Secp256k1::new();
+        let digest: [u8; 32] = Sha256::digest(self.canonical(&nonce, timestamp).as_bytes()).into();
+        let signature = secp.sign_schnorr_no_aux_rand(
+            &Message::from_digest(digest),
+            &Keypair::from_secret_key(&secp, secret_key),
+        );
+        ActionAuth {
+            nonce,
+            timestamp,
+            signature: signature.to_string(),
+        }
+    }
+}
+
+/// Random nonce for a new action
+pub fn new_nonce() -> EscrowResult<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| EscrowError::crypto(format!("Failed to generate nonce: {}", e)))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Nonces of recently authorised actions, per signer
+///
+/// Nonces are only kept as long as their timestamp is fresh; older actions
+/// are refused as stale anyway.
+pub struct NonceRegistry {
+    max_age_secs: i64,
+    seen: RwLock<HashMap<(String, String), i64>>,
+}
+
+impl NonceRegistry {
+    /// Accept actions signed at most `max_age_secs` ago
+    pub fn new(max_age_secs: u64) -> Self {
+        Self {
+            max_age_secs: max_age_secs as i64,
+            seen: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Refuse stale or replayed actions of `pubkey`, recording the nonce
+    pub async fn check_and_record(&self, pubkey: &str, auth: &ActionAuth) -> EscrowResult<()> {
+        let now = Utc::now().timestamp();
+        if auth.timestamp < now - self.max_age_secs {
+            return Err(EscrowError::task_validation(format!(
+                "Action signed at {} is stale",
+                auth.timestamp
+            )));
+        }
+        if auth.timestamp > now + MAX_CLOCK_SKEW_SECS {
+            return Err(EscrowError::task_validation(format!(
+                "Action signed at {} is in the future",
+                auth.timestamp
+            )));
+        }
+        if auth.nonce.trim().is_empty() {
+            return Err(EscrowError::task_validation("Action nonce is required"));
+        }
+
+        // The same key may be written as hex or npub
+        let signer = verification_service::parse_nostr_pubkey(pubkey)
+            .map(|key| key.to_string())
+            .unwrap_or_else(|_| pubkey.to_string());
+
+        let mut seen = self.seen.write().await;
+        seen.retain(|_, timestamp| *timestamp >= now - self.max_age_secs);
+        if seen.contains_key(&(signer.clone(), auth.nonce.clone())) {
+            return Err(EscrowError::task_validation(format!(
+                "Action nonce {} was already used",
+                auth.nonce
+            )));
+        }
+        seen.insert((signer, auth.nonce.clone()), auth.timestamp);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verification_service::{VerificationService, tests::nostr_pubkey};
+
+    #[tokio::test]
+    async fn test_canonical_messages() {
+        let task_id = Uuid::parse_str("6f1c6a2e-3b7d-4c1e-9a55-0d2f1f7c8e90").unwrap();
+        let message = ActionMessage::fund(
+            task_id,
+            FundingMode::OnchainSubmarine,
+            Some("bc1qrefund"),
+            None,
+        );
+        assert_eq!(
+            message.canonical("00ff", 1_700_000_000),
+            r#"["escrow-action",1,"fund","6f1c6a2e-3b7d-4c1e-9a55-0d2f1f7c8e90",{"mode":"OnchainSubmarine","refund_address":"bc1qrefund"},"00ff",1700000000]"#
+        );
+        assert_eq!(
+            ActionMessage::verify(task_id, false, "Blurry").action,
+            TaskAction::Dispute
+        );
+
+        // The signature covers the action, task, parameters, nonce and time
+        let key = SecretKey::from_slice(&[0x81; 32]).unwrap();
+        let pubkey = nostr_pubkey(&key);
+        let service = VerificationService::default();
+        let claim = ActionMessage::claim(task_id, "lnbc1worker");
+        let auth = claim.sign(&key).unwrap();
+        let canonical = claim.canonical(&auth.nonce, auth.timestamp);
+        service
+            .verify_signature(&auth.signature, &canonical, &pubkey)
+            .await
+            .unwrap();
+        for canonical in [
+            ActionMessage::claim(task_id, "lnbc1attacker").canonical(&auth.nonce, auth.timestamp),
+            ActionMessage::claim(Uuid::new_v4(), "lnbc1worker")
+                .canonical(&auth.nonce, auth.timestamp),
+            ActionMessage::cancel(task_id, None).canonical(&auth.nonce, auth.timestamp),
+            claim.canonical("00", auth.timestamp),
+            claim.canonical(&auth.nonce, auth.timestamp + 1),
+        ] {
+            assert!(
+                service
+                    .verify_signature(&auth.signature, &canonical, &pubkey)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replays_and_stale_actions() {
+        let registry = NonceRegistry::new(300);
+        let key = SecretKey::from_slice(&[0x82; 32]).unwrap();
+        let pubkey = nostr_pubkey(&key);
+        let now = Utc::now().timestamp();
+        let auth = |nonce: &str, timestamp: i64| ActionAuth {
+            nonce: nonce.to_string(),
+            timestamp,
+            signature: String::new(),
+        };
+
+        registry
+            .check_and_record(&pubkey, &auth("01", now))
+            .await
+            .unwrap();
+        assert!(
+            registry
+                .check_and_record(&pubkey, &auth("01", now))
+                .await
+                .is_err()
+        );
+        // Nonces are per signer
+        registry
+            .check_and_record("other", &auth("01", now))
+            .await
+            .unwrap();
+
+        assert!(
+            registry
+                .check_and_record(&pubkey, &auth("02", now - 301))
+                .await
+                .is_err()
+        );
+        assert!(
+            registry
+                .check_and_record(&pubkey, &auth("03", now + 120))
+                .await
+                .is_err()
+        );
+        assert!(
+            registry
+                .check_and_record(&pubkey, &auth("", now))
+                .await
+                .is_err()
+        );
+    }
+}
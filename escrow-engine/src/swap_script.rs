@@ -0,0 +1,627 @@
+//! Swap Script - Boltz taproot swap scripts and the transactions spending them
+//!
+//! Boltz swap lockups are taproot outputs. The key path is a MuSig2
+//! aggregate of Boltz's key and ours, so either side can be settled
+//! cooperatively. The script paths let each party act alone:
+//! - claim leaf: `SIZE 32 EQUALVERIFY HASH160 <hash> EQUALVERIFY <claim key> CHECKSIG`,
+//!   spendable by the claim key with the swap preimage
+//! - refund leaf: `<refund key> CHECKSIGVERIFY <timeout> CLTV`, spendable by
+//!   the refund key once the swap's timeout block height is reached
+//!
+//! For submarine swaps (funding) we hold the refund key; for reverse swaps
+//! (on-chain payouts) we hold the claim key and the preimage.
+
+use crate::{EscrowResult, boltz::SwapTree, error::EscrowError, musig::KeyAggContext};
+use bitcoin::{
+    Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+    absolute::LockTime,
+    hashes::{Hash, ripemd160},
+    opcodes::all::{OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_CLTV, OP_EQUALVERIFY, OP_HASH160, OP_SIZE},
+    script::Builder,
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot::{LeafVersion, TapLeafHash, TaprootBuilder, TaprootSpendInfo},
+    transaction,
+};
+use secp256k1::{Keypair, Message, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey, schnorr};
+use serde::{Deserialize, Serialize};
+
+/// Outputs below this value are not relayed
+pub(crate) const DUST_LIMIT_SATS: u64 = 546;
+
+/// How a refund spends the lockup output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RefundPath {
+    /// Key-path spend co-signed by Boltz (no timelock)
+    Cooperative,
+    /// Refund-leaf spend, valid from the timeout block height
+    Timeout,
+}
+
+/// Spending condition used by a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendPath {
+    /// MuSig2 key path, signed together with Boltz
+    KeyPath,
+    /// Refund leaf, after the timeout
+    RefundLeaf,
+    /// Claim leaf, revealing the preimage
+    ClaimLeaf([u8; 32]),
+}
+
+impl From<RefundPath> for SpendPath {
+    fn from(path: RefundPath) -> Self {
+        match path {
+            RefundPath::Cooperative => SpendPath::KeyPath,
+            RefundPath::Timeout => SpendPath::RefundLeaf,
+        }
+    }
+}
+
+/// Rebuilt taproot lockup script of a swap
+#[derive(Debug, Clone)]
+pub struct SwapScript {
+    key_agg: KeyAggContext,
+    spend_info: TaprootSpendInfo,
+    claim_leaf: ScriptBuf,
+    refund_leaf: ScriptBuf,
+    timeout_block_height: u32,
+}
+
+impl SwapScript {
+    /// Rebuild a submarine swap lockup, where we hold the refund key
+    ///
+    /// The refund leaf is checked against our own key and the agreed
+    /// timeout, so a swap whose tree we cannot refund from is rejected.
+    pub fn submarine(
+        claim_public_key: &PublicKey,
+        refund_public_key: &PublicKey,
+        tree: &SwapTree,
+        timeout_block_height: u32,
+    ) -> EscrowResult<Self> {
+        let refund_leaf = refund_leaf_script(
+            &refund_public_key.x_only_public_key().0,
+            timeout_block_height,
+        );
+        if hex::encode(refund_leaf.as_bytes()) != tree.refund_leaf.output.to_lowercase() {
+            return Err(EscrowError::payment(
+                "Swap refund leaf does not match our refund key and timeout",
+            ));
+        }
+
+        let claim_leaf = decode_leaf(&tree.claim_leaf.output)?;
+        Self::from_leaves(
+            claim_public_key,
+            refund_public_key,
+            claim_leaf,
+            refund_leaf,
+            timeout_block_height,
+        )
+    }
+
+    /// Rebuild a reverse swap lockup, where we hold the claim key
+    ///
+    /// The claim leaf is checked against our own key and preimage hash, so a
+    /// swap whose tree we cannot claim from is rejected.
+    pub fn reverse(
+        refund_public_key: &PublicKey,
+        claim_public_key: &PublicKey,
+        preimage_hash: &[u8; 32],
+        tree: &SwapTree,
+        timeout_block_height: u32,
+    ) -> EscrowResult<Self> {
+        let claim_leaf = claim_leaf_script(&claim_public_key.x_only_public_key().0, preimage_hash);
+        if hex::encode(claim_leaf.as_bytes()) != tree.claim_leaf.output.to_lowercase() {
+            return Err(EscrowError::payment(
+                "Swap claim leaf does not match our claim key and preimage",
+            ));
+        }
+
+        let refund_leaf = decode_leaf(&tree.refund_leaf.output)?;
+        Self::from_leaves(
+            refund_public_key,
+            claim_public_key,
+            claim_leaf,
+            refund_leaf,
+            timeout_block_height,
+        )
+    }
+
+    fn from_leaves(
+        boltz_public_key: &PublicKey,
+        our_public_key: &PublicKey,
+        claim_leaf: ScriptBuf,
+        refund_leaf: ScriptBuf,
+        timeout_block_height: u32,
+    ) -> EscrowResult<Self> {
+        // Boltz orders the aggregated keys as [boltz, user]
+        let internal = KeyAggContext::new(vec![*boltz_public_key, *our_public_key])?;
+
+        let secp = Secp256k1::verification_only();
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, claim_leaf.clone())
+            .and_then(|builder| builder.add_leaf(1, refund_leaf.clone()))
+            .map_err(|e| EscrowError::payment(format!("Invalid swap tree: {}", e)))?
+            .finalize(&secp, internal.x_only_public_key())
+            .map_err(|_| EscrowError::payment("Incomplete swap tree"))?;
+
+        let key_agg = internal.with_xonly_tweak(spend_info.tap_tweak().to_scalar())?;
+
+        Ok(Self {
+            key_agg,
+            spend_info,
+            claim_leaf,
+            refund_leaf,
+            timeout_block_height,
+        })
+    }
+
+    /// Output script of the lockup
+    pub fn script_pubkey(&self) -> ScriptBuf {
+        ScriptBuf::new_p2tr_tweaked(self.spend_info.output_key())
+    }
+
+    /// Check that the lockup address Boltz handed out commits to this script
+    pub fn verify_address(&self, address: &Address) -> EscrowResult<()> {
+        if address.script_pubkey() != self.script_pubkey() {
+            return Err(EscrowError::payment(
+                "Swap lockup address does not match the swap tree",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Key aggregation context of the (tweaked) key path
+    pub fn key_agg(&self) -> &KeyAggContext {
+        &self.key_agg
+    }
+
+    /// Block height from which the refund leaf can be spent
+    pub fn timeout_block_height(&self) -> u32 {
+        self.timeout_block_height
+    }
+
+    fn leaf(&self, path: SpendPath) -> EscrowResult<&ScriptBuf> {
+        match path {
+            SpendPath::KeyPath => Err(EscrowError::internal("Key-path spends use no leaf")),
+            SpendPath::RefundLeaf => Ok(&self.refund_leaf),
+            SpendPath::ClaimLeaf(_) => Ok(&self.claim_leaf),
+        }
+    }
+
+    fn witness(&self, path: SpendPath, signature: &[u8]) -> EscrowResult<Witness> {
+        if path == SpendPath::KeyPath {
+            return Ok(Witness::from_slice(&[signature]));
+        }
+
+        let leaf = self.leaf(path)?;
+        let control_block = self
+            .spend_info
+            .control_block(&(leaf.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| EscrowError::internal("Leaf missing from swap tree"))?;
+
+        let mut witness = Witness::new();
+        witness.push(signature);
+        if let SpendPath::ClaimLeaf(preimage) = path {
+            witness.push(preimage);
+        }
+        witness.push(leaf.as_bytes());
+        witness.push(control_block.serialize());
+        Ok(witness)
+    }
+}
+
+fn decode_leaf(output: &str) -> EscrowResult<ScriptBuf> {
+    hex::decode(output)
+        .map(ScriptBuf::from_bytes)
+        .map_err(|e| EscrowError::payment(format!("Invalid swap leaf: {}", e)))
+}
+
+/// Claim leaf script: `SIZE 32 EQUALVERIFY HASH160 <hash> EQUALVERIFY <claim key> CHECKSIG`
+pub fn claim_leaf_script(claim_key: &XOnlyPublicKey, preimage_hash: &[u8; 32]) -> ScriptBuf {
+    // HASH160 of the preimage is RIPEMD160 of its SHA256
+    let hash = ripemd160::Hash::hash(preimage_hash);
+    Builder::new()
+        .push_opcode(OP_SIZE)
+        .push_int(32)
+        .push_opcode(OP_EQUALVERIFY)
+        .push_opcode(OP_HASH160)
+        .push_slice(hash.to_byte_array())
+        .push_opcode(OP_EQUALVERIFY)
+        .push_x_only_key(claim_key)
+        .push_opcode(OP_CHECKSIG)
+        .into_script()
+}
+
+/// Refund leaf script: `<refund key> CHECKSIGVERIFY <timeout> CHECKLOCKTIMEVERIFY`
+pub fn refund_leaf_script(refund_key: &XOnlyPublicKey, timeout_block_height: u32) -> ScriptBuf {
+    Builder::new()
+        .push_x_only_key(refund_key)
+        .push_opcode(OP_CHECKSIGVERIFY)
+        .push_int(timeout_block_height as i64)
+        .push_opcode(OP_CLTV)
+        .into_script()
+}
+
+/// Transaction spending a swap lockup, awaiting its signature
+#[derive(Debug, Clone)]
+pub struct UnsignedSwapSpend {
+    path: SpendPath,
+    transaction: Transaction,
+    prevout: TxOut,
+}
+
+impl UnsignedSwapSpend {
+    /// Build a spend of the swap lockup in `lockup_tx` to `destination`
+    ///
+    /// The fee is `fee_rate_sat_vb` times the virtual size of the signed
+    /// transaction for the chosen path.
+    pub fn new(
+        script: &SwapScript,
+        lockup_tx: &Transaction,
+        destination: &Address,
+        fee_rate_sat_vb: u64,
+        path: SpendPath,
+    ) -> EscrowResult<Self> {
+        let script_pubkey = script.script_pubkey();
+        let (vout, prevout) = lockup_tx
+            .output
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.script_pubkey == script_pubkey)
+            .ok_or_else(|| EscrowError::payment("Transaction does not pay to the swap lockup"))?;
+
+        let lock_time = match path {
+            SpendPath::RefundLeaf => LockTime::from_height(script.timeout_block_height)
+                .map_err(|e| EscrowError::payment(format!("Invalid timeout height: {}", e)))?,
+            _ => LockTime::ZERO,
+        };
+
+        let mut transaction = Transaction {
+            version: transaction::Version::TWO,
+            lock_time,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(lockup_tx.txid(), vout as u32),
+                script_sig: ScriptBuf::new(),
+                // Non-final so the timelock is enforced; also signals RBF
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: prevout.value,
+                script_pubkey: destination.script_pubkey(),
+            }],
+        };
+
+        // Size the fee with a placeholder witness of the final shape
+        transaction.input[0].witness = script.witness(path, &[0u8; 64])?;
+        let fee_sats = transaction.vsize() as u64 * fee_rate_sat_vb;
+        transaction.input[0].witness = Witness::new();
+
+        let amount_sats = prevout
+            .value
+            .to_sat()
+            .checked_sub(fee_sats)
+            .filter(|amount| *amount >= DUST_LIMIT_SATS)
+            .ok_or_else(|| {
+                EscrowError::payment(format!(
+                    "Lockup of {} sats cannot cover a {} sat fee",
+                    prevout.value.to_sat(),
+                    fee_sats
+                ))
+            })?;
+        transaction.output[0].value = Amount::from_sat(amount_sats);
+
+        Ok(Self {
+            path,
+            transaction,
+            prevout: prevout.clone(),
+        })
+    }
+
+    /// Spend path of this transaction
+    pub fn path(&self) -> SpendPath {
+        self.path
+    }
+
+    /// The unsigned transaction
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Value of the lockup output being spent
+    pub fn input_sats(&self) -> u64 {
+        self.prevout.value.to_sat()
+    }
+
+    /// BIP-341 key-path sighash (signed cooperatively with Boltz)
+    pub fn key_spend_sighash(&self) -> EscrowResult<[u8; 32]> {
+        SighashCache::new(&self.transaction)
+            .taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(&[&self.prevout]),
+                TapSighashType::Default,
+            )
+            .map(|sighash| sighash.to_byte_array())
+            .map_err(|e| EscrowError::crypto(format!("Failed to compute sighash: {}", e)))
+    }
+
+    /// Attach the aggregated key-path signature
+    pub fn with_key_spend_signature(mut self, signature: &schnorr::Signature) -> Transaction {
+        self.transaction.input[0].witness = Witness::from_slice(&[signature.as_ref()]);
+        self.transaction
+    }
+
+    /// Sign a script-path spend with our key alone
+    pub fn sign_script_path(
+        mut self,
+        script: &SwapScript,
+        secret_key: &SecretKey,
+    ) -> EscrowResult<Transaction> {
+        let leaf_hash = TapLeafHash::from_script(script.leaf(self.path)?, LeafVersion::TapScript);
+        let sighash = SighashCache::new(&self.transaction)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[&self.prevout]),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .map_err(|e| EscrowError::crypto(format!("Failed to compute sighash: {}", e)))?;
+
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        let signature =
+            secp.sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), &keypair);
+
+        self.transaction.input[0].witness = script.witness(self.path, signature.as_ref())?;
+        Ok(self.transaction)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::boltz::SwapTreeLeaf;
+    use bitcoin::Network;
+    use sha2::Digest;
+
+    /// A Boltz-style submarine swap tree (Boltz claims) and its lockup address
+    pub(crate) fn submarine_swap_fixture(
+        boltz_public_key: &PublicKey,
+        refund_public_key: &PublicKey,
+        timeout: u32,
+    ) -> (SwapTree, Address) {
+        swap_fixture(
+            boltz_public_key,
+            refund_public_key,
+            boltz_public_key,
+            refund_public_key,
+            &[0; 32],
+            timeout,
+        )
+    }
+
+    /// A Boltz-style reverse swap tree (Boltz refunds) and its lockup address
+    pub(crate) fn reverse_swap_fixture(
+        claim_public_key: &PublicKey,
+        boltz_public_key: &PublicKey,
+        preimage_hash: &[u8; 32],
+        timeout: u32,
+    ) -> (SwapTree, Address) {
+        swap_fixture(
+            boltz_public_key,
+            claim_public_key,
+            claim_public_key,
+            boltz_public_key,
+            preimage_hash,
+            timeout,
+        )
+    }
+
+    fn swap_fixture(
+        boltz_public_key: &PublicKey,
+        our_public_key: &PublicKey,
+        claim_public_key: &PublicKey,
+        refund_public_key: &PublicKey,
+        preimage_hash: &[u8; 32],
+        timeout: u32,
+    ) -> (SwapTree, Address) {
+        let claim_leaf = claim_leaf_script(&claim_public_key.x_only_public_key().0, preimage_hash);
+        let refund_leaf = refund_leaf_script(&refund_public_key.x_only_public_key().0, timeout);
+        let tree = SwapTree {
+            claim_leaf: SwapTreeLeaf {
+                version: 192,
+                output: hex::encode(claim_leaf.as_bytes()),
+            },
+            refund_leaf: SwapTreeLeaf {
+                version: 192,
+                output: hex::encode(refund_leaf.as_bytes()),
+            },
+        };
+
+        let script = SwapScript::from_leaves(
+            boltz_public_key,
+            our_public_key,
+            claim_leaf,
+            refund_leaf,
+            timeout,
+        )
+        .unwrap();
+        let address = Address::p2tr_tweaked(script.spend_info.output_key(), Network::Regtest);
+        (tree, address)
+    }
+
+    /// A transaction paying `amount_sats` to `address`
+    pub(crate) fn lockup_transaction(address: &Address, amount_sats: u64) -> Transaction {
+        Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(amount_sats),
+                script_pubkey: address.script_pubkey(),
+            }],
+        }
+    }
+
+    fn destination() -> Address {
+        "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Regtest)
+            .unwrap()
+    }
+
+    fn verify_leaf_signature(
+        spend: &Transaction,
+        lockup: &Transaction,
+        leaf: &ScriptBuf,
+        public_key: &PublicKey,
+    ) -> bool {
+        let leaf_hash = TapLeafHash::from_script(leaf, LeafVersion::TapScript);
+        let sighash = SighashCache::new(spend)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[&lockup.output[0]]),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .unwrap();
+        let signature = schnorr::Signature::from_slice(&spend.input[0].witness[0]).unwrap();
+        Secp256k1::verification_only()
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(sighash.to_byte_array()),
+                &public_key.x_only_public_key().0,
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn test_timeout_refund_transaction() {
+        let secp = Secp256k1::new();
+        let claim_key = SecretKey::from_slice(&[0x01; 32]).unwrap();
+        let refund_key = SecretKey::from_slice(&[0x02; 32]).unwrap();
+        let claim_public_key = PublicKey::from_secret_key(&secp, &claim_key);
+        let refund_public_key = PublicKey::from_secret_key(&secp, &refund_key);
+        let (tree, address) =
+            submarine_swap_fixture(&claim_public_key, &refund_public_key, 850_000);
+
+        let script =
+            SwapScript::submarine(&claim_public_key, &refund_public_key, &tree, 850_000).unwrap();
+        script.verify_address(&address).unwrap();
+
+        // A tree with someone else's refund key is rejected
+        assert!(
+            SwapScript::submarine(&claim_public_key, &claim_public_key, &tree, 850_000).is_err()
+        );
+        assert!(
+            SwapScript::submarine(&claim_public_key, &refund_public_key, &tree, 850_001).is_err()
+        );
+
+        let lockup = lockup_transaction(&address, 100_000);
+        let unsigned = UnsignedSwapSpend::new(
+            &script,
+            &lockup,
+            &destination(),
+            2,
+            RefundPath::Timeout.into(),
+        )
+        .unwrap();
+        let refund = unsigned.sign_script_path(&script, &refund_key).unwrap();
+
+        assert_eq!(refund.lock_time, LockTime::from_height(850_000).unwrap());
+        assert_eq!(
+            refund.input[0].previous_output,
+            OutPoint::new(lockup.txid(), 0)
+        );
+        assert_eq!(refund.input[0].witness.len(), 3);
+        let fee = 100_000 - refund.output[0].value.to_sat();
+        assert_eq!(fee, refund.vsize() as u64 * 2);
+
+        // The signature commits to the refund leaf
+        assert!(verify_leaf_signature(
+            &refund,
+            &lockup,
+            &script.refund_leaf,
+            &refund_public_key
+        ));
+
+        // Fees above the lockup value are refused
+        assert!(
+            UnsignedSwapSpend::new(
+                &script,
+                &lockup,
+                &destination(),
+                1_000,
+                SpendPath::RefundLeaf
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_preimage_claim_transaction() {
+        let secp = Secp256k1::new();
+        let claim_key = SecretKey::from_slice(&[0x04; 32]).unwrap();
+        let refund_key = SecretKey::from_slice(&[0x05; 32]).unwrap();
+        let claim_public_key = PublicKey::from_secret_key(&secp, &claim_key);
+        let refund_public_key = PublicKey::from_secret_key(&secp, &refund_key);
+        let preimage = [0x06; 32];
+        let preimage_hash: [u8; 32] = sha2::Sha256::digest(preimage).into();
+        let (tree, address) = reverse_swap_fixture(
+            &claim_public_key,
+            &refund_public_key,
+            &preimage_hash,
+            850_000,
+        );
+
+        let script = SwapScript::reverse(
+            &refund_public_key,
+            &claim_public_key,
+            &preimage_hash,
+            &tree,
+            850_000,
+        )
+        .unwrap();
+        script.verify_address(&address).unwrap();
+
+        // A tree locked to another preimage is rejected
+        assert!(
+            SwapScript::reverse(
+                &refund_public_key,
+                &claim_public_key,
+                &[0x07; 32],
+                &tree,
+                850_000
+            )
+            .is_err()
+        );
+
+        let lockup = lockup_transaction(&address, 50_000);
+        let claim = UnsignedSwapSpend::new(
+            &script,
+            &lockup,
+            &destination(),
+            3,
+            SpendPath::ClaimLeaf(preimage),
+        )
+        .unwrap()
+        .sign_script_path(&script, &claim_key)
+        .unwrap();
+
+        // Claims are not timelocked and reveal the preimage
+        assert_eq!(claim.lock_time, LockTime::ZERO);
+        assert_eq!(claim.input[0].witness.len(), 4);
+        assert_eq!(&claim.input[0].witness[1], preimage.as_slice());
+        assert_eq!(
+            50_000 - claim.output[0].value.to_sat(),
+            claim.vsize() as u64 * 3
+        );
+        assert!(verify_leaf_signature(
+            &claim,
+            &lockup,
+            &script.claim_leaf,
+            &claim_public_key
+        ));
+    }
+}
@@ -0,0 +1,557 @@
+//! Taproot Escrow - escrow outputs with a MuSig2 cooperative key path
+//!
+//! Large on-chain escrows lock funds in a taproot output. The key path is a
+//! MuSig2 aggregate of the employer and worker keys, so a cooperative
+//! release or refund looks like any single-key spend on-chain. Fallbacks
+//! live in script leaves:
+//! - arbitrator + employer: `<arbitrator> CHECKSIGVERIFY <employer> CHECKSIG`
+//! - arbitrator + worker: `<arbitrator> CHECKSIGVERIFY <worker> CHECKSIG`
+//! - timeout refund: `<employer> CHECKSIGVERIFY <delay> CSV`, spendable by
+//!   the employer alone once the funding output is `delay` blocks deep
+
+use crate::{
+    EscrowResult,
+    error::EscrowError,
+    multisig_escrow::{MultisigKeys, MultisigOutcome, settlement_transaction},
+    musig::KeyAggContext,
+    network::Network,
+};
+use bitcoin::{
+    Address, OutPoint, Psbt, ScriptBuf, Sequence, TapSighashType, Transaction, TxOut, Witness,
+    hashes::Hash,
+    opcodes::all::{OP_CHECKSIG, OP_CHECKSIGVERIFY, OP_CSV},
+    script::Builder,
+    sighash::{Prevouts, SighashCache},
+    taproot::{self, LeafVersion, TapLeafHash, TaprootBuilder, TaprootSpendInfo},
+};
+use secp256k1::{Keypair, Message, PublicKey, Secp256k1, SecretKey, XOnlyPublicKey, schnorr};
+
+/// Script leaves of a taproot escrow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscrowLeaf {
+    ArbitratorEmployer,
+    ArbitratorWorker,
+    TimeoutRefund,
+}
+
+impl EscrowLeaf {
+    const ALL: [EscrowLeaf; 3] = [
+        EscrowLeaf::ArbitratorEmployer,
+        EscrowLeaf::ArbitratorWorker,
+        EscrowLeaf::TimeoutRefund,
+    ];
+}
+
+/// A taproot escrow output
+#[derive(Debug, Clone)]
+pub struct TaprootEscrow {
+    keys: MultisigKeys,
+    internal_key: XOnlyPublicKey,
+    /// Employer and worker keys, tweaked for the key path
+    key_agg: KeyAggContext,
+    spend_info: TaprootSpendInfo,
+    refund_delay_blocks: u16,
+    network: Network,
+}
+
+impl TaprootEscrow {
+    /// Build the escrow for the given participants
+    ///
+    /// The employer can reclaim the funds alone `refund_delay_blocks` after
+    /// the funding confirms.
+    pub fn new(
+        keys: MultisigKeys,
+        refund_delay_blocks: u16,
+        network: Network,
+    ) -> EscrowResult<Self> {
+        if refund_delay_blocks == 0 {
+            return Err(EscrowError::config(
+                "Escrow refund delay must be at least one block",
+            ));
+        }
+
+        // Key path signers in a fixed order: [employer, worker]
+        let internal = KeyAggContext::new(vec![keys.employer, keys.worker])?;
+        let internal_key = internal.x_only_public_key();
+
+        // The timeout refund sits highest: it is the most likely fallback
+        let secp = Secp256k1::verification_only();
+        let leaf = |leaf| escrow_leaf_script(&keys, refund_delay_blocks, leaf);
+        let spend_info = TaprootBuilder::new()
+            .add_leaf(1, leaf(EscrowLeaf::TimeoutRefund))
+            .and_then(|builder| builder.add_leaf(2, leaf(EscrowLeaf::ArbitratorEmployer)))
+            .and_then(|builder| builder.add_leaf(2, leaf(EscrowLeaf::ArbitratorWorker)))
+            .map_err(|e| EscrowError::internal(format!("Invalid escrow tree: {}", e)))?
+            .finalize(&secp, internal_key)
+            .map_err(|_| EscrowError::internal("Incomplete escrow tree"))?;
+
+        let key_agg = internal.with_xonly_tweak(spend_info.tap_tweak().to_scalar())?;
+
+        Ok(Self {
+            keys,
+            internal_key,
+            key_agg,
+            spend_info,
+            refund_delay_blocks,
+            network,
+        })
+    }
+
+    /// Participant keys
+    pub fn keys(&self) -> &MultisigKeys {
+        &self.keys
+    }
+
+    /// Escrow address the employer funds
+    pub fn address(&self) -> Address {
+        Address::p2tr_tweaked(self.spend_info.output_key(), self.network)
+    }
+
+    /// Untweaked MuSig2 key of the employer and worker
+    pub fn internal_key(&self) -> XOnlyPublicKey {
+        self.internal_key
+    }
+
+    /// Key aggregation context for signing the key path
+    pub fn key_agg(&self) -> &KeyAggContext {
+        &self.key_agg
+    }
+
+    /// Blocks after funding from which the employer can refund alone
+    pub fn refund_delay_blocks(&self) -> u16 {
+        self.refund_delay_blocks
+    }
+
+    /// Script of one of the escrow's leaves
+    pub fn leaf_script(&self, leaf: EscrowLeaf) -> ScriptBuf {
+        escrow_leaf_script(&self.keys, self.refund_delay_blocks, leaf)
+    }
+
+    /// Find the output of `tx` paying this escrow
+    pub fn find_funding(&self, tx: &Transaction) -> Option<(OutPoint, TxOut)> {
+        let script_pubkey = self.address().script_pubkey();
+        tx.output
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.script_pubkey == script_pubkey)
+            .map(|(vout, output)| (OutPoint::new(tx.txid(), vout as u32), output.clone()))
+    }
+
+    /// Build the unsigned PSBT settling the escrow output with `outcome`
+    ///
+    /// Releases and refunds spend the key path; arbitrated outcomes need the
+    /// arbitrator and one party; timeout refunds wait for the refund delay.
+    pub fn settlement_psbt(
+        &self,
+        funding: OutPoint,
+        funding_output: &TxOut,
+        outcome: &MultisigOutcome,
+        fee_rate_sat_vb: u64,
+    ) -> EscrowResult<Psbt> {
+        let signature = [0u8; 64];
+        let (sequence, placeholder_witness) = match outcome {
+            MultisigOutcome::Release { .. } | MultisigOutcome::Refund { .. } => (
+                Sequence::ENABLE_RBF_NO_LOCKTIME,
+                Witness::from_slice(&[signature]),
+            ),
+            MultisigOutcome::Arbitrated { .. } => (
+                Sequence::ENABLE_RBF_NO_LOCKTIME,
+                self.leaf_witness(EscrowLeaf::ArbitratorWorker, &[&signature, &signature])?,
+            ),
+            MultisigOutcome::TimeoutRefund { .. } => (
+                Sequence::from_height(self.refund_delay_blocks),
+                self.leaf_witness(EscrowLeaf::TimeoutRefund, &[&signature])?,
+            ),
+        };
+        let transaction = settlement_transaction(
+            funding,
+            funding_output,
+            outcome,
+            self.network,
+            sequence,
+            placeholder_witness,
+            fee_rate_sat_vb,
+        )?;
+
+        let mut psbt = Psbt::from_unsigned_tx(transaction)
+            .map_err(|e| EscrowError::internal(format!("Failed to build PSBT: {}", e)))?;
+        let input = &mut psbt.inputs[0];
+        input.witness_utxo = Some(funding_output.clone());
+        input.tap_internal_key = Some(self.internal_key);
+        input.tap_merkle_root = self.spend_info.merkle_root();
+        for leaf in EscrowLeaf::ALL {
+            let script = self.leaf_script(leaf);
+            let control_block = self.control_block(&script)?;
+            input
+                .tap_scripts
+                .insert(control_block, (script, LeafVersion::TapScript));
+        }
+        Ok(psbt)
+    }
+
+    /// BIP-341 key-path sighash the employer and worker sign with MuSig2
+    pub fn key_spend_sighash(&self, psbt: &Psbt) -> EscrowResult<[u8; 32]> {
+        let prevout = prevout(psbt)?;
+        SighashCache::new(&psbt.unsigned_tx)
+            .taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(&[prevout]),
+                TapSighashType::Default,
+            )
+            .map(|sighash| sighash.to_byte_array())
+            .map_err(|e| EscrowError::crypto(format!("Failed to compute sighash: {}", e)))
+    }
+
+    /// Complete a key-path spend with the aggregated MuSig2 signature
+    pub fn finalize_key_path(
+        &self,
+        psbt: Psbt,
+        signature: &schnorr::Signature,
+    ) -> EscrowResult<Transaction> {
+        let message = Message::from_digest(self.key_spend_sighash(&psbt)?);
+        let output_key = self.spend_info.output_key().to_inner();
+        Secp256k1::verification_only()
+            .verify_schnorr(signature, &message, &output_key)
+            .map_err(|_| EscrowError::crypto("Invalid key-path signature"))?;
+
+        let mut transaction = psbt.unsigned_tx;
+        transaction.input[0].witness = Witness::from_slice(&[signature.as_ref()]);
+        Ok(transaction)
+    }
+
+    /// Add a signature by `secret_key` for spending `leaf` to the PSBT
+    pub fn sign_leaf(
+        &self,
+        psbt: &mut Psbt,
+        leaf: EscrowLeaf,
+        secret_key: &SecretKey,
+    ) -> EscrowResult<()> {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        let public_key = keypair.public_key();
+        if !self.leaf_signers(leaf).contains(&public_key) {
+            return Err(EscrowError::crypto("Key cannot sign this escrow leaf"));
+        }
+
+        let leaf_hash = TapLeafHash::from_script(&self.leaf_script(leaf), LeafVersion::TapScript);
+        let message = self.leaf_sighash(psbt, leaf_hash)?;
+        let signature = taproot::Signature {
+            sig: secp.sign_schnorr_no_aux_rand(&message, &keypair),
+            hash_ty: TapSighashType::Default,
+        };
+        psbt.inputs[0]
+            .tap_script_sigs
+            .insert((public_key.x_only_public_key().0, leaf_hash), signature);
+        Ok(())
+    }
+
+    /// Merge signed copies of a script-path `psbt`, check their signatures
+    /// and finalise through the first leaf whose signers all signed
+    pub fn finalize(&self, mut psbt: Psbt, signed: Vec<Psbt>) -> EscrowResult<Transaction> {
+        for other in signed {
+            psbt.combine(other).map_err(|e| {
+                EscrowError::payment(format!("PSBT does not match the settlement: {}", e))
+            })?;
+        }
+        if psbt.inputs.len() != 1 || prevout(&psbt)?.script_pubkey != self.address().script_pubkey()
+        {
+            return Err(EscrowError::payment("PSBT does not spend this escrow"));
+        }
+
+        let secp = Secp256k1::verification_only();
+        for ((x_only, leaf_hash), signature) in &psbt.inputs[0].tap_script_sigs {
+            let message = self.leaf_sighash(&psbt, *leaf_hash)?;
+            if signature.hash_ty != TapSighashType::Default
+                || secp
+                    .verify_schnorr(&signature.sig, &message, x_only)
+                    .is_err()
+            {
+                return Err(EscrowError::crypto(format!(
+                    "Invalid signature by {}",
+                    x_only
+                )));
+            }
+        }
+
+        let timeout = Sequence::from_height(self.refund_delay_blocks);
+        for leaf in EscrowLeaf::ALL {
+            // The timeout leaf only validates with the refund delay in place
+            if leaf == EscrowLeaf::TimeoutRefund && psbt.unsigned_tx.input[0].sequence != timeout {
+                continue;
+            }
+
+            let leaf_hash =
+                TapLeafHash::from_script(&self.leaf_script(leaf), LeafVersion::TapScript);
+            let signatures: Option<Vec<Vec<u8>>> = self
+                .leaf_signers(leaf)
+                .iter()
+                .map(|key| {
+                    psbt.inputs[0]
+                        .tap_script_sigs
+                        .get(&(key.x_only_public_key().0, leaf_hash))
+                        .map(|signature| signature.to_vec())
+                })
+                .collect();
+            if let Some(signatures) = signatures {
+                // The first key in the script checks the top stack element
+                let stack: Vec<&[u8]> = signatures.iter().rev().map(Vec::as_slice).collect();
+                let mut transaction = psbt.unsigned_tx;
+                transaction.input[0].witness = self.leaf_witness(leaf, &stack)?;
+                return Ok(transaction);
+            }
+        }
+
+        Err(EscrowError::payment(
+            "No escrow leaf has all of its signatures",
+        ))
+    }
+
+    /// Keys signing a leaf, in script order
+    fn leaf_signers(&self, leaf: EscrowLeaf) -> Vec<PublicKey> {
+        match leaf {
+            EscrowLeaf::ArbitratorEmployer => vec![self.keys.arbitrator, self.keys.employer],
+            EscrowLeaf::ArbitratorWorker => vec![self.keys.arbitrator, self.keys.worker],
+            EscrowLeaf::TimeoutRefund => vec![self.keys.employer],
+        }
+    }
+
+    fn control_block(&self, script: &ScriptBuf) -> EscrowResult<taproot::ControlBlock> {
+        self.spend_info
+            .control_block(&(script.clone(), LeafVersion::TapScript))
+            .ok_or_else(|| EscrowError::internal("Leaf missing from escrow tree"))
+    }
+
+    /// Witness spending `leaf` with `signatures` (bottom of the stack first)
+    fn leaf_witness(&self, leaf: EscrowLeaf, signatures: &[&[u8]]) -> EscrowResult<Witness> {
+        let script = self.leaf_script(leaf);
+        let control_block = self.control_block(&script)?;
+
+        let mut witness = Witness::new();
+        for signature in signatures {
+            witness.push(signature);
+        }
+        witness.push(script.as_bytes());
+        witness.push(control_block.serialize());
+        Ok(witness)
+    }
+
+    fn leaf_sighash(&self, psbt: &Psbt, leaf_hash: TapLeafHash) -> EscrowResult<Message> {
+        let prevout = prevout(psbt)?;
+        let sighash = SighashCache::new(&psbt.unsigned_tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[prevout]),
+                leaf_hash,
+                TapSighashType::Default,
+            )
+            .map_err(|e| EscrowError::crypto(format!("Failed to compute sighash: {}", e)))?;
+        Ok(Message::from_digest(sighash.to_byte_array()))
+    }
+}
+
+fn prevout(psbt: &Psbt) -> EscrowResult<&TxOut> {
+    psbt.inputs
+        .first()
+        .and_then(|input| input.witness_utxo.as_ref())
+        .ok_or_else(|| EscrowError::payment("PSBT is missing the escrow output"))
+}
+
+fn escrow_leaf_script(
+    keys: &MultisigKeys,
+    refund_delay_blocks: u16,
+    leaf: EscrowLeaf,
+) -> ScriptBuf {
+    let x_only = |key: &PublicKey| key.x_only_public_key().0;
+    match leaf {
+        EscrowLeaf::ArbitratorEmployer | EscrowLeaf::ArbitratorWorker => {
+            let party = if leaf == EscrowLeaf::ArbitratorEmployer {
+                keys.employer
+            } else {
+                keys.worker
+            };
+            Builder::new()
+                .push_x_only_key(&x_only(&keys.arbitrator))
+                .push_opcode(OP_CHECKSIGVERIFY)
+                .push_x_only_key(&x_only(&party))
+                .push_opcode(OP_CHECKSIG)
+                .into_script()
+        }
+        EscrowLeaf::TimeoutRefund => Builder::new()
+            .push_x_only_key(&x_only(&keys.employer))
+            .push_opcode(OP_CHECKSIGVERIFY)
+            .push_int(refund_delay_blocks as i64)
+            .push_opcode(OP_CSV)
+            .into_script(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::musig::{self, SigningSession};
+    use bitcoin::{Amount, TxIn, absolute::LockTime, transaction};
+
+    const WORKER_ADDRESS: &str = "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080";
+
+    fn key(byte: u8) -> (SecretKey, PublicKey) {
+        let secret_key = SecretKey::from_slice(&[byte; 32]).unwrap();
+        (
+            secret_key,
+            PublicKey::from_secret_key(&Secp256k1::new(), &secret_key),
+        )
+    }
+
+    fn funded_escrow() -> (TaprootEscrow, [SecretKey; 3], OutPoint, TxOut) {
+        let (employer_key, employer) = key(0x31);
+        let (worker_key, worker) = key(0x32);
+        let (arbitrator_key, arbitrator) = key(0x33);
+        let escrow = TaprootEscrow::new(
+            MultisigKeys {
+                employer,
+                worker,
+                arbitrator,
+            },
+            144,
+            Network::Regtest,
+        )
+        .unwrap();
+
+        let funding = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: Amount::from_sat(2_000_000),
+                script_pubkey: escrow.address().script_pubkey(),
+            }],
+        };
+        let (outpoint, output) = escrow.find_funding(&funding).unwrap();
+        (
+            escrow,
+            [employer_key, worker_key, arbitrator_key],
+            outpoint,
+            output,
+        )
+    }
+
+    fn employer_address(escrow: &TaprootEscrow) -> String {
+        Address::p2wpkh(
+            &bitcoin::PublicKey::new(escrow.keys().employer),
+            Network::Regtest,
+        )
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn test_cooperative_key_path_release() {
+        let (escrow, [employer_key, worker_key, arbitrator_key], outpoint, output) =
+            funded_escrow();
+        assert!(escrow.address().to_string().starts_with("bcrt1p"));
+
+        let outcome = MultisigOutcome::Release {
+            worker_address: WORKER_ADDRESS.to_string(),
+        };
+        let psbt = escrow
+            .settlement_psbt(outpoint, &output, &outcome, 2)
+            .unwrap();
+        let sighash = escrow.key_spend_sighash(&psbt).unwrap();
+
+        // Employer and worker sign with MuSig2
+        let (employer_secnonce, employer_nonce) = musig::generate_nonce().unwrap();
+        let (worker_secnonce, worker_nonce) = musig::generate_nonce().unwrap();
+        let aggregated = musig::aggregate_nonces(&[employer_nonce, worker_nonce]).unwrap();
+        let session = SigningSession::new(escrow.key_agg(), &aggregated, sighash).unwrap();
+        let employer_partial = session
+            .partial_sign(employer_secnonce, &employer_key)
+            .unwrap();
+        // The arbitrator is not part of the key path
+        let (arbitrator_secnonce, _) = musig::generate_nonce().unwrap();
+        assert!(
+            session
+                .partial_sign(arbitrator_secnonce, &arbitrator_key)
+                .is_err()
+        );
+        let worker_partial = session.partial_sign(worker_secnonce, &worker_key).unwrap();
+        let signature = session
+            .aggregate(&[employer_partial, worker_partial])
+            .unwrap();
+
+        let transaction = escrow.finalize_key_path(psbt, &signature).unwrap();
+        // Indistinguishable from a single-key spend
+        assert_eq!(transaction.input[0].witness.len(), 1);
+        assert_eq!(
+            2_000_000 - transaction.output[0].value.to_sat(),
+            transaction.vsize() as u64 * 2
+        );
+    }
+
+    #[test]
+    fn test_script_path_fallbacks() {
+        let (escrow, [employer_key, worker_key, arbitrator_key], outpoint, output) =
+            funded_escrow();
+
+        // Arbitrator and worker settle a dispute
+        let outcome = MultisigOutcome::Arbitrated {
+            worker_address: WORKER_ADDRESS.to_string(),
+            worker_sats: 1_500_000,
+            employer_address: employer_address(&escrow),
+        };
+        let psbt = escrow
+            .settlement_psbt(outpoint, &output, &outcome, 2)
+            .unwrap();
+        let mut by_arbitrator = psbt.clone();
+        for leaf in [EscrowLeaf::ArbitratorEmployer, EscrowLeaf::ArbitratorWorker] {
+            escrow
+                .sign_leaf(&mut by_arbitrator, leaf, &arbitrator_key)
+                .unwrap();
+        }
+        // The worker cannot sign for the employer's leaf
+        let mut by_worker = psbt.clone();
+        assert!(
+            escrow
+                .sign_leaf(&mut by_worker, EscrowLeaf::ArbitratorEmployer, &worker_key)
+                .is_err()
+        );
+        assert!(
+            escrow
+                .finalize(psbt.clone(), vec![by_arbitrator.clone()])
+                .is_err()
+        );
+
+        escrow
+            .sign_leaf(&mut by_worker, EscrowLeaf::ArbitratorWorker, &worker_key)
+            .unwrap();
+        let transaction = escrow
+            .finalize(psbt, vec![by_arbitrator, by_worker])
+            .unwrap();
+        let witness = &transaction.input[0].witness;
+        assert_eq!(witness.len(), 4);
+        assert_eq!(
+            witness.nth(2).unwrap(),
+            escrow.leaf_script(EscrowLeaf::ArbitratorWorker).as_bytes()
+        );
+        assert_eq!(transaction.output[0].value.to_sat(), 1_500_000);
+
+        // The employer alone can refund after the delay
+        let outcome = MultisigOutcome::TimeoutRefund {
+            employer_address: employer_address(&escrow),
+        };
+        let mut psbt = escrow
+            .settlement_psbt(outpoint, &output, &outcome, 2)
+            .unwrap();
+        assert_eq!(
+            psbt.unsigned_tx.input[0].sequence,
+            Sequence::from_height(144)
+        );
+        escrow
+            .sign_leaf(&mut psbt, EscrowLeaf::TimeoutRefund, &employer_key)
+            .unwrap();
+        let transaction = escrow.finalize(psbt, Vec::new()).unwrap();
+        assert_eq!(transaction.input[0].witness.len(), 3);
+        assert_eq!(
+            2_000_000 - transaction.output[0].value.to_sat(),
+            transaction.vsize() as u64 * 2
+        );
+    }
+}
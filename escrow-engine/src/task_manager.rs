@@ -6,18 +6,33 @@
 
 use crate::EscrowResult;
 use crate::{
-    engine::EscrowEngine,
+    acceptance_criteria::{AcceptanceCriteria, CriteriaReport},
+    cashu,
+    engine::{EscrowEngine, InvoiceStatusUpdate},
     error::EscrowError,
     models::{
-        Dispute, EscrowEvent, Funding, FundingMode, FundingStatus, Reputation, Task, TaskState,
-        User,
+        Dispute, EscrowEvent, FiatAmount, FiatQuote, Funding, FundingMode, FundingStatus,
+        InvoiceSettlementData, Reputation, Task, TaskState, User,
     },
+    multisig_escrow::MultisigOutcome,
+    network,
     nostr_publisher::NostrPublisher,
+    payment_coordinator::{
+        CashuSettlement, EscrowParties, KeyPathContribution, MultisigSettlement, OnchainPayout,
+        PaymentCoordinator, PaymentRequest, PaymentResponse, PaymentStatusUpdate, PayoutState,
+        SwapRefund, SwapState, SwapStatusChange,
+    },
+    price_oracle::{self, PriceOracle, PriceSourceConfig, RequotePolicy},
+    proof_archive::{ArchivedProof, ProofArchive, RetrievedProof},
+    proof_verifier,
     reputation_indexer::ReputationIndexer,
-    verification_service::VerificationService,
+    settlement_scheduler::{PendingSettlement, SettlementBatchResult, SettlementScheduler},
+    signed_action::{ActionAuth, ActionMessage, NonceRegistry},
+    verification_service::{self, CompletionVerificationResult, VerificationService},
+    webhook_dispatcher::{WebhookDispatcher, WebhookEvent},
 };
 use chrono::{DateTime, Utc};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 use uuid::Uuid;
@@ -33,6 +48,14 @@ pub struct TaskManagerConfig {
     pub require_reputation_check: bool,
     /// Minimum reputation score to create tasks
     pub min_reputation_score: i32,
+    /// Exchange rate sources for fiat-denominated rewards, tried in order
+    pub price_sources: Vec<PriceSourceConfig>,
+    /// Timeout for exchange rate requests in seconds
+    pub price_timeout_secs: u64,
+    /// How fiat tasks are priced again after an invoice expired unpaid
+    pub requote_policy: RequotePolicy,
+    /// Maximum age of a signed action in seconds
+    pub max_action_age_secs: u64,
 }
 
 impl Default for TaskManagerConfig {
@@ -42,6 +65,10 @@ impl Default for TaskManagerConfig {
             max_task_reward_sats: 10_000_000, // 0.1 BTC
             require_reputation_check: false,
             min_reputation_score: 100,
+            price_sources: Vec::new(),
+            price_timeout_secs: 10,
+            requote_policy: RequotePolicy::Requote,
+            max_action_age_secs: 300,
         }
     }
 }
@@ -64,6 +91,50 @@ pub struct TaskManager {
     nostr_publisher: Arc<NostrPublisher>,
     /// Reputation indexer for user scoring
     reputation_indexer: Arc<ReputationIndexer>,
+    /// Webhook dispatcher for marketplace notifications
+    webhook_dispatcher: Arc<WebhookDispatcher>,
+    /// Scheduler for batched settlement of verified tasks
+    settlement_scheduler: Arc<SettlementScheduler>,
+    /// Payment coordinator creating fundings and on-chain payouts
+    payment_coordinator: Option<Arc<PaymentCoordinator>>,
+    /// Released settlements awaiting an on-chain payout (task_id -> payout)
+    pending_payouts: Arc<RwLock<HashMap<Uuid, PendingPayout>>>,
+    /// Exchange rate sources for fiat-denominated rewards
+    price_oracles: RwLock<Vec<Arc<dyn PriceOracle>>>,
+    /// Nonces of recently authorised actions
+    action_nonces: NonceRegistry,
+    /// Archive keeping copies of submitted proofs
+    proof_archive: Option<Arc<ProofArchive>>,
+    /// Automated verification results by task
+    completion_results: RwLock<HashMap<Uuid, CompletionVerificationResult>>,
+}
+
+/// Funds released from a task's funding for its settlement
+#[derive(Debug, Clone)]
+struct ReleasedFunds {
+    amount_sats: u64,
+    released_at: DateTime<Utc>,
+    /// Preimage of the settled hold invoice, if the funds were held in one
+    preimage: Option<String>,
+}
+
+impl From<&InvoiceSettlementData> for ReleasedFunds {
+    fn from(settlement: &InvoiceSettlementData) -> Self {
+        Self {
+            amount_sats: settlement.amount_sats,
+            released_at: settlement.settled_at,
+            preimage: Some(settlement.preimage.clone()),
+        }
+    }
+}
+
+/// Released settlement whose on-chain payout has not confirmed yet
+#[derive(Debug, Clone)]
+struct PendingPayout {
+    funding_id: Uuid,
+    released: ReleasedFunds,
+    /// Swap of the current payout attempt
+    swap_id: Option<String>,
 }
 
 /// Task creation request
@@ -75,6 +146,8 @@ pub struct CreateTaskRequest {
     pub employer_pubkey: String,
     pub deadline: Option<DateTime<Utc>>,
     pub metadata: Option<serde_json::Value>,
+    /// Fiat price of the task; `reward_sats` is ignored when set
+    pub reward_fiat: Option<FiatAmount>,
 }
 
 /// Task funding request
@@ -83,6 +156,12 @@ pub struct FundTaskRequest {
     pub task_id: Uuid,
     pub employer_pubkey: String,
     pub mode: FundingMode,
+    /// Employer's on-chain address for refunds of on-chain fundings
+    pub refund_address: Option<String>,
+    /// Employer and worker keys, for multisig escrow
+    pub escrow_parties: Option<EscrowParties>,
+    /// Employer's signature over `ActionMessage::fund`
+    pub auth: ActionAuth,
 }
 
 /// Task claiming request
@@ -91,6 +170,8 @@ pub struct ClaimTaskRequest {
     pub task_id: Uuid,
     pub worker_pubkey: String,
     pub worker_invoice: String,
+    /// Worker's signature over `ActionMessage::claim`
+    pub auth: ActionAuth,
 }
 
 /// Proof submission request
@@ -100,8 +181,10 @@ pub struct SubmitProofRequest {
     pub worker_pubkey: String,
     pub proof_url: String,
     pub proof_hash: String,
-    pub nostr_event_id: String,
-    pub nostr_signature: String,
+    /// Worker's signed NIP-01 proof event, as JSON
+    pub proof_event: String,
+    /// Worker's signature over `ActionMessage::submit_proof`
+    pub auth: ActionAuth,
 }
 
 /// Task verification request
@@ -111,7 +194,8 @@ pub struct VerifyTaskRequest {
     pub verifier_pubkey: String,
     pub approved: bool,
     pub reason: String,
-    pub signature: String,
+    /// Employer's signature over `ActionMessage::verify`
+    pub auth: ActionAuth,
 }
 
 impl TaskManager {
@@ -122,7 +206,21 @@ impl TaskManager {
         verification_service: Arc<VerificationService>,
         nostr_publisher: Arc<NostrPublisher>,
         reputation_indexer: Arc<ReputationIndexer>,
+        webhook_dispatcher: Arc<WebhookDispatcher>,
+        settlement_scheduler: Arc<SettlementScheduler>,
     ) -> Result<Self, EscrowError> {
+        let price_oracles = config
+            .price_sources
+            .iter()
+            .filter_map(|source| {
+                source
+                    .build(Duration::from_secs(config.price_timeout_secs))
+                    .inspect_err(|e| warn!("Price source unavailable: {}", e))
+                    .ok()
+            })
+            .collect();
+        let action_nonces = NonceRegistry::new(config.max_action_age_secs);
+
         Ok(Self {
             config,
             tasks: Arc::new(RwLock::new(HashMap::new())),
@@ -132,10 +230,74 @@ impl TaskManager {
             verification_service,
             nostr_publisher,
             reputation_indexer,
+            webhook_dispatcher,
+            settlement_scheduler,
+            payment_coordinator: None,
+            pending_payouts: Arc::new(RwLock::new(HashMap::new())),
+            price_oracles: RwLock::new(price_oracles),
+            action_nonces,
+            proof_archive: None,
+            completion_results: RwLock::new(HashMap::new()),
         })
     }
 
+    /// Check that `pubkey` signed `message` and that the action is fresh
+    ///
+    /// The nonce is recorded only once the signature is valid, so forged
+    /// requests cannot use up a party's nonces.
+    pub async fn authorize_action(
+        &self,
+        pubkey: &str,
+        message: &ActionMessage,
+        auth: &ActionAuth,
+    ) -> Result<(), EscrowError> {
+        self.verification_service
+            .verify_signature(
+                &auth.signature,
+                &message.canonical(&auth.nonce, auth.timestamp),
+                pubkey,
+            )
+            .await?;
+        self.action_nonces.check_and_record(pubkey, auth).await
+    }
+
+    /// Fund tasks and pay them out through the given payment coordinator
+    pub fn with_payment_coordinator(
+        mut self,
+        payment_coordinator: Arc<PaymentCoordinator>,
+    ) -> Self {
+        self.payment_coordinator = Some(payment_coordinator);
+        self
+    }
+
+    /// Snapshot every submitted proof into the given archive
+    pub fn with_proof_archive(mut self, proof_archive: Arc<ProofArchive>) -> Self {
+        self.proof_archive = Some(proof_archive);
+        self
+    }
+
+    /// Register an additional exchange rate source
+    pub async fn add_price_oracle(&self, oracle: Arc<dyn PriceOracle>) {
+        info!("Registered price oracle: {}", oracle.name());
+        self.price_oracles.write().await.push(oracle);
+    }
+
+    /// Convert a fiat amount to sats at the current exchange rate
+    pub async fn quote_fiat(&self, amount: &FiatAmount) -> Result<FiatQuote, EscrowError> {
+        let quote = price_oracle::quote(&self.price_oracles.read().await, amount).await?;
+        if quote.amount_sats as i64 > self.config.max_task_reward_sats {
+            return Err(EscrowError::task_validation(format!(
+                "Reward {} ({} sats) exceeds maximum {} sats",
+                amount, quote.amount_sats, self.config.max_task_reward_sats
+            )));
+        }
+        Ok(quote)
+    }
+
     /// Create a new task
+    ///
+    /// Tasks priced in fiat get an indicative `reward_sats` at the current
+    /// rate; the amount is locked when the task is funded.
     pub async fn create_task(&self, request: CreateTaskRequest) -> Result<Task, EscrowError> {
         info!("Creating task: {}", request.title);
 
@@ -157,14 +319,23 @@ impl TaskManager {
             }
         }
 
+        let reward_sats = match &request.reward_fiat {
+            Some(amount) => self.quote_fiat(amount).await?.amount_sats as i64,
+            None => request.reward_sats,
+        };
+
         // Create task
         let mut task = Task::new(
             request.title,
             request.description,
-            request.reward_sats,
+            reward_sats,
             request.employer_pubkey,
             request.deadline,
         );
+        if let Some(amount) = request.reward_fiat {
+            task.currency = amount.currency.clone();
+            task.reward_fiat = Some(amount);
+        }
 
         if let Some(metadata) = request.metadata {
             task.metadata = Some(metadata);
@@ -196,7 +367,8 @@ impl TaskManager {
             None,
             Some(serde_json::json!({
                 "title": task.title,
-                "reward_sats": task.reward_sats
+                "reward_sats": task.reward_sats,
+                "reward_fiat": task.reward_fiat
             })),
         )
         .await?;
@@ -206,319 +378,2139 @@ impl TaskManager {
         Ok(task)
     }
 
-    /// Fund a task with a hold invoice
+    /// Fund a task through the payment coordinator
+    ///
+    /// The requested mode is tried first, then the coordinator's fallbacks;
+    /// the funding records the mode actually used. The task waits in
+    /// `PendingFunding` until the hold invoice is paid, the swap completes or
+    /// the escrow output confirms. Fiat rewards are converted to sats here
+    /// and the rate is kept on the funding; after an expired invoice the
+    /// configured `requote_policy` decides whether the previous rate holds.
     pub async fn fund_task(
         &self,
         request: FundTaskRequest,
-    ) -> Result<crate::models::HoldInvoiceData, EscrowError> {
+    ) -> Result<PaymentResponse, EscrowError> {
+        self.open_funding(request, None).await
+    }
+
+    /// Fund a task, falling back only to the modes in `fallbacks`
+    pub async fn fund_task_with_fallbacks(
+        &self,
+        request: FundTaskRequest,
+        fallbacks: &[FundingMode],
+    ) -> Result<PaymentResponse, EscrowError> {
+        self.open_funding(request, Some(fallbacks)).await
+    }
+
+    async fn open_funding(
+        &self,
+        request: FundTaskRequest,
+        fallbacks: Option<&[FundingMode]>,
+    ) -> Result<PaymentResponse, EscrowError> {
         info!("Funding task: {}", request.task_id);
 
-        // Get task
+        let coordinator = self.payment_coordinator()?;
         let mut task = self.get_task(request.task_id).await?;
 
         // Validate funding request
         self.validate_fund_task_request(&request, &task)?;
-
-        // Transition task state
         task.validate_transition(TaskState::PendingFunding)?;
-        task.state = TaskState::PendingFunding;
-        task.updated_at = Utc::now();
+        self.authorize_action(
+            &request.employer_pubkey,
+            &ActionMessage::fund(
+                task.id,
+                request.mode,
+                request.refund_address.as_deref(),
+                request.escrow_parties.as_ref(),
+            ),
+            &request.auth,
+        )
+        .await?;
+
+        // Lock the sat amount of fiat rewards
+        let fiat_quote = match &task.reward_fiat {
+            Some(amount) => Some(self.lock_fiat_rate(&task, amount).await?),
+            None => None,
+        };
+        if let Some(quote) = &fiat_quote {
+            task.reward_sats = quote.amount_sats as i64;
+        }
+
+        let payment_request = PaymentRequest {
+            task_id: task.id,
+            amount_sats: task.reward_sats as u64,
+            preferred_mode: request.mode,
+            payer_pubkey: request.employer_pubkey.clone(),
+            description: format!("Task: {}", task.title),
+            refund_address: request.refund_address.clone(),
+            escrow_parties: request.escrow_parties,
+        };
+        let payment = match fallbacks {
+            Some(fallbacks) => {
+                coordinator
+                    .create_payment_with_fallbacks(payment_request, fallbacks)
+                    .await?
+            }
+            None => coordinator.create_payment(payment_request).await?,
+        };
+
+        let funding = self
+            .record_funding(task, &request, &payment, fiat_quote)
+            .await?;
+
+        info!(
+            "Funding task {} via {:?} ({})",
+            request.task_id, funding.mode, funding.provider
+        );
 
-        // Create funding record
+        Ok(payment)
+    }
+
+    /// Rate a fiat task's new funding is locked at
+    ///
+    /// The rate of the task's previous (expired) funding is kept when the
+    /// requote policy allows it; otherwise the oracles are asked again.
+    async fn lock_fiat_rate(
+        &self,
+        task: &Task,
+        amount: &FiatAmount,
+    ) -> Result<FiatQuote, EscrowError> {
+        if let Some(funding_id) = task.funding_id
+            && let Ok(previous) = self.get_funding(funding_id).await
+            && let Some(quote) = previous.fiat_quote
+            && quote.amount == *amount
+            && self.config.requote_policy.keeps(&quote)
+        {
+            info!(
+                "Keeping rate {} {}/BTC from {} for task {}",
+                quote.btc_price, amount.currency, quote.source, task.id
+            );
+            return Ok(quote);
+        }
+        self.quote_fiat(amount).await
+    }
+
+    /// Store the funding of a created payment and move its task to `PendingFunding`
+    async fn record_funding(
+        &self,
+        mut task: Task,
+        request: &FundTaskRequest,
+        payment: &PaymentResponse,
+        fiat_quote: Option<FiatQuote>,
+    ) -> Result<Funding, EscrowError> {
         let mut funding = Funding::new(
-            request.task_id,
-            request.mode,
-            "ldk".to_string(), // provider
+            task.id,
+            payment.mode,
+            funding_provider(payment.mode).to_string(),
             task.reward_sats,
-            Some(Utc::now() + chrono::Duration::hours(1)), // expires in 1 hour
+            payment.expires_at,
+        );
+        funding.id = payment.funding_id;
+        funding.invoice = payment.invoice.clone();
+        funding.invoice_hash = payment.invoice_hash.clone();
+        funding.hold_invoice_id = payment.hold_invoice_id.clone();
+        funding.onchain_address = payment.onchain_address.clone();
+        funding.swap_id = payment.swap_id.clone();
+        funding.lockup_script = payment.lockup_script.clone();
+        funding.timeout_block = payment.timeout_block.map(|height| height as i32);
+        funding.refund_address = request.refund_address.clone();
+        funding.fiat_quote = fiat_quote;
+        funding.external_id = payment.swap_id.clone();
+        if payment.swap_id.is_some() {
+            funding.external_metadata = Some(serde_json::json!({
+                "swap": { "state": SwapState::Created, "boltz_status": "swap.created" }
+            }));
+        }
+
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        task.state = TaskState::PendingFunding;
+        task.funding_id = Some(funding.id);
+        task.updated_at = Utc::now();
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        if let Some(swap_id) = &funding.swap_id {
+            self.payment_coordinator()?
+                .track_swap(swap_id, funding.id)
+                .await;
+        }
+
+        let event_type = match funding.mode {
+            FundingMode::LightningHold | FundingMode::LightningStandard => "invoice.created",
+            FundingMode::OnchainSubmarine | FundingMode::OnchainReverse => "swap.created",
+            FundingMode::OnchainMultisig => "escrow.created",
+            FundingMode::Cashu => "cashu.requested",
+        };
+        self.record_payment_event(
+            event_type,
+            &task,
+            &funding,
+            Some(task.reward_sats),
+            Some(serde_json::json!({
+                "invoice": funding.invoice,
+                "onchain_address": funding.onchain_address,
+                "swap_id": funding.swap_id,
+                "attempts": payment.attempts,
+                "fiat_quote": funding.fiat_quote,
+            })),
+        )
+        .await?;
+
+        Ok(funding)
+    }
+
+    /// Record a payment received for a task's hold invoice
+    ///
+    /// Called when LDK reports a claimable HTLC set. Payments below the invoice
+    /// amount are refused and leave the task awaiting funding; accepted payments
+    /// record the exact received amount on the funding and move the task to
+    /// `Funded`.
+    pub async fn process_invoice_payment(
+        &self,
+        invoice_hash: &str,
+        amount_received_sats: u64,
+    ) -> Result<Task, EscrowError> {
+        info!(
+            "Processing payment of {} sats for invoice: {}",
+            amount_received_sats, invoice_hash
+        );
+
+        let funding = self.get_funding_by_invoice_hash(invoice_hash).await?;
+        let task = self.get_task(funding.task_id).await?;
+
+        let update = match self
+            .escrow_engine
+            .handle_payment_claimable(invoice_hash, amount_received_sats)
+            .await
+        {
+            Ok(update) => update,
+            Err(e) => {
+                self.record_payment_event(
+                    "invoice.payment_rejected",
+                    &task,
+                    &funding,
+                    Some(amount_received_sats as i64),
+                    Some(serde_json::json!({
+                        "amount_expected_sats": funding.amount_sats,
+                        "amount_received_sats": amount_received_sats,
+                        "reason": e.to_string()
+                    })),
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+
+        self.apply_accepted_payment(task, funding, &update).await
+    }
+
+    /// Record one HTLC of a multi-part payment for a task's hold invoice
+    ///
+    /// The funding stays `Pending` until the full HTLC set has arrived, then
+    /// the task moves to `Funded` exactly as for a single-part payment.
+    pub async fn process_htlc_part(
+        &self,
+        invoice_hash: &str,
+        htlc_id: &str,
+        amount_sats: u64,
+    ) -> Result<Task, EscrowError> {
+        info!(
+            "Processing HTLC part {} ({} sats) for invoice: {}",
+            htlc_id, amount_sats, invoice_hash
         );
 
-        // Create hold invoice
-        let invoice_data = self
+        let mut funding = self.get_funding_by_invoice_hash(invoice_hash).await?;
+        let task = self.get_task(funding.task_id).await?;
+
+        let update = match self
             .escrow_engine
-            .create_hold_invoice(
-                task.reward_sats as u64,
-                format!("Task: {}", task.title),
-                task.id.to_string(),
+            .receive_htlc_part(invoice_hash, htlc_id, amount_sats)
+            .await
+        {
+            Ok(update) => update,
+            Err(e) => {
+                self.record_payment_event(
+                    "invoice.payment_rejected",
+                    &task,
+                    &funding,
+                    Some(amount_sats as i64),
+                    Some(serde_json::json!({
+                        "amount_expected_sats": funding.amount_sats,
+                        "htlc_id": htlc_id,
+                        "reason": e.to_string()
+                    })),
+                )
+                .await?;
+                return Err(e);
+            }
+        };
+
+        if update.status == FundingStatus::Accepted {
+            return self.apply_accepted_payment(task, funding, &update).await;
+        }
+
+        // Partial set: keep waiting for the remaining parts
+        funding.status = FundingStatus::Pending;
+        funding.updated_at = Utc::now();
+        Self::set_mpp_metadata(&mut funding, &update);
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        Ok(task)
+    }
+
+    /// Fail back incomplete multi-part payments that timed out
+    ///
+    /// Returns the IDs of fundings that were reset to await a new payment.
+    pub async fn expire_incomplete_payments(&self) -> Result<Vec<Uuid>, EscrowError> {
+        let mut reset = Vec::new();
+
+        for update in self.escrow_engine.expire_incomplete_htlc_sets().await {
+            let mut funding = match self.get_funding_by_invoice_hash(&update.invoice_hash).await {
+                Ok(funding) => funding,
+                Err(e) => {
+                    warn!("Timed-out HTLC set has no funding: {}", e);
+                    continue;
+                }
+            };
+            let task = self.get_task(funding.task_id).await?;
+
+            funding.status = FundingStatus::Created;
+            funding.updated_at = Utc::now();
+            if let Some(metadata) = funding.external_metadata.as_mut()
+                && let Some(object) = metadata.as_object_mut()
+            {
+                object.remove("mpp");
+            }
+            self.funding
+                .write()
+                .await
+                .insert(funding.id, funding.clone());
+
+            self.record_payment_event(
+                "invoice.htlcs_timed_out",
+                &task,
+                &funding,
+                update.amount_sats.map(|sats| sats as i64),
+                Some(serde_json::json!({
+                    "part_count": update.part_count,
+                    "amount_failed_sats": update.amount_sats
+                })),
+            )
+            .await?;
+
+            reset.push(funding.id);
+        }
+
+        Ok(reset)
+    }
+
+    /// Return tasks whose funding invoice expired unpaid to `Draft`
+    ///
+    /// The hold invoice is cancelled so a late payment cannot fund the task;
+    /// funding the task again issues a new invoice, priced per the requote
+    /// policy for fiat rewards. Returns the IDs of the expired fundings.
+    pub async fn expire_unpaid_fundings(&self) -> Result<Vec<Uuid>, EscrowError> {
+        let now = Utc::now();
+        let expired: Vec<Funding> = self
+            .funding
+            .read()
+            .await
+            .values()
+            .filter(|funding| {
+                matches!(
+                    funding.mode,
+                    FundingMode::LightningHold | FundingMode::LightningStandard
+                ) && funding.status == FundingStatus::Created
+                    && funding
+                        .expires_at
+                        .is_some_and(|expires_at| expires_at <= now)
+            })
+            .cloned()
+            .collect();
+
+        let mut ids = Vec::new();
+        for mut funding in expired {
+            let mut task = self.get_task(funding.task_id).await?;
+            if task.state != TaskState::PendingFunding || task.funding_id != Some(funding.id) {
+                continue;
+            }
+
+            if let Some(hold_invoice_id) = &funding.hold_invoice_id
+                && let Err(e) = self
+                    .escrow_engine
+                    .cancel_hold_invoice(hold_invoice_id)
+                    .await
+            {
+                warn!(
+                    "Failed to cancel expired invoice {}: {}",
+                    hold_invoice_id, e
+                );
+            }
+
+            funding.status = FundingStatus::Expired;
+            funding.cancelled_at = Some(now);
+            funding.updated_at = now;
+            self.funding
+                .write()
+                .await
+                .insert(funding.id, funding.clone());
+
+            task.validate_transition(TaskState::Draft)?;
+            task.state = TaskState::Draft;
+            task.updated_at = now;
+            self.tasks.write().await.insert(task.id, task.clone());
+
+            self.record_payment_event(
+                "invoice.expired",
+                &task,
+                &funding,
+                Some(funding.amount_sats),
+                Some(serde_json::json!({
+                    "expires_at": funding.expires_at,
+                    "fiat_quote": funding.fiat_quote,
+                })),
             )
             .await?;
 
-        // Update funding record
-        funding.invoice = Some(invoice_data.invoice.clone());
-        funding.invoice_hash = Some(invoice_data.invoice_hash.clone());
-        funding.hold_invoice_id = Some(invoice_data.hold_invoice_id.clone());
-        funding.status = FundingStatus::Created;
-        funding.expires_at = Some(invoice_data.expires_at);
+            info!("Funding invoice of task {} expired unpaid", task.id);
+            ids.push(funding.id);
+        }
+
+        Ok(ids)
+    }
+
+    /// Mark a funding as accepted with the exact received amount and move
+    /// its task to `Funded`
+    async fn apply_accepted_payment(
+        &self,
+        mut task: Task,
+        mut funding: Funding,
+        update: &InvoiceStatusUpdate,
+    ) -> Result<Task, EscrowError> {
+        // Boltz only completes a submarine swap once it learns the preimage;
+        // the reward then waits in the node's balance until settlement
+        if funding.mode == FundingMode::OnchainSubmarine {
+            self.escrow_engine
+                .release_hold_invoice(&hold_invoice_id(&funding)?)
+                .await?;
+        }
 
-        // Store funding
+        let received_sats = update.amount_sats.unwrap_or(0) as i64;
+        let overpayment_sats = (received_sats - funding.amount_sats).max(0);
+
+        // Update funding with the exact received amount
+        funding.status = FundingStatus::Accepted;
+        funding.amount_received_sats = Some(received_sats);
+        funding.payment_received_at = Some(update.timestamp);
+        funding.updated_at = Utc::now();
+        Self::set_mpp_metadata(&mut funding, update);
+        if overpayment_sats > 0 {
+            warn!(
+                "Invoice {} overpaid by {} sats",
+                update.invoice_hash, overpayment_sats
+            );
+            if let Some(metadata) = funding.external_metadata.as_mut() {
+                metadata["overpayment_sats"] = serde_json::json!(overpayment_sats);
+            }
+        }
         self.funding
             .write()
             .await
             .insert(funding.id, funding.clone());
 
-        // Update task with funding reference
-        task.funding_id = Some(funding.id);
+        // Transition task state
+        task.validate_transition(TaskState::Funded)?;
+        task.state = TaskState::Funded;
+        task.updated_at = Utc::now();
         self.tasks.write().await.insert(task.id, task.clone());
 
-        // Create escrow event
-        self.create_escrow_event(
-            "invoice.created".to_string(),
-            Some(request.task_id),
-            Some(funding.id),
-            Some(invoice_data.invoice_hash.clone()),
-            Some(request.employer_pubkey),
-            Some(format!("{:?}", funding.status)),
+        self.record_payment_event(
+            "invoice.accepted",
+            &task,
+            &funding,
+            Some(received_sats),
             Some(serde_json::json!({
-                "amount_sats": task.reward_sats,
-                "invoice": invoice_data.invoice
+                "amount_expected_sats": funding.amount_sats,
+                "amount_received_sats": received_sats,
+                "overpayment_sats": overpayment_sats,
+                "part_count": update.part_count
             })),
         )
         .await?;
 
         info!(
-            "Funded task: {} with invoice: {}",
-            request.task_id, invoice_data.invoice_hash
+            "Task {} funded with {} sats in {} part(s)",
+            task.id, received_sats, update.part_count
         );
 
-        Ok(invoice_data)
+        Ok(task)
     }
 
-    /// Claim a task for work
-    pub async fn claim_task(&self, request: ClaimTaskRequest) -> Result<Task, EscrowError> {
-        info!("Claiming task: {}", request.task_id);
+    /// Record the HTLC part count and running total on a funding
+    fn set_mpp_metadata(funding: &mut Funding, update: &InvoiceStatusUpdate) {
+        let metadata = funding
+            .external_metadata
+            .get_or_insert_with(|| serde_json::json!({}));
+        metadata["mpp"] = serde_json::json!({
+            "part_count": update.part_count,
+            "total_sats": update.amount_sats.unwrap_or(0)
+        });
+    }
 
-        // Get task
-        let mut task = self.get_task(request.task_id).await?;
+    /// Apply a swap state change to its funding and task
+    ///
+    /// A completed submarine swap funds the task, as does the confirmed
+    /// lockup of a reverse swap (which is ours to claim from then on); a
+    /// failed, expired or refunded swap returns a task still awaiting funding
+    /// to `Draft` so it can be funded again.
+    pub async fn apply_swap_status(&self, change: &SwapStatusChange) -> Result<Task, EscrowError> {
+        if let Some(payout) = self.find_payout(&change.swap_id).await {
+            return self.apply_payout_status(payout, change).await;
+        }
 
-        // Validate claim request
-        self.validate_claim_task_request(&request, &task)?;
+        let mut funding = self.get_funding_by_swap_id(&change.swap_id).await?;
+        let mut task = self.get_task(funding.task_id).await?;
 
-        // Transition task state
-        task.validate_transition(TaskState::Claimed)?;
-        task.state = TaskState::Claimed;
-        task.worker_pubkey = Some(request.worker_pubkey.clone());
-        task.claimed_at = Some(Utc::now());
+        funding.status = match change.state {
+            SwapState::Created => FundingStatus::Created,
+            SwapState::Mempool | SwapState::Confirmed => FundingStatus::Pending,
+            SwapState::InvoicePaid => FundingStatus::Accepted,
+            SwapState::Failed => FundingStatus::Failed,
+            SwapState::Expired => FundingStatus::Expired,
+            SwapState::Refunded => FundingStatus::Cancelled,
+        };
+        funding.updated_at = Utc::now();
+
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["swap"] = serde_json::json!({
+            "state": change.state,
+            "boltz_status": change.boltz_status,
+            "transaction_id": change.transaction_id,
+            "failure_reason": change.failure_reason,
+            "updated_at": change.changed_at,
+        });
+        funding.external_metadata = Some(metadata);
+
+        let funded_state = if funding.mode == FundingMode::OnchainReverse {
+            SwapState::Confirmed
+        } else {
+            SwapState::InvoicePaid
+        };
+        match change.state {
+            state if state == funded_state && task.state == TaskState::PendingFunding => {
+                task.validate_transition(TaskState::Funded)?;
+                task.state = TaskState::Funded;
+                funding.status = FundingStatus::Accepted;
+                funding.amount_received_sats = Some(funding.amount_sats);
+                funding.payment_received_at = Some(Utc::now());
+            }
+            SwapState::Failed | SwapState::Expired | SwapState::Refunded
+                if task.state == TaskState::PendingFunding =>
+            {
+                task.validate_transition(TaskState::Draft)?;
+                task.state = TaskState::Draft;
+                funding.cancelled_at = Some(Utc::now());
+            }
+            _ => {}
+        }
         task.updated_at = Utc::now();
 
-        // Store updated task
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
         self.tasks.write().await.insert(task.id, task.clone());
 
-        // Update reputation (task claimed)
-        self.reputation_indexer
-            .update_reputation(&request.worker_pubkey, |rep| {
-                rep.tasks_claimed += 1;
-                rep.last_active_at = Utc::now();
-            })
-            .await?;
+        let event_type = match change.state {
+            SwapState::Created => "swap.created",
+            SwapState::Mempool => "swap.mempool",
+            SwapState::Confirmed => "swap.confirmed",
+            SwapState::InvoicePaid => "swap.invoice_paid",
+            SwapState::Failed => "swap.failed",
+            SwapState::Expired => "swap.expired",
+            SwapState::Refunded => "swap.refunded",
+        };
+        self.record_payment_event(
+            event_type,
+            &task,
+            &funding,
+            funding.amount_received_sats,
+            Some(serde_json::json!({
+                "swap_id": change.swap_id,
+                "boltz_status": change.boltz_status,
+                "transaction_id": change.transaction_id,
+                "failure_reason": change.failure_reason,
+            })),
+        )
+        .await?;
 
-        // Publish Nostr event
-        self.nostr_publisher
-            .publish_task_claimed(task.clone())
-            .await?;
+        Ok(task)
+    }
 
-        // Create escrow event
-        self.create_escrow_event(
-            "task.claimed".to_string(),
-            Some(request.task_id),
-            task.funding_id,
-            None,
-            Some(request.worker_pubkey),
-            None,
-            None,
+    /// Record the on-chain refund of a swap that never funded its task
+    ///
+    /// The funding is cancelled and a task still awaiting funding returns to
+    /// `Draft`.
+    pub async fn record_swap_refund(&self, refund: &SwapRefund) -> Result<Task, EscrowError> {
+        let mut funding = self.get_funding_by_swap_id(&refund.swap_id).await?;
+        let mut task = self.get_task(funding.task_id).await?;
+
+        funding.status = FundingStatus::Cancelled;
+        funding.cancelled_at = Some(Utc::now());
+        funding.updated_at = Utc::now();
+
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["refund"] = serde_json::json!({
+            "path": refund.path,
+            "txid": refund.txid,
+            "amount_sats": refund.amount_sats,
+            "fee_sats": refund.fee_sats,
+            "broadcast": refund.broadcast,
+            "spendable_at_height": refund.spendable_at_height,
+        });
+        funding.external_metadata = Some(metadata);
+
+        if task.state == TaskState::PendingFunding {
+            task.validate_transition(TaskState::Draft)?;
+            task.state = TaskState::Draft;
+            task.updated_at = Utc::now();
+        }
+
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        let event_type = if refund.broadcast {
+            "swap.refund_broadcast"
+        } else {
+            "swap.refund_signed"
+        };
+        self.record_payment_event(
+            event_type,
+            &task,
+            &funding,
+            Some(refund.amount_sats as i64),
+            Some(serde_json::json!({
+                "swap_id": refund.swap_id,
+                "path": refund.path,
+                "txid": refund.txid,
+                "spendable_at_height": refund.spendable_at_height,
+            })),
         )
         .await?;
 
-        info!("Claimed task: {}", request.task_id);
+        info!(
+            "Recorded {:?} refund {} of swap {} for task {}",
+            refund.path, refund.txid, refund.swap_id, task.id
+        );
 
         Ok(task)
     }
 
-    /// Submit proof of work completion
-    pub async fn submit_proof(&self, request: SubmitProofRequest) -> Result<Task, EscrowError> {
-        info!("Submitting proof for task: {}", request.task_id);
+    /// Refund a funded but unclaimed task to the employer
+    ///
+    /// Hold invoices are cancelled, failing the payment back to the payer.
+    /// Completed submarine swaps are paid back from the node's balance and
+    /// reverse swap lockups claimed, both to the funding's refund address.
+    /// A multisig escrow gets a refund PSBT for the parties to sign; the
+    /// task stays `Funded` until it is finalised.
+    pub async fn refund_task(
+        &self,
+        task_id: Uuid,
+        employer_pubkey: &str,
+        auth: &ActionAuth,
+    ) -> Result<Task, EscrowError> {
+        info!("Refunding task: {}", task_id);
 
-        // Get task
-        let mut task = self.get_task(request.task_id).await?;
+        let task = self.get_task(task_id).await?;
 
-        // Validate proof submission
-        self.validate_proof_submission(&request, &task)?;
+        if !verification_service::same_pubkey(&task.employer_pubkey, employer_pubkey) {
+            return Err(EscrowError::task_validation(
+                "Only task creator can refund task",
+            ));
+        }
+
+        // Disputed tasks are refunded only through arbitration
+        if task.state != TaskState::Funded {
+            return Err(EscrowError::task_validation(format!(
+                "Only funded, unclaimed tasks can be refunded (task is {:?})",
+                task.state
+            )));
+        }
+        self.authorize_action(employer_pubkey, &ActionMessage::cancel(task.id, None), auth)
+            .await?;
+
+        let funding = self.task_funding(&task).await?;
+        let received_sats = received_sats(&funding);
+
+        let (refunded_sats, event_type, metadata) = match funding.mode {
+            FundingMode::LightningHold | FundingMode::LightningStandard => {
+                // Cancel the hold invoice, returning exactly what was received
+                let cancellation = self
+                    .escrow_engine
+                    .cancel_hold_invoice(&hold_invoice_id(&funding)?)
+                    .await?;
+                (
+                    cancellation.refunded_sats,
+                    "invoice.cancelled",
+                    serde_json::json!({ "refunded_sats": cancellation.refunded_sats }),
+                )
+            }
+            FundingMode::OnchainSubmarine => {
+                let payout = self
+                    .escrow_engine
+                    .pay_destination(&refund_address(&funding)?, received_sats)
+                    .await?;
+                (
+                    payout.amount_sats,
+                    "refund.paid",
+                    serde_json::json!({
+                        "refunded_sats": payout.amount_sats,
+                        "destination": payout.destination,
+                        "payout_id": payout.payment_id,
+                    }),
+                )
+            }
+            FundingMode::OnchainReverse => {
+                let payout = self
+                    .payment_coordinator()?
+                    .claim_funding_swap(task.id, &swap_id(&funding)?, &refund_address(&funding)?)
+                    .await?;
+                self.record_payout(&payout).await?;
+                let refunded_sats = payout.claimed_sats.unwrap_or_default();
+                (
+                    refunded_sats,
+                    "refund.claimed",
+                    serde_json::json!({
+                        "refunded_sats": refunded_sats,
+                        "swap_id": payout.swap_id,
+                        "address": payout.address,
+                        "claim_txid": payout.claim_txid,
+                    }),
+                )
+            }
+            FundingMode::OnchainMultisig => {
+                let employer_address = refund_address(&funding)?;
+                self.prepare_escrow_settlement(
+                    &task,
+                    funding,
+                    MultisigOutcome::Refund { employer_address },
+                )
+                .await?;
+                return Ok(task);
+            }
+            FundingMode::Cashu => {
+                // Swap the ecash for a token locked to the employer's key
+                let settlement = self
+                    .payment_coordinator()?
+                    .refund_cashu_escrow(funding.id)
+                    .await?;
+                (
+                    settlement.amount_sats,
+                    "cashu.refunded",
+                    serde_json::json!({
+                        "refunded_sats": settlement.amount_sats,
+                        "token": settlement.token,
+                        "fee_sats": settlement.fee_sats,
+                    }),
+                )
+            }
+        };
+
+        let task = self
+            .finish_refund(task, funding, refunded_sats, event_type, metadata)
+            .await?;
+
+        info!("Refunded {} sats for task: {}", refunded_sats, task_id);
+
+        Ok(task)
+    }
+
+    /// Mark a task refunded and its funding cancelled
+    async fn finish_refund(
+        &self,
+        mut task: Task,
+        mut funding: Funding,
+        refunded_sats: u64,
+        event_type: &str,
+        metadata: serde_json::Value,
+    ) -> Result<Task, EscrowError> {
+        task.validate_transition(TaskState::Refunded)?;
+
+        funding.status = FundingStatus::Cancelled;
+        funding.cancelled_at = Some(Utc::now());
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        task.state = TaskState::Refunded;
+        task.updated_at = Utc::now();
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        self.reputation_indexer
+            .update_reputation(&task.employer_pubkey, |rep| {
+                rep.tasks_cancelled += 1;
+                rep.last_active_at = Utc::now();
+            })
+            .await?;
+
+        self.record_payment_event(
+            event_type,
+            &task,
+            &funding,
+            Some(refunded_sats as i64),
+            Some(metadata),
+        )
+        .await?;
+
+        Ok(task)
+    }
+
+    /// Claim a task for work
+    pub async fn claim_task(&self, request: ClaimTaskRequest) -> Result<Task, EscrowError> {
+        info!("Claiming task: {}", request.task_id);
+
+        // Get task
+        let mut task = self.get_task(request.task_id).await?;
+
+        // Validate claim request
+        self.validate_claim_task_request(&request, &task)?;
+        self.authorize_action(
+            &request.worker_pubkey,
+            &ActionMessage::claim(task.id, &request.worker_invoice),
+            &request.auth,
+        )
+        .await?;
+
+        // Funds locked on-chain can only be paid out on-chain
+        let funding = self.task_funding(&task).await?;
+        if matches!(
+            funding.mode,
+            FundingMode::OnchainReverse | FundingMode::OnchainMultisig
+        ) && !self.is_onchain_destination(&request.worker_invoice)
+        {
+            return Err(EscrowError::task_validation(format!(
+                "{:?} funding can only be paid out to an on-chain address",
+                funding.mode
+            )));
+        }
+
+        // Ecash is handed over locked to a Cashu key, and only ecash is
+        let cashu_destination = is_cashu_destination(&request.worker_invoice);
+        if funding.mode == FundingMode::Cashu && !cashu_destination {
+            return Err(EscrowError::task_validation(
+                "Cashu funding can only be paid out to a Cashu public key",
+            ));
+        }
+        if funding.mode != FundingMode::Cashu && cashu_destination {
+            return Err(EscrowError::task_validation(format!(
+                "{:?} funding cannot be paid out to a Cashu public key",
+                funding.mode
+            )));
+        }
+
+        // Transition task state
+        task.validate_transition(TaskState::Claimed)?;
+        task.state = TaskState::Claimed;
+        task.worker_pubkey = Some(request.worker_pubkey.clone());
+        task.payout_destination = Some(request.worker_invoice.clone());
+        task.claimed_at = Some(Utc::now());
+        task.updated_at = Utc::now();
+
+        // Store updated task
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        // Update reputation (task claimed)
+        self.reputation_indexer
+            .update_reputation(&request.worker_pubkey, |rep| {
+                rep.tasks_claimed += 1;
+                rep.last_active_at = Utc::now();
+            })
+            .await?;
+
+        // Publish Nostr event
+        self.nostr_publisher
+            .publish_task_claimed(task.clone())
+            .await?;
+
+        // Create escrow event
+        self.create_escrow_event(
+            "task.claimed".to_string(),
+            Some(request.task_id),
+            task.funding_id,
+            None,
+            Some(request.worker_pubkey),
+            None,
+            None,
+        )
+        .await?;
+
+        info!("Claimed task: {}", request.task_id);
+
+        Ok(task)
+    }
+
+    /// Submit proof of work completion
+    pub async fn submit_proof(&self, request: SubmitProofRequest) -> Result<Task, EscrowError> {
+        info!("Submitting proof for task: {}", request.task_id);
+
+        // Get task
+        let mut task = self.get_task(request.task_id).await?;
+
+        // Validate proof submission
+        self.validate_proof_submission(&request, &task)?;
+
+        // The proof event must be signed by the worker and describe the proof
+        let event = self
+            .verification_service
+            .verify_proof_event(
+                &request.proof_event,
+                task.id,
+                &request.worker_pubkey,
+                &request.proof_url,
+                &request.proof_hash,
+            )
+            .await?;
+        self.authorize_action(
+            &request.worker_pubkey,
+            &ActionMessage::submit_proof(
+                task.id,
+                &request.proof_url,
+                &request.proof_hash,
+                &event.id,
+            ),
+            &request.auth,
+        )
+        .await?;
+
+        // Keep our own copy, which must match the declared hash
+        let archived = match &self.proof_archive {
+            Some(archive) => {
+                let proof = self
+                    .verification_service
+                    .download_proof(&request.proof_url, &request.proof_hash)
+                    .await?;
+                archive.archive(&task, &request.proof_url, &proof).await?;
+                Some(proof)
+            }
+            None => None,
+        };
+
+        // Update task with proof
+        task.proof_url = Some(request.proof_url.clone());
+        task.proof_hash = Some(request.proof_hash.clone());
+        task.proof_nostr_event_id = Some(event.id.clone());
+        task.proof_nostr_event = Some(request.proof_event.clone());
+        task.updated_at = Utc::now();
+
+        // Store updated task
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        // Create escrow event
+        self.create_escrow_event(
+            "proof.submitted".to_string(),
+            Some(request.task_id),
+            task.funding_id,
+            None,
+            Some(request.worker_pubkey),
+            None,
+            Some(serde_json::json!({
+                "proof_url": request.proof_url,
+                "proof_hash": request.proof_hash,
+                "nostr_event_id": event.id,
+                "archived": archived.is_some()
+            })),
+        )
+        .await?;
+
+        info!("Submitted proof for task: {}", request.task_id);
+
+        // Check the proof automatically if its task type has a verifier
+        if let Some(result) = self
+            .verification_service
+            .verify_task_completion(&task, &request.proof_url, archived.as_ref())
+            .await
+        {
+            task = self.record_completion_result(task, result).await?;
+        }
+
+        Ok(task)
+    }
+
+    /// Keep an automated verification result, approving the task with it
+    /// when the result is positive and the employer opted in
+    async fn record_completion_result(
+        &self,
+        task: Task,
+        result: CompletionVerificationResult,
+    ) -> Result<Task, EscrowError> {
+        info!(
+            "Automated verification of task {} by {}: approved {} (score {})",
+            task.id, result.verification_method, result.approved, result.score
+        );
+        self.completion_results
+            .write()
+            .await
+            .insert(task.id, result.clone());
+
+        self.create_escrow_event(
+            "proof.auto_verified".to_string(),
+            Some(task.id),
+            task.funding_id,
+            None,
+            None,
+            None,
+            Some(serde_json::json!({
+                "approved": result.approved,
+                "score": result.score,
+                "feedback": result.feedback,
+                "verification_method": result.verification_method,
+                "criteria": result.criteria
+            })),
+        )
+        .await?;
+
+        if !result.approved || !proof_verifier::auto_approve(&task) {
+            return Ok(task);
+        }
+
+        // The proof is stored either way, so a failed approval leaves the
+        // task for the employer to verify
+        let task_id = task.id;
+        let verifier = format!("automated:{}", result.verification_method);
+        match self
+            .approve_task(task, &verifier, &result.feedback, None)
+            .await
+        {
+            Ok(task) => {
+                info!("Auto-approved task: {}", task_id);
+                Ok(task)
+            }
+            Err(e) => {
+                warn!("Auto-approval of task {} failed: {}", task_id, e);
+                self.get_task(task_id).await
+            }
+        }
+    }
+
+    /// Automated verification result of a task's proof, if one was run
+    pub async fn get_completion_result(
+        &self,
+        task_id: Uuid,
+    ) -> Option<CompletionVerificationResult> {
+        self.completion_results.read().await.get(&task_id).cloned()
+    }
+
+    /// Acceptance criteria report of a task's proof, if it was evaluated
+    async fn criteria_report(&self, task_id: Uuid) -> Option<CriteriaReport> {
+        self.completion_results
+            .read()
+            .await
+            .get(&task_id)
+            .and_then(|result| result.criteria.clone())
+    }
+
+    /// Verify task completion and approve for payment
+    pub async fn verify_task(&self, request: VerifyTaskRequest) -> Result<Task, EscrowError> {
+        info!("Verifying task: {}", request.task_id);
+
+        // Get task
+        let mut task = self.get_task(request.task_id).await?;
+
+        // Validate verification request
+        self.validate_verification_request(&request, &task)?;
+
+        // The decision must be signed by the employer
+        self.authorize_action(
+            &request.verifier_pubkey,
+            &ActionMessage::verify(task.id, request.approved, &request.reason),
+            &request.auth,
+        )
+        .await?;
+
+        if request.approved {
+            let task = self
+                .approve_task(
+                    task,
+                    &request.verifier_pubkey,
+                    &request.reason,
+                    Some(request.verifier_pubkey.clone()),
+                )
+                .await?;
+            info!("Verified task: {} (approved: true)", request.task_id);
+            return Ok(task);
+        }
+
+        // Reject and create dispute
+        task.state = TaskState::Disputed;
+        task.updated_at = Utc::now();
+
+        // Create dispute
+        if let Some(ref worker_pubkey) = task.worker_pubkey {
+            let dispute = Dispute::new(
+                task.id,
+                request.verifier_pubkey.clone(),
+                worker_pubkey.clone(),
+                request.reason.clone(),
+                vec![],
+            );
+
+            // In production, store dispute in database
+            warn!("Created dispute for task: {}", task.id);
+        }
+
+        // Store updated task
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        // Publish Nostr event
+        self.nostr_publisher
+            .publish_task_disputed(task.clone())
+            .await?;
+
+        // Create escrow event
+        self.create_escrow_event(
+            "proof.rejected".to_string(),
+            Some(request.task_id),
+            task.funding_id,
+            None,
+            Some(request.verifier_pubkey),
+            None,
+            Some(serde_json::json!({
+                "approved": false,
+                "reason": request.reason,
+                "criteria": self.criteria_report(task.id).await
+            })),
+        )
+        .await?;
+
+        info!("Verified task: {} (approved: false)", request.task_id);
+
+        Ok(task)
+    }
+
+    /// Mark a task verified by `verifier` and proceed to settlement
+    ///
+    /// `actor_pubkey` is the party who approved, or `None` for automated
+    /// approvals.
+    async fn approve_task(
+        &self,
+        mut task: Task,
+        verifier: &str,
+        reason: &str,
+        actor_pubkey: Option<String>,
+    ) -> Result<Task, EscrowError> {
+        // Approve and transition to verified state
+        task.validate_transition(TaskState::Verified)?;
+        task.state = TaskState::Verified;
+        task.verified_by = Some(verifier.to_string());
+        task.verified_at = Some(Utc::now());
+        task.verification_reason = Some(reason.to_string());
+        task.completed_at = Some(Utc::now());
+        task.updated_at = Utc::now();
+
+        // Store updated task
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        // Publish Nostr event
+        self.nostr_publisher
+            .publish_task_verified(task.clone())
+            .await?;
+
+        // Create escrow event
+        self.create_escrow_event(
+            "proof.verified".to_string(),
+            Some(task.id),
+            task.funding_id,
+            None,
+            actor_pubkey,
+            None,
+            Some(serde_json::json!({
+                "approved": true,
+                "verified_by": verifier,
+                "reason": reason,
+                "criteria": self.criteria_report(task.id).await
+            })),
+        )
+        .await?;
+
+        // Proceed to settlement, either inline or via the batch scheduler;
+        // only hold invoices paid out over Lightning are batched
+        let onchain = task
+            .payout_destination
+            .as_deref()
+            .is_some_and(|destination| self.is_onchain_destination(destination));
+        let holds_invoice = self.task_funding(&task).await?.hold_invoice_id.is_some();
+        if self.settlement_scheduler.is_enabled() && holds_invoice && !onchain {
+            self.queue_settlement(&task).await?;
+        } else {
+            self.settle_task(task.id).await?;
+        }
+        self.get_task(task.id).await
+    }
+
+    /// Settle a verified task by releasing funds
+    ///
+    /// Hold invoices are settled to the worker, completed submarine swaps
+    /// are paid out of the node's balance, reverse swap lockups are claimed
+    /// to the worker's address and multisig escrows get a release PSBT for
+    /// the parties to sign. Cashu escrows are swapped for a token locked to
+    /// the worker's key. On-chain payouts leave the task `Verified` until
+    /// they confirm; escrow releases until they are finalised.
+    async fn settle_task(&self, task_id: Uuid) -> Result<(), EscrowError> {
+        info!("Settling task: {}", task_id);
+
+        // Get task and funding
+        let task = self.get_task(task_id).await?;
+        let funding = self.task_funding(&task).await?;
+        let destination = task
+            .payout_destination
+            .clone()
+            .ok_or_else(|| EscrowError::task_validation("Task has no payout destination"))?;
+        let onchain = self.is_onchain_destination(&destination);
+
+        match funding.mode {
+            FundingMode::LightningHold | FundingMode::LightningStandard => {
+                let hold_invoice_id = hold_invoice_id(&funding)?;
+                if onchain {
+                    let settlement = self
+                        .escrow_engine
+                        .release_hold_invoice(&hold_invoice_id)
+                        .await?;
+                    return self
+                        .start_onchain_payout(&task, funding, (&settlement).into(), &destination)
+                        .await;
+                }
+
+                // Settle hold invoice
+                let settlement = self
+                    .escrow_engine
+                    .settle_hold_invoice(&hold_invoice_id, &destination)
+                    .await?;
+                self.complete_settlement(task_id, funding.id, &(&settlement).into(), None)
+                    .await?;
+            }
+            FundingMode::OnchainSubmarine => {
+                // Boltz paid the swap invoice, so the reward is in the node's balance
+                let released = ReleasedFunds {
+                    amount_sats: received_sats(&funding),
+                    released_at: Utc::now(),
+                    preimage: None,
+                };
+                if onchain {
+                    return self
+                        .start_onchain_payout(&task, funding, released, &destination)
+                        .await;
+                }
+
+                self.escrow_engine
+                    .pay_destination(&destination, released.amount_sats)
+                    .await?;
+                self.complete_settlement(task_id, funding.id, &released, None)
+                    .await?;
+            }
+            FundingMode::OnchainReverse => {
+                return self.start_lockup_payout(&task, funding, &destination).await;
+            }
+            FundingMode::OnchainMultisig => {
+                let outcome = MultisigOutcome::Release {
+                    worker_address: destination,
+                };
+                self.prepare_escrow_settlement(&task, funding, outcome)
+                    .await?;
+                return Ok(());
+            }
+            FundingMode::Cashu => {
+                let settlement = self
+                    .payment_coordinator()?
+                    .settle_cashu_escrow(funding.id, &destination)
+                    .await?;
+                let funding_id = funding.id;
+                self.record_cashu_settlement(funding, &settlement).await?;
+                let released = ReleasedFunds {
+                    amount_sats: settlement.amount_sats,
+                    released_at: settlement.settled_at,
+                    preimage: None,
+                };
+                self.complete_settlement(task_id, funding_id, &released, None)
+                    .await?;
+            }
+        }
+
+        info!("Settled task: {}", task_id);
+
+        Ok(())
+    }
+
+    /// Pay released funds out on-chain through a reverse swap
+    ///
+    /// The swap invoice is paid from the released funds. The task stays
+    /// `Verified` until the claim transaction to the worker's address
+    /// confirms (see `record_payout_confirmations`).
+    async fn start_onchain_payout(
+        &self,
+        task: &Task,
+        mut funding: Funding,
+        released: ReleasedFunds,
+        destination: &str,
+    ) -> Result<(), EscrowError> {
+        funding.status = FundingStatus::Settled;
+        funding.settled_at = Some(released.released_at);
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+        self.pending_payouts.write().await.insert(
+            task.id,
+            PendingPayout {
+                funding_id: funding.id,
+                released,
+                swap_id: None,
+            },
+        );
+
+        self.open_payout_swap(task, destination).await?;
+
+        Ok(())
+    }
+
+    /// Pay a reverse swap funding out by claiming its lockup to the worker
+    ///
+    /// Like other on-chain payouts, the task stays `Verified` until the claim
+    /// confirms.
+    async fn start_lockup_payout(
+        &self,
+        task: &Task,
+        mut funding: Funding,
+        destination: &str,
+    ) -> Result<(), EscrowError> {
+        let swap_id = swap_id(&funding)?;
+
+        funding.status = FundingStatus::Settled;
+        funding.settled_at = Some(Utc::now());
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+        self.pending_payouts.write().await.insert(
+            task.id,
+            PendingPayout {
+                funding_id: funding.id,
+                released: ReleasedFunds {
+                    amount_sats: received_sats(&funding),
+                    released_at: Utc::now(),
+                    preimage: None,
+                },
+                swap_id: Some(swap_id.clone()),
+            },
+        );
+
+        let payout = self
+            .payment_coordinator()?
+            .claim_funding_swap(task.id, &swap_id, destination)
+            .await?;
+        let funding = self.record_payout(&payout).await?;
+        self.record_payment_event(
+            "payout.claimed",
+            task,
+            &funding,
+            payout.claimed_sats.map(|sats| sats as i64),
+            Some(serde_json::json!({
+                "swap_id": payout.swap_id,
+                "address": payout.address,
+                "claim_txid": payout.claim_txid,
+            })),
+        )
+        .await?;
+
+        info!(
+            "Claimed funding lockup {} of task {} to {}",
+            payout.swap_id, task.id, payout.address
+        );
+
+        Ok(())
+    }
+
+    /// Start settling a task's multisig escrow with `outcome`
+    ///
+    /// The PSBT for the parties to sign is kept on the funding; the task
+    /// moves on once the settlement is finalised (see
+    /// `finalize_escrow_settlement`).
+    async fn prepare_escrow_settlement(
+        &self,
+        task: &Task,
+        mut funding: Funding,
+        outcome: MultisigOutcome,
+    ) -> Result<String, EscrowError> {
+        let psbt = self
+            .payment_coordinator()?
+            .prepare_multisig_settlement(funding.id, outcome.clone())
+            .await?;
+
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["escrow_settlement"] = serde_json::json!({
+            "outcome": outcome,
+            "psbt": psbt,
+        });
+        funding.external_metadata = Some(metadata);
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        let event_type = match outcome {
+            MultisigOutcome::Release { .. } => "escrow.release_prepared",
+            MultisigOutcome::Refund { .. } | MultisigOutcome::TimeoutRefund { .. } => {
+                "escrow.refund_prepared"
+            }
+            MultisigOutcome::Arbitrated { .. } => "escrow.split_prepared",
+        };
+        self.record_payment_event(
+            event_type,
+            task,
+            &funding,
+            None,
+            Some(serde_json::json!({ "outcome": outcome, "psbt": psbt })),
+        )
+        .await?;
+
+        info!(
+            "Prepared {:?} settlement of escrow for task {}",
+            outcome, task.id
+        );
+
+        Ok(psbt)
+    }
+
+    /// Finalise a task's multisig escrow settlement from the parties' signed PSBTs
+    pub async fn finalize_escrow_settlement(
+        &self,
+        task_id: Uuid,
+        signed_psbts: &[String],
+    ) -> Result<MultisigSettlement, EscrowError> {
+        let funding = self.escrow_funding(task_id).await?;
+        let settlement = self
+            .payment_coordinator()?
+            .finalize_multisig_settlement(funding.id, signed_psbts)
+            .await?;
+        self.record_escrow_settlement(funding, &settlement).await?;
+        Ok(settlement)
+    }
+
+    /// Finalise a cooperative release or refund of a task's Taproot escrow
+    /// from the parties' MuSig2 contributions
+    pub async fn finalize_cooperative_escrow_settlement(
+        &self,
+        task_id: Uuid,
+        contributions: &[KeyPathContribution],
+    ) -> Result<MultisigSettlement, EscrowError> {
+        let funding = self.escrow_funding(task_id).await?;
+        let settlement = self
+            .payment_coordinator()?
+            .finalize_cooperative_settlement(funding.id, contributions)
+            .await?;
+        self.record_escrow_settlement(funding, &settlement).await?;
+        Ok(settlement)
+    }
+
+    /// Record a finalised escrow settlement on its task
+    ///
+    /// A release pays the task and a refund returns it to the employer; an
+    /// arbitrated split is only recorded, as disputes resolve the task.
+    async fn record_escrow_settlement(
+        &self,
+        mut funding: Funding,
+        settlement: &MultisigSettlement,
+    ) -> Result<(), EscrowError> {
+        let details = serde_json::json!({
+            "outcome": settlement.outcome,
+            "txid": settlement.txid,
+            "fee_sats": settlement.fee_sats,
+            "broadcast": settlement.broadcast,
+        });
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["escrow_settlement"] = details.clone();
+        funding.external_metadata = Some(metadata);
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        let task = self.get_task(funding.task_id).await?;
+        let paid_sats = received_sats(&funding).saturating_sub(settlement.fee_sats);
+        match settlement.outcome {
+            MultisigOutcome::Release { .. } => {
+                let released = ReleasedFunds {
+                    amount_sats: paid_sats,
+                    released_at: Utc::now(),
+                    preimage: None,
+                };
+                self.complete_settlement(task.id, funding.id, &released, None)
+                    .await?;
+            }
+            MultisigOutcome::Refund { .. } | MultisigOutcome::TimeoutRefund { .. } => {
+                self.finish_refund(task, funding, paid_sats, "escrow.refunded", details)
+                    .await?;
+            }
+            MultisigOutcome::Arbitrated { .. } => {
+                self.record_payment_event("escrow.split", &task, &funding, None, Some(details))
+                    .await?;
+            }
+        }
+
+        info!(
+            "Recorded {:?} settlement {} of escrow {}",
+            settlement.outcome, settlement.txid, settlement.funding_id
+        );
+
+        Ok(())
+    }
+
+    /// Record confirmations of a multisig escrow's funding transaction
+    ///
+    /// The first confirmation funds a task awaiting funding. A reorg that
+    /// drops an unspent funding output moves the task back to awaiting
+    /// funding, keeping any claim, until the transaction is re-mined.
+    pub async fn record_escrow_confirmations(
+        &self,
+        update: &PaymentStatusUpdate,
+        reorged: bool,
+    ) -> Result<Task, EscrowError> {
+        let mut funding = self.get_funding(update.funding_id).await?;
+        let mut task = self.get_task(funding.task_id).await?;
+        let confirmations = update.confirmations.unwrap_or_default();
+
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["escrow"] = serde_json::json!({
+            "txid": update.transaction_id,
+            "confirmations": confirmations,
+            "reorged": reorged,
+        });
+        funding.external_metadata = Some(metadata);
+        funding.updated_at = Utc::now();
+
+        let funding_output = self
+            .payment_coordinator()?
+            .get_multisig_funding(funding.id)
+            .await
+            .and_then(|escrow| escrow.funding_output);
+
+        let event_type = if reorged {
+            warn!("Escrow funding of task {} left the best chain", task.id);
+            // The coordinator keeps the output once a settlement spends it
+            if funding_output.is_none()
+                && matches!(task.state, TaskState::Funded | TaskState::Claimed)
+            {
+                task.state = TaskState::PendingFunding;
+                task.updated_at = Utc::now();
+                funding.status = FundingStatus::Pending;
+                funding.amount_received_sats = None;
+                funding.payment_received_at = None;
+            }
+            Some("escrow.reorged")
+        } else if confirmations > 0 && task.state == TaskState::PendingFunding {
+            let funded_sats = funding_output.map(|(_, output)| output.value.to_sat() as i64);
+            // A task claimed before a reorg resumes where it was
+            if task.worker_pubkey.is_some() {
+                task.state = TaskState::Claimed;
+            } else {
+                task.validate_transition(TaskState::Funded)?;
+                task.state = TaskState::Funded;
+            }
+            task.updated_at = Utc::now();
+            funding.status = FundingStatus::Accepted;
+            funding.amount_received_sats = funded_sats.or(Some(funding.amount_sats));
+            funding.payment_received_at = Some(Utc::now());
+            Some("escrow.funded")
+        } else {
+            None
+        };
+
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+        self.tasks.write().await.insert(task.id, task.clone());
+
+        if let Some(event_type) = event_type {
+            self.record_payment_event(
+                event_type,
+                &task,
+                &funding,
+                funding.amount_received_sats,
+                Some(serde_json::json!({
+                    "txid": update.transaction_id,
+                    "confirmations": confirmations,
+                })),
+            )
+            .await?;
+        }
+
+        Ok(task)
+    }
+
+    /// Fund a Cashu task with the employer's ecash token
+    ///
+    /// The token must be locked to the escrow as described by the funding's
+    /// lockup script; see `PaymentCoordinator::accept_cashu_token`.
+    pub async fn submit_cashu_token(
+        &self,
+        task_id: Uuid,
+        employer_pubkey: &str,
+        token: &str,
+        auth: &ActionAuth,
+    ) -> Result<Task, EscrowError> {
+        let mut task = self.get_task(task_id).await?;
+        if !verification_service::same_pubkey(&task.employer_pubkey, employer_pubkey) {
+            return Err(EscrowError::task_validation(
+                "Only task creator can fund task",
+            ));
+        }
+        let mut funding = self.task_funding(&task).await?;
+        if funding.mode != FundingMode::Cashu {
+            return Err(EscrowError::task_validation(format!(
+                "Task {} is not funded with Cashu",
+                task_id
+            )));
+        }
+        task.validate_transition(TaskState::Funded)?;
+        self.authorize_action(
+            employer_pubkey,
+            &ActionMessage::fund(task.id, FundingMode::Cashu, None, None),
+            auth,
+        )
+        .await?;
 
-        // Verify Nostr signature
-        self.verification_service
-            .verify_nostr_signature(&request.nostr_signature, &request.nostr_event_id)
+        let escrow = self
+            .payment_coordinator()?
+            .accept_cashu_token(funding.id, token)
             .await?;
 
-        // Update task with proof
-        task.proof_url = Some(request.proof_url.clone());
-        task.proof_hash = Some(request.proof_hash.clone());
-        task.proof_nostr_event_id = Some(request.nostr_event_id.clone());
-        task.updated_at = Utc::now();
+        funding.status = FundingStatus::Accepted;
+        funding.amount_received_sats = Some(escrow.funded_sats() as i64);
+        funding.payment_received_at = Some(Utc::now());
+        funding.updated_at = Utc::now();
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
 
-        // Store updated task
+        task.state = TaskState::Funded;
+        task.updated_at = Utc::now();
         self.tasks.write().await.insert(task.id, task.clone());
 
-        // Create escrow event
-        self.create_escrow_event(
-            "proof.submitted".to_string(),
-            Some(request.task_id),
-            task.funding_id,
-            None,
-            Some(request.worker_pubkey),
-            None,
+        self.record_payment_event(
+            "cashu.funded",
+            &task,
+            &funding,
+            funding.amount_received_sats,
             Some(serde_json::json!({
-                "proof_url": request.proof_url,
-                "proof_hash": request.proof_hash,
-                "nostr_event_id": request.nostr_event_id
+                "mint_url": escrow.lock.mint_url,
+                "proofs": escrow.proofs.len(),
             })),
         )
         .await?;
 
-        info!("Submitted proof for task: {}", request.task_id);
+        info!(
+            "Task {} funded with {} sats of ecash",
+            task_id,
+            escrow.funded_sats()
+        );
 
         Ok(task)
     }
 
-    /// Verify task completion and approve for payment
-    pub async fn verify_task(&self, request: VerifyTaskRequest) -> Result<Task, EscrowError> {
-        info!("Verifying task: {}", request.task_id);
+    /// Keep the token a Cashu escrow was paid out as on its funding
+    async fn record_cashu_settlement(
+        &self,
+        mut funding: Funding,
+        settlement: &CashuSettlement,
+    ) -> Result<(), EscrowError> {
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["cashu_settlement"] = serde_json::to_value(settlement)?;
+        funding.external_metadata = Some(metadata);
+        funding.updated_at = Utc::now();
+        self.funding.write().await.insert(funding.id, funding);
+        Ok(())
+    }
 
-        // Get task
-        let mut task = self.get_task(request.task_id).await?;
+    /// The multisig escrow funding of a task
+    async fn escrow_funding(&self, task_id: Uuid) -> Result<Funding, EscrowError> {
+        let task = self.get_task(task_id).await?;
+        let funding = self.task_funding(&task).await?;
+        if funding.mode != FundingMode::OnchainMultisig {
+            return Err(EscrowError::task_validation(format!(
+                "Task {} is not funded through a multisig escrow",
+                task_id
+            )));
+        }
+        Ok(funding)
+    }
 
-        // Validate verification request
-        self.validate_verification_request(&request, &task)?;
+    /// Create a payout swap for a released settlement and pay its invoice
+    async fn open_payout_swap(
+        &self,
+        task: &Task,
+        destination: &str,
+    ) -> Result<OnchainPayout, EscrowError> {
+        let coordinator = self.payment_coordinator()?;
+        let pending = self
+            .pending_payouts
+            .read()
+            .await
+            .get(&task.id)
+            .cloned()
+            .ok_or_else(|| {
+                EscrowError::payment(format!("Task {} has no pending payout", task.id))
+            })?;
+
+        let payout = coordinator
+            .create_onchain_payout(
+                task.id,
+                pending.funding_id,
+                pending.released.amount_sats,
+                destination,
+            )
+            .await?;
+        if let Some(pending) = self.pending_payouts.write().await.get_mut(&task.id) {
+            pending.swap_id = Some(payout.swap_id.clone());
+        }
 
-        // Verify signature
-        self.verification_service
-            .verify_signature(&request.signature, &request.verifier_pubkey)
+        self.escrow_engine
+            .pay_destination(&payout.invoice, payout.invoice_amount_sats)
+            .await?;
+        let payout = coordinator
+            .mark_payout_invoice_paid(&payout.swap_id)
             .await?;
 
-        if request.approved {
-            // Approve and transition to verified state
-            task.validate_transition(TaskState::Verified)?;
-            task.state = TaskState::Verified;
-            task.verified_by = Some(request.verifier_pubkey.clone());
-            task.verified_at = Some(Utc::now());
-            task.verification_reason = Some(request.reason.clone());
-            task.completed_at = Some(Utc::now());
-            task.updated_at = Utc::now();
+        let funding = self.record_payout(&payout).await?;
+        self.record_payment_event(
+            "payout.created",
+            task,
+            &funding,
+            Some(payout.invoice_amount_sats as i64),
+            Some(serde_json::json!({
+                "swap_id": payout.swap_id,
+                "address": payout.address,
+                "onchain_amount_sats": payout.onchain_amount_sats,
+            })),
+        )
+        .await?;
 
-            // Proceed to settlement
-            self.settle_task(task.id).await?;
-        } else {
-            // Reject and create dispute
-            task.state = TaskState::Disputed;
-            task.updated_at = Utc::now();
+        info!(
+            "Started on-chain payout {} for task {} to {}",
+            payout.swap_id, task.id, payout.address
+        );
 
-            // Create dispute
-            if let Some(ref worker_pubkey) = task.worker_pubkey {
-                let dispute = Dispute::new(
-                    task.id,
-                    request.verifier_pubkey.clone(),
-                    worker_pubkey.clone(),
-                    request.reason.clone(),
-                    vec![],
-                );
+        Ok(payout)
+    }
+
+    /// Retry the on-chain payout of a verified task
+    ///
+    /// A payout whose claim could not be broadcast is claimed again; a failed
+    /// payout swap is replaced by a new one.
+    pub async fn retry_onchain_payout(&self, task_id: Uuid) -> Result<OnchainPayout, EscrowError> {
+        let coordinator = self.payment_coordinator()?;
+        let task = self.get_task(task_id).await?;
+        let pending = self
+            .pending_payouts
+            .read()
+            .await
+            .get(&task_id)
+            .cloned()
+            .ok_or_else(|| {
+                EscrowError::payment(format!("Task {} has no pending payout", task_id))
+            })?;
 
-                // In production, store dispute in database
-                warn!("Created dispute for task: {}", task.id);
+        let payout = match &pending.swap_id {
+            Some(swap_id) => coordinator.get_payout(swap_id).await,
+            None => None,
+        };
+        match payout {
+            Some(payout) if payout.state == PayoutState::InvoicePaid => {
+                let payout = coordinator.claim_payout(&payout.swap_id).await?;
+                self.record_payout(&payout).await?;
+                Ok(payout)
+            }
+            Some(payout) if payout.state != PayoutState::Failed => {
+                Err(EscrowError::payment(format!(
+                    "Payout {} is {:?} and cannot be retried",
+                    payout.swap_id, payout.state
+                )))
+            }
+            _ => {
+                let destination = task.payout_destination.clone().ok_or_else(|| {
+                    EscrowError::task_validation("Task has no payout destination")
+                })?;
+                self.open_payout_swap(&task, &destination).await
             }
         }
+    }
 
-        // Store updated task
-        self.tasks.write().await.insert(task.id, task.clone());
-
-        // Publish Nostr event
-        if request.approved {
-            self.nostr_publisher
-                .publish_task_verified(task.clone())
-                .await?;
-        } else {
-            self.nostr_publisher
-                .publish_task_disputed(task.clone())
-                .await?;
-        }
+    /// Apply a status change of a payout swap
+    async fn apply_payout_status(
+        &self,
+        payout: OnchainPayout,
+        change: &SwapStatusChange,
+    ) -> Result<Task, EscrowError> {
+        let task = self.get_task(payout.task_id).await?;
+        let Some(payout) = self.payment_coordinator()?.advance_payout(change).await? else {
+            return Ok(task);
+        };
 
-        // Create escrow event
-        self.create_escrow_event(
-            if request.approved {
-                "proof.verified".to_string()
-            } else {
-                "proof.rejected".to_string()
-            },
-            Some(request.task_id),
-            task.funding_id,
-            None,
-            Some(request.verifier_pubkey),
-            None,
+        let funding = self.record_payout(&payout).await?;
+        let event_type = match payout.state {
+            PayoutState::Claimed => "payout.claimed",
+            PayoutState::Failed => "payout.failed",
+            _ => return Ok(task),
+        };
+        self.record_payment_event(
+            event_type,
+            &task,
+            &funding,
+            payout.claimed_sats.map(|sats| sats as i64),
             Some(serde_json::json!({
-                "approved": request.approved,
-                "reason": request.reason
+                "swap_id": payout.swap_id,
+                "claim_txid": payout.claim_txid,
+                "failure_reason": payout.failure_reason,
             })),
         )
         .await?;
 
-        info!(
-            "Verified task: {} (approved: {})",
-            request.task_id, request.approved
-        );
-
         Ok(task)
     }
 
-    /// Settle a verified task by releasing funds
-    async fn settle_task(
+    /// Record confirmations of a payout's claim transaction
+    ///
+    /// The task is marked paid once the claim has enough confirmations.
+    pub async fn record_payout_confirmations(
         &self,
-        task_id: Uuid,
-    ) -> Result<crate::models::InvoiceSettlementData, EscrowError> {
-        info!("Settling task: {}", task_id);
+        swap_id: &str,
+        confirmations: u32,
+    ) -> Result<Task, EscrowError> {
+        let payout = self
+            .payment_coordinator()?
+            .update_payout_confirmations(swap_id, confirmations)
+            .await?;
+        self.record_payout(&payout).await?;
+
+        if payout.state == PayoutState::Confirmed {
+            let pending = self.pending_payouts.write().await.remove(&payout.task_id);
+            if let Some(pending) = pending {
+                self.complete_settlement(
+                    payout.task_id,
+                    pending.funding_id,
+                    &pending.released,
+                    None,
+                )
+                .await?;
+                info!(
+                    "Payout {} for task {} confirmed in {}",
+                    swap_id,
+                    payout.task_id,
+                    payout.claim_txid.as_deref().unwrap_or_default()
+                );
+            }
+        }
 
-        // Get task and funding
-        let task = self.get_task(task_id).await?;
-        let funding = self.get_funding(task.funding_id.unwrap()).await?;
+        self.get_task(payout.task_id).await
+    }
 
-        // Get worker invoice from task claim (in production, this would be stored)
-        let worker_invoice = "worker_invoice_placeholder".to_string(); // TODO: Get from task data
+    /// Store the latest payout state on the task's funding
+    async fn record_payout(&self, payout: &OnchainPayout) -> Result<Funding, EscrowError> {
+        let task = self.get_task(payout.task_id).await?;
+        let mut funding = self.task_funding(&task).await?;
 
-        // Settle hold invoice
-        let settlement_data = self
-            .escrow_engine
-            .settle_hold_invoice(&funding.hold_invoice_id.unwrap(), &worker_invoice)
+        let mut metadata = funding
+            .external_metadata
+            .take()
+            .unwrap_or_else(|| serde_json::json!({}));
+        metadata["payout"] = serde_json::to_value(payout)?;
+        funding.external_metadata = Some(metadata);
+        funding.updated_at = Utc::now();
+
+        self.funding
+            .write()
+            .await
+            .insert(funding.id, funding.clone());
+
+        Ok(funding)
+    }
+
+    /// Find the payout a swap belongs to, if any
+    async fn find_payout(&self, swap_id: &str) -> Option<OnchainPayout> {
+        self.payment_coordinator.as_ref()?.get_payout(swap_id).await
+    }
+
+    fn payment_coordinator(&self) -> Result<&Arc<PaymentCoordinator>, EscrowError> {
+        self.payment_coordinator
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("No payment coordinator configured"))
+    }
+
+    fn proof_archive(&self) -> Result<&Arc<ProofArchive>, EscrowError> {
+        self.proof_archive
+            .as_ref()
+            .ok_or_else(|| EscrowError::config("No proof archive configured"))
+    }
+
+    /// The funding of a task
+    async fn task_funding(&self, task: &Task) -> Result<Funding, EscrowError> {
+        let funding_id = task
+            .funding_id
+            .ok_or_else(|| EscrowError::task_validation("Task has no funding"))?;
+        self.get_funding(funding_id).await
+    }
+
+    /// Whether a payout destination is an on-chain address
+    fn is_onchain_destination(&self, destination: &str) -> bool {
+        network::parse_address(destination, self.escrow_engine.network()).is_ok()
+    }
+
+    /// Queue a verified task for batched settlement
+    async fn queue_settlement(&self, task: &Task) -> Result<(), EscrowError> {
+        let (funding, hold_invoice_id, destination) = self.settlement_params(task).await?;
+
+        let queued = self
+            .settlement_scheduler
+            .enqueue(PendingSettlement {
+                task_id: task.id,
+                funding_id: funding.id,
+                hold_invoice_id,
+                destination: destination.clone(),
+                queued_at: Utc::now(),
+                released: None,
+                paid: None,
+            })
+            .await;
+
+        if queued {
+            self.record_payment_event(
+                "settlement.queued",
+                task,
+                &funding,
+                funding.amount_received_sats,
+                Some(serde_json::json!({ "destination": destination })),
+            )
             .await?;
+            info!("Queued settlement for task: {}", task.id);
+        }
+
+        Ok(())
+    }
+
+    /// Settle all queued tasks, aggregating payouts per destination
+    ///
+    /// Each hold invoice is released individually so every task keeps its own
+    /// exact settlement record; the released amounts for a destination are then
+    /// paid in one payment. A failed payout leaves the released settlements
+    /// queued so the next run retries the payout without re-releasing, and a
+    /// task that fails to complete stays queued without being paid again.
+    pub async fn run_settlement_batch(&self) -> Result<Vec<SettlementBatchResult>, EscrowError> {
+        let batches = self.settlement_scheduler.take_batches().await;
+        let mut results = Vec::new();
+
+        for batch in batches {
+            info!(
+                "Processing settlement batch {} ({} task(s)) for {}",
+                batch.id,
+                batch.settlements.len(),
+                batch.destination
+            );
+
+            // Release every hold invoice in the batch
+            let mut released = Vec::new();
+            let mut retry = Vec::new();
+            for mut pending in batch.settlements {
+                if pending.released.is_none() {
+                    match self
+                        .escrow_engine
+                        .release_hold_invoice(&pending.hold_invoice_id)
+                        .await
+                    {
+                        Ok(settlement) => pending.released = Some(settlement),
+                        Err(e) => {
+                            error!(
+                                "Failed to release hold invoice for task {}: {}",
+                                pending.task_id, e
+                            );
+                            retry.push(pending);
+                            continue;
+                        }
+                    }
+                }
+                released.push(pending);
+            }
+
+            // Settlements paid by an earlier run only need completing
+            let (mut paid, unpaid): (Vec<_>, Vec<_>) =
+                released.into_iter().partition(|p| p.paid.is_some());
+            let total_sats: u64 = unpaid
+                .iter()
+                .filter_map(|p| p.released.as_ref())
+                .map(|s| s.amount_sats)
+                .sum();
+
+            let mut result = SettlementBatchResult {
+                batch_id: batch.id,
+                destination: batch.destination.clone(),
+                paid_task_ids: Vec::new(),
+                total_paid_sats: 0,
+                payout_id: None,
+                error: None,
+            };
+
+            if !unpaid.is_empty() {
+                match self
+                    .escrow_engine
+                    .pay_destination(&batch.destination, total_sats)
+                    .await
+                {
+                    Ok(payout) => {
+                        for mut pending in unpaid {
+                            pending.paid = Some((batch.id, payout.payment_id.clone()));
+                            paid.push(pending);
+                        }
+                        result.total_paid_sats = payout.amount_sats;
+                        result.payout_id = Some(payout.payment_id);
+                    }
+                    Err(e) => {
+                        error!("Payout for batch {} failed: {}", batch.id, e);
+                        result.error = Some(e.to_string());
+                        retry.extend(unpaid);
+                    }
+                }
+            }
+
+            for pending in paid {
+                let (Some(settlement), Some((batch_id, payout_id))) =
+                    (&pending.released, &pending.paid)
+                else {
+                    continue;
+                };
+                match self
+                    .complete_settlement(
+                        pending.task_id,
+                        pending.funding_id,
+                        &settlement.into(),
+                        Some((*batch_id, payout_id)),
+                    )
+                    .await
+                {
+                    Ok(()) => result.paid_task_ids.push(pending.task_id),
+                    Err(e) => {
+                        error!(
+                            "Failed to complete settlement of task {}: {}",
+                            pending.task_id, e
+                        );
+                        result.error = Some(e.to_string());
+                        retry.push(pending);
+                    }
+                }
+            }
+
+            if !retry.is_empty() {
+                self.settlement_scheduler.requeue(retry).await;
+            }
+
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// Resolve the funding, hold invoice and payout destination for a task
+    async fn settlement_params(
+        &self,
+        task: &Task,
+    ) -> Result<(Funding, String, String), EscrowError> {
+        let funding = self.task_funding(task).await?;
+        let hold_invoice_id = hold_invoice_id(&funding)?;
+        let destination = task
+            .payout_destination
+            .clone()
+            .ok_or_else(|| EscrowError::task_validation("Task has no payout destination"))?;
+
+        Ok((funding, hold_invoice_id, destination))
+    }
 
+    /// Mark a task paid after its funds were released and paid out
+    async fn complete_settlement(
+        &self,
+        task_id: Uuid,
+        funding_id: Uuid,
+        released: &ReleasedFunds,
+        batch: Option<(Uuid, &str)>,
+    ) -> Result<(), EscrowError> {
         // Update task state
         let mut task = self.get_task(task_id).await?;
+        task.validate_transition(TaskState::Paid)?;
         task.state = TaskState::Paid;
-        task.settled_at = Some(settlement_data.settled_at);
+        task.settled_at = Some(released.released_at);
         task.updated_at = Utc::now();
         self.tasks.write().await.insert(task.id, task.clone());
 
         // Update funding status
-        let mut funding = self.get_funding(funding.id).await?;
+        let mut funding = self.get_funding(funding_id).await?;
         funding.status = FundingStatus::Settled;
-        funding.settled_at = Some(settlement_data.settled_at);
+        funding.settled_at = Some(released.released_at);
+        funding.updated_at = Utc::now();
         self.funding
             .write()
             .await
             .insert(funding.id, funding.clone());
 
+        // Worker is credited the exact amount held, including any accepted overpayment
+        let paid_sats = released.amount_sats as i64;
+
         // Update reputation scores
         if let Some(ref worker_pubkey) = task.worker_pubkey {
+            let reward_sats = task.reward_sats;
             self.reputation_indexer
                 .update_reputation(worker_pubkey, move |rep| {
                     rep.tasks_completed += 1;
-                    rep.total_sats_earned += task.reward_sats;
-                    rep.update_score(true, task.reward_sats, true); // completed, on time
+                    rep.total_sats_earned += paid_sats;
+                    rep.update_score(true, reward_sats, true); // completed, on time
                     rep.last_active_at = Utc::now();
                 })
                 .await?;
 
             let employer_pubkey = task.employer_pubkey.clone();
-            let reward_sats = task.reward_sats;
             self.reputation_indexer
                 .update_reputation(&employer_pubkey, move |rep| {
                     rep.tasks_funded += 1;
-                    rep.total_sats_paid += reward_sats;
+                    rep.total_sats_paid += paid_sats;
                     rep.last_active_at = Utc::now();
                 })
                 .await?;
@@ -528,23 +2520,21 @@ impl TaskManager {
         self.nostr_publisher.publish_task_paid(task.clone()).await?;
 
         // Create escrow event
-        self.create_escrow_event(
-            "settlement.completed".to_string(),
-            Some(task_id),
-            Some(funding.id),
-            Some(settlement_data.invoice_hash.clone()),
-            None,
-            Some("Settled".to_string()),
+        self.record_payment_event(
+            "settlement.completed",
+            &task,
+            &funding,
+            Some(paid_sats),
             Some(serde_json::json!({
-                "amount_sats": task.reward_sats,
-                "preimage": settlement_data.preimage
+                "amount_sats": paid_sats,
+                "preimage": released.preimage,
+                "batch_id": batch.map(|(batch_id, _)| batch_id),
+                "payout_id": batch.map(|(_, payout_id)| payout_id)
             })),
         )
         .await?;
 
-        info!("Settled task: {}", task_id);
-
-        Ok(settlement_data)
+        Ok(())
     }
 
     /// Get a task by ID
@@ -569,6 +2559,38 @@ impl TaskManager {
             })
     }
 
+    /// Get funding by Boltz swap id
+    pub async fn get_funding_by_swap_id(&self, swap_id: &str) -> Result<Funding, EscrowError> {
+        self.funding
+            .read()
+            .await
+            .values()
+            .find(|funding| funding.swap_id.as_deref() == Some(swap_id))
+            .cloned()
+            .ok_or_else(|| {
+                EscrowError::task_validation(format!("Funding for swap {} not found", swap_id))
+            })
+    }
+
+    /// Get funding by invoice hash
+    pub async fn get_funding_by_invoice_hash(
+        &self,
+        invoice_hash: &str,
+    ) -> Result<Funding, EscrowError> {
+        self.funding
+            .read()
+            .await
+            .values()
+            .find(|funding| funding.invoice_hash.as_deref() == Some(invoice_hash))
+            .cloned()
+            .ok_or_else(|| {
+                EscrowError::task_validation(format!(
+                    "Funding for invoice {} not found",
+                    invoice_hash
+                ))
+            })
+    }
+
     /// Get all tasks for a user
     pub async fn get_user_tasks(&self, pubkey: &str) -> Result<Vec<Task>, EscrowError> {
         let tasks = self.tasks.read().await;
@@ -584,6 +2606,50 @@ impl TaskManager {
         Ok(user_tasks)
     }
 
+    /// Read a task's archived proof, for its parties and arbitrators
+    ///
+    /// The requester signs `ActionMessage::retrieve_proof`.
+    pub async fn retrieve_archived_proof(
+        &self,
+        task_id: Uuid,
+        requester_pubkey: &str,
+        auth: &ActionAuth,
+    ) -> Result<RetrievedProof, EscrowError> {
+        let archive = self.proof_archive()?;
+        self.authorize_action(
+            requester_pubkey,
+            &ActionMessage::retrieve_proof(task_id),
+            auth,
+        )
+        .await?;
+        archive.retrieve(task_id, requester_pubkey).await
+    }
+
+    /// Archived proof of a task, without its content
+    pub async fn get_archived_proof(
+        &self,
+        task_id: Uuid,
+    ) -> Result<Option<ArchivedProof>, EscrowError> {
+        Ok(self.proof_archive()?.get(task_id).await)
+    }
+
+    /// Prune archived proofs of tasks closed past the retention period
+    pub async fn prune_proof_archive(&self) -> Result<Vec<Uuid>, EscrowError> {
+        let archive = self.proof_archive()?;
+        let closed_at = self
+            .tasks
+            .read()
+            .await
+            .values()
+            .filter_map(|task| match task.state {
+                TaskState::Paid => Some((task.id, task.settled_at.unwrap_or(task.updated_at))),
+                TaskState::Refunded => Some((task.id, task.updated_at)),
+                _ => None,
+            })
+            .collect();
+        archive.prune(&closed_at).await
+    }
+
     /// Get escrow events for a task
     pub async fn get_task_events(&self, task_id: Uuid) -> Result<Vec<EscrowEvent>, EscrowError> {
         let events = self.escrow_events.read().await;
@@ -624,24 +2690,106 @@ impl TaskManager {
             created_at: Utc::now(),
         };
 
+        self.store_escrow_event(event).await
+    }
+
+    /// Create a payment escrow event carrying the exact amount moved
+    async fn record_payment_event(
+        &self,
+        event_type: &str,
+        task: &Task,
+        funding: &Funding,
+        amount_sats: Option<i64>,
+        metadata: Option<serde_json::Value>,
+    ) -> Result<(), EscrowError> {
+        let event = EscrowEvent {
+            id: 0, // Would be auto-generated by database
+            event_type: event_type.to_string(),
+            task_id: Some(task.id),
+            funding_id: Some(funding.id),
+            invoice_hash: funding.invoice_hash.clone(),
+            preimage: None,
+            amount_sats,
+            actor_pubkey: None,
+            provider: Some(funding.provider.clone()),
+            status: Some(format!("{:?}", funding.status)),
+            metadata,
+            nostr_event_id: None,
+            signature: None,
+            created_at: Utc::now(),
+        };
+
+        self.store_escrow_event(event).await
+    }
+
+    /// Append an event to the audit trail and notify webhooks
+    async fn store_escrow_event(&self, event: EscrowEvent) -> Result<(), EscrowError> {
+        self.notify_webhooks(&event).await;
         self.escrow_events.write().await.push(event);
 
         Ok(())
     }
 
+    /// Forward an escrow event to registered webhook endpoints
+    async fn notify_webhooks(&self, event: &EscrowEvent) {
+        let task = match event.task_id {
+            Some(task_id) => self.tasks.read().await.get(&task_id).cloned(),
+            None => None,
+        };
+
+        // Marketplaces identify their tasks via `metadata.marketplace_id`
+        let marketplace_id = task
+            .as_ref()
+            .and_then(|task| task.metadata.as_ref())
+            .and_then(|metadata| metadata.get("marketplace_id"))
+            .and_then(|value| value.as_str())
+            .map(String::from);
+
+        let data = serde_json::json!({
+            "task_id": event.task_id,
+            "funding_id": event.funding_id,
+            "task_state": task.as_ref().map(|task| task.state),
+            "invoice_hash": event.invoice_hash,
+            "amount_sats": event.amount_sats,
+            "actor_pubkey": event.actor_pubkey,
+            "status": event.status,
+            "created_at": event.created_at,
+            "metadata": webhook_metadata(&event.event_type, event.metadata.as_ref()),
+        });
+
+        self.webhook_dispatcher.dispatch(WebhookEvent::new(
+            event.event_type.clone(),
+            marketplace_id,
+            event.task_id,
+            data,
+        ));
+    }
+
     /// Validate task creation request
     fn validate_create_task_request(&self, request: &CreateTaskRequest) -> Result<(), EscrowError> {
         if request.title.trim().is_empty() {
             return Err(EscrowError::task_validation("Title cannot be empty"));
         }
 
-        if request.reward_sats <= 0 {
+        if let Some(amount) = &request.reward_fiat {
+            if amount.currency.len() != 3
+                || !amount.currency.chars().all(|c| c.is_ascii_uppercase())
+            {
+                return Err(EscrowError::task_validation(format!(
+                    "Invalid currency code: {}",
+                    amount.currency
+                )));
+            }
+            if amount.amount_cents <= 0 {
+                return Err(EscrowError::task_validation(
+                    "Reward must be greater than 0",
+                ));
+            }
+        } else if request.reward_sats <= 0 {
             return Err(EscrowError::task_validation(
                 "Reward must be greater than 0",
             ));
-        }
-
-        if request.reward_sats > self.config.max_task_reward_sats {
+        } else if request.reward_sats > self.config.max_task_reward_sats {
             return Err(EscrowError::task_validation(format!(
                 "Reward {} sats exceeds maximum {}",
                 request.reward_sats, self.config.max_task_reward_sats
@@ -654,6 +2802,9 @@ impl TaskManager {
             ));
         }
 
+        // Declared acceptance criteria must be checkable once proof arrives
+        AcceptanceCriteria::from_metadata(request.metadata.as_ref())?;
+
         Ok(())
     }
 
@@ -663,7 +2814,7 @@ impl TaskManager {
         request: &FundTaskRequest,
         task: &Task,
     ) -> Result<(), EscrowError> {
-        if task.employer_pubkey != request.employer_pubkey {
+        if !verification_service::same_pubkey(&task.employer_pubkey, &request.employer_pubkey) {
             return Err(EscrowError::task_validation(
                 "Only task creator can fund task",
             ));
@@ -706,6 +2857,24 @@ impl TaskManager {
             ));
         }
 
+        // Reject payout destinations for another network up front
+        if !is_cashu_destination(&request.worker_invoice) {
+            network::validate_payout_destination(
+                &request.worker_invoice,
+                self.escrow_engine.network(),
+            )
+            .map_err(|e| EscrowError::task_validation(format!("Invalid worker invoice: {}", e)))?;
+        }
+
+        // On-chain payouts go through a Boltz reverse swap
+        if self.is_onchain_destination(&request.worker_invoice) {
+            self.payment_coordinator()
+                .and_then(|coordinator| coordinator.boltz_client().map(|_| ()))
+                .map_err(|e| {
+                    EscrowError::task_validation(format!("On-chain payouts unavailable: {}", e))
+                })?;
+        }
+
         Ok(())
     }
 
@@ -715,7 +2884,11 @@ impl TaskManager {
         request: &SubmitProofRequest,
         task: &Task,
     ) -> Result<(), EscrowError> {
-        if task.worker_pubkey.as_ref() != Some(&request.worker_pubkey) {
+        if !task
+            .worker_pubkey
+            .as_ref()
+            .is_some_and(|worker| verification_service::same_pubkey(worker, &request.worker_pubkey))
+        {
             return Err(EscrowError::task_validation(
                 "Only assigned worker can submit proof",
             ));
@@ -747,7 +2920,7 @@ impl TaskManager {
         task: &Task,
     ) -> Result<(), EscrowError> {
         // Only employer or system can verify
-        if request.verifier_pubkey != task.employer_pubkey {
+        if !verification_service::same_pubkey(&request.verifier_pubkey, &task.employer_pubkey) {
             return Err(EscrowError::task_validation("Only task creator can verify"));
         }
 
@@ -762,3 +2935,102 @@ impl TaskManager {
         Ok(())
     }
 }
+
+/// Provider recorded on fundings of `mode`
+fn funding_provider(mode: FundingMode) -> &'static str {
+    match mode {
+        FundingMode::LightningHold | FundingMode::LightningStandard => "ldk",
+        FundingMode::OnchainSubmarine | FundingMode::OnchainReverse => "boltz",
+        FundingMode::OnchainMultisig => "multisig",
+        FundingMode::Cashu => "cashu",
+    }
+}
+
+/// Whether a payout destination is a Cashu public key
+fn is_cashu_destination(destination: &str) -> bool {
+    cashu::parse_pubkey(destination).is_ok()
+}
+
+/// Amount a funding received, or its requested amount if not recorded
+fn received_sats(funding: &Funding) -> u64 {
+    funding.amount_received_sats.unwrap_or(funding.amount_sats) as u64
+}
+
+/// Event metadata fields that may leave the node in a webhook
+///
+/// Only ids, states, amounts and timestamps are listed; preimages, PSBTs,
+/// ecash tokens, invoices and payout destinations never are.
+fn webhook_fields(event_type: &str) -> &'static [&'static str] {
+    match event_type {
+        "task.created" => &["reward_sats", "reward_fiat"],
+        "proof.submitted" => &["proof_hash", "nostr_event_id", "archived"],
+        "proof.auto_verified" => &["approved", "score", "verification_method"],
+        "proof.verified" => &["approved", "verified_by"],
+        "proof.rejected" => &["approved"],
+        "invoice.accepted" => &[
+            "amount_expected_sats",
+            "amount_received_sats",
+            "overpayment_sats",
+            "part_count",
+        ],
+        "invoice.payment_rejected" => &["amount_expected_sats", "amount_received_sats"],
+        "invoice.htlcs_timed_out" => &["part_count", "amount_failed_sats"],
+        "invoice.expired" => &["expires_at"],
+        "invoice.cancelled" => &["refunded_sats"],
+        "refund.paid" => &["refunded_sats", "payout_id"],
+        "refund.claimed" => &["refunded_sats", "swap_id", "claim_txid"],
+        "swap.refund_broadcast" | "swap.refund_signed" => {
+            &["swap_id", "txid", "spendable_at_height"]
+        }
+        "swap.created" => &["swap_id"],
+        "swap.mempool" | "swap.confirmed" | "swap.invoice_paid" | "swap.failed"
+        | "swap.expired" | "swap.refunded" => &[
+            "swap_id",
+            "boltz_status",
+            "transaction_id",
+            "failure_reason",
+        ],
+        "escrow.funded" | "escrow.reorged" => &["txid", "confirmations"],
+        "escrow.refunded" | "escrow.split" => &["txid", "fee_sats", "broadcast"],
+        "cashu.funded" => &["proofs"],
+        "cashu.refunded" => &["refunded_sats", "fee_sats"],
+        "payout.created" => &["swap_id", "onchain_amount_sats"],
+        "payout.claimed" => &["swap_id", "claim_txid"],
+        "payout.failed" => &["swap_id", "claim_txid", "failure_reason"],
+        "settlement.completed" => &["amount_sats", "batch_id", "payout_id"],
+        _ => &[],
+    }
+}
+
+/// Webhook-safe subset of an event's metadata
+fn webhook_metadata(event_type: &str, metadata: Option<&serde_json::Value>) -> serde_json::Value {
+    let fields = webhook_fields(event_type)
+        .iter()
+        .filter_map(|field| {
+            let value = metadata?.get(*field)?;
+            Some((field.to_string(), value.clone()))
+        })
+        .collect();
+    serde_json::Value::Object(fields)
+}
+
+fn hold_invoice_id(funding: &Funding) -> Result<String, EscrowError> {
+    funding
+        .hold_invoice_id
+        .clone()
+        .ok_or_else(|| EscrowError::task_validation("Funding has no hold invoice"))
+}
+
+fn swap_id(funding: &Funding) -> Result<String, EscrowError> {
+    funding
+        .swap_id
+        .clone()
+        .ok_or_else(|| EscrowError::task_validation("Funding has no swap"))
+}
+
+fn refund_address(funding: &Funding) -> Result<String, EscrowError> {
+    funding
+        .refund_address
+        .clone()
+        .ok_or_else(|| EscrowError::task_validation("Funding has no refund address"))
+}
@@ -0,0 +1,285 @@
+//! Shared test helpers
+//!
+//! A minimal HTTP/1.1 server used to stand in for external services
+//! (webhook receivers, Boltz, proof hosts) in unit tests, and signed BOLT11
+//! invoices for the swaps those services hand out.
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::RwLock,
+};
+
+/// Request captured by the mock server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// Get a header by (case-insensitive) name
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_lowercase()).map(|v| v.as_str())
+    }
+
+    /// Body as UTF-8 text
+    pub fn body_text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// Response returned by the mock server
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// JSON response with the given status
+    pub fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json".to_string(),
+            headers: Vec::new(),
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    /// Raw response with the given status and content type
+    pub fn bytes(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type: content_type.to_string(),
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// Add a response header
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&RecordedRequest) -> MockResponse + Send + Sync;
+
+/// Local HTTP server that records requests and answers via a handler
+pub struct MockHttpServer {
+    addr: SocketAddr,
+    requests: Arc<RwLock<Vec<RecordedRequest>>>,
+}
+
+impl MockHttpServer {
+    /// Start a server on an ephemeral localhost port
+    pub async fn start<F>(handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> MockResponse + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(RwLock::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let recorded = recorded.clone();
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    recorded.write().await.push(request.clone());
+                    let response = handler(&request);
+                    let headers: String = response
+                        .headers
+                        .iter()
+                        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+                        .collect();
+                    let head = format!(
+                        "HTTP/1.1 {} Mock\r\ncontent-type: {}\r\ncontent-length: {}\r\n{}connection: close\r\n\r\n",
+                        response.status,
+                        response.content_type,
+                        response.body.len(),
+                        headers
+                    );
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(&response.body).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// Base URL of the server (no trailing slash)
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// All requests received so far
+    pub async fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.read().await.clone()
+    }
+}
+
+async fn read_request(stream: &mut tokio::net::TcpStream) -> Option<RecordedRequest> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+/// Signed BOLT11 invoice paying `amount_sats` to `payment_hash`
+///
+/// `prefix` is the network's BOLT11 prefix, e.g. `lnbcrt`.
+pub fn bolt11_invoice(prefix: &str, payment_hash: &[u8; 32], amount_sats: u64) -> String {
+    use secp256k1::{Message, Secp256k1, SecretKey};
+    use sha2::{Digest, Sha256};
+
+    // Amounts in nano-bitcoin: 1n is 0.1 sat
+    let hrp = format!("{}{}n", prefix, amount_sats * 10);
+
+    let timestamp: u64 = 1_700_000_000;
+    let mut data: Vec<u8> = (0..7)
+        .rev()
+        .map(|i| ((timestamp >> (i * 5)) & 31) as u8)
+        .collect();
+    let mut tagged = |tag: u8, field: Vec<u8>| {
+        data.extend([tag, (field.len() >> 5) as u8, (field.len() & 31) as u8]);
+        data.extend(field);
+    };
+    tagged(1, to_u5(payment_hash));
+    tagged(16, to_u5(&[0x11; 32]));
+    tagged(13, to_u5(b"escrow payout"));
+    // var_onion_optin and payment_secret required
+    tagged(5, vec![16, 8, 0]);
+
+    let mut preimage = hrp.as_bytes().to_vec();
+    preimage.extend(from_u5(&data));
+    let digest: [u8; 32] = Sha256::digest(&preimage).into();
+    let signature = Secp256k1::new().sign_ecdsa(
+        &Message::from_digest(digest),
+        &SecretKey::from_slice(&[0x42; 32]).unwrap(),
+    );
+    // Any recovery id recovers a key the signature verifies against
+    let mut signature = signature.serialize_compact().to_vec();
+    signature.push(0);
+    data.extend(to_u5(&signature));
+
+    let mut values = hrp_expand(&hrp);
+    values.extend(&data);
+    values.extend([0; 6]);
+    let checksum = bech32_polymod(&values) ^ 1;
+    data.extend((0..6).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8));
+
+    const CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    let encoded: String = data.iter().map(|&v| CHARSET[v as usize] as char).collect();
+    format!("{}1{}", hrp, encoded)
+}
+
+fn to_u5(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        acc = ((acc << 8) | byte as u32) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(((acc >> bits) & 31) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push(((acc << (5 - bits)) & 31) as u8);
+    }
+    out
+}
+
+fn from_u5(values: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &value in values {
+        acc = ((acc << 5) | value as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push((acc << (8 - bits)) as u8);
+    }
+    out
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    values
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk = 1u32;
+    for &value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
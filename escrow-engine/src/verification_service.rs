@@ -4,9 +4,22 @@
 //! Nostr signatures, and other security validations required for the escrow system.
 
 use crate::EscrowResult;
-use crate::{error::EscrowError, models::Task};
+use crate::{
+    acceptance_criteria::{AcceptanceCriteria, CriteriaReport},
+    error::EscrowError,
+    models::Task,
+    nostr_publisher::EscrowEventKind,
+    proof_fetcher::{FetchedProof, ProofFetcher, ProofFetcherConfig},
+    proof_verifier::{self, ProofVerifier, ProofVerifierConfig},
+};
 use chrono::{DateTime, Utc};
-// sha2 and other crypto deps can be added when implementing real checks
+use secp256k1::{Message, Secp256k1, XOnlyPublicKey, schnorr::Signature};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
 
 /// Configuration for the verification service
 #[derive(Debug, Clone)]
@@ -15,8 +28,12 @@ pub struct VerificationServiceConfig {
     pub max_proof_size_bytes: usize,
     /// Allowed proof file extensions
     pub allowed_proof_extensions: Vec<String>,
-    /// Require Nostr signature verification
-    pub require_nostr_verification: bool,
+    /// Timeout for proof downloads in seconds
+    pub proof_fetch_timeout_secs: u64,
+    /// Allow proofs hosted on loopback and private networks (local setups only)
+    pub allow_private_proof_hosts: bool,
+    /// Automated proof verifiers by task type
+    pub proof_verifiers: HashMap<String, ProofVerifierConfig>,
 }
 
 impl Default for VerificationServiceConfig {
@@ -32,7 +49,9 @@ impl Default for VerificationServiceConfig {
                 "txt".to_string(),
                 "md".to_string(),
             ],
-            require_nostr_verification: true,
+            proof_fetch_timeout_secs: 30,
+            allow_private_proof_hosts: false,
+            proof_verifiers: HashMap::new(),
         }
     }
 }
@@ -40,48 +59,134 @@ impl Default for VerificationServiceConfig {
 /// Main verification service
 pub struct VerificationService {
     config: VerificationServiceConfig,
+    proof_fetcher: ProofFetcher,
+    /// Automated proof verifiers by task type
+    verifiers: RwLock<HashMap<String, Arc<dyn ProofVerifier>>>,
 }
 
 impl VerificationService {
     /// Create a new verification service
     pub fn new(config: VerificationServiceConfig) -> Self {
-        Self { config }
+        let fetcher_config = ProofFetcherConfig {
+            max_size_bytes: config.max_proof_size_bytes,
+            timeout_secs: config.proof_fetch_timeout_secs,
+            allow_private_hosts: config.allow_private_proof_hosts,
+        };
+        let verifiers = config
+            .proof_verifiers
+            .iter()
+            .filter_map(|(task_type, verifier)| {
+                verifier
+                    .build(&fetcher_config)
+                    .inspect_err(|e| warn!("Proof verifier for {} unavailable: {}", task_type, e))
+                    .ok()
+                    .map(|verifier| (task_type.clone(), verifier))
+            })
+            .collect();
+        Self {
+            config,
+            proof_fetcher: ProofFetcher::new(fetcher_config),
+            verifiers: RwLock::new(verifiers),
+        }
     }
 
-    /// Verify a Nostr signature
+    /// Register the automated proof verifier for tasks of `task_type`
+    pub async fn register_verifier(&self, task_type: &str, verifier: Arc<dyn ProofVerifier>) {
+        info!(
+            "Registered proof verifier for {}: {}",
+            task_type,
+            verifier.name()
+        );
+        self.verifiers
+            .write()
+            .await
+            .insert(task_type.to_string(), verifier);
+    }
+
+    /// Verify a Nostr event signature
+    ///
+    /// `signature` must be the BIP-340 signature of `pubkey` (hex or npub)
+    /// over the event ID, as in the event's `sig` field.
     pub async fn verify_nostr_signature(
         &self,
         signature: &str,
         event_id: &str,
+        pubkey: &str,
     ) -> Result<(), EscrowError> {
-        // In production, this would:
-        // 1. Parse the Nostr signature
-        // 2. Verify it against the event ID and public key
-        // 3. Check timestamp validity
+        verify_event_signature(signature, event_id, pubkey)
+    }
 
-        if !self.config.require_nostr_verification {
-            return Ok(());
-        }
+    /// Verify a worker's signed proof event
+    ///
+    /// `raw_event` is the NIP-01 event as JSON. Its ID must match its
+    /// content, it must be a proof event signed by `worker_pubkey`, and its
+    /// tags must name the task (`d`), the proof URL (`url`) and the proof's
+    /// SHA-256 hash (`x`), as in NIP-94 file metadata.
+    pub async fn verify_proof_event(
+        &self,
+        raw_event: &str,
+        task_id: Uuid,
+        worker_pubkey: &str,
+        proof_url: &str,
+        proof_hash: &str,
+    ) -> Result<NostrEvent, EscrowError> {
+        let event: NostrEvent = serde_json::from_str(raw_event)
+            .map_err(|e| EscrowError::proof_verification(format!("Invalid proof event: {}", e)))?;
 
-        if signature.trim().is_empty() {
+        if event.id != event.compute_id() {
             return Err(EscrowError::proof_verification(
-                "Nostr signature is required",
+                "Proof event ID does not match its content",
             ));
         }
 
-        if event_id.trim().is_empty() {
+        if event.kind != EscrowEventKind::ProofSubmitted.as_u32() {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof event has kind {}, expected {}",
+                event.kind,
+                EscrowEventKind::ProofSubmitted.as_u32()
+            )));
+        }
+
+        if !same_pubkey(&event.pubkey, worker_pubkey) {
             return Err(EscrowError::proof_verification(
-                "Nostr event ID is required",
+                "Proof event is not authored by the worker",
             ));
         }
 
-        // TODO: Implement actual Nostr signature verification using nostr-sdk
+        verify_event_signature(&event.sig, &event.id, &event.pubkey)?;
 
-        Ok(())
+        let task_id = task_id.to_string();
+        if !event.tag_values("d").any(|value| value == task_id) {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof event does not reference task {}",
+                task_id
+            )));
+        }
+        if !event.tag_values("url").any(|value| value == proof_url) {
+            return Err(EscrowError::proof_verification(
+                "Proof event does not contain the proof URL",
+            ));
+        }
+        if !event
+            .tag_values("x")
+            .any(|value| value.eq_ignore_ascii_case(proof_hash))
+        {
+            return Err(EscrowError::proof_verification(
+                "Proof event does not contain the proof hash",
+            ));
+        }
+
+        Ok(event)
     }
 
-    /// Verify a generic signature
-    pub async fn verify_signature(&self, signature: &str, pubkey: &str) -> Result<(), EscrowError> {
+    /// Verify a BIP-340 signature of `pubkey` (hex or npub) over the
+    /// SHA-256 hash of `message`
+    pub async fn verify_signature(
+        &self,
+        signature: &str,
+        message: &str,
+        pubkey: &str,
+    ) -> Result<(), EscrowError> {
         if signature.trim().is_empty() {
             return Err(EscrowError::proof_verification("Signature is required"));
         }
@@ -90,12 +195,14 @@ impl VerificationService {
             return Err(EscrowError::proof_verification("Public key is required"));
         }
 
-        // TODO: Implement actual signature verification
-
-        Ok(())
+        verify_schnorr(signature, Sha256::digest(message.as_bytes()).into(), pubkey)
     }
 
     /// Verify proof content and hash
+    ///
+    /// Downloads the proof and checks its SHA-256 against `proof_hash` (and
+    /// `expected_hash` when given). Its type is sniffed from the content and
+    /// must match one of the allowed extensions, whatever the URL says.
     pub async fn verify_proof(
         &self,
         proof_url: &str,
@@ -127,51 +234,170 @@ impl VerificationService {
             ));
         }
 
-        // In production, this would:
-        // 1. Download the proof content
-        // 2. Calculate the actual hash
-        // 3. Compare with provided hash
-        // 4. Validate file size and extension
-        // 5. Check for malware/viruses
-
-        let is_valid = if let Some(expected) = expected_hash {
-            proof_hash == expected
-        } else {
-            // Simulate hash verification
-            true // TODO: Implement actual verification
-        };
+        let proof = self.fetch_allowed_proof(proof_url).await?;
+        let is_valid = proof.content_hash.eq_ignore_ascii_case(proof_hash)
+            && expected_hash
+                .is_none_or(|expected| proof.content_hash.eq_ignore_ascii_case(expected));
 
         Ok(ProofVerificationResult {
             is_valid,
-            content_hash: proof_hash.to_string(),
-            file_size: 1024, // TODO: Get actual size
-            content_type: "application/octet-stream".to_string(), // TODO: Get actual type
+            content_hash: proof.content_hash,
+            file_size: proof.size,
+            content_type: proof.content_type.mime_type().to_string(),
             verification_timestamp: Utc::now(),
         })
     }
 
-    /// Verify task completion criteria
+    /// Download a proof whose content must hash to `proof_hash`
+    pub async fn download_proof(
+        &self,
+        proof_url: &str,
+        proof_hash: &str,
+    ) -> Result<FetchedProof, EscrowError> {
+        let proof = self.fetch_allowed_proof(proof_url).await?;
+        if !proof.content_hash.eq_ignore_ascii_case(proof_hash) {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof content hashes to {}, not {}",
+                proof.content_hash, proof_hash
+            )));
+        }
+        Ok(proof)
+    }
+
+    /// Download a proof, refusing content of a type that is not allowed
+    async fn fetch_allowed_proof(&self, proof_url: &str) -> Result<FetchedProof, EscrowError> {
+        let proof = self.proof_fetcher.fetch(proof_url).await?;
+        let content_type = proof.content_type;
+        if !content_type.extensions().iter().any(|extension| {
+            self.config
+                .allowed_proof_extensions
+                .iter()
+                .any(|allowed| allowed == extension)
+        }) {
+            return Err(EscrowError::proof_verification(format!(
+                "Proof content {} not allowed. Allowed: {:?}",
+                content_type.mime_type(),
+                self.config.allowed_proof_extensions
+            )));
+        }
+        Ok(proof)
+    }
+
+    /// Check a proof against the task's acceptance criteria and the
+    /// automated verifier registered for its type
+    ///
+    /// Returns `None` when the task declares no criteria and no verifier is
+    /// registered for its type, leaving the proof to the employer. `proof`
+    /// is the already downloaded content, if any; otherwise it is fetched.
+    /// Either way only content hashing to the task's proof hash, which the
+    /// worker signed, is judged. A proof is approved only if it meets every
+    /// criterion and passes the verifier. Failures to check the proof count
+    /// as a rejection, so the result always records why it was not approved.
     pub async fn verify_task_completion(
         &self,
         task: &Task,
         proof_url: &str,
-    ) -> Result<CompletionVerificationResult, EscrowError> {
-        // In production, this would:
-        // 1. Check if proof URL is accessible
-        // 2. Validate proof content matches task requirements
-        // 3. Run automated tests if applicable
-        // 4. Check against acceptance criteria
-
-        let verification_result = CompletionVerificationResult {
-            approved: true,
-            score: 100,
-            feedback: "Proof submitted successfully".to_string(),
-            verification_method: "automated".to_string(),
-            verified_at: Utc::now(),
-            verifier_notes: None,
+        proof: Option<&FetchedProof>,
+    ) -> Option<CompletionVerificationResult> {
+        let criteria = AcceptanceCriteria::from_metadata(task.metadata.as_ref());
+        let verifier = match proof_verifier::task_type(task) {
+            Some(task_type) => self.verifiers.read().await.get(task_type).cloned(),
+            None => None,
         };
+        if matches!(criteria, Ok(None)) && verifier.is_none() {
+            return None;
+        }
+        let method = verifier
+            .as_ref()
+            .map_or("acceptance_criteria", |verifier| verifier.name())
+            .to_string();
+
+        let result = self
+            .check_completion(task, proof_url, proof, criteria, verifier)
+            .await;
+        Some(result.unwrap_or_else(|e| {
+            warn!("Automated verification of task {} failed: {}", task.id, e);
+            CompletionVerificationResult {
+                approved: false,
+                score: 0,
+                feedback: format!("Automated verification failed: {}", e),
+                verification_method: method,
+                verified_at: Utc::now(),
+                verifier_notes: None,
+                criteria: None,
+            }
+        }))
+    }
 
-        Ok(verification_result)
+    /// Evaluate the criteria and run the verifier, combining their results
+    async fn check_completion(
+        &self,
+        task: &Task,
+        proof_url: &str,
+        proof: Option<&FetchedProof>,
+        criteria: EscrowResult<Option<AcceptanceCriteria>>,
+        verifier: Option<Arc<dyn ProofVerifier>>,
+    ) -> EscrowResult<CompletionVerificationResult> {
+        let criteria = criteria?;
+        let proof_hash = task
+            .proof_hash
+            .as_deref()
+            .ok_or_else(|| EscrowError::proof_verification("Task has no proof hash"))?;
+
+        // Judge only the content the worker committed to, not whatever the
+        // URL serves by now
+        let fetched;
+        let proof = match proof {
+            Some(proof) if proof.content_hash.eq_ignore_ascii_case(proof_hash) => proof,
+            Some(proof) => {
+                return Err(EscrowError::proof_verification(format!(
+                    "Proof content hashes to {}, not {}",
+                    proof.content_hash, proof_hash
+                )));
+            }
+            None => {
+                fetched = self.download_proof(proof_url, proof_hash).await?;
+                &fetched
+            }
+        };
+
+        let report = criteria.map(|criteria| criteria.evaluate(proof));
+        let mut result = match verifier {
+            Some(verifier) => verifier.verify(task, proof_url, proof).await?,
+            None => CompletionVerificationResult {
+                approved: true,
+                score: 100,
+                feedback: String::new(),
+                verification_method: "acceptance_criteria".to_string(),
+                verified_at: Utc::now(),
+                verifier_notes: None,
+                criteria: None,
+            },
+        };
+
+        if let Some(report) = report {
+            let total = report.results.len();
+            let passed = report.passed_count();
+            let summary = match report.results.iter().find(|result| !result.passed) {
+                None => format!("All {} acceptance criteria met", total),
+                Some(failed) => format!(
+                    "{} of {} acceptance criteria met; {} failed: {}",
+                    passed,
+                    total,
+                    failed.criterion.name(),
+                    failed.detail
+                ),
+            };
+            result.feedback = if result.feedback.is_empty() {
+                summary
+            } else {
+                format!("{}. {}", result.feedback, summary)
+            };
+            result.approved &= report.passed;
+            result.score = result.score.min((passed * 100 / total) as u32);
+            result.criteria = Some(report);
+        }
+        Ok(result)
     }
 
     /// Validate file extension
@@ -193,6 +419,95 @@ impl VerificationService {
     }
 }
 
+/// Signed Nostr event as defined by NIP-01
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NostrEvent {
+    pub id: String,
+    pub pubkey: String,
+    pub created_at: i64,
+    pub kind: u32,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+    pub sig: String,
+}
+
+impl NostrEvent {
+    /// Event ID: hex SHA-256 of `[0,pubkey,created_at,kind,tags,content]`
+    pub fn compute_id(&self) -> String {
+        let serialized = serde_json::json!([
+            0,
+            self.pubkey,
+            self.created_at,
+            self.kind,
+            self.tags,
+            self.content
+        ]);
+        hex::encode(Sha256::digest(serialized.to_string().as_bytes()))
+    }
+
+    /// Values of the tags named `name`
+    pub fn tag_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.tags
+            .iter()
+            .filter(move |tag| tag.first().is_some_and(|tag_name| tag_name == name))
+            .filter_map(|tag| tag.get(1).map(String::as_str))
+    }
+}
+
+/// Parse a Nostr public key given as 32-byte hex or npub
+pub fn parse_nostr_pubkey(pubkey: &str) -> EscrowResult<XOnlyPublicKey> {
+    let pubkey = nostr_sdk::PublicKey::parse(pubkey.trim()).map_err(|e| {
+        EscrowError::proof_verification(format!("Invalid public key {}: {}", pubkey, e))
+    })?;
+    XOnlyPublicKey::from_slice(&pubkey.to_bytes())
+        .map_err(|e| EscrowError::proof_verification(format!("Invalid public key: {}", e)))
+}
+
+/// Whether two public keys name the same party, whatever their encoding
+///
+/// Keys that do not parse name nobody, so they never match.
+pub fn same_pubkey(a: &str, b: &str) -> bool {
+    match (parse_nostr_pubkey(a), parse_nostr_pubkey(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Verify the hex BIP-340 signature of a Nostr event over its hex ID
+fn verify_event_signature(signature: &str, event_id: &str, pubkey: &str) -> EscrowResult<()> {
+    if signature.trim().is_empty() {
+        return Err(EscrowError::proof_verification(
+            "Nostr signature is required",
+        ));
+    }
+
+    if event_id.trim().is_empty() {
+        return Err(EscrowError::proof_verification(
+            "Nostr event ID is required",
+        ));
+    }
+
+    let event_id: [u8; 32] = hex::decode(event_id)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| EscrowError::proof_verification("Nostr event ID must be 32 bytes of hex"))?;
+
+    verify_schnorr(signature, event_id, pubkey)
+}
+
+/// Verify a hex BIP-340 signature over a 32-byte digest
+fn verify_schnorr(signature: &str, digest: [u8; 32], pubkey: &str) -> EscrowResult<()> {
+    let pubkey = parse_nostr_pubkey(pubkey)?;
+    let signature = hex::decode(signature.trim())
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| EscrowError::proof_verification("Signature must be 64 bytes of hex"))?;
+
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature, &Message::from_digest(digest), &pubkey)
+        .map_err(|_| EscrowError::proof_verification("Invalid signature"))
+}
+
 impl Default for VerificationService {
     fn default() -> Self {
         Self::new(VerificationServiceConfig::default())
@@ -210,7 +525,7 @@ pub struct ProofVerificationResult {
 }
 
 /// Result of task completion verification
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompletionVerificationResult {
     pub approved: bool,
     pub score: u32,
@@ -218,11 +533,267 @@ pub struct CompletionVerificationResult {
     pub verification_method: String,
     pub verified_at: DateTime<Utc>,
     pub verifier_notes: Option<String>,
+    /// Per-criterion report when the task declares acceptance criteria
+    pub criteria: Option<CriteriaReport>,
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use nostr_sdk::ToBech32;
+    use secp256k1::{Keypair, SecretKey};
+
+    /// Hex Nostr public key of `secret_key`
+    pub(crate) fn nostr_pubkey(secret_key: &SecretKey) -> String {
+        secret_key
+            .x_only_public_key(&Secp256k1::new())
+            .0
+            .to_string()
+    }
+
+    /// Signature of `secret_key` over a Nostr event ID
+    pub(crate) fn sign_event(secret_key: &SecretKey, event_id: &str) -> String {
+        let digest: [u8; 32] = hex::decode(event_id).unwrap().try_into().unwrap();
+        sign_digest(secret_key, digest)
+    }
+
+    /// Signature of `secret_key` over the SHA-256 hash of `message`
+    pub(crate) fn sign_message(secret_key: &SecretKey, message: &str) -> String {
+        sign_digest(secret_key, Sha256::digest(message.as_bytes()).into())
+    }
+
+    /// Sign `event` as `secret_key`, filling in its pubkey and ID
+    pub(crate) fn sign_nostr_event(secret_key: &SecretKey, mut event: NostrEvent) -> NostrEvent {
+        event.pubkey = nostr_pubkey(secret_key);
+        event.id = event.compute_id();
+        event.sig = sign_event(secret_key, &event.id);
+        event
+    }
+
+    /// Proof event of `secret_key` for a task's proof, as JSON
+    pub(crate) fn proof_event(
+        secret_key: &SecretKey,
+        task_id: Uuid,
+        proof_url: &str,
+        proof_hash: &str,
+    ) -> String {
+        let event = NostrEvent {
+            id: String::new(),
+            pubkey: String::new(),
+            created_at: 1_700_000_000,
+            kind: EscrowEventKind::ProofSubmitted.as_u32(),
+            tags: vec![
+                vec!["d".to_string(), task_id.to_string()],
+                vec!["url".to_string(), proof_url.to_string()],
+                vec!["x".to_string(), proof_hash.to_string()],
+            ],
+            content: "Proof of work".to_string(),
+            sig: String::new(),
+        };
+        serde_json::to_string(&sign_nostr_event(secret_key, event)).unwrap()
+    }
+
+    fn sign_digest(secret_key: &SecretKey, digest: [u8; 32]) -> String {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        secp.sign_schnorr_no_aux_rand(&Message::from_digest(digest), &keypair)
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_nostr_signatures() {
+        let service = VerificationService::default();
+        let worker = SecretKey::from_slice(&[0x71; 32]).unwrap();
+        let other = SecretKey::from_slice(&[0x72; 32]).unwrap();
+        let worker_hex = nostr_pubkey(&worker);
+        let worker_npub = nostr_sdk::PublicKey::from_hex(&worker_hex)
+            .unwrap()
+            .to_bech32()
+            .unwrap();
+        let event_id = hex::encode(Sha256::digest(b"proof event"));
+        let signature = sign_event(&worker, &event_id);
+
+        service
+            .verify_nostr_signature(&signature, &event_id, &worker_hex)
+            .await
+            .unwrap();
+        service
+            .verify_nostr_signature(&signature, &event_id, &worker_npub)
+            .await
+            .unwrap();
+        assert!(same_pubkey(&worker_hex, &worker_npub));
+        assert!(!same_pubkey(&worker_hex, &nostr_pubkey(&other)));
+        // Unparseable keys identify nobody, not even themselves
+        assert!(!same_pubkey("employer_pubkey", "employer_pubkey"));
+        assert!(!same_pubkey(&worker_hex, "employer_pubkey"));
+
+        // Forged: another key's signature, or a flipped bit
+        let forged = sign_event(&other, &event_id);
+        assert!(
+            service
+                .verify_nostr_signature(&forged, &event_id, &worker_hex)
+                .await
+                .is_err()
+        );
+        let mut flipped = hex::decode(&signature).unwrap();
+        flipped[10] ^= 1;
+        assert!(
+            service
+                .verify_nostr_signature(&hex::encode(flipped), &event_id, &worker_hex)
+                .await
+                .is_err()
+        );
+
+        // Swapped: a valid signature over another event, or checked against another key
+        let other_event = hex::encode(Sha256::digest(b"other event"));
+        assert!(
+            service
+                .verify_nostr_signature(&signature, &other_event, &worker_hex)
+                .await
+                .is_err()
+        );
+        assert!(
+            service
+                .verify_nostr_signature(&signature, &event_id, &nostr_pubkey(&other))
+                .await
+                .is_err()
+        );
+
+        // Malformed signatures, event IDs and keys
+        for (signature, event_id, pubkey) in [
+            ("signature", event_id.as_str(), worker_hex.as_str()),
+            (&signature[..126], &event_id, &worker_hex),
+            ("", &event_id, &worker_hex),
+            (&signature, "event_id", &worker_hex),
+            (&signature, &event_id[..62], &worker_hex),
+            (&signature, &event_id, "worker_pubkey"),
+            (&signature, &event_id, "npub1invalid"),
+        ] {
+            assert!(
+                service
+                    .verify_nostr_signature(signature, event_id, pubkey)
+                    .await
+                    .is_err()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proof_events() {
+        let service = VerificationService::default();
+        let worker = SecretKey::from_slice(&[0x74; 32]).unwrap();
+        let other = SecretKey::from_slice(&[0x75; 32]).unwrap();
+        let worker_hex = nostr_pubkey(&worker);
+        let task_id = Uuid::new_v4();
+        let url = "https://example.com/proof.png";
+        let hash = "ab".repeat(32);
+        let raw = proof_event(&worker, task_id, url, &hash);
+
+        let event = service
+            .verify_proof_event(&raw, task_id, &worker_hex, url, &hash.to_uppercase())
+            .await
+            .unwrap();
+        assert_eq!(event.id, event.compute_id());
+        // The ID covers the NIP-01 serialisation, escapes included
+        let quoted = NostrEvent {
+            id: String::new(),
+            pubkey: "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            created_at: 1_700_000_000,
+            kind: 1,
+            tags: vec![vec!["t".to_string(), "a\"b".to_string()]],
+            content: "line\nbreak".to_string(),
+            sig: String::new(),
+        };
+        assert_eq!(
+            quoted.compute_id(),
+            hex::encode(Sha256::digest(
+                r#"[0,"79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",1700000000,1,[["t","a\"b"]],"line\nbreak"]"#
+            ))
+        );
+
+        // Checked against another task, URL, hash or worker
+        for (task_id, url, hash, worker) in [
+            (Uuid::new_v4(), url, hash.as_str(), worker_hex.as_str()),
+            (task_id, "https://example.com/other.png", &hash, &worker_hex),
+            (task_id, url, &"cd".repeat(32), &worker_hex),
+            (task_id, url, &hash, &nostr_pubkey(&other)),
+        ] {
+            assert!(
+                service
+                    .verify_proof_event(&raw, task_id, worker, url, hash)
+                    .await
+                    .is_err()
+            );
+        }
+
+        // Tampered content, a signature of another key, another kind
+        let mut tampered = event.clone();
+        tampered.content = "Other work".to_string();
+        let mut resigned = event.clone();
+        resigned.sig = sign_event(&other, &event.id);
+        let mut kind = event.clone();
+        kind.kind = 1;
+        let kind = sign_nostr_event(&worker, kind);
+        for forged in [tampered, resigned, kind] {
+            let forged = serde_json::to_string(&forged).unwrap();
+            assert!(
+                service
+                    .verify_proof_event(&forged, task_id, &worker_hex, url, &hash)
+                    .await
+                    .is_err()
+            );
+        }
+        assert!(
+            service
+                .verify_proof_event("{}", task_id, &worker_hex, url, &hash)
+                .await
+                .is_err()
+        );
+
+        // An unsigned event never stands in for the worker's commitment
+        let mut unsigned = event.clone();
+        unsigned.sig = "00".repeat(64);
+        let unsigned = serde_json::to_string(&unsigned).unwrap();
+        assert!(
+            service
+                .verify_proof_event(&unsigned, task_id, &worker_hex, url, &hash)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_message_signatures() {
+        let service = VerificationService::default();
+        let employer = SecretKey::from_slice(&[0x73; 32]).unwrap();
+        let employer_hex = nostr_pubkey(&employer);
+        let message = "approve task";
+        let signature = sign_message(&employer, message);
+
+        service
+            .verify_signature(&signature, message, &employer_hex)
+            .await
+            .unwrap();
+        // Bound to the exact message
+        assert!(
+            service
+                .verify_signature(&signature, "reject task", &employer_hex)
+                .await
+                .is_err()
+        );
+        assert!(
+            service
+                .verify_signature("", message, &employer_hex)
+                .await
+                .is_err()
+        );
+        assert!(
+            service
+                .verify_signature(&signature, message, "")
+                .await
+                .is_err()
+        );
+    }
 
     #[test]
     fn test_validate_file_extension() {
@@ -235,4 +806,56 @@ mod tests {
         // Invalid extension
         assert!(service.validate_file_extension("proof.exe").is_err());
     }
+
+    #[tokio::test]
+    async fn test_verify_proof_downloads_content() {
+        use crate::test_utils::{MockHttpServer, MockResponse};
+
+        let server = MockHttpServer::start(|request| match request.path.as_str() {
+            "/report.md" => MockResponse::bytes(
+                200,
+                "application/octet-stream",
+                "# Done\n\nAll tests pass.\n",
+            ),
+            // An executable dressed up as an image
+            "/proof.png" => MockResponse::bytes(200, "image/png", b"MZ\x90\x00\x03\x00".to_vec()),
+            _ => MockResponse::bytes(404, "text/plain", "not found"),
+        })
+        .await;
+        let service = VerificationService::new(VerificationServiceConfig {
+            allow_private_proof_hosts: true,
+            ..VerificationServiceConfig::default()
+        });
+        let url = format!("{}/report.md", server.url());
+        let hash = hex::encode(Sha256::digest(b"# Done\n\nAll tests pass.\n"));
+
+        let result = service
+            .verify_proof(&url, &hash, Some(&hash))
+            .await
+            .unwrap();
+        assert!(result.is_valid);
+        assert_eq!(
+            (result.file_size, result.content_type.as_str()),
+            (24, "text/plain")
+        );
+        assert_eq!(result.content_hash, hash);
+
+        // A declared hash that does not match the content
+        let result = service
+            .verify_proof(&url, &"0".repeat(64), None)
+            .await
+            .unwrap();
+        assert!(!result.is_valid);
+        assert_eq!(result.content_hash, hash);
+
+        let png = format!("{}/proof.png", server.url());
+        assert!(service.verify_proof(&png, &hash, None).await.is_err());
+        // Proofs on private hosts are refused by default
+        assert!(
+            VerificationService::default()
+                .verify_proof(&url, &hash, None)
+                .await
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,503 @@
+//! Webhook Dispatcher - Signed delivery of escrow events to marketplaces
+//!
+//! This module posts JSON payloads for invoice status changes and task state
+//! transitions to registered HTTP endpoints. Every payload is signed with
+//! HMAC-SHA256 over `"<timestamp>.<body>"` so receivers can authenticate it
+//! and reject replays, and every delivery attempt is recorded for auditing.
+
+use crate::{EscrowResult, error::EscrowError};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::RwLock, task::JoinSet};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Header carrying the `t=<timestamp>,v1=<signature>` pair
+pub const SIGNATURE_HEADER: &str = "X-Escrow-Signature";
+/// Header carrying the event type
+pub const EVENT_TYPE_HEADER: &str = "X-Escrow-Event";
+/// Header carrying the event ID (stable across retries)
+pub const EVENT_ID_HEADER: &str = "X-Escrow-Event-Id";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Configuration for the webhook dispatcher
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcherConfig {
+    /// Registered webhook endpoints
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Maximum delivery attempts per endpoint (including the first)
+    pub max_attempts: u32,
+    /// Delay before the first retry in milliseconds (doubles each retry)
+    pub initial_backoff_ms: u64,
+    /// Upper bound for the retry delay in milliseconds
+    pub max_backoff_ms: u64,
+    /// HTTP request timeout in seconds
+    pub request_timeout_secs: u64,
+}
+
+impl Default for WebhookDispatcherConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_attempts: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+            request_timeout_secs: 10,
+        }
+    }
+}
+
+/// A webhook endpoint registered by a marketplace
+#[derive(Debug, Clone)]
+pub struct WebhookEndpoint {
+    /// Endpoint identifier
+    pub id: String,
+    /// Marketplace this endpoint belongs to (`None` receives every marketplace)
+    pub marketplace_id: Option<String>,
+    /// Target URL
+    pub url: String,
+    /// Shared secret used for HMAC signing
+    pub secret: String,
+    /// Event type filters, e.g. `"invoice.created"` or `"task.*"` (empty = all)
+    pub event_types: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    /// Check whether this endpoint should receive the given event
+    pub fn accepts(&self, event: &WebhookEvent) -> bool {
+        if let Some(ref marketplace_id) = self.marketplace_id
+            && event.marketplace_id.as_ref() != Some(marketplace_id)
+        {
+            return false;
+        }
+
+        if self.event_types.is_empty() {
+            return true;
+        }
+
+        self.event_types.iter().any(|filter| {
+            if filter == "*" {
+                true
+            } else if let Some(prefix) = filter.strip_suffix(".*") {
+                event
+                    .event_type
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('.'))
+            } else {
+                *filter == event.event_type
+            }
+        })
+    }
+}
+
+/// Event payload delivered to webhook endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub marketplace_id: Option<String>,
+    pub task_id: Option<Uuid>,
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    /// Create a new webhook event
+    pub fn new(
+        event_type: String,
+        marketplace_id: Option<String>,
+        task_id: Option<Uuid>,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type,
+            marketplace_id,
+            task_id,
+            data,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Record of a single delivery attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDeliveryAttempt {
+    pub id: Uuid,
+    pub event_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub event_type: String,
+    pub endpoint_id: String,
+    pub attempt: u32,
+    pub status_code: Option<u16>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// Main webhook dispatcher
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    config: WebhookDispatcherConfig,
+    client: reqwest::Client,
+    /// In-memory delivery log (in production, this would be a database)
+    attempts: Arc<RwLock<Vec<WebhookDeliveryAttempt>>>,
+}
+
+impl WebhookDispatcher {
+    /// Create a new webhook dispatcher
+    pub fn new(config: WebhookDispatcherConfig) -> EscrowResult<Self> {
+        for endpoint in &config.endpoints {
+            if endpoint.secret.is_empty() {
+                return Err(EscrowError::config(format!(
+                    "Webhook endpoint {} has no signing secret",
+                    endpoint.id
+                )));
+            }
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.request_timeout_secs))
+            .build()
+            .map_err(|e| EscrowError::config(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(Self {
+            config,
+            client,
+            attempts: Arc::new(RwLock::new(Vec::new())),
+        })
+    }
+
+    /// Queue an event for background delivery to all matching endpoints
+    pub fn dispatch(&self, event: WebhookEvent) {
+        if !self.config.endpoints.iter().any(|e| e.accepts(&event)) {
+            return;
+        }
+
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            dispatcher.deliver(&event).await;
+        });
+    }
+
+    /// Deliver an event to all matching endpoints, retrying with backoff
+    pub async fn deliver(&self, event: &WebhookEvent) -> Vec<WebhookDeliveryAttempt> {
+        let endpoints: Vec<WebhookEndpoint> = self
+            .config
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.accepts(event))
+            .cloned()
+            .collect();
+
+        let body = match serde_json::to_string(event) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Failed to serialize webhook event {}: {}", event.id, e);
+                return Vec::new();
+            }
+        };
+
+        // Deliver to endpoints concurrently so one slow receiver does not
+        // hold back the others
+        let mut deliveries = JoinSet::new();
+        for endpoint in endpoints {
+            let dispatcher = self.clone();
+            let event = event.clone();
+            let body = body.clone();
+            deliveries.spawn(async move {
+                dispatcher
+                    .deliver_to_endpoint(&endpoint, &event, &body)
+                    .await
+            });
+        }
+
+        let mut attempts = Vec::new();
+        while let Some(result) = deliveries.join_next().await {
+            match result {
+                Ok(endpoint_attempts) => attempts.extend(endpoint_attempts),
+                Err(e) => warn!("Webhook delivery task failed: {}", e),
+            }
+        }
+
+        attempts
+    }
+
+    /// Get recorded delivery attempts for an event
+    pub async fn get_delivery_attempts(&self, event_id: Uuid) -> Vec<WebhookDeliveryAttempt> {
+        self.attempts
+            .read()
+            .await
+            .iter()
+            .filter(|attempt| attempt.event_id == event_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Get recorded delivery attempts for all events of a task
+    pub async fn get_task_attempts(&self, task_id: Uuid) -> Vec<WebhookDeliveryAttempt> {
+        self.attempts
+            .read()
+            .await
+            .iter()
+            .filter(|attempt| attempt.task_id == Some(task_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Get all recorded delivery attempts for an endpoint
+    pub async fn get_endpoint_attempts(&self, endpoint_id: &str) -> Vec<WebhookDeliveryAttempt> {
+        self.attempts
+            .read()
+            .await
+            .iter()
+            .filter(|attempt| attempt.endpoint_id == endpoint_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Deliver to a single endpoint until success, a permanent failure or
+    /// the attempt budget is exhausted
+    async fn deliver_to_endpoint(
+        &self,
+        endpoint: &WebhookEndpoint,
+        event: &WebhookEvent,
+        body: &str,
+    ) -> Vec<WebhookDeliveryAttempt> {
+        let mut attempts = Vec::new();
+        let mut backoff_ms = self.config.initial_backoff_ms;
+
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            let timestamp = Utc::now().timestamp();
+            let signature = sign_payload(&endpoint.secret, timestamp, body);
+
+            let result = self
+                .client
+                .post(&endpoint.url)
+                .header("content-type", "application/json")
+                .header(
+                    SIGNATURE_HEADER,
+                    format!("t={},v1={}", timestamp, signature),
+                )
+                .header(EVENT_TYPE_HEADER, &event.event_type)
+                .header(EVENT_ID_HEADER, event.id.to_string())
+                .body(body.to_string())
+                .send()
+                .await;
+
+            let (status_code, error, retryable) = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable = status.is_server_error() || status.as_u16() == 429;
+                    let error = (!status.is_success()).then(|| format!("HTTP {}", status));
+                    (Some(status.as_u16()), error, retryable)
+                }
+                Err(e) => (None, Some(e.to_string()), true),
+            };
+
+            let record = WebhookDeliveryAttempt {
+                id: Uuid::new_v4(),
+                event_id: event.id,
+                task_id: event.task_id,
+                event_type: event.event_type.clone(),
+                endpoint_id: endpoint.id.clone(),
+                attempt,
+                status_code,
+                success: error.is_none(),
+                error,
+                attempted_at: Utc::now(),
+            };
+            self.attempts.write().await.push(record.clone());
+            attempts.push(record.clone());
+
+            if record.success {
+                info!(
+                    "Delivered webhook {} ({}) to {}",
+                    event.id, event.event_type, endpoint.id
+                );
+                break;
+            }
+
+            if !retryable || attempt == self.config.max_attempts {
+                warn!(
+                    "Giving up on webhook {} to {} after {} attempt(s): {:?}",
+                    event.id, endpoint.id, attempt, record.error
+                );
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+            backoff_ms = (backoff_ms * 2).min(self.config.max_backoff_ms);
+        }
+
+        attempts
+    }
+}
+
+/// Compute the hex HMAC-SHA256 signature of `"<timestamp>.<body>"`
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verify a signature header against a body (for webhook receivers)
+pub fn verify_signature_header(
+    secret: &str,
+    header: &str,
+    body: &str,
+    tolerance_secs: i64,
+) -> EscrowResult<()> {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = Some(value),
+            _ => {}
+        }
+    }
+
+    let timestamp =
+        timestamp.ok_or_else(|| EscrowError::crypto("Missing webhook signature timestamp"))?;
+    let signature = signature.ok_or_else(|| EscrowError::crypto("Missing webhook signature"))?;
+
+    if (Utc::now().timestamp() - timestamp).abs() > tolerance_secs {
+        return Err(EscrowError::crypto(
+            "Webhook signature timestamp outside tolerance",
+        ));
+    }
+
+    let signature = hex::decode(signature)
+        .map_err(|_| EscrowError::crypto("Webhook signature is not valid hex"))?;
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.verify_slice(&signature)
+        .map_err(|_| EscrowError::crypto("Webhook signature mismatch"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockHttpServer, MockResponse};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn endpoint(url: String, event_types: Vec<&str>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: "marketplace-a".to_string(),
+            marketplace_id: Some("a".to_string()),
+            url,
+            secret: "whsec_test".to_string(),
+            event_types: event_types.into_iter().map(String::from).collect(),
+        }
+    }
+
+    fn config(endpoints: Vec<WebhookEndpoint>) -> WebhookDispatcherConfig {
+        WebhookDispatcherConfig {
+            endpoints,
+            max_attempts: 3,
+            initial_backoff_ms: 10,
+            max_backoff_ms: 50,
+            request_timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_with_retry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let server = MockHttpServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                MockResponse::json(503, serde_json::json!({}))
+            } else {
+                MockResponse::json(200, serde_json::json!({ "ok": true }))
+            }
+        })
+        .await;
+
+        let dispatcher =
+            WebhookDispatcher::new(config(vec![endpoint(server.url(), vec!["invoice.*"])]))
+                .unwrap();
+        let event = WebhookEvent::new(
+            "invoice.created".to_string(),
+            Some("a".to_string()),
+            Some(Uuid::new_v4()),
+            serde_json::json!({ "amount_sats": 50000 }),
+        );
+
+        let attempts = dispatcher.deliver(&event).await;
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].status_code, Some(503));
+        assert!(attempts[1].success);
+        assert_eq!(dispatcher.get_delivery_attempts(event.id).await.len(), 2);
+
+        let requests = server.requests().await;
+        let last = requests.last().unwrap();
+        assert_eq!(last.method, "POST");
+        assert_eq!(last.path, "/");
+        let header = last.header(SIGNATURE_HEADER).unwrap();
+        assert!(verify_signature_header("whsec_test", header, &last.body_text(), 300).is_ok());
+        assert!(verify_signature_header("wrong_secret", header, &last.body_text(), 300).is_err());
+        assert_eq!(last.header(EVENT_TYPE_HEADER), Some("invoice.created"));
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_filters() {
+        let server =
+            MockHttpServer::start(|_| MockResponse::json(200, serde_json::json!({}))).await;
+        let dispatcher =
+            WebhookDispatcher::new(config(vec![endpoint(server.url(), vec!["task.*"])])).unwrap();
+
+        let invoice_event = WebhookEvent::new(
+            "invoice.created".to_string(),
+            Some("a".to_string()),
+            None,
+            serde_json::json!({}),
+        );
+        let other_marketplace = WebhookEvent::new(
+            "task.claimed".to_string(),
+            Some("b".to_string()),
+            None,
+            serde_json::json!({}),
+        );
+        let matching = WebhookEvent::new(
+            "task.claimed".to_string(),
+            Some("a".to_string()),
+            None,
+            serde_json::json!({}),
+        );
+
+        assert!(dispatcher.deliver(&invoice_event).await.is_empty());
+        assert!(dispatcher.deliver(&other_marketplace).await.is_empty());
+        assert_eq!(dispatcher.deliver(&matching).await.len(), 1);
+        assert_eq!(server.requests().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_not_retried() {
+        let server =
+            MockHttpServer::start(|_| MockResponse::json(400, serde_json::json!({}))).await;
+        let dispatcher =
+            WebhookDispatcher::new(config(vec![endpoint(server.url(), vec![])])).unwrap();
+        let event = WebhookEvent::new(
+            "task.created".to_string(),
+            Some("a".to_string()),
+            None,
+            serde_json::json!({}),
+        );
+
+        let attempts = dispatcher.deliver(&event).await;
+        assert_eq!(attempts.len(), 1);
+        assert!(!attempts[0].success);
+    }
+}